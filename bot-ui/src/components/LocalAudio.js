@@ -0,0 +1,117 @@
+import React from "react";
+import {websocketUrl, apiUrl} from "../utils.js";
+import Websocket from "react-websocket";
+
+const OBS_CSS = [
+  "body.local-audio-body { background-color: rgba(0, 0, 0, 0); }",
+  ".overlay-hidden { display: none }"
+]
+
+export default class LocalAudio extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.audioRef = React.createRef();
+
+    this.state = {
+      playing: false,
+      stopped: true,
+      path: null,
+    };
+  }
+
+  handleData(d) {
+    let data = null;
+
+    try {
+      data = JSON.parse(d);
+    } catch(e) {
+      console.log("failed to deserialize message");
+      return;
+    }
+
+    let audio = this.audioRef.current;
+
+    if (!audio) {
+      return;
+    }
+
+    switch (data.type) {
+      case "local/current":
+        switch (data.event.type) {
+          case "play":
+            let update = { stopped: false, playing: true };
+
+            if (this.state.path !== data.event.path) {
+              audio.src = `${apiUrl()}/local/tracks/${encodeURIComponent(data.event.path)}`;
+              audio.currentTime = data.event.elapsed;
+              update.path = data.event.path;
+            } else if (Math.abs(data.event.elapsed - audio.currentTime) > 2) {
+              // We are a bit out of sync.
+              audio.currentTime = data.event.elapsed;
+            }
+
+            if (audio.paused) {
+              audio.play().catch(e => console.log("failed to play audio", e));
+            }
+
+            this.setState(update);
+            break;
+          case "pause":
+            audio.pause();
+            this.setState({ playing: false, stopped: false });
+            break;
+          case "stop":
+            audio.pause();
+            audio.removeAttribute("src");
+            this.setState({ playing: false, stopped: true, path: null });
+            break;
+          default:
+            break;
+        }
+
+        break;
+      case "local/volume":
+        audio.volume = data.volume / 100;
+        break;
+      default:
+        return;
+    }
+  }
+
+  componentWillMount() {
+    document.body.classList.add('local-audio-body');
+  }
+
+  componentWillUnmount() {
+    document.body.classList.remove('local-audio-body');
+  }
+
+  render() {
+    var noAudio = null;
+
+    if (this.state.stopped) {
+      noAudio = (
+        <div className="overlay-hidden p-4 container">
+          <h1>No Track Loaded</h1>
+
+          <p>
+            If you want to embed this into OBS, please add the following Custom CSS:
+          </p>
+
+          <pre><code>
+            {OBS_CSS.join("\n")}
+          </code></pre>
+        </div>
+      );
+    }
+
+    return (
+      <div id="local-audio">
+        <Websocket url={websocketUrl("ws/local")} onMessage={this.handleData.bind(this)} />
+        {noAudio}
+        <audio ref={this.audioRef} preload="auto" />
+      </div>
+    );
+  }
+}
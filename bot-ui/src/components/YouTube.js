@@ -23,6 +23,10 @@ export default class YouTube extends React.Component {
     this.playerElement = null;
     this.player = null;
     this.playerRef = React.createRef();
+    // Secondary player used to buffer the next video.
+    this.preloadPlayer = null;
+    this.preloadRef = React.createRef();
+    this.volume = null;
 
     this.state = {
       playing: false,
@@ -31,9 +35,31 @@ export default class YouTube extends React.Component {
       events: [],
       api: null,
       videoId: null,
+      preloadedId: null,
+      active: 0,
     };
   }
 
+  /**
+   * Switch to the preloaded player, which has the next video buffered.
+   */
+  swapPlayers() {
+    let previous = this.player;
+    this.player = this.preloadPlayer;
+    this.preloadPlayer = previous;
+
+    previous.stopVideo();
+    previous.mute();
+
+    this.player.unMute();
+
+    if (this.volume !== null) {
+      this.player.setVolume(this.volume);
+    }
+
+    return 1 - this.state.active;
+  }
+
   handleData(d) {
     let data = null;
 
@@ -57,9 +83,18 @@ export default class YouTube extends React.Component {
 
             if (this.state.videoId !== data.event.video_id) {
               let videoId = data.event.video_id;
-              this.player.loadVideoById({videoId, suggestedQuality: SUGGESTED_QUALITY});
-              this.player.seekTo(data.event.elapsed, true);
-              update.videoId = data.event.video_id;
+
+              if (this.preloadPlayer && this.state.preloadedId === videoId) {
+                update.active = this.swapPlayers();
+                this.player.seekTo(data.event.elapsed, true);
+                this.player.playVideo();
+              } else {
+                this.player.loadVideoById({videoId, suggestedQuality: SUGGESTED_QUALITY});
+                this.player.seekTo(data.event.elapsed, true);
+              }
+
+              update.videoId = videoId;
+              update.preloadedId = null;
             } else {
               // We are a bit out of sync.
               if (Math.abs(data.event.elapsed - this.player.getCurrentTime()) > 2) {
@@ -87,8 +122,29 @@ export default class YouTube extends React.Component {
             break;
         }
 
+        break;
+      case "youtube/next":
+        switch (data.event.type) {
+          case "preload":
+            let videoId = data.event.video_id;
+
+            if (!this.preloadPlayer || this.state.preloadedId === videoId || this.state.videoId === videoId) {
+              break;
+            }
+
+            // Load the video muted and pause it as soon as it starts playing,
+            // which buffers it without it being heard.
+            this.preloadPlayer.mute();
+            this.preloadPlayer.loadVideoById({videoId, suggestedQuality: SUGGESTED_QUALITY});
+            this.setState({ preloadedId: videoId });
+            break;
+          default:
+            break;
+        }
+
         break;
       case "youtube/volume":
+        this.volume = data.volume;
         this.player.setVolume(data.volume);
         break;
       case "song/progress":
@@ -119,15 +175,38 @@ export default class YouTube extends React.Component {
         },
         onPlaybackQualityChange: e => {
         },
-        onStateChange: event => {
-          if (event.data === -1) {
-            if (this.state.playing) {
-              this.player.playVideo();
-            }
-          }
-        },
+        onStateChange: event => this.onStateChange(event),
       }
     });
+
+    this.preloadPlayer = new YT.Player(this.preloadRef.current, {
+      width: 1280,
+      height: 720,
+      autoplay: false,
+      events: {
+        onStateChange: event => this.onStateChange(event),
+      }
+    });
+  }
+
+  /**
+   * Handle state changes of either player, keeping the preloaded video paused
+   * once it has started buffering.
+   */
+  onStateChange(event) {
+    if (event.target !== this.player) {
+      if (event.data === PLAYING) {
+        event.target.pauseVideo();
+      }
+
+      return;
+    }
+
+    if (event.data === UNSTARTED) {
+      if (this.state.playing) {
+        this.player.playVideo();
+      }
+    }
   }
 
   componentDidMount() {
@@ -194,7 +273,12 @@ export default class YouTube extends React.Component {
         <Loading isLoading={this.state.loading} />
 
         <div className="youtube-container" style={playerStyle}>
-          <div ref={this.playerRef} className="youtube-embedded"></div>
+          <div style={{display: this.state.active === 0 ? "block" : "none"}}>
+            <div ref={this.playerRef} className="youtube-embedded"></div>
+          </div>
+          <div style={{display: this.state.active === 1 ? "block" : "none"}}>
+            <div ref={this.preloadRef} className="youtube-embedded"></div>
+          </div>
         </div>
       </div>
     );
@@ -18,6 +18,7 @@ import Promotions from "./components/Promotions";
 import Aliases from "./components/Aliases";
 import Themes from "./components/Themes";
 import YouTube from "./components/YouTube";
+import LocalAudio from "./components/LocalAudio";
 import Chat from "./components/Chat";
 import Authorization from "./components/Authorization";
 import ConfigurationPrompt from "./components/ConfigurationPrompt";
@@ -353,6 +354,9 @@ class Layout extends React.Component {
                 <NavDropdown.Item as={Link} active={path === "/youtube"} to="/youtube" target="youtube">
                   YouTube Player
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/local"} to="/local" target="local">
+                  Local Audio Player
+                </NavDropdown.Item>
                 <NavDropdown.Item as={Link} active={path === "/chat"} to="/chat" target="chat">
                   Chat
                 </NavDropdown.Item>
@@ -405,6 +409,7 @@ function AppRouter() {
       )} />
       <Route path="/overlay/" component={Overlay} />
       <Route path="/youtube" component={YouTube} />
+      <Route path="/local" component={LocalAudio} />
       <Route path="/chat" component={Chat} />
     </Router>
   );
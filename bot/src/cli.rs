@@ -327,6 +327,25 @@ async fn try_main(
     injector.update(settings.clone()).await;
 
     let bad_words = db::Words::load(db.clone()).await?;
+
+    // NB: each dictionary is only imported once, so that words which have
+    // since been edited or deleted aren't brought back on every startup.
+    let path = settings.get::<String>("chat/bad-words/path").await?;
+    let imported = settings
+        .get::<String>("chat/bad-words/imported-path")
+        .await?;
+
+    if let Some(path) = path.filter(|path| imported.as_ref() != Some(path)) {
+        match import_bad_words(&bad_words, Path::new(&path)).await {
+            Ok(()) => {
+                settings.set("chat/bad-words/imported-path", &path).await?;
+            }
+            Err(e) => {
+                common::log_error!(e, "Failed to import bad words from: {}", path);
+            }
+        }
+    }
+
     injector.update(bad_words).await;
 
     injector
@@ -338,6 +357,26 @@ async fn try_main(
         .update(db::Promotions::load(db.clone()).await?)
         .await;
     injector.update(db::Themes::load(db.clone()).await?).await;
+    injector.update(db::Strikes::load(db.clone()).await?).await;
+    injector.update(db::Polls::load(db.clone()).await?).await;
+    injector
+        .update(db::SongVotes::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::SongBans::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::SongBumps::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::Bets::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::QueueSnapshots::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::Counters::load(db.clone()).await?)
+        .await;
 
     let message_bus = bus::Bus::new();
     injector.update(message_bus.clone()).await;
@@ -345,6 +384,8 @@ async fn try_main(
     injector.update(global_bus.clone()).await;
     let youtube_bus = bus::Bus::new();
     injector.update(youtube_bus.clone()).await;
+    let local_bus = bus::Bus::new();
+    injector.update(local_bus.clone()).await;
     let command_bus = bus::Bus::new();
     injector.update(command_bus.clone()).await;
 
@@ -367,6 +408,7 @@ async fn try_main(
         message_bus.clone(),
         global_bus.clone(),
         youtube_bus.clone(),
+        local_bus.clone(),
         command_bus.clone(),
         auth.clone(),
         latest.clone(),
@@ -413,7 +455,7 @@ async fn try_main(
     );
     let twitch_token_future = token!("twitch-bot", tags::Token::Twitch(tags::Twitch::Bot));
 
-    let twitch_pubsub_future = api::twitch::pubsub::connect(&settings, &injector);
+    let twitch_eventsub_future = api::twitch::eventsub::connect(&settings, &injector);
 
     let twitch_streamer_client_future = api::provider::twitch_and_user(
         crate::USER_AGENT,
@@ -444,6 +486,7 @@ async fn try_main(
         db.clone(),
         global_bus.clone(),
         youtube_bus.clone(),
+        local_bus.clone(),
         settings.clone(),
     );
 
@@ -468,6 +511,7 @@ async fn try_main(
     chat.module(module::time::Module);
     chat.module(module::song::Module);
     chat.module(module::command_admin::Module);
+    chat.module(module::counter::Module);
     chat.module(module::admin::Module);
     chat.module(module::alias_admin::Module);
     chat.module(module::theme_admin::Module);
@@ -483,7 +527,11 @@ async fn try_main(
     chat.module(module::speedrun::Module);
     chat.module(module::auth::Module);
     chat.module(module::poll::Module);
+    chat.module(module::prediction::Module);
+    chat.module(module::gambling::Module);
+    chat.module(module::watch_time::Module);
     chat.module(module::weather::Module);
+    chat.module(module::strikes::Module);
     chat.module(module::help::Module);
 
     let notify_after_streams = notify_after_streams(&injector, stream_state_rx, system.clone());
@@ -501,7 +549,7 @@ async fn try_main(
         nightbot_token_future,
         streamer_token_future,
         twitch_token_future,
-        twitch_pubsub_future,
+        twitch_eventsub_future,
         twitch_streamer_client_future,
         twitch_bot_client_future,
         weather_future,
@@ -535,6 +583,17 @@ async fn try_main(
     }
 }
 
+/// Import bad words from the given path.
+async fn import_bad_words(bad_words: &db::Words, path: &Path) -> Result<()> {
+    let format = db::WordsFormat::from_path(path)
+        .ok_or_else(|| anyhow!("unsupported file extension, expected .csv or .yaml"))?;
+
+    let input = tokio::fs::read(path).await?;
+    let imported = bad_words.import(format, &input).await?;
+    tracing::info!("Imported {} bad words from: {}", imported, path.display());
+    Ok(())
+}
+
 /// Notify if there are any after streams.
 ///
 /// If this is clicked, open the after-streams page.
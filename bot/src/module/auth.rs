@@ -7,6 +7,9 @@ use common::Duration;
 use chat::command;
 use chat::module;
 
+/// How often expired temporary permits are removed.
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Handler for the !auth command.
 pub(crate) struct Handler {
     auth: async_injector::Ref<auth::Auth>,
@@ -54,8 +57,13 @@ impl command::Handler for Handler {
                     ));
                 }
 
-                for role in user.roles() {
-                    let by_role = filter(auth.scopes_for_role(role).await);
+                let roles = user
+                    .roles()
+                    .into_iter()
+                    .chain(auth.custom_roles_for_user(user.login()).await);
+
+                for role in roles {
+                    let by_role = filter(auth.scopes_for_role(role.clone()).await);
 
                     if !by_role.is_empty() {
                         result.push(format!("{}: {}", role, by_role.join(", ")));
@@ -68,7 +76,7 @@ impl command::Handler for Handler {
                 ctx.check_scope(auth::Scope::AuthPermit).await?;
 
                 let duration: Duration = ctx.next_parse("<duration> <principal> <scope>")?;
-                let principal = ctx.next_parse("<duration> <principal> <scope>")?;
+                let principal = principal(ctx, auth, "<duration> <principal> <scope>").await?;
                 let scope = ctx.next_parse("<duration> <principal> <scope>")?;
 
                 if !ctx.user.has_scope(scope).await {
@@ -92,13 +100,13 @@ impl command::Handler for Handler {
                 );
 
                 auth.insert_temporary(scope, principal, expires_at, TemporaryKind::Allow)
-                    .await;
+                    .await?;
             }
             Some("deny") => {
                 ctx.check_scope(auth::Scope::AuthPermit).await?;
 
                 let duration: Duration = ctx.next_parse("<duration> <principal> <scope>")?;
-                let principal = ctx.next_parse("<duration> <principal> <scope>")?;
+                let principal = principal(ctx, auth, "<duration> <principal> <scope>").await?;
                 let scope = ctx.next_parse("<duration> <principal> <scope>")?;
 
                 if !ctx.user.has_scope(scope).await {
@@ -122,10 +130,49 @@ impl command::Handler for Handler {
                 );
 
                 auth.insert_temporary(scope, principal, expires_at, TemporaryKind::Deny)
-                    .await;
+                    .await?;
+            }
+            Some("permits") => {
+                let permits = auth
+                    .temporary()
+                    .await
+                    .into_iter()
+                    .map(|t| {
+                        format!(
+                            "{} {} to {} ({} left)",
+                            t.kind, t.scope, t.principal, t.remaining
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                ctx.respond_lines(permits, "*no temporary permits*").await;
+            }
+            Some("revoke") => {
+                ctx.check_scope(auth::Scope::AuthPermit).await?;
+
+                let principal = principal(ctx, auth, "<principal> <scope>").await?;
+                let scope = ctx.next_parse("<principal> <scope>")?;
+
+                if !ctx.user.has_scope(scope).await {
+                    chat::respond!(
+                        ctx,
+                        "Trying to revoke scope `{}` that you don't have :(",
+                        scope
+                    );
+                    return Ok(());
+                }
+
+                if !auth.delete_temporary(scope, &principal).await? {
+                    chat::respond_bail!("No temporary permit for {} on {}", principal, scope);
+                }
+
+                chat::respond!(ctx, "Revoked: {} from {}", scope, principal);
+            }
+            Some("role") => {
+                role(ctx, auth).await?;
             }
             _ => {
-                chat::respond!(ctx, "Expected: scopes, permit");
+                chat::respond!(ctx, "Expected: scopes, permit, deny, permits, revoke, role");
             }
         }
 
@@ -133,6 +180,132 @@ impl command::Handler for Handler {
     }
 }
 
+/// Handle the `!auth role` command.
+async fn role(ctx: &mut command::Context<'_>, auth: &auth::Auth) -> Result<()> {
+    match ctx.next().as_deref() {
+        Some("list") => {
+            let roles = auth
+                .custom_roles()
+                .await
+                .into_iter()
+                .map(|r| format!("{} ({})", r.role, r.members.len()))
+                .collect::<Vec<_>>();
+
+            ctx.respond_lines(roles, "*no custom roles*").await;
+        }
+        Some("members") => {
+            let role = custom_role(ctx, "<role>")?;
+
+            let Some(info) = auth.custom_role(&role).await else {
+                chat::respond_bail!("No such role: {}", role);
+            };
+
+            ctx.respond_lines(info.members, "*no members*").await;
+        }
+        Some("create") => {
+            ctx.check_scope(auth::Scope::AuthRoles).await?;
+
+            let role = custom_role(ctx, "<role> [doc]")?;
+
+            let doc = match ctx.rest().trim() {
+                "" => None,
+                doc => Some(doc.to_string()),
+            };
+
+            if !auth.insert_role(role.clone(), doc).await? {
+                chat::respond_bail!("Role {} already exists", role);
+            }
+
+            chat::respond!(ctx, "Created role {}", role);
+        }
+        Some("delete") => {
+            ctx.check_scope(auth::Scope::AuthRoles).await?;
+
+            let role = custom_role(ctx, "<role>")?;
+
+            if !auth.delete_role(&role).await? {
+                chat::respond_bail!("No such role: {}", role);
+            }
+
+            chat::respond!(ctx, "Deleted role {}", role);
+        }
+        Some("add") => {
+            ctx.check_scope(auth::Scope::AuthRoles).await?;
+
+            let role = custom_role(ctx, "<role> <user>")?;
+            let user = ctx.next_str("<role> <user>")?;
+
+            if auth.custom_role(&role).await.is_none() {
+                chat::respond_bail!("No such role: {}", role);
+            }
+
+            if !auth.insert_member(&role, &user).await? {
+                chat::respond_bail!("{} is already a member of {}", user, role);
+            }
+
+            chat::respond!(ctx, "Added {} to {}", user, role);
+        }
+        Some("remove") => {
+            ctx.check_scope(auth::Scope::AuthRoles).await?;
+
+            let role = custom_role(ctx, "<role> <user>")?;
+            let user = ctx.next_str("<role> <user>")?;
+
+            if auth.custom_role(&role).await.is_none() {
+                chat::respond_bail!("No such role: {}", role);
+            }
+
+            if !auth.delete_member(&role, &user).await? {
+                chat::respond_bail!("{} is not a member of {}", user, role);
+            }
+
+            chat::respond!(ctx, "Removed {} from {}", user, role);
+        }
+        _ => {
+            chat::respond!(ctx, "Expected: list, members, create, delete, add, remove");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the next argument as a user or a role, making sure that custom roles
+/// are defined.
+async fn principal(
+    ctx: &mut command::Context<'_>,
+    auth: &auth::Auth,
+    m: &str,
+) -> Result<auth::RoleOrUser> {
+    let principal = ctx.next_parse::<auth::RoleOrUser, _>(m)?;
+
+    if let auth::RoleOrUser::Role(role) = &principal {
+        match role {
+            auth::Role::Unknown => {
+                chat::respond_bail!("Expected a role like `@moderator` or `@regulars`");
+            }
+            auth::Role::Custom(..) if auth.custom_role(role).await.is_none() => {
+                chat::respond_bail!("No role named {}", role);
+            }
+            _ => (),
+        }
+    }
+
+    Ok(principal)
+}
+
+/// Parse the next argument as a custom role, like `@regulars`.
+fn custom_role(ctx: &mut command::Context<'_>, m: &str) -> Result<auth::Role> {
+    let role = ctx.next_parse::<auth::Role, _>(m)?;
+
+    if !matches!(role, auth::Role::Custom(..)) {
+        chat::respond_bail!(
+            "Expected a custom role like `@regulars`, which is lowercase and not a built-in role"
+        );
+    }
+
+    Ok(role)
+}
+
 pub(crate) struct Module;
 
 #[async_trait]
@@ -144,15 +317,35 @@ impl chat::Module for Module {
     async fn hook(
         &self,
         module::HookContext {
-            injector, handlers, ..
+            injector,
+            handlers,
+            tasks,
+            ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
-        handlers.insert(
-            "auth",
-            Handler {
-                auth: injector.var().await,
-            },
-        );
+        let auth = injector.var::<auth::Auth>().await;
+
+        handlers.insert("auth", Handler { auth: auth.clone() });
+
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+
+        let future = async move {
+            loop {
+                interval.tick().await;
+
+                let Some(auth) = auth.load().await else {
+                    continue;
+                };
+
+                match auth.sweep_temporary().await {
+                    Ok(0) => (),
+                    Ok(count) => tracing::trace!(count, "Swept expired temporary permits"),
+                    Err(e) => common::log_error!(e, "Failed to sweep temporary permits"),
+                }
+            }
+        };
+
+        tasks.push(Box::pin(future));
         Ok(())
     }
 }
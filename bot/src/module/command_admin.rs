@@ -2,6 +2,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use chat::command;
 use chat::module;
+use common::Duration;
 
 pub(crate) struct Handler {
     pub(crate) enabled: settings::Var<bool>,
@@ -61,10 +62,94 @@ impl command::Handler for Handler {
 
                 chat::respond!(ctx, "Edited pattern for command.");
             }
+            Some("scope") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [scope]")?;
+
+                let scope = match ctx.next() {
+                    Some(scope) => {
+                        let known = if scope.starts_with('@') {
+                            !matches!(str::parse::<auth::Role>(&scope)?, auth::Role::Unknown)
+                        } else {
+                            !matches!(str::parse::<auth::Scope>(&scope)?, auth::Scope::Unknown)
+                        };
+
+                        if !known {
+                            chat::respond_bail!("No such scope or role: `{}`", scope);
+                        }
+
+                        Some(scope)
+                    }
+                    None => None,
+                };
+
+                let edited = commands
+                    .edit_restrictions(ctx.channel(), &name, |r| r.scope = scope)
+                    .await?;
+
+                if !edited {
+                    chat::respond_bail!("No such command: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Edited scope for command.");
+            }
+            Some("cooldown") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [duration]")?;
+                let cooldown = ctx.next_parse_optional::<Duration>()?;
+
+                let edited = commands
+                    .edit_restrictions(ctx.channel(), &name, |r| r.cooldown = cooldown)
+                    .await?;
+
+                if !edited {
+                    chat::respond_bail!("No such command: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Edited cooldown for command.");
+            }
+            Some("user-cooldown") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [duration]")?;
+                let cooldown = ctx.next_parse_optional::<Duration>()?;
+
+                let edited = commands
+                    .edit_restrictions(ctx.channel(), &name, |r| r.user_cooldown = cooldown)
+                    .await?;
+
+                if !edited {
+                    chat::respond_bail!("No such command: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Edited user cooldown for command.");
+            }
+            Some("cost") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [amount]")?;
+                let cost = ctx.next_parse_optional::<i64>()?;
+
+                if cost.is_some_and(|cost| cost < 0) {
+                    chat::respond_bail!("Cost must not be negative");
+                }
+
+                let edited = commands
+                    .edit_restrictions(ctx.channel(), &name, |r| r.cost = cost)
+                    .await?;
+
+                if !edited {
+                    chat::respond_bail!("No such command: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Edited cost for command.");
+            }
             None | Some(..) => {
                 chat::respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    "Expected: show, list, edit, delete, enable, disable, group, pattern, scope, cooldown, user-cooldown, or cost."
                 );
             }
         }
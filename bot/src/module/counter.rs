@@ -0,0 +1,190 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+
+/// Handler for the `!counter` command.
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    counters: async_injector::Ref<db::Counters>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+}
+
+impl Handler {
+    /// Check that the user is allowed to modify the given counter.
+    ///
+    /// Users with `counter/edit` can modify every counter, since they could
+    /// change its scope anyway. The scope of a counter only lets other users
+    /// modify it as well, so a counter scoped to `@subscriber` can still be
+    /// modified by moderators.
+    async fn check_counter_scope(
+        &self,
+        ctx: &command::Context<'_>,
+        counter: Option<&db::Counter>,
+    ) -> Result<()> {
+        let scope = match counter.and_then(|c| c.scope.as_deref()) {
+            Some(scope) if !ctx.user.has_scope(auth::Scope::CounterEdit).await => scope,
+            _ => return ctx.check_scope(auth::Scope::CounterEdit).await,
+        };
+
+        if scope.starts_with('@') {
+            let allowed = match str::parse::<auth::Role>(scope)? {
+                auth::Role::Unknown => false,
+                role => ctx.user.has_role(&role).await,
+            };
+
+            if !allowed {
+                chat::respond_bail!("You are not allowed to modify that counter");
+            }
+
+            return Ok(());
+        }
+
+        match str::parse::<auth::Scope>(scope)? {
+            auth::Scope::Unknown => {
+                chat::respond_bail!("You are not allowed to modify that counter");
+            }
+            scope => ctx.check_scope(scope).await,
+        }
+    }
+
+    /// Notify listeners that a counter changed.
+    async fn publish(&self, counter: &db::Counter) {
+        if let Some(global_bus) = self.global_bus.load().await {
+            global_bus
+                .send(bus::Global::Counter {
+                    name: counter.name.clone(),
+                    count: counter.count,
+                })
+                .await;
+        }
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Counter)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let counters = match self.counters.load().await {
+            Some(counters) => counters,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            None | Some("list") => {
+                let counters = counters.list(ctx.channel()).await?;
+
+                if counters.is_empty() {
+                    chat::respond!(ctx, "No counters.");
+                    return Ok(());
+                }
+
+                let counters = counters
+                    .iter()
+                    .map(|c| format!("{} = {}", c.name, c.count))
+                    .collect::<Vec<_>>();
+
+                chat::respond!(ctx, "{}", counters.join(", "));
+            }
+            Some("delete") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+
+                let name = ctx.next_str("<name>")?;
+
+                if !counters.delete(ctx.channel(), &name).await? {
+                    chat::respond_bail!("No such counter: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Deleted counter `{}`.", name);
+            }
+            Some("scope") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+
+                let name = ctx.next_str("<name> [scope]")?;
+
+                let scope = match ctx.next() {
+                    Some(scope) => {
+                        let known = if scope.starts_with('@') {
+                            !matches!(str::parse::<auth::Role>(&scope)?, auth::Role::Unknown)
+                        } else {
+                            !matches!(str::parse::<auth::Scope>(&scope)?, auth::Scope::Unknown)
+                        };
+
+                        if !known {
+                            chat::respond_bail!("No such scope or role: `{}`", scope);
+                        }
+
+                        Some(scope)
+                    }
+                    None => None,
+                };
+
+                if !counters.edit_scope(ctx.channel(), &name, scope).await? {
+                    chat::respond_bail!("No such counter: `{}`", name);
+                }
+
+                chat::respond!(ctx, "Edited scope for counter.");
+            }
+            Some(name) => {
+                let name = name.to_string();
+                let counter = counters.get(ctx.channel(), &name).await?;
+
+                let Some(op) = ctx.next_parse_optional::<db::CounterOp>()? else {
+                    match counter {
+                        Some(counter) => {
+                            chat::respond!(ctx, "{} = {}", counter.name, counter.count)
+                        }
+                        None => chat::respond!(ctx, "No such counter: `{}`", name),
+                    }
+
+                    return Ok(());
+                };
+
+                self.check_counter_scope(ctx, counter.as_ref()).await?;
+
+                let counter = counters.modify(ctx.channel(), &name, op).await?;
+                self.publish(&counter).await;
+                chat::respond!(ctx, "{} = {}", counter.name, counter.count);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "counter"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "counter",
+            Handler {
+                enabled: settings.var("counter/enabled", true).await?,
+                counters: injector.var().await,
+                global_bus: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,870 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{self, Instant};
+
+use anyhow::Result;
+use async_injector::Injector;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chrono::{NaiveDateTime, Utc};
+use common::{display, Cooldown, Duration, OwnedChannel};
+use currency::Currency;
+use tokio::sync::Mutex;
+
+/// An amount wagered, which might be relative to the balance of the user.
+#[derive(Debug, Clone, Copy)]
+enum Wager {
+    /// A fixed amount.
+    Amount(i64),
+    /// The whole balance of the user.
+    All,
+    /// A percentage of the balance of the user.
+    Percentage(u32),
+}
+
+impl Wager {
+    /// Resolve the wager against the current balance of the user.
+    fn resolve(self, balance: i64) -> i64 {
+        match self {
+            Wager::Amount(amount) => amount,
+            Wager::All => balance,
+            Wager::Percentage(p) => {
+                let amount = i128::from(balance) * i128::from(p) / 100;
+                i64::try_from(amount).unwrap_or(i64::MAX)
+            }
+        }
+    }
+}
+
+impl FromStr for Wager {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(Wager::All);
+        }
+
+        if let Some(p) = s.strip_suffix('%') {
+            let p = str::parse::<u32>(p)?;
+
+            if p > 100 {
+                anyhow::bail!("percentage must be at most 100%");
+            }
+
+            return Ok(Wager::Percentage(p));
+        }
+
+        Ok(Wager::Amount(str::parse(s)?))
+    }
+}
+
+/// Limits and per-user cooldowns which apply to a single game.
+struct Stakes {
+    min_bet: settings::Var<i64>,
+    max_bet: settings::Var<Option<i64>>,
+    cooldown: settings::Var<Cooldown>,
+    cooldowns: Mutex<HashMap<String, Cooldown>>,
+}
+
+impl Stakes {
+    /// Load stakes for the game with the given settings prefix.
+    async fn load(settings: &settings::Settings<::auth::Scope>, game: &str) -> Result<Self> {
+        Ok(Self {
+            min_bet: settings.var(&format!("{}/min-bet", game), 1).await?,
+            max_bet: settings.optional(&format!("{}/max-bet", game)).await?,
+            cooldown: settings
+                .var(
+                    &format!("{}/cooldown", game),
+                    Cooldown::from_duration(Duration::seconds(30)),
+                )
+                .await?,
+            cooldowns: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Check that the given amount is within limits and covered by the
+    /// balance of the user.
+    async fn check_amount(&self, currency: &Currency, amount: i64, balance: i64) -> Result<()> {
+        let min_bet = self.min_bet.load().await;
+
+        if amount <= 0 || amount < min_bet {
+            chat::respond_bail!(
+                "You need to bet at least {} {}",
+                i64::max(min_bet, 1),
+                currency.name
+            );
+        }
+
+        if let Some(max_bet) = self.max_bet.load().await {
+            if amount > max_bet {
+                chat::respond_bail!("You can bet at most {} {}", max_bet, currency.name);
+            }
+        }
+
+        if amount > balance {
+            chat::respond_bail!(
+                "You don't have enough {}, your balance is {}",
+                currency.name,
+                balance
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check and poke the cooldown of the given user.
+    async fn check_cooldown(&self, user: &str) -> Result<()> {
+        let cooldown = self.cooldown.load().await;
+        let mut cooldowns = self.cooldowns.lock().await;
+
+        let user_cooldown = cooldowns
+            .entry(user.to_string())
+            .or_insert_with(|| cooldown.clone());
+
+        // NB: pick up changes to the cooldown setting.
+        user_cooldown.cooldown = cooldown.cooldown;
+
+        let now = Instant::now();
+
+        if let Some(remaining) = user_cooldown.check(now) {
+            chat::respond_bail!(
+                "Cooldown in effect, please wait at least {}!",
+                display::compact_duration(remaining)
+            );
+        }
+
+        user_cooldown.poke(now);
+        Ok(())
+    }
+}
+
+/// Get the configured currency, or bail with a response.
+async fn load_currency(currency: &async_injector::Ref<Currency>) -> Result<Currency> {
+    match currency.load().await {
+        Some(currency) => Ok(currency),
+        None => chat::respond_bail!("No currency configured for stream, sorry :("),
+    }
+}
+
+/// Get the current balance of a user, defaulting to zero.
+async fn balance_of(ctx: &command::Context<'_>, currency: &Currency, user: &str) -> Result<i64> {
+    let balance = currency.balance_of(ctx.channel(), user).await?;
+    Ok(balance.map(|b| b.balance).unwrap_or_default())
+}
+
+/// Take the wager from the balance of the user, or bail if they can no longer
+/// afford it.
+///
+/// This is a single conditional update, so the balance can't change between
+/// checking it and taking the wager.
+async fn debit(
+    ctx: &command::Context<'_>,
+    currency: &Currency,
+    user: &str,
+    amount: i64,
+) -> Result<()> {
+    if !currency.balance_debit(ctx.channel(), user, amount).await? {
+        chat::respond_bail!("You don't have enough {}", currency.name);
+    }
+
+    Ok(())
+}
+
+/// Get the ledger of outstanding bets, or bail with a response.
+async fn load_bets(bets: &async_injector::Ref<db::Bets>) -> Result<db::Bets> {
+    match bets.load().await {
+        Some(bets) => Ok(bets),
+        None => chat::respond_bail!("Betting is not available right now, sorry :("),
+    }
+}
+
+/// Refund bets which were placed before the betting pools were last set up.
+///
+/// Pools only live in memory, so any bets still in the ledger at this point
+/// belong to a pool which was lost when the bot restarted.
+async fn refund_lost(injector: Injector, started_at: NaiveDateTime) -> Result<()> {
+    let (mut currency_stream, mut currency) = injector.stream::<Currency>().await;
+    let (mut bets_stream, mut bets) = injector.stream::<db::Bets>().await;
+
+    let (currency, bets) = loop {
+        if let (Some(currency), Some(bets)) = (&currency, &bets) {
+            break (currency, bets);
+        }
+
+        tokio::select! {
+            update = currency_stream.recv() => currency = update,
+            update = bets_stream.recv() => bets = update,
+        }
+    };
+
+    let mut refunded = Vec::new();
+
+    for bet in bets.list().await? {
+        if bet.placed_at >= started_at {
+            continue;
+        }
+
+        if let Err(e) = currency
+            .balance_add(&bet.channel, &bet.user, bet.amount)
+            .await
+        {
+            common::log_error!(e, "Failed to refund bet of {} to {}", bet.amount, bet.user);
+            continue;
+        }
+
+        refunded.push(bet.id);
+    }
+
+    if !refunded.is_empty() {
+        tracing::info!(
+            "Refunded {} bet(s) from a lost betting pool",
+            refunded.len()
+        );
+        bets.delete(refunded).await?;
+    }
+
+    std::future::pending().await
+}
+
+/// Roll the dice, returning `true` with the given percentage chance.
+fn roll(chance: u32) -> bool {
+    rand::random::<u32>() % 100 < chance
+}
+
+/// Handler for the `!gamble` command.
+pub(crate) struct Gamble {
+    enabled: settings::Var<bool>,
+    win_chance: settings::Var<u32>,
+    stakes: Stakes,
+    currency: async_injector::Ref<Currency>,
+}
+
+#[async_trait]
+impl command::Handler for Gamble {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Gamble)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = load_currency(&self.currency).await?;
+
+        let user = match ctx.user.real() {
+            Some(user) => user.login().to_string(),
+            None => chat::respond_bail!("Only real users can gamble"),
+        };
+
+        let wager = ctx.next_parse::<Wager, _>("<amount|all|%>")?;
+        let balance = balance_of(ctx, &currency, &user).await?;
+        let amount = wager.resolve(balance);
+
+        self.stakes.check_amount(&currency, amount, balance).await?;
+        self.stakes.check_cooldown(&user).await?;
+        debit(ctx, &currency, &user, amount).await?;
+
+        if roll(self.win_chance.load().await) {
+            currency
+                .balance_add(ctx.channel(), &user, amount.saturating_mul(2))
+                .await?;
+
+            chat::respond!(
+                ctx,
+                "You won {amount} {currency}! Your balance is now {balance}",
+                amount = amount,
+                currency = currency.name,
+                balance = balance_of(ctx, &currency, &user).await?,
+            );
+        } else {
+            chat::respond!(
+                ctx,
+                "You lost {amount} {currency} :( Your balance is now {balance}",
+                amount = amount,
+                currency = currency.name,
+                balance = balance_of(ctx, &currency, &user).await?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// A betting pool which viewers can wager on.
+struct Pool {
+    title: String,
+    outcomes: Vec<String>,
+    /// If the pool is still accepting wagers.
+    open: bool,
+    /// Wagers by user, as the index of an outcome and the amount wagered.
+    wagers: HashMap<String, (usize, i64)>,
+}
+
+impl Pool {
+    /// Find the index of an outcome, either by name or by its 1-based position.
+    fn outcome(&self, outcome: &str) -> Option<usize> {
+        match str::parse::<usize>(outcome) {
+            Ok(n) => n.checked_sub(1).filter(|n| *n < self.outcomes.len()),
+            Err(..) => self
+                .outcomes
+                .iter()
+                .position(|o| o.eq_ignore_ascii_case(outcome)),
+        }
+    }
+
+    /// Total amount wagered on the given outcome, or on all outcomes if `None`.
+    fn total(&self, outcome: Option<usize>) -> i64 {
+        self.wagers
+            .values()
+            .filter(|(o, _)| outcome.map(|outcome| *o == outcome).unwrap_or(true))
+            .map(|(_, amount)| *amount)
+            .sum()
+    }
+
+    /// Calculate payouts, where each winner gets a share of the whole pool
+    /// proportional to what they wagered.
+    ///
+    /// If nobody wagered on the winning outcome, everyone is refunded.
+    fn payouts(&self, winner: usize) -> Vec<(String, i64)> {
+        let total = i128::from(self.total(None));
+        let winning = i128::from(self.total(Some(winner)));
+
+        if winning == 0 {
+            return self.refunds();
+        }
+
+        self.wagers
+            .iter()
+            .filter(|(_, (o, _))| *o == winner)
+            .map(|(user, (_, amount))| {
+                let payout = i128::from(*amount) * total / winning;
+                (user.clone(), i64::try_from(payout).unwrap_or(i64::MAX))
+            })
+            .collect()
+    }
+
+    /// Refund everyone what they wagered.
+    fn refunds(&self) -> Vec<(String, i64)> {
+        self.wagers
+            .iter()
+            .map(|(user, (_, amount))| (user.clone(), *amount))
+            .collect()
+    }
+}
+
+impl fmt::Display for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.title)?;
+
+        for (n, outcome) in self.outcomes.iter().enumerate() {
+            let sep = if n == 0 { " " } else { ", " };
+            write!(f, "{}{}. {} ({})", sep, n + 1, outcome, self.total(Some(n)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the `!bet` command.
+pub(crate) struct Bet {
+    enabled: settings::Var<bool>,
+    stakes: Stakes,
+    currency: async_injector::Ref<Currency>,
+    bets: async_injector::Ref<db::Bets>,
+    pool: Mutex<Option<Pool>>,
+}
+
+impl Bet {
+    /// Pay out the given amounts through the currency backend and clear the
+    /// ledger of outstanding bets.
+    async fn pay(
+        &self,
+        ctx: &command::Context<'_>,
+        currency: &Currency,
+        bets: &db::Bets,
+        payouts: Vec<(String, i64)>,
+    ) -> Result<()> {
+        for (user, amount) in payouts {
+            if let Err(e) = currency.balance_add(ctx.channel(), &user, amount).await {
+                common::log_error!(e, "Failed to pay out {} to {}", amount, user);
+            }
+        }
+
+        bets.clear(ctx.channel()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl command::Handler for Bet {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Bet)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = load_currency(&self.currency).await?;
+        let bets = load_bets(&self.bets).await?;
+        let mut pool = self.pool.lock().await;
+
+        match ctx.next().as_deref() {
+            Some("open") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                if let Some(pool) = pool.as_ref() {
+                    chat::respond_bail!("Betting on `{}` is already running", pool.title);
+                }
+
+                let title = ctx.next_str("<title> <outcome> <outcome...>")?;
+                let outcomes = ctx.by_ref().collect::<Vec<_>>();
+
+                if outcomes.len() < 2 {
+                    chat::respond_bail!("Expected at least two outcomes");
+                }
+
+                let new = Pool {
+                    title,
+                    outcomes,
+                    open: true,
+                    wagers: HashMap::new(),
+                };
+
+                chat::respond!(
+                    ctx,
+                    "Betting is open! {}. Use `!bet <outcome> <amount>` to place your bet",
+                    new
+                );
+
+                *pool = Some(new);
+            }
+            Some("close") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let Some(pool) = pool.as_mut().filter(|p| p.open) else {
+                    chat::respond_bail!("No open betting pool");
+                };
+
+                pool.open = false;
+                chat::respond!(ctx, "Betting is closed! {}", pool);
+            }
+            Some("resolve") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let outcome = ctx.next_str("<outcome>")?;
+
+                let Some(current) = pool.as_ref() else {
+                    chat::respond_bail!("No running betting pool");
+                };
+
+                let winner = current
+                    .outcome(&outcome)
+                    .ok_or(chat::respond_err!("No outcome matching `{}`", outcome))?;
+
+                let current = pool.take().expect("pool is running");
+                let payouts = current.payouts(winner);
+                let winners = payouts.len();
+
+                self.pay(ctx, &currency, &bets, payouts).await?;
+
+                chat::respond!(
+                    ctx,
+                    "`{}` won! {} {} has been paid out to {} winner(s)",
+                    current.outcomes[winner],
+                    current.total(None),
+                    currency.name,
+                    winners,
+                );
+            }
+            Some("cancel") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let Some(current) = pool.take() else {
+                    chat::respond_bail!("No running betting pool");
+                };
+
+                self.pay(ctx, &currency, &bets, current.refunds()).await?;
+                chat::respond!(
+                    ctx,
+                    "Canceled betting on `{}`, all bets have been refunded",
+                    current.title
+                );
+            }
+            Some("status") | None => match pool.as_ref() {
+                Some(pool) => chat::respond!(ctx, "{}", pool),
+                None => chat::respond!(ctx, "No running betting pool"),
+            },
+            Some(outcome) => {
+                let user = match ctx.user.real() {
+                    Some(user) => user.login().to_string(),
+                    None => chat::respond_bail!("Only real users can bet"),
+                };
+
+                let Some(pool) = pool.as_mut().filter(|p| p.open) else {
+                    chat::respond_bail!("No open betting pool");
+                };
+
+                let index = pool
+                    .outcome(outcome)
+                    .ok_or(chat::respond_err!("No outcome matching `{}`", outcome))?;
+
+                if pool.wagers.contains_key(&user) {
+                    chat::respond_bail!("You have already placed a bet");
+                }
+
+                let wager = ctx.next_parse::<Wager, _>("<amount|all|%>")?;
+                let balance = balance_of(ctx, &currency, &user).await?;
+                let amount = wager.resolve(balance);
+
+                self.stakes.check_amount(&currency, amount, balance).await?;
+                self.stakes.check_cooldown(&user).await?;
+
+                debit(ctx, &currency, &user, amount).await?;
+
+                if let Err(e) = bets.insert(ctx.channel(), &user, amount).await {
+                    currency.balance_add(ctx.channel(), &user, amount).await?;
+                    return Err(e);
+                }
+
+                pool.wagers.insert(user, (index, amount));
+
+                chat::respond!(
+                    ctx,
+                    "You bet {} {} on `{}`",
+                    amount,
+                    currency.name,
+                    pool.outcomes[index]
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How often expired duels are looked for.
+const EXPIRE_INTERVAL: time::Duration = time::Duration::from_secs(5);
+
+/// A duel waiting to be accepted.
+///
+/// The stake of the challenger has already been taken from their balance.
+struct Challenge {
+    channel: OwnedChannel,
+    challenger: String,
+    amount: i64,
+    expires_at: Instant,
+}
+
+/// Remove expired challenges and refund the stakes of their challengers.
+async fn expire_challenges(
+    challenges: &mut HashMap<String, Challenge>,
+    currency: &Currency,
+    now: Instant,
+) {
+    let expired = challenges
+        .iter()
+        .filter(|(_, c)| c.expires_at <= now)
+        .map(|(target, _)| target.clone())
+        .collect::<Vec<_>>();
+
+    for target in expired {
+        let Some(c) = challenges.remove(&target) else {
+            continue;
+        };
+
+        if let Err(e) = currency
+            .balance_add(&c.channel, &c.challenger, c.amount)
+            .await
+        {
+            common::log_error!(
+                e,
+                "Failed to refund duel of {} to {}",
+                c.amount,
+                c.challenger
+            );
+        }
+    }
+}
+
+/// Expire challenges in the background, so that stakes are refunded even if
+/// nobody uses `!duel` again.
+async fn expire_duels(
+    challenges: Arc<Mutex<HashMap<String, Challenge>>>,
+    currency: async_injector::Ref<Currency>,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(EXPIRE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let Some(currency) = currency.load().await else {
+            continue;
+        };
+
+        let mut challenges = challenges.lock().await;
+        expire_challenges(&mut challenges, &currency, Instant::now()).await;
+    }
+}
+
+/// Handler for the `!duel` command.
+pub(crate) struct Duel {
+    enabled: settings::Var<bool>,
+    timeout: settings::Var<Duration>,
+    stakes: Stakes,
+    currency: async_injector::Ref<Currency>,
+    /// Pending challenges, by the login of the challenged user.
+    challenges: Arc<Mutex<HashMap<String, Challenge>>>,
+}
+
+#[async_trait]
+impl command::Handler for Duel {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Duel)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = load_currency(&self.currency).await?;
+
+        let user = match ctx.user.real() {
+            Some(user) => user.login().to_string(),
+            None => chat::respond_bail!("Only real users can duel"),
+        };
+
+        let mut challenges = self.challenges.lock().await;
+        let now = Instant::now();
+        expire_challenges(&mut challenges, &currency, now).await;
+
+        match ctx.next().as_deref() {
+            Some("accept") => {
+                let Some(challenge) = challenges.remove(&user) else {
+                    chat::respond_bail!("You have no pending duels");
+                };
+
+                if !currency
+                    .balance_debit(ctx.channel(), &user, challenge.amount)
+                    .await?
+                {
+                    let amount = challenge.amount;
+                    challenges.insert(user, challenge);
+
+                    chat::respond_bail!(
+                        "You don't have enough {} to accept a duel for {}",
+                        currency.name,
+                        amount
+                    );
+                }
+
+                let (winner, loser) = if roll(50) {
+                    (&user, &challenge.challenger)
+                } else {
+                    (&challenge.challenger, &user)
+                };
+
+                currency
+                    .balance_add(ctx.channel(), winner, challenge.amount.saturating_mul(2))
+                    .await?;
+
+                chat::respond!(
+                    ctx,
+                    "{winner} won the duel against {loser} and takes {amount} {currency}!",
+                    winner = winner,
+                    loser = loser,
+                    amount = challenge.amount,
+                    currency = currency.name,
+                );
+            }
+            Some("decline") => {
+                let Some(challenge) = challenges.remove(&user) else {
+                    chat::respond_bail!("You have no pending duels");
+                };
+
+                currency
+                    .balance_add(ctx.channel(), &challenge.challenger, challenge.amount)
+                    .await?;
+
+                chat::respond!(ctx, "Declined duel from {}", challenge.challenger);
+            }
+            Some(target) => {
+                let target = target.trim_start_matches('@').to_lowercase();
+
+                if target == user {
+                    chat::respond_bail!("You can't duel yourself");
+                }
+
+                if challenges.contains_key(&target) {
+                    chat::respond_bail!("{} already has a pending duel", target);
+                }
+
+                let wager = ctx.next_parse::<Wager, _>("<user> <amount|all|%>")?;
+                let balance = balance_of(ctx, &currency, &user).await?;
+                let amount = wager.resolve(balance);
+
+                self.stakes.check_amount(&currency, amount, balance).await?;
+
+                if balance_of(ctx, &currency, &target).await? < amount {
+                    chat::respond_bail!(
+                        "{} can't cover a bet of {} {}",
+                        target,
+                        amount,
+                        currency.name
+                    );
+                }
+
+                self.stakes.check_cooldown(&user).await?;
+                debit(ctx, &currency, &user, amount).await?;
+
+                let timeout = self.timeout.load().await;
+
+                challenges.insert(
+                    target.clone(),
+                    Challenge {
+                        channel: ctx.channel().to_owned(),
+                        challenger: user.clone(),
+                        amount,
+                        expires_at: now + timeout.as_std(),
+                    },
+                );
+
+                chat::respond!(
+                    ctx,
+                    "{target}, {user} challenged you to a duel for {amount} {currency}! Type `!duel accept` within {timeout} to accept",
+                    target = target,
+                    user = user,
+                    amount = amount,
+                    currency = currency.name,
+                    timeout = timeout,
+                );
+            }
+            None => {
+                ctx.respond("Expected: <user> <amount>, accept, or decline.")
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "gambling"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            tasks,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "gamble",
+            Gamble {
+                enabled: settings.var("gamble/enabled", false).await?,
+                win_chance: settings.var("gamble/win%", 45).await?,
+                stakes: Stakes::load(settings, "gamble").await?,
+                currency: injector.var().await,
+            },
+        );
+
+        handlers.insert(
+            "bet",
+            Bet {
+                enabled: settings.var("bet/enabled", false).await?,
+                stakes: Stakes::load(settings, "bet").await?,
+                currency: injector.var().await,
+                bets: injector.var().await,
+                pool: Mutex::new(None),
+            },
+        );
+
+        tasks.push(Box::pin(refund_lost(
+            injector.clone(),
+            Utc::now().naive_utc(),
+        )));
+
+        let challenges = Arc::new(Mutex::new(HashMap::new()));
+        let currency = injector.var().await;
+
+        tasks.push(Box::pin(expire_duels(challenges.clone(), currency.clone())));
+
+        handlers.insert(
+            "duel",
+            Duel {
+                enabled: settings.var("duel/enabled", false).await?,
+                timeout: settings.var("duel/timeout", Duration::seconds(60)).await?,
+                stakes: Stakes::load(settings, "duel").await?,
+                currency,
+                challenges,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pool, Wager};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_wager() {
+        assert_eq!(50, str::parse::<Wager>("50").unwrap().resolve(100));
+        assert_eq!(100, str::parse::<Wager>("all").unwrap().resolve(100));
+        assert_eq!(25, str::parse::<Wager>("25%").unwrap().resolve(100));
+        assert_eq!(
+            i64::MAX / 2,
+            str::parse::<Wager>("50%").unwrap().resolve(i64::MAX)
+        );
+        assert!(str::parse::<Wager>("101%").is_err());
+        assert!(str::parse::<Wager>("lots").is_err());
+    }
+
+    #[test]
+    fn test_payouts() {
+        let mut wagers = HashMap::new();
+        wagers.insert(String::from("a"), (0, 100));
+        wagers.insert(String::from("b"), (0, 300));
+        wagers.insert(String::from("c"), (1, 400));
+
+        let pool = Pool {
+            title: String::from("test"),
+            outcomes: vec![String::from("yes"), String::from("no")],
+            open: false,
+            wagers,
+        };
+
+        let mut payouts = pool.payouts(0);
+        payouts.sort();
+        assert_eq!(
+            vec![(String::from("a"), 200), (String::from("b"), 600)],
+            payouts
+        );
+
+        // nobody bet on the winner, so everyone is refunded.
+        let pool = Pool {
+            wagers: [(String::from("c"), (1, 400))].into_iter().collect(),
+            ..pool
+        };
+
+        assert_eq!(vec![(String::from("c"), 400)], pool.payouts(0));
+    }
+}
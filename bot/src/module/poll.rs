@@ -1,15 +1,211 @@
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::Arc;
+
 use anyhow::Result;
+use api::twitch::{eventsub, model};
+use async_fuse::Fuse;
+use async_injector::Injector;
 use async_trait::async_trait;
 use chat::command;
 use chat::module;
 use chrono::{DateTime, Utc};
-use std::collections::{HashMap, HashSet};
+use common::stream::StreamExt;
+use common::{Channel, Duration, OwnedChannel};
 use tokio::sync::Mutex;
 
+/// How often to weigh and publish new votes, and to check for polls which
+/// should be automatically closed.
+const UPDATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The number of leading options carried over into a runoff.
+const RUNOFF_OPTIONS: usize = 2;
+
+/// How long native polls run for unless a duration is configured.
+const NATIVE_DURATION: u64 = 60;
+/// The shortest and longest durations supported by native polls.
+const NATIVE_DURATION_RANGE: (u64, u64) = (15, 1800);
+/// The fewest and most choices supported by native polls.
+const NATIVE_CHOICES_RANGE: (usize, usize) = (2, 5);
+/// The longest title supported by native polls, in characters.
+const NATIVE_MAX_TITLE: usize = 60;
+/// The longest choice supported by native polls, in characters.
+const NATIVE_MAX_CHOICE: usize = 25;
+
+type Polls = Arc<Mutex<HashMap<i32, RunningPoll>>>;
+
 /// Handler for the !poll command.
 pub(crate) struct Poll {
     enabled: settings::Var<bool>,
-    polls: Mutex<HashMap<command::HookId, ActivePoll>>,
+    duration: settings::Var<Option<Duration>>,
+    voting: Voting,
+    polls: Polls,
+    db: async_injector::Ref<db::Polls>,
+    streamer: api::TwitchAndUser,
+}
+
+impl Poll {
+    /// Start a new poll with the given options.
+    async fn start(
+        &self,
+        ctx: &mut command::Context<'_>,
+        db: &db::Polls,
+        question: &str,
+        round: i32,
+        parent_id: Option<i32>,
+        options: Vec<(String, Option<String>)>,
+    ) -> Result<()> {
+        let row = db
+            .insert(ctx.channel(), question, round, parent_id, &options)
+            .await?;
+
+        let duration = self.duration.load().await;
+        let created_at = Utc::now();
+
+        let poll = ActivePoll {
+            id: row.id,
+            channel: ctx.channel().to_owned(),
+            question: question.to_string(),
+            round,
+            options,
+            voting: self.voting.clone(),
+            inner: settings::Var::new(Inner::default()),
+        };
+
+        let hook_id = ctx.insert_hook(poll.clone()).await;
+        poll.publish(false).await;
+
+        self.polls.lock().await.insert(
+            poll.id,
+            RunningPoll {
+                poll,
+                hook: ctx.hook_handle(hook_id),
+                created_at,
+                closes_at: duration
+                    .as_ref()
+                    .map(|duration| created_at + duration.as_chrono()),
+            },
+        );
+
+        match duration {
+            Some(duration) => chat::respond!(
+                ctx,
+                "Started poll `{}` (id: {}), closing in {}",
+                question,
+                row.id,
+                duration
+            ),
+            None => chat::respond!(ctx, "Started poll `{}` (id: {})", question, row.id),
+        }
+
+        Ok(())
+    }
+
+    /// Start a native Twitch poll with the given options.
+    async fn start_native(
+        &self,
+        ctx: &mut command::Context<'_>,
+        question: &str,
+        options: Vec<(String, Option<String>)>,
+    ) -> Result<()> {
+        check_native(question, &options)?;
+
+        let (min, max) = NATIVE_DURATION_RANGE;
+
+        let duration = match self.duration.load().await {
+            Some(duration) => duration.num_seconds().clamp(min, max),
+            None => NATIVE_DURATION,
+        };
+
+        let choices = options
+            .iter()
+            .map(|(key, description)| model::PollChoiceTitle {
+                title: description.as_deref().unwrap_or(key),
+            })
+            .collect();
+
+        let poll = self
+            .streamer
+            .client
+            .create_poll(model::CreatePollRequest {
+                broadcaster_id: &self.streamer.user.id,
+                title: question,
+                choices,
+                duration: duration as u32,
+            })
+            .await?
+            .ok_or(chat::respond_err!("Twitch did not create the poll"))?;
+
+        chat::respond!(
+            ctx,
+            "Started Twitch poll `{}`, closing in {}",
+            poll.title,
+            Duration::seconds(duration)
+        );
+
+        Ok(())
+    }
+
+    /// Terminate the currently running native Twitch poll.
+    async fn close_native(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        let poll = {
+            let mut polls = pin!(self.streamer.client.polls(&self.streamer.user.id));
+            polls.next().await.transpose()?
+        };
+
+        let poll = match poll {
+            Some(poll) if poll.status == model::PollStatus::Active => poll,
+            _ => chat::respond_bail!("No running Twitch poll"),
+        };
+
+        self.streamer
+            .client
+            .end_poll(
+                &self.streamer.user.id,
+                &poll.id,
+                model::PollStatus::Terminated,
+            )
+            .await?;
+
+        chat::respond!(ctx, "Closed Twitch poll `{}`", poll.title);
+        Ok(())
+    }
+}
+
+/// Check that a poll fits within the limits of native Twitch polls, so that
+/// we can give a clear error instead of the one from the API.
+fn check_native(question: &str, options: &[(String, Option<String>)]) -> Result<()> {
+    let (min, max) = NATIVE_CHOICES_RANGE;
+
+    if !(min..=max).contains(&options.len()) {
+        chat::respond_bail!(
+            "Twitch polls need between {} and {} options, but got {}",
+            min,
+            max,
+            options.len()
+        );
+    }
+
+    if question.chars().count() > NATIVE_MAX_TITLE {
+        chat::respond_bail!(
+            "Twitch poll questions can be at most {} characters long",
+            NATIVE_MAX_TITLE
+        );
+    }
+
+    for (key, description) in options {
+        let choice = description.as_deref().unwrap_or(key);
+
+        if choice.chars().count() > NATIVE_MAX_CHOICE {
+            chat::respond_bail!(
+                "Twitch poll options can be at most {} characters long, but `{}` is longer",
+                NATIVE_MAX_CHOICE,
+                choice
+            );
+        }
+    }
+
+    Ok(())
 }
 
 #[async_trait]
@@ -23,11 +219,21 @@ impl command::Handler for Poll {
             return Ok(());
         }
 
+        let db = match self.db.load().await {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+
         match ctx.next().as_deref() {
             Some("run") => {
-                let question = ctx.next_str("<question> <options...>")?;
+                let mut question = ctx.next_str("[--native] <question> <options...>")?;
+                let native = question == "--native";
 
-                let mut options = HashMap::new();
+                if native {
+                    question = ctx.next_str("<question> <options...>")?;
+                }
+
+                let mut options = Vec::<(String, Option<String>)>::new();
 
                 for option in ctx.by_ref() {
                     let (key, description) = match option.find('=') {
@@ -38,133 +244,382 @@ impl command::Handler for Poll {
                         None => (option, None),
                     };
 
-                    options.insert(key.to_lowercase(), description);
+                    let key = key.to_lowercase();
+
+                    if options.iter().any(|(o, _)| *o == key) {
+                        chat::respond_bail!("Option `{}` specified more than once", key);
+                    }
+
+                    options.push((key, description));
                 }
 
-                let poll = ActivePoll {
-                    question: question.clone(),
-                    created_at: Utc::now(),
-                    options,
-                    inner: settings::Var::new(Inner {
-                        voted: Default::default(),
-                        votes: Default::default(),
-                    }),
+                if native {
+                    self.start_native(ctx, &question, options).await?;
+                } else {
+                    self.start(ctx, &db, &question, 1, None, options).await?;
+                }
+            }
+            Some("runoff") => {
+                let parent = match ctx.next() {
+                    Some(id) => {
+                        let id = str::parse::<i32>(&id)
+                            .map_err(|_| chat::respond_err!("Bad id `{}`", id))?;
+
+                        db.get(ctx.channel(), id)
+                            .await?
+                            .ok_or(chat::respond_err!("No poll with id `{}`!", id))?
+                    }
+                    None => db
+                        .last_closed(ctx.channel())
+                        .await?
+                        .ok_or(chat::respond_err!("No closed polls"))?,
                 };
 
-                let hook_id = ctx.insert_hook(poll.clone()).await;
-                self.polls.lock().await.insert(hook_id, poll);
-                ctx.respond(format!("Started poll `{}` (id: {})", question, hook_id))
-                    .await;
+                if parent.poll.closed_at.is_none() {
+                    chat::respond_bail!("Poll `{}` has not been closed yet", parent.poll.id);
+                }
+
+                // NB: options are stored in order of votes.
+                let options = parent
+                    .options
+                    .into_iter()
+                    .take(RUNOFF_OPTIONS)
+                    .map(|o| (o.key, o.description))
+                    .collect();
+
+                self.start(
+                    ctx,
+                    &db,
+                    &parent.poll.question,
+                    parent.poll.round + 1,
+                    Some(parent.poll.id),
+                    options,
+                )
+                .await?;
             }
             Some("close") => {
-                let mut polls = self.polls.lock().await;
-
-                let id = match ctx.next() {
-                    Some(id) => str::parse::<command::HookId>(&id)
-                        .map_err(|_| chat::respond_err!("Bad id `{}`", id))?,
-                    None => {
-                        *polls
-                            .iter()
-                            .max_by_key(|e| e.1.created_at)
-                            .ok_or(chat::respond_err!("No running polls"))?
-                            .0
-                    }
+                if ctx.rest().trim() == "--native" {
+                    self.close_native(ctx).await?;
+                    return Ok(());
+                }
+
+                let running = {
+                    let mut polls = self.polls.lock().await;
+
+                    let id = match ctx.next() {
+                        Some(id) => str::parse::<i32>(&id)
+                            .map_err(|_| chat::respond_err!("Bad id `{}`", id))?,
+                        None => {
+                            *polls
+                                .iter()
+                                .max_by_key(|e| e.1.created_at)
+                                .ok_or(chat::respond_err!("No running polls"))?
+                                .0
+                        }
+                    };
+
+                    polls
+                        .remove(&id)
+                        .ok_or(chat::respond_err!("No poll with id `{}`!", id))?
                 };
 
-                let poll = polls
-                    .remove(&id)
-                    .ok_or(chat::respond_err!("No poll with id `{}`!", id))?;
+                let results = running.close(&db).await?;
+                ctx.respond(results).await;
+            }
+            _ => {
+                ctx.respond("Expected: run, runoff, close.").await;
+            }
+        }
 
-                ctx.remove_hook(id).await;
-                let results = poll.close().await;
+        Ok(())
+    }
+}
 
-                let total = results.iter().map(|(_, c)| c).sum::<u32>();
+/// Settings and dependencies which determine how votes are counted.
+#[derive(Clone)]
+struct Voting {
+    allow_change: settings::Var<bool>,
+    currency_weighted: settings::Var<bool>,
+    subscriber_multiplier: settings::Var<u32>,
+    currency: async_injector::Ref<currency::Currency>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+}
 
-                let mut formatted = Vec::new();
+impl Voting {
+    /// Load the weighting currently in effect.
+    async fn weighting(&self) -> Weighting {
+        Weighting {
+            currency: self.currency_weighted.load().await,
+            subscriber_multiplier: self.subscriber_multiplier.load().await,
+        }
+    }
 
-                for (key, votes) in results {
-                    let p = common::percentage(votes, total);
+    /// Look up the balances of everyone who voted since the last lookup.
+    ///
+    /// This is done in one batch for all new voters, rather than once for
+    /// every vote as it comes in.
+    async fn resolve(&self, channel: &Channel, inner: &settings::Var<Inner>) -> Result<()> {
+        if !self.currency_weighted.load().await {
+            return Ok(());
+        }
 
-                    let votes = match votes {
-                        0 => "no votes".to_string(),
-                        1 => "one vote".to_string(),
-                        n => format!("{} votes", n),
-                    };
+        let Some(currency) = self.currency.load().await else {
+            return Ok(());
+        };
 
-                    formatted.push(format!("{} = {} ({})", key, votes, p));
-                }
+        let pending = inner
+            .read()
+            .await
+            .votes
+            .iter()
+            .filter(|(_, vote)| vote.balance.is_none())
+            .map(|(login, _)| login.clone())
+            .collect::<Vec<_>>();
 
-                chat::respond!(ctx, "{} -> {}.", poll.question, formatted.join(", "));
-            }
-            _ => {
-                ctx.respond("Expected: run, close.").await;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let balances = currency.balances_of(channel, pending.clone()).await?;
+        let mut inner = inner.write().await;
+
+        for login in pending {
+            if let Some(vote) = inner.votes.get_mut(&login) {
+                vote.balance = Some(balances.get(&login).copied().unwrap_or_default());
             }
         }
 
+        inner.dirty = true;
         Ok(())
     }
 }
 
+/// How much votes weigh.
+#[derive(Debug, Clone, Copy)]
+struct Weighting {
+    /// Weigh votes by the balance of the voter.
+    currency: bool,
+    /// Multiply the votes of subscribers by this much.
+    subscriber_multiplier: u32,
+}
+
+impl Weighting {
+    /// Calculate how much the given vote weighs.
+    ///
+    /// A vote always weighs at least one, even if the balance of the voter
+    /// hasn't been looked up yet.
+    fn weight(&self, vote: &Vote) -> u64 {
+        let mut weight = 1;
+
+        if self.currency {
+            let balance = vote.balance.unwrap_or_default();
+            weight = u64::try_from(balance).unwrap_or_default().max(1);
+        }
+
+        if vote.subscriber {
+            let multiplier = self.subscriber_multiplier.max(1);
+            weight = weight.saturating_mul(u64::from(multiplier));
+        }
+
+        weight
+    }
+}
+
+/// A single vote cast in a poll.
+#[derive(Debug, Clone)]
+struct Vote {
+    /// The option voted for.
+    option: String,
+    /// If the voter is a subscriber.
+    subscriber: bool,
+    /// The balance of the voter, if it has been looked up.
+    balance: Option<i64>,
+}
+
+#[derive(Default)]
 struct Inner {
-    voted: HashSet<String>,
-    votes: HashMap<String, u32>,
+    /// Votes by login.
+    votes: HashMap<String, Vote>,
+    /// If the votes have changed since they were last published.
+    dirty: bool,
+}
+
+/// Tally the votes for each option, ordered by the number of votes.
+fn tally(
+    options: &[(String, Option<String>)],
+    votes: &HashMap<String, Vote>,
+    weighting: Weighting,
+) -> Vec<bus::PollOption> {
+    let mut totals = HashMap::<&str, u64>::new();
+
+    for vote in votes.values() {
+        let total = totals.entry(vote.option.as_str()).or_default();
+        *total = total.saturating_add(weighting.weight(vote));
+    }
+
+    let mut results = Vec::new();
+
+    for (key, description) in options {
+        results.push(bus::PollOption {
+            key: key.clone(),
+            description: description.clone(),
+            votes: totals.get(key.as_str()).copied().unwrap_or_default(),
+        });
+    }
+
+    results.sort_by_key(|o| std::cmp::Reverse(o.votes));
+    results
 }
 
 #[derive(Clone)]
 struct ActivePoll {
+    id: i32,
+    channel: OwnedChannel,
     question: String,
-    created_at: DateTime<Utc>,
-    options: HashMap<String, Option<String>>,
+    round: i32,
+    options: Vec<(String, Option<String>)>,
+    voting: Voting,
     inner: settings::Var<Inner>,
 }
 
 impl ActivePoll {
-    /// Close the poll.
-    pub(crate) async fn close(&self) -> Vec<(String, u32)> {
+    /// Tally the votes for each option, ordered by the number of votes.
+    async fn tally(&self) -> Vec<bus::PollOption> {
+        let weighting = self.voting.weighting().await;
         let inner = self.inner.read().await;
+        tally(&self.options, &inner.votes, weighting)
+    }
 
-        let mut results = Vec::new();
+    /// Weigh new votes and publish the poll if it has changed.
+    async fn update(&self) -> Result<()> {
+        self.voting.resolve(&self.channel, &self.inner).await?;
 
-        for (o, description) in &self.options {
-            results.push((
-                description.clone().unwrap_or_else(|| o.to_string()),
-                inner.votes.get(o).cloned().unwrap_or_default(),
-            ));
+        if std::mem::take(&mut self.inner.write().await.dirty) {
+            self.publish(false).await;
         }
 
-        results.sort_by(|a, b| b.1.cmp(&a.1));
-        results
+        Ok(())
+    }
+
+    /// Publish the current state of the poll on the global bus.
+    async fn publish(&self, closed: bool) {
+        let global_bus = match self.voting.global_bus.load().await {
+            Some(global_bus) => global_bus,
+            None => return,
+        };
+
+        global_bus
+            .send(bus::Global::Poll {
+                id: self.id,
+                question: self.question.clone(),
+                round: self.round,
+                options: self.tally().await,
+                closed,
+            })
+            .await;
     }
 }
 
 #[async_trait]
 impl command::MessageHook for ActivePoll {
     async fn peek(&self, user: &chat::User, m: &str) -> Result<()> {
-        let mut inner = self.inner.write().await;
-
         let user = match user.real() {
             Some(user) => user,
             None => return Ok(()),
         };
 
-        if inner.voted.contains(user.login()) {
-            return Ok(());
-        }
+        let option = common::words::trimmed(m)
+            .map(|word| word.to_lowercase())
+            .find(|word| self.options.iter().any(|(o, _)| o == word));
 
-        for word in common::words::trimmed(m) {
-            if !self.options.contains_key(&word.to_lowercase()) {
-                continue;
-            }
+        let option = match option {
+            Some(option) => option,
+            None => return Ok(()),
+        };
+
+        let allow_change = self.voting.allow_change.load().await;
+        let subscriber = user.roles().contains(&auth::Role::Subscriber);
+
+        let mut inner = self.inner.write().await;
 
-            *inner.votes.entry(word.to_string()).or_default() += 1;
-            inner.voted.insert(user.login().to_string());
-            break;
+        // NB: votes are weighed and published in batches by the poll task, so
+        // that a burst of votes doesn't hit the database once for every vote.
+        match inner.votes.get_mut(user.login()) {
+            Some(vote) if allow_change => {
+                vote.option = option;
+                vote.subscriber = subscriber;
+            }
+            Some(..) => return Ok(()),
+            None => {
+                inner.votes.insert(
+                    user.login().to_string(),
+                    Vote {
+                        option,
+                        subscriber,
+                        balance: None,
+                    },
+                );
+            }
         }
 
+        inner.dirty = true;
         Ok(())
     }
 }
 
+/// A poll which is currently accepting votes.
+struct RunningPoll {
+    poll: ActivePoll,
+    hook: command::HookHandle,
+    created_at: DateTime<Utc>,
+    closes_at: Option<DateTime<Utc>>,
+}
+
+impl RunningPoll {
+    /// Close the poll, store its results and format them for chat.
+    async fn close(self, db: &db::Polls) -> Result<String> {
+        self.hook.remove().await;
+
+        self.poll
+            .voting
+            .resolve(&self.poll.channel, &self.poll.inner)
+            .await?;
+
+        let results = self.poll.tally().await;
+
+        let votes = results
+            .iter()
+            .map(|o| (o.key.clone(), o.votes))
+            .collect::<Vec<_>>();
+
+        db.close(self.poll.id, &votes).await?;
+        self.poll.publish(true).await;
+
+        let total = results.iter().map(|o| o.votes).sum::<u64>();
+
+        let mut formatted = Vec::new();
+
+        for option in results {
+            let p = common::percentage(option.votes, total);
+
+            let votes = match option.votes {
+                0 => "no votes".to_string(),
+                1 => "one vote".to_string(),
+                n => format!("{} votes", n),
+            };
+
+            let name = option.description.unwrap_or(option.key);
+            formatted.push(format!("{} = {} ({})", name, votes, p));
+        }
+
+        Ok(format!(
+            "{} -> {}.",
+            self.poll.question,
+            formatted.join(", ")
+        ))
+    }
+}
+
 pub(crate) struct Module;
 
 #[async_trait]
@@ -177,17 +632,273 @@ impl chat::Module for Module {
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            injector,
+            handlers,
+            streamer,
+            settings,
+            sender,
+            tasks,
+            ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
+        let polls = Polls::default();
+        let db = injector.var::<db::Polls>().await;
+
+        tasks.push(Box::pin(forward_native(injector.clone())));
+
         handlers.insert(
             "poll",
             Poll {
-                polls: Mutex::new(Default::default()),
                 enabled: settings.var("poll/enabled", false).await?,
+                duration: settings.optional("poll/duration").await?,
+                voting: Voting {
+                    allow_change: settings.var("poll/allow-vote-change", false).await?,
+                    currency_weighted: settings.var("poll/currency-weighted", false).await?,
+                    subscriber_multiplier: settings.var("poll/subscriber-multiplier", 1).await?,
+                    currency: injector.var().await,
+                    global_bus: injector.var().await,
+                },
+                polls: polls.clone(),
+                db: db.clone(),
+                streamer: streamer.clone(),
             },
         );
 
+        let sender = sender.clone();
+        let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+        let mut started_at = Some(Utc::now().naive_utc());
+
+        let future = async move {
+            loop {
+                interval.tick().await;
+
+                let Some(db) = db.load().await else {
+                    continue;
+                };
+
+                if let Some(before) = started_at.take() {
+                    match db.close_abandoned(before).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Closed {} poll(s) abandoned on restart", n),
+                        Err(e) => common::log_error!(e, "Failed to close abandoned polls"),
+                    }
+                }
+
+                let running = {
+                    let polls = polls.lock().await;
+                    polls.values().map(|p| p.poll.clone()).collect::<Vec<_>>()
+                };
+
+                for poll in running {
+                    if let Err(e) = poll.update().await {
+                        common::log_error!(e, "Failed to update poll");
+                    }
+                }
+
+                let expired = {
+                    let mut polls = polls.lock().await;
+                    let now = Utc::now();
+
+                    let ids = polls
+                        .iter()
+                        .filter(|(_, p)| p.closes_at.map(|at| at <= now).unwrap_or_default())
+                        .map(|(id, _)| *id)
+                        .collect::<Vec<_>>();
+
+                    ids.into_iter()
+                        .flat_map(|id| polls.remove(&id))
+                        .collect::<Vec<_>>()
+                };
+
+                for running in expired {
+                    match running.close(&db).await {
+                        Ok(results) => sender.privmsg(results).await,
+                        Err(e) => common::log_error!(e, "Failed to close poll"),
+                    }
+                }
+            }
+        };
+
+        tasks.push(Box::pin(future));
         Ok(())
     }
 }
+
+/// Forward updates to native Twitch polls to the global bus.
+async fn forward_native(injector: Injector) -> Result<()> {
+    let (mut eventsub_stream, eventsub) = injector.stream::<eventsub::TwitchEventSub>().await;
+    let global_bus = injector.var::<bus::Bus<bus::Global>>().await;
+
+    let mut updates = Fuse::empty();
+
+    if let Some(eventsub) = &eventsub {
+        updates.set(eventsub.polls());
+    }
+
+    loop {
+        tokio::select! {
+            eventsub = eventsub_stream.recv() => {
+                match eventsub {
+                    Some(eventsub) => updates.set(eventsub.polls()),
+                    None => updates.clear(),
+                }
+            }
+            Some(update) = updates.next() => {
+                let (poll, closed) = match update {
+                    eventsub::PollUpdate::Begin(poll) => (poll, false),
+                    eventsub::PollUpdate::Progress(poll) => (poll, false),
+                    eventsub::PollUpdate::End(poll) => (poll, true),
+                };
+
+                let Some(global_bus) = global_bus.load().await else {
+                    continue;
+                };
+
+                let choices = poll
+                    .choices
+                    .into_iter()
+                    .map(|c| bus::TwitchPollChoice {
+                        title: c.title,
+                        votes: c.votes,
+                    })
+                    .collect();
+
+                global_bus
+                    .send(bus::Global::TwitchPoll {
+                        id: poll.id,
+                        title: poll.title,
+                        choices,
+                        closed,
+                    })
+                    .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{check_native, tally, Vote, Weighting};
+
+    fn vote(option: &str, subscriber: bool, balance: Option<i64>) -> Vote {
+        Vote {
+            option: option.to_string(),
+            subscriber,
+            balance,
+        }
+    }
+
+    #[test]
+    fn test_weight() {
+        let flat = Weighting {
+            currency: false,
+            subscriber_multiplier: 1,
+        };
+
+        assert_eq!(flat.weight(&vote("a", false, Some(100))), 1);
+        assert_eq!(flat.weight(&vote("a", true, Some(100))), 1);
+
+        let weighted = Weighting {
+            currency: true,
+            subscriber_multiplier: 3,
+        };
+
+        assert_eq!(weighted.weight(&vote("a", false, Some(100))), 100);
+        assert_eq!(weighted.weight(&vote("a", true, Some(100))), 300);
+        // votes always count for something, even without a balance.
+        assert_eq!(weighted.weight(&vote("a", false, Some(-5))), 1);
+        assert_eq!(weighted.weight(&vote("a", false, Some(0))), 1);
+        assert_eq!(weighted.weight(&vote("a", true, None)), 3);
+
+        let zero = Weighting {
+            currency: false,
+            subscriber_multiplier: 0,
+        };
+
+        assert_eq!(zero.weight(&vote("a", true, None)), 1);
+
+        let huge = Weighting {
+            currency: true,
+            subscriber_multiplier: u32::MAX,
+        };
+
+        assert_eq!(huge.weight(&vote("a", true, Some(i64::MAX))), u64::MAX);
+    }
+
+    #[test]
+    fn test_tally() {
+        let options = vec![
+            (String::from("a"), None),
+            (String::from("b"), Some(String::from("Bee"))),
+            (String::from("c"), None),
+        ];
+
+        let mut votes = HashMap::new();
+        votes.insert(String::from("alice"), vote("a", false, Some(10)));
+        votes.insert(String::from("bob"), vote("b", true, Some(4)));
+        votes.insert(String::from("carol"), vote("b", false, None));
+
+        let weighting = Weighting {
+            currency: false,
+            subscriber_multiplier: 2,
+        };
+
+        let results = tally(&options, &votes, weighting)
+            .into_iter()
+            .map(|o| (o.key, o.votes))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [
+                (String::from("b"), 3),
+                (String::from("a"), 1),
+                (String::from("c"), 0)
+            ]
+        );
+
+        let weighting = Weighting {
+            currency: true,
+            subscriber_multiplier: 2,
+        };
+
+        let results = tally(&options, &votes, weighting);
+        assert_eq!(results[0].key, "a");
+        assert_eq!(results[0].votes, 10);
+        assert_eq!(results[1].key, "b");
+        assert_eq!(results[1].votes, 9);
+        assert_eq!(results[1].description.as_deref(), Some("Bee"));
+        assert_eq!(results[2].votes, 0);
+    }
+
+    #[test]
+    fn test_check_native() {
+        let options = |n: usize| (0..n).map(|n| (format!("{}", n), None)).collect::<Vec<_>>();
+
+        assert!(check_native("question?", &options(1)).is_err());
+        assert!(check_native("question?", &options(2)).is_ok());
+        assert!(check_native("question?", &options(5)).is_ok());
+        assert!(check_native("question?", &options(6)).is_err());
+
+        // limits are in characters, not bytes.
+        assert!(check_native(&"ä".repeat(60), &options(2)).is_ok());
+        assert!(check_native(&"a".repeat(61), &options(2)).is_err());
+
+        // the description is what's shown, so that's what's checked.
+        let long = vec![
+            (String::from("a"), Some("b".repeat(25))),
+            ("c".repeat(26), Some(String::from("d"))),
+        ];
+
+        assert!(check_native("question?", &long).is_ok());
+
+        let long = vec![
+            (String::from("a"), Some("b".repeat(26))),
+            (String::from("c"), None),
+        ];
+
+        assert!(check_native("question?", &long).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use std::pin::pin;
+
+use anyhow::Result;
+use api::twitch::{eventsub, model};
+use async_fuse::Fuse;
+use async_injector::Injector;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::stream::StreamExt;
+use common::Duration;
+
+/// The shortest and longest prediction windows supported by Twitch.
+const WINDOW_RANGE: (u64, u64) = (30, 1800);
+/// The fewest and most outcomes supported by Twitch.
+const OUTCOMES_RANGE: (usize, usize) = (2, 10);
+/// The longest title supported by Twitch, in characters.
+const MAX_TITLE: usize = 45;
+/// The longest outcome supported by Twitch, in characters.
+const MAX_OUTCOME: usize = 25;
+
+/// Handler for the `!prediction` command.
+pub(crate) struct Prediction {
+    enabled: settings::Var<bool>,
+    window: settings::Var<Duration>,
+    streamer: api::TwitchAndUser,
+}
+
+impl Prediction {
+    /// Get the most recent prediction, if it is still running.
+    async fn running(&self) -> Result<model::Prediction> {
+        let prediction = {
+            let mut predictions = pin!(self.streamer.client.predictions(&self.streamer.user.id));
+            predictions.next().await.transpose()?
+        };
+
+        match prediction {
+            Some(p)
+                if matches!(
+                    p.status,
+                    model::PredictionStatus::Active | model::PredictionStatus::Locked
+                ) =>
+            {
+                Ok(p)
+            }
+            _ => chat::respond_bail!("No running prediction"),
+        }
+    }
+
+    /// End the given prediction with the specified status.
+    async fn end(
+        &self,
+        prediction: &model::Prediction,
+        status: model::PredictionStatus,
+        winning_outcome_id: Option<&str>,
+    ) -> Result<()> {
+        self.streamer
+            .client
+            .end_prediction(
+                &self.streamer.user.id,
+                &prediction.id,
+                status,
+                winning_outcome_id,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Check that a prediction fits within the limits of Twitch, so that we can
+/// give a clear error instead of the one from the API.
+fn check(title: &str, outcomes: &[String]) -> Result<()> {
+    let (min, max) = OUTCOMES_RANGE;
+
+    if !(min..=max).contains(&outcomes.len()) {
+        chat::respond_bail!(
+            "Predictions need between {} and {} outcomes, but got {}",
+            min,
+            max,
+            outcomes.len()
+        );
+    }
+
+    if title.chars().count() > MAX_TITLE {
+        chat::respond_bail!(
+            "Prediction titles can be at most {} characters long",
+            MAX_TITLE
+        );
+    }
+
+    for outcome in outcomes {
+        if outcome.chars().count() > MAX_OUTCOME {
+            chat::respond_bail!(
+                "Prediction outcomes can be at most {} characters long, but `{}` is longer",
+                MAX_OUTCOME,
+                outcome
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl command::Handler for Prediction {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Prediction)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        match ctx.next().as_deref() {
+            Some("start") => {
+                let title = ctx.next_str("<title> <outcome> <outcome...>")?;
+                let outcomes = ctx.by_ref().collect::<Vec<_>>();
+
+                check(&title, &outcomes)?;
+
+                let (min, max) = WINDOW_RANGE;
+                let window = self.window.load().await.num_seconds().clamp(min, max);
+
+                let outcomes = outcomes
+                    .iter()
+                    .map(|title| model::PredictionOutcomeTitle { title })
+                    .collect();
+
+                let prediction = self
+                    .streamer
+                    .client
+                    .create_prediction(model::CreatePredictionRequest {
+                        broadcaster_id: &self.streamer.user.id,
+                        title: &title,
+                        outcomes,
+                        prediction_window: window as u32,
+                    })
+                    .await?
+                    .ok_or(chat::respond_err!("Twitch did not create the prediction"))?;
+
+                chat::respond!(
+                    ctx,
+                    "Started prediction `{}`, locking in {}",
+                    prediction.title,
+                    Duration::seconds(window)
+                );
+            }
+            Some("lock") => {
+                let prediction = self.running().await?;
+
+                if prediction.status == model::PredictionStatus::Locked {
+                    chat::respond_bail!("Prediction `{}` is already locked", prediction.title);
+                }
+
+                self.end(&prediction, model::PredictionStatus::Locked, None)
+                    .await?;
+                chat::respond!(ctx, "Locked prediction `{}`", prediction.title);
+            }
+            Some("resolve") => {
+                let outcome = ctx.next_str("<outcome>")?;
+                let prediction = self.running().await?;
+
+                // NB: outcomes can be referenced by title or by their 1-based
+                // position.
+                let winner = match str::parse::<usize>(&outcome) {
+                    Ok(n) => n.checked_sub(1).and_then(|n| prediction.outcomes.get(n)),
+                    Err(..) => prediction
+                        .outcomes
+                        .iter()
+                        .find(|o| o.title.eq_ignore_ascii_case(&outcome)),
+                };
+
+                let winner =
+                    winner.ok_or(chat::respond_err!("No outcome matching `{}`", outcome))?;
+
+                self.end(
+                    &prediction,
+                    model::PredictionStatus::Resolved,
+                    Some(winner.id.as_str()),
+                )
+                .await?;
+
+                chat::respond!(
+                    ctx,
+                    "Resolved prediction `{}` with `{}` as the winner",
+                    prediction.title,
+                    winner.title
+                );
+            }
+            Some("cancel") => {
+                let prediction = self.running().await?;
+                self.end(&prediction, model::PredictionStatus::Canceled, None)
+                    .await?;
+                chat::respond!(
+                    ctx,
+                    "Canceled prediction `{}`, all points have been refunded",
+                    prediction.title
+                );
+            }
+            _ => {
+                ctx.respond("Expected: start, lock, resolve, cancel.").await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "prediction"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            streamer,
+            settings,
+            tasks,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "prediction",
+            Prediction {
+                enabled: settings.var("prediction/enabled", false).await?,
+                window: settings
+                    .var("prediction/window", Duration::seconds(120))
+                    .await?,
+                streamer: streamer.clone(),
+            },
+        );
+
+        tasks.push(Box::pin(forward_native(injector.clone())));
+        Ok(())
+    }
+}
+
+/// Forward updates to native Twitch predictions to the global bus.
+async fn forward_native(injector: Injector) -> Result<()> {
+    let (mut eventsub_stream, eventsub) = injector.stream::<eventsub::TwitchEventSub>().await;
+    let global_bus = injector.var::<bus::Bus<bus::Global>>().await;
+
+    let mut updates = Fuse::empty();
+
+    if let Some(eventsub) = &eventsub {
+        updates.set(eventsub.predictions());
+    }
+
+    loop {
+        tokio::select! {
+            eventsub = eventsub_stream.recv() => {
+                match eventsub {
+                    Some(eventsub) => updates.set(eventsub.predictions()),
+                    None => updates.clear(),
+                }
+            }
+            Some(update) = updates.next() => {
+                let (prediction, status) = match update {
+                    eventsub::PredictionUpdate::Begin(p) => (p, None),
+                    eventsub::PredictionUpdate::Progress(p) => (p, None),
+                    eventsub::PredictionUpdate::Lock(p) => (p, Some("locked")),
+                    eventsub::PredictionUpdate::End(p) => (p, None),
+                };
+
+                // NB: only the end event reports its own status.
+                let status = match (status, prediction.status.as_deref()) {
+                    (Some(status), _) | (None, Some(status)) => status.to_string(),
+                    (None, None) => String::from("active"),
+                };
+
+                let Some(global_bus) = global_bus.load().await else {
+                    continue;
+                };
+
+                let outcomes = prediction
+                    .outcomes
+                    .into_iter()
+                    .map(|o| bus::TwitchPredictionOutcome {
+                        id: o.id,
+                        title: o.title,
+                        color: o.color,
+                        users: o.users,
+                        channel_points: o.channel_points,
+                    })
+                    .collect();
+
+                global_bus
+                    .send(bus::Global::TwitchPrediction {
+                        id: prediction.id,
+                        title: prediction.title,
+                        outcomes,
+                        status,
+                        winning_outcome_id: prediction.winning_outcome_id,
+                    })
+                    .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn test_check() {
+        let outcomes = |n: usize| (0..n).map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert!(check("title", &outcomes(1)).is_err());
+        assert!(check("title", &outcomes(2)).is_ok());
+        assert!(check("title", &outcomes(10)).is_ok());
+        assert!(check("title", &outcomes(11)).is_err());
+
+        assert!(check(&"a".repeat(45), &outcomes(2)).is_ok());
+        assert!(check(&"a".repeat(46), &outcomes(2)).is_err());
+
+        let long = vec![String::from("yes"), "n".repeat(26)];
+        assert!(check("title", &long).is_err());
+    }
+}
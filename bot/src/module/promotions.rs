@@ -69,6 +69,7 @@ impl chat::Module for Module {
             sender,
             settings,
             idle,
+            template_helpers,
             ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
@@ -92,6 +93,7 @@ impl chat::Module for Module {
         let sender = sender.clone();
         let mut interval = tokio::time::interval(frequency.as_std());
         let idle = idle.clone();
+        let template_helpers = template_helpers.clone();
 
         let future = async move {
             loop {
@@ -119,7 +121,9 @@ impl chat::Module for Module {
                             let promotions = promotions.clone();
                             let sender = sender.clone();
 
-                            if let Err(e) = promote(promotions, sender).await {
+                            let result = promote(promotions, sender, &template_helpers).await;
+
+                            if let Err(e) = result {
                                 tracing::error!("Failed to send promotion: {}", e);
                             }
                         }
@@ -134,11 +138,19 @@ impl chat::Module for Module {
 }
 
 /// Run the next promotion.
-async fn promote(promotions: db::Promotions, sender: chat::Sender) -> Result<()> {
+async fn promote(
+    promotions: db::Promotions,
+    sender: chat::Sender,
+    template_helpers: &chat::TemplateHelpers,
+) -> Result<()> {
     let channel = sender.channel();
 
     if let Some(p) = pick(promotions.list(channel).await) {
-        let text = p.render(&PromoData { channel })?;
+        let helpers = template_helpers
+            .resolve(&p.template, channel, None, Vec::new())
+            .await?;
+
+        let text = p.render(&PromoData { channel, helpers })?;
         promotions.bump_promoted_at(&p).await?;
         sender.privmsg(text).await;
     }
@@ -149,6 +161,7 @@ async fn promote(promotions: db::Promotions, sender: chat::Sender) -> Result<()>
 #[derive(Debug, serde::Serialize)]
 struct PromoData<'a> {
     channel: &'a Channel,
+    helpers: template::HelperData,
 }
 
 /// Pick the best promo.
@@ -1,6 +1,10 @@
+mod bans;
+mod bumps;
 mod feedback;
+mod history;
 mod redemption;
 mod requester;
+mod votes;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -21,6 +25,10 @@ pub(crate) struct Handler {
     request_help_cooldown: Mutex<Cooldown>,
     currency: async_injector::Ref<currency::Currency>,
     requester: requester::SongRequester,
+    votes: votes::Votes,
+    history: history::History,
+    bans: bans::Bans,
+    bumps: bumps::Bumps,
     streamer: api::TwitchAndUser,
 }
 
@@ -162,6 +170,10 @@ impl command::Handler for Handler {
                     chat::respond!(ctx, "No such song to promote");
                 }
             }
+            Some("bump") => {
+                ctx.check_scope(auth::Scope::SongBump).await?;
+                self.bumps.bump(ctx, &player).await?;
+            }
             Some("close") => {
                 ctx.check_scope(auth::Scope::SongEditQueue).await?;
 
@@ -242,9 +254,54 @@ impl command::Handler for Handler {
             },
             Some("purge") => {
                 ctx.check_scope(auth::Scope::SongEditQueue).await?;
-                player.purge().await?;
+                let purged = player.purge().await?;
+                self.bumps.refund(ctx.channel(), &player, &purged).await;
                 chat::respond!(ctx, "Song queue purged.");
             }
+            Some("save") => {
+                ctx.check_scope(auth::Scope::SongEditQueue).await?;
+                let name = ctx.next().ok_or(chat::respond_err!("Expected <name>"))?;
+                let saved = player.save_snapshot(&name).await?;
+                chat::respond!(ctx, "Saved {} song(s) to snapshot `{}`", saved, name);
+            }
+            Some("load") => {
+                ctx.check_scope(auth::Scope::SongEditQueue).await?;
+                let name = ctx.next().ok_or(chat::respond_err!("Expected <name>"))?;
+
+                let user = ctx
+                    .user
+                    .real()
+                    .ok_or(chat::respond_err!("Only real users can load snapshots"))?
+                    .login()
+                    .to_string();
+
+                match player.load_snapshot(&name, &user).await? {
+                    Some((added, 0)) => {
+                        chat::respond!(ctx, "Added {} song(s) from snapshot `{}`", added, name);
+                    }
+                    Some((added, skipped)) => {
+                        chat::respond!(
+                            ctx,
+                            "Added {} song(s) from snapshot `{}`, skipped {} which couldn't be added",
+                            added,
+                            name,
+                            skipped
+                        );
+                    }
+                    None => {
+                        chat::respond!(ctx, "No snapshot named `{}`", name);
+                    }
+                }
+            }
+            Some("undo") => {
+                ctx.check_scope(auth::Scope::SongEditQueue).await?;
+
+                match player.undo().await?.as_slice() {
+                    [] => chat::respond!(ctx, "Nothing to undo"),
+                    [item] => chat::respond!(ctx, "Restored: {}!", item.what()),
+                    items => chat::respond!(ctx, "Restored {} songs!", items.len()),
+                }
+            }
             // print when your next song will play.
             Some("when") => {
                 let user = ctx.next();
@@ -350,7 +407,12 @@ impl command::Handler for Handler {
 
                 match removed {
                     None => ctx.respond("No song removed, sorry :(").await,
-                    Some(item) => ctx.respond(format!("Removed: {}!", item.what())).await,
+                    Some(item) => {
+                        self.bumps
+                            .refund(ctx.channel(), &player, std::slice::from_ref(&item))
+                            .await;
+                        ctx.respond(format!("Removed: {}!", item.what())).await;
+                    }
                 }
             }
             Some("volume") => {
@@ -401,7 +463,43 @@ impl command::Handler for Handler {
             }
             Some("skip") => {
                 ctx.check_scope(auth::Scope::SongPlaybackControl).await?;
+                let current = player.current().await;
                 player.skip().await?;
+
+                if let Some(current) = current {
+                    self.bumps
+                        .refund(ctx.channel(), &player, &[current.into_item()])
+                        .await;
+                }
+            }
+            Some("voteskip") => {
+                ctx.check_scope(auth::Scope::SongVoteSkip).await?;
+
+                if let Some(skipped) = self.votes.voteskip(ctx, &player).await? {
+                    self.bumps.refund(ctx.channel(), &player, &[skipped]).await;
+                }
+            }
+            Some("like") => {
+                ctx.check_scope(auth::Scope::SongLike).await?;
+                self.votes.like(ctx, &player, true).await?;
+            }
+            Some("dislike") => {
+                ctx.check_scope(auth::Scope::SongLike).await?;
+                self.votes.like(ctx, &player, false).await?;
+            }
+            Some("ban") => {
+                ctx.check_scope(auth::Scope::SongBan).await?;
+                self.bans.ban(ctx, &player).await?;
+            }
+            Some("unban") => {
+                ctx.check_scope(auth::Scope::SongBan).await?;
+                self.bans.unban(ctx, &player).await?;
+            }
+            Some("history") => {
+                self.history.history(ctx, &player).await?;
+            }
+            Some("top") => {
+                self.history.top(ctx, &player).await?;
             }
             Some("request") => {
                 self.handle_request(ctx, &player).await?;
@@ -452,11 +550,23 @@ impl command::Handler for Handler {
                     alts.push("close");
                     alts.push("open");
                     alts.push("purge");
+                    alts.push("save");
+                    alts.push("load");
+                    alts.push("undo");
                 } else {
                     alts.push("promote 🛇");
                     alts.push("close 🛇");
                     alts.push("open 🛇");
                     alts.push("purge 🛇");
+                    alts.push("save 🛇");
+                    alts.push("load 🛇");
+                    alts.push("undo 🛇");
+                }
+
+                if ctx.user.has_scope(auth::Scope::SongBump).await {
+                    alts.push("bump");
+                } else {
+                    alts.push("bump 🛇");
                 }
 
                 if ctx.user.has_scope(auth::Scope::SongVolume).await {
@@ -477,12 +587,36 @@ impl command::Handler for Handler {
                     alts.push("pause 🛇");
                 }
 
+                if ctx.user.has_scope(auth::Scope::SongVoteSkip).await {
+                    alts.push("voteskip");
+                } else {
+                    alts.push("voteskip 🛇");
+                }
+
+                if ctx.user.has_scope(auth::Scope::SongLike).await {
+                    alts.push("like");
+                    alts.push("dislike");
+                } else {
+                    alts.push("like 🛇");
+                    alts.push("dislike 🛇");
+                }
+
+                if ctx.user.has_scope(auth::Scope::SongBan).await {
+                    alts.push("ban");
+                    alts.push("unban");
+                } else {
+                    alts.push("ban 🛇");
+                    alts.push("unban 🛇");
+                }
+
                 alts.push("list");
                 alts.push("current");
                 alts.push("when");
                 alts.push("delete");
                 alts.push("request");
                 alts.push("length");
+                alts.push("history");
+                alts.push("top");
                 chat::respond!(ctx, format!("Expected argument: {}.", alts.join(", ")));
             }
         }
@@ -509,10 +643,12 @@ impl module::Module for Module {
             settings,
             injector,
             streamer,
+            stream_info,
             ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
         let currency = injector.var().await;
+        let song_votes = injector.var().await;
         let settings = settings.scoped("song");
 
         let enabled = settings.var("enabled", false).await?;
@@ -521,9 +657,36 @@ impl module::Module for Module {
 
         let spotify = Constraint::build(&mut settings.scoped("spotify"), true, 0).await?;
         let youtube = Constraint::build(&mut settings.scoped("youtube"), false, 60).await?;
+        let local = Constraint::build(&mut settings.scoped("local"), false, 0).await?;
+
+        let skip_cooldown = settings.optional("voteskip/cooldown").await?;
+        let skip_cooldown_skips = settings.var("voteskip/cooldown-skips", 3).await?;
+
+        let votes = votes::Votes::new(
+            settings.var("voteskip/enabled", false).await?,
+            settings.var("voteskip/min-votes", 3).await?,
+            settings.optional("voteskip/percentage").await?,
+            settings
+                .var("voteskip/active-window", Duration::seconds(10 * 60))
+                .await?,
+            song_votes.clone(),
+            injector.var().await,
+            stream_info.clone(),
+        );
 
         let help_cooldown = Cooldown::from_duration(Duration::seconds(5));
-        let requester = requester::SongRequester::new(request_reward, spotify, youtube);
+        let requester = requester::SongRequester::new(
+            request_reward,
+            spotify,
+            youtube,
+            local,
+            song_votes,
+            skip_cooldown,
+            skip_cooldown_skips,
+            settings.var("fair-queue/subscriber-weight", 1).await?,
+            settings.var("fair-queue/redemption-weight", 1).await?,
+            settings.var("playlist/max-tracks", 10).await?,
+        );
 
         handlers.insert(
             "song",
@@ -533,6 +696,16 @@ impl module::Module for Module {
                 player: injector.var().await,
                 currency,
                 requester: requester.clone(),
+                votes,
+                history: history::History::new(injector.var().await),
+                bans: bans::Bans::new(injector.var().await),
+                bumps: bumps::Bumps::new(
+                    settings.var("bump/enabled", false).await?,
+                    settings.var("bump/cost", 10).await?,
+                    settings.var("bump/max-positions", 3).await?,
+                    injector.var().await,
+                    injector.var().await,
+                ),
                 streamer: streamer.clone(),
             },
         );
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use chat::command;
+
+/// Handles banning songs from being requested.
+pub(super) struct Bans {
+    song_bans: async_injector::Ref<db::SongBans>,
+}
+
+impl Bans {
+    pub(super) fn new(song_bans: async_injector::Ref<db::SongBans>) -> Self {
+        Self { song_bans }
+    }
+
+    /// Ban a track, artist, YouTube channel, or keyword.
+    pub(super) async fn ban(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<()> {
+        let song_bans = self.song_bans().await?;
+        let (kind, pattern) = parse(ctx, player).await?;
+
+        let banned_by = ctx.user.real().map(|user| user.login().to_string());
+
+        if song_bans
+            .insert(kind, &pattern, None, banned_by.as_deref())
+            .await?
+        {
+            chat::respond!(ctx, "Banned {} {}", kind, pattern);
+        } else {
+            chat::respond!(ctx, "The {} {} is already banned", kind, pattern);
+        }
+
+        Ok(())
+    }
+
+    /// Remove a ban on a track, artist, YouTube channel, or keyword.
+    pub(super) async fn unban(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<()> {
+        let song_bans = self.song_bans().await?;
+        let (kind, pattern) = parse(ctx, player).await?;
+
+        if song_bans.delete(kind, &pattern).await? {
+            chat::respond!(ctx, "Unbanned {} {}", kind, pattern);
+        } else {
+            chat::respond!(ctx, "The {} {} is not banned", kind, pattern);
+        }
+
+        Ok(())
+    }
+
+    async fn song_bans(&self) -> Result<db::SongBans> {
+        Ok(self
+            .song_bans
+            .load()
+            .await
+            .ok_or(chat::respond_err!("Song bans are not available"))?)
+    }
+}
+
+/// Parse the kind and pattern of a ban.
+///
+/// Tracks can be given as URIs or URLs, and default to the current song.
+async fn parse(
+    ctx: &mut command::Context<'_>,
+    player: &player::Player,
+) -> Result<(db::SongBanKind, String)> {
+    let kind = ctx.next_parse::<db::SongBanKind, _>("<track|artist|channel|keyword>")?;
+    let rest = ctx.rest().trim().to_string();
+
+    let pattern = match kind {
+        db::SongBanKind::Track if rest.is_empty() => player
+            .current()
+            .await
+            .ok_or(chat::respond_err!("No song is playing"))?
+            .item()
+            .track_id()
+            .to_string(),
+        db::SongBanKind::Track => match player.parse_track(&rest) {
+            Ok(track_id) => track_id.to_string(),
+            Err(e) => chat::respond_bail!("Bad track: {}", e),
+        },
+        _ if rest.is_empty() => chat::respond_bail!("Expected <pattern>"),
+        _ => rest,
+    };
+
+    Ok((kind, pattern))
+}
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chat::command;
+use common::models::Item;
+use common::Channel;
+
+/// Handles viewers paying to move their own requests forward in the queue.
+pub(super) struct Bumps {
+    enabled: settings::Var<bool>,
+    cost: settings::Var<i64>,
+    max_positions: settings::Var<u32>,
+    currency: async_injector::Ref<currency::Currency>,
+    song_bumps: async_injector::Ref<db::SongBumps>,
+}
+
+impl Bumps {
+    pub(super) fn new(
+        enabled: settings::Var<bool>,
+        cost: settings::Var<i64>,
+        max_positions: settings::Var<u32>,
+        currency: async_injector::Ref<currency::Currency>,
+        song_bumps: async_injector::Ref<db::SongBumps>,
+    ) -> Self {
+        Self {
+            enabled,
+            cost,
+            max_positions,
+            currency,
+            song_bumps,
+        }
+    }
+
+    /// Move one of your own requests forward in the queue, paying for each
+    /// position it moves.
+    pub(super) async fn bump(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<()> {
+        if !self.enabled.load().await {
+            chat::respond_bail!("Bumping songs is not enabled");
+        }
+
+        let index = ctx.next().ok_or(chat::respond_err!("Expected <number>"))?;
+        let n = super::parse_queue_position(&index).await?;
+
+        let max_positions = self.max_positions.load().await;
+        let mut positions = ctx
+            .next_parse_optional::<u32>()?
+            .unwrap_or(max_positions)
+            .min(max_positions) as usize;
+
+        let user = ctx
+            .user
+            .real()
+            .ok_or(chat::respond_err!("Only real users can bump songs"))?
+            .login()
+            .to_string();
+
+        if positions == 0 {
+            chat::respond_bail!("Songs can't be bumped right now");
+        }
+
+        let cost = self.cost.load().await.max(0);
+
+        let paid = if cost > 0 {
+            let currency = self
+                .currency
+                .load()
+                .await
+                .ok_or(chat::respond_err!("No currency configured"))?;
+
+            let song_bumps = self
+                .song_bumps
+                .load()
+                .await
+                .ok_or(chat::respond_err!("Bumping songs is not available"))?;
+
+            let balance = currency
+                .balance_of(ctx.channel(), &user)
+                .await?
+                .unwrap_or_default();
+
+            let affordable = usize::try_from(balance.balance / cost).unwrap_or_default();
+            positions = positions.min(affordable);
+            let charged = positions as i64 * cost;
+
+            if positions == 0
+                || !currency
+                    .balance_debit(ctx.channel(), &user, charged)
+                    .await?
+            {
+                chat::respond_bail!(
+                    "You need at least {cost} {currency} to bump a song, you have {balance} {currency}",
+                    cost = cost,
+                    currency = currency.name,
+                    balance = balance.balance,
+                );
+            }
+
+            Some((currency, song_bumps, charged))
+        } else {
+            None
+        };
+
+        self.prune(player, &[]).await;
+
+        let bumped = match player.bump_song(Some(&user), n, positions).await {
+            Ok(bumped) => Ok(bumped),
+            Err(player::BumpError::Error(e)) => Err(e),
+            Err(e) => Err(chat::respond_err!("{}", e).into()),
+        };
+
+        let (currency, song_bumps, charged) = match paid {
+            Some(paid) => paid,
+            None => {
+                let (to, item) = bumped?;
+                chat::respond!(ctx, "Bumped {} to position #{}", item.what(), to + 1);
+                return Ok(());
+            }
+        };
+
+        let (to, item) = match bumped {
+            Ok(bumped) => bumped,
+            Err(e) => {
+                currency.balance_add(ctx.channel(), &user, charged).await?;
+                return Err(e);
+            }
+        };
+
+        // NB: the song might have moved fewer positions than were paid for.
+        let amount = (n - to) as i64 * cost;
+
+        if charged > amount {
+            currency
+                .balance_add(ctx.channel(), &user, charged - amount)
+                .await?;
+        }
+
+        if let Err(e) = song_bumps.insert(item.track_id(), &user, amount).await {
+            common::log_error!(e, "Failed to record bump of {} by {}", amount, user);
+        }
+
+        chat::respond!(
+            ctx,
+            "Bumped {} to position #{} for {} {}",
+            item.what(),
+            to + 1,
+            amount,
+            currency.name
+        );
+
+        Ok(())
+    }
+
+    /// Refund any bumps paid for the given songs, which have been deleted or
+    /// skipped.
+    pub(super) async fn refund(
+        &self,
+        channel: &Channel,
+        player: &player::Player,
+        items: &[Arc<Item>],
+    ) {
+        let song_bumps = match self.song_bumps.load().await {
+            Some(song_bumps) => song_bumps,
+            None => return,
+        };
+
+        self.prune(player, items).await;
+
+        let mut refunds = Vec::new();
+
+        for item in items {
+            let user = match item.user() {
+                Some(user) => user,
+                None => continue,
+            };
+
+            match song_bumps.take(item.track_id(), user).await {
+                Ok(0) => (),
+                Ok(amount) => refunds.push((user.clone(), amount)),
+                Err(e) => {
+                    common::log_error!(e, "Failed to look up bumps for {}", item.track_id());
+                }
+            }
+        }
+
+        if refunds.is_empty() {
+            return;
+        }
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => {
+                tracing::warn!("Currency not available to refund {} bumps", refunds.len());
+                return;
+            }
+        };
+
+        for (user, amount) in refunds {
+            if let Err(e) = currency.balance_add(channel, &user, amount).await {
+                common::log_error!(e, "Failed to refund bump of {} to {}", amount, user);
+            }
+        }
+    }
+
+    /// Forget about bumps for songs which are no longer queued or playing,
+    /// except for the given songs which are about to be refunded.
+    async fn prune(&self, player: &player::Player, keep: &[Arc<Item>]) {
+        let song_bumps = match self.song_bumps.load().await {
+            Some(song_bumps) => song_bumps,
+            None => return,
+        };
+
+        let bumps = match song_bumps.list().await {
+            Ok(bumps) => bumps,
+            Err(e) => {
+                common::log_error!(e, "Failed to list song bumps");
+                return;
+            }
+        };
+
+        let items = player.list().await;
+
+        let stale = bumps
+            .into_iter()
+            .filter(|bump| {
+                !items.iter().chain(keep).any(|item| {
+                    item.track_id() == &bump.track_id
+                        && item.user().map(|u| db::user_id(u)).as_deref() == Some(&bump.user)
+                })
+            })
+            .map(|bump| bump.id)
+            .collect::<Vec<_>>();
+
+        if let Err(e) = song_bumps.delete(stale).await {
+            common::log_error!(e, "Failed to prune song bumps");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use chat::command;
+use common::models::TrackId;
+use common::Duration;
+
+/// The number of entries listed in chat.
+const LIST_LIMIT: i64 = 5;
+
+/// The window used for `!song top` unless one is specified.
+const DEFAULT_TOP_WINDOW: Duration = Duration::seconds(7 * 24 * 60 * 60);
+
+/// Handles statistics over past song requests.
+pub(super) struct History {
+    db: async_injector::Ref<db::Database>,
+}
+
+impl History {
+    pub(super) fn new(db: async_injector::Ref<db::Database>) -> Self {
+        Self { db }
+    }
+
+    /// List the most recent song requests, optionally by the given user.
+    pub(super) async fn history(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<()> {
+        let db = self
+            .db
+            .load()
+            .await
+            .ok_or(chat::respond_err!("Song history is not available"))?;
+
+        let user = ctx.next();
+        let songs = db.player_history(user.as_deref(), LIST_LIMIT).await?;
+
+        if songs.is_empty() {
+            match &user {
+                Some(user) => chat::respond!(ctx, "{} hasn't requested any songs", user),
+                None => chat::respond!(ctx, "No songs have been requested"),
+            }
+
+            return Ok(());
+        }
+
+        let mut lines = Vec::new();
+
+        for song in songs {
+            let what = describe(player, &song.track_id).await;
+
+            match (&user, &song.user) {
+                (None, Some(user)) => lines.push(format!("{} ({})", what, user)),
+                _ => lines.push(what),
+            }
+        }
+
+        match &user {
+            Some(user) => chat::respond!(ctx, "Recent requests by {}: {}.", user, lines.join("; ")),
+            None => chat::respond!(ctx, "Recent requests: {}.", lines.join("; ")),
+        }
+
+        Ok(())
+    }
+
+    /// List the most requested tracks and the most active requesters within
+    /// an optional window.
+    pub(super) async fn top(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<()> {
+        let db = self
+            .db
+            .load()
+            .await
+            .ok_or(chat::respond_err!("Song history is not available"))?;
+
+        let window = ctx
+            .next_parse_optional::<Duration>()?
+            .unwrap_or(DEFAULT_TOP_WINDOW);
+
+        let tracks = db.player_top_tracks(Some(&window), LIST_LIMIT).await?;
+
+        if tracks.is_empty() {
+            chat::respond!(ctx, "No songs have been requested in the last {}", window);
+            return Ok(());
+        }
+
+        let requesters = db.player_top_requesters(Some(&window), LIST_LIMIT).await?;
+
+        let mut top_tracks = Vec::new();
+
+        for (index, track) in tracks.into_iter().enumerate() {
+            let what = describe(player, &track.track_id).await;
+            top_tracks.push(format!("#{}: {} ({})", index + 1, what, track.count));
+        }
+
+        let top_requesters = requesters
+            .into_iter()
+            .map(|r| format!("{} ({})", r.user, r.count))
+            .collect::<Vec<_>>();
+
+        chat::respond!(
+            ctx,
+            "Top songs in the last {}: {}. Top requesters: {}.",
+            window,
+            top_tracks.join("; "),
+            top_requesters.join(", ")
+        );
+
+        Ok(())
+    }
+}
+
+/// Describe the given track, falling back to its URL if it can't be looked
+/// up.
+async fn describe(player: &player::Player, track_id: &TrackId) -> String {
+    match player.lookup_track(track_id).await {
+        Ok(Some(item)) => item.what(),
+        Ok(None) => track_id.url(),
+        Err(e) => {
+            common::log_warn!(e, "Failed to look up track: {}", track_id);
+            track_id.url()
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use api::twitch::pubsub;
+use api::twitch::eventsub;
 use async_fuse::Fuse;
 use async_injector::Injector;
 
@@ -15,7 +15,7 @@ pub(crate) async fn task(
     requester: SongRequester,
     streamer: api::TwitchAndUser,
 ) -> Result<()> {
-    let (mut pubsub_stream, pubsub) = injector.stream::<pubsub::TwitchPubSub>().await;
+    let (mut eventsub_stream, eventsub) = injector.stream::<eventsub::TwitchEventSub>().await;
     let (mut player_stream, player) = injector.stream::<player::Player>().await;
     let (mut request_redemption_stream, request_redemption) = settings
         .stream::<String>("request-redemption")
@@ -26,7 +26,7 @@ pub(crate) async fn task(
         requester,
         streamer,
         player,
-        pubsub,
+        eventsub,
         sender: sender.clone(),
         request_redemption: request_redemption.map(Into::into),
         redemptions_stream: Fuse::empty(),
@@ -40,8 +40,8 @@ pub(crate) async fn task(
                 state.request_redemption = request_redemption.map(Into::into);
                 state.build();
             }
-            pubsub = pubsub_stream.recv() => {
-                state.pubsub = pubsub;
+            eventsub = eventsub_stream.recv() => {
+                state.eventsub = eventsub;
                 state.build();
             }
             player = player_stream.recv() => {
@@ -59,11 +59,11 @@ pub(crate) async fn task(
 struct State {
     requester: SongRequester,
     streamer: api::TwitchAndUser,
-    pubsub: Option<pubsub::TwitchPubSub>,
+    eventsub: Option<eventsub::TwitchEventSub>,
     player: Option<player::Player>,
     sender: chat::Sender,
     request_redemption: Option<Arc<str>>,
-    redemptions_stream: Fuse<pubsub::TwitchStream<pubsub::Redemption>>,
+    redemptions_stream: Fuse<eventsub::TwitchStream<eventsub::Redemption>>,
 }
 
 impl State {
@@ -71,19 +71,23 @@ impl State {
         // Whether any redemptions are enabled or not.
         let any_redemptions = self.request_redemption.is_some();
 
-        let pubsub = match (self.pubsub.as_ref(), any_redemptions) {
-            (Some(pubsub), true) => pubsub,
+        let eventsub = match (self.eventsub.as_ref(), any_redemptions) {
+            (Some(eventsub), true) => eventsub,
             _ => {
                 self.redemptions_stream.clear();
                 return;
             }
         };
 
-        self.redemptions_stream.set(pubsub.redemptions());
+        self.redemptions_stream.set(eventsub.redemptions());
     }
 
     /// Process a single incoming redemption.
-    async fn process_redemption(&mut self, sender: &chat::Sender, redemption: pubsub::Redemption) {
+    async fn process_redemption(
+        &mut self,
+        sender: &chat::Sender,
+        redemption: eventsub::Redemption,
+    ) {
         match &self.request_redemption {
             Some(title) if title.as_ref() == redemption.reward.title => {
                 let title = title.clone();
@@ -99,7 +103,7 @@ impl State {
         &mut self,
         sender: &chat::Sender,
         title: &str,
-        redemption: pubsub::Redemption,
+        redemption: eventsub::Redemption,
     ) {
         let input = match redemption.user_input.as_ref() {
             Some(input) => input,
@@ -130,14 +134,14 @@ impl State {
             .request(
                 sender.channel(),
                 input,
-                &redemption.user.login,
+                &redemption.user_login,
                 None,
                 RequestCurrency::Redemption,
                 player,
             )
             .await;
 
-        let display_name = &redemption.user.display_name;
+        let display_name = &redemption.user_name;
 
         let status = match result {
             Ok(outcome) => {
@@ -145,11 +149,11 @@ impl State {
                     .privmsg(chat::respond(display_name, outcome))
                     .await;
 
-                pubsub::Status::Fulfilled
+                eventsub::Status::Fulfilled
             }
             Err(e) => {
                 self.sender.privmsg(chat::respond(display_name, e)).await;
-                pubsub::Status::Canceled
+                eventsub::Status::Canceled
             }
         };
 
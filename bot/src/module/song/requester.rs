@@ -4,7 +4,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use auth::Scope;
 use common::models::{track_id, TrackId};
-use common::Channel;
+use common::{Channel, Duration, Uri};
 
 use crate::module::song::Constraint;
 
@@ -20,6 +20,13 @@ pub(crate) struct SongRequester {
     request_reward: settings::Var<u32>,
     spotify: Constraint,
     youtube: Constraint,
+    local: Constraint,
+    song_votes: async_injector::Ref<db::SongVotes>,
+    skip_cooldown: settings::Var<Option<Duration>>,
+    skip_cooldown_skips: settings::Var<u32>,
+    subscriber_weight: settings::Var<u32>,
+    redemption_weight: settings::Var<u32>,
+    playlist_max_tracks: settings::Var<u32>,
 }
 
 impl SongRequester {
@@ -28,14 +35,244 @@ impl SongRequester {
         request_reward: settings::Var<u32>,
         spotify: Constraint,
         youtube: Constraint,
+        local: Constraint,
+        song_votes: async_injector::Ref<db::SongVotes>,
+        skip_cooldown: settings::Var<Option<Duration>>,
+        skip_cooldown_skips: settings::Var<u32>,
+        subscriber_weight: settings::Var<u32>,
+        redemption_weight: settings::Var<u32>,
+        playlist_max_tracks: settings::Var<u32>,
     ) -> Self {
         Self {
             request_reward,
             spotify,
             youtube,
+            local,
+            song_votes,
+            skip_cooldown,
+            skip_cooldown_skips,
+            subscriber_weight,
+            redemption_weight,
+            playlist_max_tracks,
         }
     }
 
+    /// Get the weight of a request in the fair queue.
+    async fn weight(
+        &self,
+        real_user: Option<&chat::RealUser<'_>>,
+        currency: &RequestCurrency<'_>,
+    ) -> u32 {
+        let mut weight = 1;
+
+        if let RequestCurrency::Redemption = currency {
+            weight = weight.max(self.redemption_weight.load().await);
+        }
+
+        if let Some(user) = real_user {
+            if user.roles().contains(&auth::Role::Subscriber) {
+                weight = weight.max(self.subscriber_weight.load().await);
+            }
+        }
+
+        weight
+    }
+
+    /// Test if the given user has had too many songs skipped by vote
+    /// recently, in which case they're not allowed to request songs.
+    async fn check_skip_cooldown(&self, user: &str) -> Result<(), RequestError> {
+        let Some(cooldown) = self.skip_cooldown.load().await else {
+            return Ok(());
+        };
+
+        let Some(song_votes) = self.song_votes.load().await else {
+            return Ok(());
+        };
+
+        let limit = self.skip_cooldown_skips.load().await;
+
+        let skips = song_votes
+            .skips_within(user, &cooldown)
+            .await
+            .map_err(RequestError::Error)?;
+
+        if limit > 0 && skips >= limit {
+            return Err(RequestError::SkipCooldown { skips, cooldown });
+        }
+
+        Ok(())
+    }
+
+    /// Get what requests for the given track are called, the scope required
+    /// to make them, and the constraints which apply to them.
+    fn source(&self, track_id: &TrackId) -> (&'static str, Scope, &Constraint) {
+        match track_id {
+            TrackId::Spotify(..) => ("Spotify", Scope::SongSpotify, &self.spotify),
+            TrackId::YouTube(..) => ("YouTube", Scope::SongYouTube, &self.youtube),
+            TrackId::Local(..) => ("Local", Scope::SongLocal, &self.local),
+        }
+    }
+
+    /// Check that the given user is allowed to request songs from a source
+    /// with the given name, scope and constraints.
+    ///
+    /// Returns `true` if the user bypasses constraints.
+    async fn check_request(
+        &self,
+        channel: &Channel,
+        (what, scope, constraint): (&'static str, Scope, &Constraint),
+        user: &str,
+        real_user: Option<&chat::RealUser<'_>>,
+        currency: &RequestCurrency<'_>,
+    ) -> Result<bool, RequestError> {
+        if !constraint.enabled.load().await {
+            return Err(RequestError::NotEnabled(what));
+        }
+
+        let has_bypass_constraints = if let Some(user) = real_user {
+            if !user.has_scope(scope).await {
+                return Err(RequestError::NotAllowed(what));
+            }
+
+            user.has_scope(Scope::SongBypassConstraints).await
+        } else {
+            false
+        };
+
+        if has_bypass_constraints {
+            return Ok(true);
+        }
+
+        self.check_skip_cooldown(user).await?;
+
+        match constraint.min_currency.load().await {
+            // don't test if min_currency is not defined.
+            0 => (),
+            min_currency => {
+                match currency {
+                    RequestCurrency::BotCurrency(currency) => {
+                        let currency = match currency {
+                            Some(currency) => currency,
+                            None => {
+                                return Err(RequestError::NoCurrency);
+                            }
+                        };
+
+                        let balance = currency
+                            .balance_of(channel, user)
+                            .await
+                            .map_err(RequestError::Error)?
+                            .unwrap_or_default();
+
+                        if balance.balance < min_currency {
+                            return Err(RequestError::NoBalance {
+                                currency: currency.name.clone(),
+                                required: min_currency,
+                                balance: balance.balance,
+                            });
+                        }
+                    }
+                    // Redemption uses own mechanism for paying.
+                    RequestCurrency::Redemption => (),
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Request the tracks of a Spotify playlist or album, truncated to the
+    /// configured limit.
+    ///
+    /// The same checks apply as when requesting a single Spotify track.
+    async fn request_collection(
+        &self,
+        channel: &Channel,
+        uri: &Uri,
+        user: &str,
+        real_user: Option<&chat::RealUser<'_>>,
+        currency: &RequestCurrency<'_>,
+        player: &player::Player,
+    ) -> Result<RequestOutcome, RequestError> {
+        match real_user {
+            Some(user) if user.has_scope(Scope::SongPlaylist).await => (),
+            _ => return Err(RequestError::NotAllowed("playlist")),
+        }
+
+        let source = ("Spotify", Scope::SongSpotify, &self.spotify);
+
+        let has_bypass_constraints = self
+            .check_request(channel, source, user, real_user, currency)
+            .await?;
+
+        let limit = self.playlist_max_tracks.load().await as usize;
+
+        if limit == 0 {
+            return Err(RequestError::NotEnabled("Playlist"));
+        }
+
+        let (what, items) = match player
+            .load_collection(uri, limit)
+            .await
+            .map_err(RequestError::Error)?
+        {
+            Some(collection) => collection,
+            None => {
+                return Err(RequestError::AddTrackError(
+                    player::AddTrackError::MissingAuth,
+                ))
+            }
+        };
+
+        let max_duration = self.spotify.max_duration.load().await;
+        let weight = self.weight(real_user, currency).await;
+
+        let total = items.len();
+        let mut added = 0;
+        let mut first_error = None;
+
+        for item in items {
+            let result = player
+                .add_track(
+                    user,
+                    item.track_id().clone(),
+                    has_bypass_constraints,
+                    max_duration,
+                    weight,
+                )
+                .await;
+
+            match result {
+                Ok(..) => added += 1,
+                Err(player::AddTrackError::Error(e)) => return Err(RequestError::Error(e)),
+                // NB: no point in trying to add the remaining tracks.
+                Err(
+                    e
+                    @ (player::AddTrackError::QueueFull | player::AddTrackError::PlayerClosed(..)),
+                ) => {
+                    first_error.get_or_insert(e);
+                    break;
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if added == 0 {
+            return Err(match first_error {
+                Some(e) => RequestError::AddTrackError(e),
+                None => RequestError::NoMatchingSong,
+            });
+        }
+
+        Ok(RequestOutcome::AddedCollection {
+            what,
+            added,
+            skipped: total - added,
+        })
+    }
+
     /// Perform the given song request.
     pub(crate) async fn request(
         &self,
@@ -50,12 +287,15 @@ impl SongRequester {
             return Err(RequestError::BadRequest(None));
         }
 
-        let channel = channel.to_owned();
         let request_reward = self.request_reward.load().await;
-        let spotify = self.spotify.clone();
-        let youtube = self.youtube.clone();
 
-        let track_id = match TrackId::parse_with_urls(q) {
+        if let Some(uri) = player.parse_collection(q) {
+            return self
+                .request_collection(channel, &uri, user, real_user, &currency, player)
+                .await;
+        }
+
+        let track_id = match player.parse_track(q) {
             Ok(track_id) => Some(track_id),
             Err(e) => {
                 match e {
@@ -86,78 +326,23 @@ impl SongRequester {
             }
         };
 
-        let (what, scope, enabled) = match track_id {
-            TrackId::Spotify(..) => {
-                let enabled = spotify.enabled.load().await;
-                ("Spotify", Scope::SongSpotify, enabled)
-            }
-            TrackId::YouTube(..) => {
-                let enabled = youtube.enabled.load().await;
-                ("YouTube", Scope::SongYouTube, enabled)
-            }
-        };
+        let (what, scope, constraint) = self.source(&track_id);
+        let max_duration = constraint.max_duration.load().await;
 
-        if !enabled {
-            return Err(RequestError::NotEnabled(what));
-        }
-
-        let has_bypass_constraints = if let Some(user) = real_user {
-            if !user.has_scope(scope).await {
-                return Err(RequestError::NotAllowed(what));
-            }
-
-            user.has_scope(Scope::SongBypassConstraints).await
-        } else {
-            false
-        };
-
-        let max_duration = match track_id {
-            TrackId::Spotify(_) => spotify.max_duration.load().await,
-            TrackId::YouTube(_) => youtube.max_duration.load().await,
-        };
-
-        let min_currency = match track_id {
-            TrackId::Spotify(_) => spotify.min_currency.load().await,
-            TrackId::YouTube(_) => youtube.min_currency.load().await,
-        };
+        let has_bypass_constraints = self
+            .check_request(
+                channel,
+                (what, scope, constraint),
+                user,
+                real_user,
+                &currency,
+            )
+            .await?;
 
-        if !has_bypass_constraints {
-            match min_currency {
-                // don't test if min_currency is not defined.
-                0 => (),
-                min_currency => {
-                    match currency {
-                        RequestCurrency::BotCurrency(currency) => {
-                            let currency = match currency {
-                                Some(currency) => currency,
-                                None => {
-                                    return Err(RequestError::NoCurrency);
-                                }
-                            };
-
-                            let balance = currency
-                                .balance_of(&channel, user)
-                                .await
-                                .map_err(RequestError::Error)?
-                                .unwrap_or_default();
-
-                            if balance.balance < min_currency {
-                                return Err(RequestError::NoBalance {
-                                    currency: currency.name.clone(),
-                                    required: min_currency,
-                                    balance: balance.balance,
-                                });
-                            }
-                        }
-                        // Redemption uses own mechanism for paying.
-                        RequestCurrency::Redemption => (),
-                    }
-                }
-            }
-        }
+        let weight = self.weight(real_user, &currency).await;
 
         let result = player
-            .add_track(user, track_id, has_bypass_constraints, max_duration)
+            .add_track(user, track_id, has_bypass_constraints, max_duration, weight)
             .await;
 
         let (pos, item) = match result {
@@ -180,7 +365,7 @@ impl SongRequester {
         };
 
         currency
-            .balance_add(&channel, user, request_reward as i64)
+            .balance_add(channel, user, request_reward as i64)
             .await
             .map_err(RequestError::Error)?;
 
@@ -219,6 +404,13 @@ pub(crate) enum RequestOutcome {
         amount: u32,
         what: String,
     },
+    /// Added tracks from the given playlist or album, skipping the ones
+    /// which couldn't be added.
+    AddedCollection {
+        what: String,
+        added: usize,
+        skipped: usize,
+    },
 }
 
 impl fmt::Display for RequestOutcome {
@@ -263,6 +455,18 @@ impl fmt::Display for RequestOutcome {
                     currency = currency,
                 )
             }
+            RequestOutcome::AddedCollection {
+                what,
+                added,
+                skipped,
+            } => match skipped {
+                0 => write!(f, "Added {} songs from the {}!", added, what),
+                skipped => write!(
+                    f,
+                    "Added {} songs from the {}, {} couldn't be added.",
+                    added, what, skipped
+                ),
+            },
         }
     }
 }
@@ -284,6 +488,8 @@ pub(crate) enum RequestError {
         required: i64,
         balance: i64,
     },
+    /// Too many of the user's songs have been skipped by vote recently.
+    SkipCooldown { skips: u32, cooldown: Duration },
     /// Error raised when adding track.
     AddTrackError(player::AddTrackError),
     /// A generic error.
@@ -333,6 +539,13 @@ impl fmt::Display for RequestError {
                     balance = balance,
                 }
             }
+            RequestError::SkipCooldown { skips, cooldown } => {
+                write!(
+                    f,
+                    "{} of your songs were vote skipped in the last {}, try again later :(",
+                    skips, cooldown
+                )
+            }
             RequestError::AddTrackError(e) => {
                 write!(f, "{}", e)
             }
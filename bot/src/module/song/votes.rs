@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chat::command;
+use chat::stream_info;
+use common::models::{Item, TrackId};
+use common::Duration;
+use tokio::sync::Mutex;
+
+/// Votes to skip the current song.
+#[derive(Default)]
+struct SkipVotes {
+    /// The track being voted on.
+    track_id: Option<TrackId>,
+    /// Users who have voted to skip the track.
+    voters: HashSet<String>,
+}
+
+/// The outcome of voting to skip a song.
+#[derive(Debug, PartialEq, Eq)]
+enum SkipVote {
+    /// The user has already voted to skip the song.
+    AlreadyVoted,
+    /// The vote was counted, but more votes are needed.
+    Counted(u32),
+    /// The vote reached the threshold and the song should be skipped.
+    Skip(u32),
+}
+
+impl SkipVotes {
+    /// Cast a vote by the given user to skip the given track.
+    ///
+    /// Votes are reset once the threshold is reached, so that exactly one
+    /// vote causes the track to be skipped.
+    fn vote(&mut self, track_id: &TrackId, user: &str, threshold: u32) -> SkipVote {
+        if self.track_id.as_ref() != Some(track_id) {
+            self.track_id = Some(track_id.clone());
+            self.voters.clear();
+        }
+
+        if !self.voters.insert(user.to_string()) {
+            return SkipVote::AlreadyVoted;
+        }
+
+        let votes = self.voters.len() as u32;
+
+        if votes < threshold {
+            return SkipVote::Counted(votes);
+        }
+
+        *self = SkipVotes::default();
+        SkipVote::Skip(votes)
+    }
+
+    /// Count the votes cast to skip the given track.
+    fn count(&self, track_id: &TrackId) -> u32 {
+        if self.track_id.as_ref() == Some(track_id) {
+            self.voters.len() as u32
+        } else {
+            0
+        }
+    }
+}
+
+/// Handles viewer votes on the current song.
+pub(super) struct Votes {
+    voteskip_enabled: settings::Var<bool>,
+    min_votes: settings::Var<u32>,
+    percentage: settings::Var<Option<u32>>,
+    active_window: settings::Var<Duration>,
+    song_votes: async_injector::Ref<db::SongVotes>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+    stream_info: stream_info::StreamInfo,
+    skips: Mutex<SkipVotes>,
+}
+
+impl Votes {
+    pub(super) fn new(
+        voteskip_enabled: settings::Var<bool>,
+        min_votes: settings::Var<u32>,
+        percentage: settings::Var<Option<u32>>,
+        active_window: settings::Var<Duration>,
+        song_votes: async_injector::Ref<db::SongVotes>,
+        global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+        stream_info: stream_info::StreamInfo,
+    ) -> Self {
+        Self {
+            voteskip_enabled,
+            min_votes,
+            percentage,
+            active_window,
+            song_votes,
+            global_bus,
+            stream_info,
+            skips: Mutex::default(),
+        }
+    }
+
+    /// Vote to skip the current song, skipping it once enough votes have been
+    /// cast.
+    ///
+    /// Returns the song if it was skipped.
+    pub(super) async fn voteskip(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+    ) -> Result<Option<Arc<Item>>> {
+        if !self.voteskip_enabled.load().await {
+            chat::respond_bail!("Vote skipping is not enabled");
+        }
+
+        let user = ctx
+            .user
+            .real()
+            .ok_or(chat::respond_err!("Only real users can vote to skip songs"))?;
+
+        let threshold = self.threshold().await;
+
+        // NB: the current song is looked up and skipped while holding the
+        // lock, so that concurrent votes can't skip the next song as well.
+        let (current, vote) = {
+            let mut skips = self.skips.lock().await;
+
+            let current = player
+                .current()
+                .await
+                .ok_or(chat::respond_err!("No song is playing"))?;
+
+            let vote = skips.vote(current.item().track_id(), user.login(), threshold);
+
+            if let SkipVote::Skip(..) = vote {
+                player.skip().await?;
+            }
+
+            (current, vote)
+        };
+
+        let track_id = current.item().track_id();
+
+        let (votes, skipped) = match vote {
+            SkipVote::AlreadyVoted => {
+                chat::respond_bail!("You already voted to skip this song");
+            }
+            SkipVote::Counted(votes) => (votes, false),
+            SkipVote::Skip(votes) => (votes, true),
+        };
+
+        let song_votes = self.song_votes.load().await;
+
+        let likes = match &song_votes {
+            Some(song_votes) => song_votes.likes(track_id).await?,
+            None => db::SongLikes::default(),
+        };
+
+        self.emit(track_id, votes, threshold, likes).await;
+
+        if !skipped {
+            chat::respond!(
+                ctx,
+                "Voted to skip {} ({}/{})",
+                current.item().what(),
+                votes,
+                threshold
+            );
+            return Ok(None);
+        }
+
+        if let Some(song_votes) = &song_votes {
+            song_votes
+                .insert_skip(track_id, current.item().user().map(String::as_str), votes)
+                .await?;
+        }
+
+        chat::respond!(
+            ctx,
+            "Skipped {} after {} votes",
+            current.item().what(),
+            votes
+        );
+        Ok(Some(current.into_item()))
+    }
+
+    /// Like or dislike the current song.
+    pub(super) async fn like(
+        &self,
+        ctx: &mut command::Context<'_>,
+        player: &player::Player,
+        liked: bool,
+    ) -> Result<()> {
+        let user = ctx
+            .user
+            .real()
+            .ok_or(chat::respond_err!("Only real users can vote on songs"))?;
+
+        let song_votes = self
+            .song_votes
+            .load()
+            .await
+            .ok_or(chat::respond_err!("Song votes are not available"))?;
+
+        let current = player
+            .current()
+            .await
+            .ok_or(chat::respond_err!("No song is playing"))?;
+
+        let track_id = current.item().track_id();
+
+        song_votes.set_like(track_id, user.login(), liked).await?;
+        let likes = song_votes.likes(track_id).await?;
+
+        let votes = self.skips.lock().await.count(track_id);
+
+        let threshold = self.threshold().await;
+        self.emit(track_id, votes, threshold, likes).await;
+
+        chat::respond!(
+            ctx,
+            "You {} {} ({} likes, {} dislikes)",
+            if liked { "liked" } else { "disliked" },
+            current.item().what(),
+            likes.likes,
+            likes.dislikes
+        );
+        Ok(())
+    }
+
+    /// Calculate the number of votes currently required to skip a song.
+    async fn threshold(&self) -> u32 {
+        let min_votes = self.min_votes.load().await;
+        let percentage = self.percentage.load().await;
+        let window = self.active_window.load().await;
+        let active = self.stream_info.active_chatters(window.as_std());
+        threshold(min_votes, percentage, active)
+    }
+
+    /// Emit the votes cast on the given track on the global bus.
+    async fn emit(
+        &self,
+        track_id: &TrackId,
+        skip_votes: u32,
+        skip_threshold: u32,
+        likes: db::SongLikes,
+    ) {
+        let Some(global_bus) = self.global_bus.load().await else {
+            return;
+        };
+
+        global_bus
+            .send(bus::Global::SongVotes {
+                track_id: track_id.clone(),
+                skip_votes,
+                skip_threshold,
+                likes: likes.likes,
+                dislikes: likes.dislikes,
+            })
+            .await;
+    }
+}
+
+/// Calculate the number of votes required to skip a song, given the minimum
+/// number of votes and the percentage of active chatters who have to vote.
+fn threshold(min_votes: u32, percentage: Option<u32>, active: usize) -> u32 {
+    let min_votes = min_votes.max(1);
+
+    let Some(percentage) = percentage else {
+        return min_votes;
+    };
+
+    let required = (active as u64 * u64::from(percentage)).div_ceil(100);
+    min_votes.max(u32::try_from(required).unwrap_or(u32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::models::TrackId;
+
+    use super::{threshold, SkipVote, SkipVotes};
+
+    #[test]
+    fn test_skip_votes() {
+        let a = TrackId::Local(String::from("a.mp3"));
+        let b = TrackId::Local(String::from("b.mp3"));
+        let mut skips = SkipVotes::default();
+
+        assert_eq!(skips.vote(&a, "alice", 3), SkipVote::Counted(1));
+        assert_eq!(skips.vote(&a, "alice", 3), SkipVote::AlreadyVoted);
+        assert_eq!(skips.vote(&a, "bob", 3), SkipVote::Counted(2));
+        assert_eq!(skips.count(&a), 2);
+        assert_eq!(skips.count(&b), 0);
+
+        // votes for another track start over.
+        assert_eq!(skips.vote(&b, "alice", 2), SkipVote::Counted(1));
+        assert_eq!(skips.count(&a), 0);
+        assert_eq!(skips.vote(&b, "bob", 2), SkipVote::Skip(2));
+
+        // votes are reset once the track is skipped.
+        assert_eq!(skips.count(&b), 0);
+        assert_eq!(skips.vote(&b, "carol", 2), SkipVote::Counted(1));
+
+        // a lowered threshold skips on the next vote.
+        assert_eq!(skips.vote(&b, "dave", 1), SkipVote::Skip(2));
+    }
+
+    #[test]
+    fn test_threshold() {
+        assert_eq!(threshold(3, None, 100), 3);
+        assert_eq!(threshold(0, None, 100), 1);
+        assert_eq!(threshold(3, Some(10), 100), 10);
+        assert_eq!(threshold(3, Some(10), 101), 11);
+        assert_eq!(threshold(3, Some(10), 5), 3);
+        assert_eq!(threshold(3, Some(50), 0), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use common::Duration;
+
+use chat::command;
+use chat::module;
+
+/// Handler for the `!strikes` command.
+pub(crate) struct Strikes {
+    pub(crate) decay: settings::Var<Duration>,
+    pub(crate) strikes: async_injector::Ref<db::Strikes>,
+}
+
+#[async_trait]
+impl command::Handler for Strikes {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Strikes)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        let strikes = match self.strikes.load().await {
+            Some(strikes) => strikes,
+            None => return Ok(()),
+        };
+
+        let user = ctx.next_str("<user>")?;
+        let user = db::user_id(&user);
+        let decay = self.decay.load().await;
+
+        let strikes = strikes.list_by_user(ctx.channel(), &user, &decay).await?;
+
+        if strikes.is_empty() {
+            chat::respond!(ctx, "{} has no active strikes.", user);
+            return Ok(());
+        }
+
+        let reasons = strikes
+            .iter()
+            .map(|s| format!("{} ({})", s.reason, s.action))
+            .collect::<Vec<_>>();
+
+        chat::respond!(
+            ctx,
+            "{} has {} active strike(s) in the last {}: {}.",
+            user,
+            strikes.len(),
+            decay,
+            reasons.join(", ")
+        );
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "strikes"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "strikes",
+            Strikes {
+                decay: settings
+                    .var("chat/strikes/decay", Duration::hours(1))
+                    .await?,
+                strikes: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::display;
+use currency::{BalanceOrder, Currency, TOP_DEFAULT, TOP_MAX};
+
+/// Handler for the !watchtime command.
+pub(crate) struct WatchTime {
+    enabled: settings::Var<bool>,
+    currency: async_injector::Ref<Currency>,
+}
+
+#[async_trait]
+impl command::Handler for WatchTime {
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = self
+            .currency
+            .load()
+            .await
+            .ok_or(chat::respond_err!("No currency configured"))?;
+
+        if !currency.tracks_watch_time() {
+            chat::respond_bail!("Watch time is not tracked by the configured currency");
+        }
+
+        match ctx.next().as_deref() {
+            Some("top") => {
+                let n = ctx
+                    .next_parse_optional::<usize>()?
+                    .unwrap_or(TOP_DEFAULT)
+                    .clamp(1, TOP_MAX);
+
+                let balances = currency
+                    .balances_top(ctx.channel(), BalanceOrder::WatchTime, 0, n)
+                    .await?;
+
+                let top = balances
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        let watch_time = std::time::Duration::from_secs(
+                            u64::try_from(b.watch_time).unwrap_or(0),
+                        );
+
+                        format!(
+                            "{}. {} ({})",
+                            i + 1,
+                            b.user,
+                            display::compact_duration(watch_time)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                if top.is_empty() {
+                    chat::respond!(ctx, "Nobody has any watch time yet");
+                } else {
+                    chat::respond!(ctx, "Top watch time: {}", top.join(", "));
+                }
+            }
+            _ => {
+                ctx.respond("Expected: top [n].").await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "watchtime"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            settings,
+            injector,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "watchtime",
+            WatchTime {
+                enabled: settings.var("watchtime/enabled", false).await?,
+                currency: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! Spotify API helpers.
 
 use anyhow::Result;
+use common::models::spotify::album::FullAlbum;
 use common::models::spotify::context::FullPlayingContext;
 use common::models::spotify::device::Device;
 use common::models::spotify::page::Page;
@@ -20,6 +21,8 @@ use crate::token::Token;
 
 const API_URL: &str = "https://api.spotify.com/v1";
 const DEFAULT_LIMIT: usize = 50;
+/// The maximum number of tracks which can be fetched in a single request.
+pub const TRACKS_LIMIT: usize = 50;
 
 /// API integration.
 #[derive(Clone, Debug)]
@@ -237,6 +240,39 @@ impl Spotify {
         req.execute().await?.json()
     }
 
+    /// Get several full tracks by ID.
+    ///
+    /// At most [TRACKS_LIMIT] tracks can be requested at a time. Tracks which
+    /// don't exist are left out.
+    pub async fn tracks(&self, ids: &[String], market: Option<&str>) -> Result<Vec<FullTrack>> {
+        let mut req = self.request(Method::GET, &["tracks"]);
+        req.query_param("ids", ids.join(","));
+
+        if let Some(market) = market {
+            req.query_param("market", market);
+        }
+
+        let r = req.execute().await?.json::<Response>()?;
+        return Ok(r.tracks.into_iter().flatten().collect());
+
+        #[derive(Deserialize)]
+        struct Response {
+            // NB: unknown ids are returned as nulls.
+            tracks: Vec<Option<FullTrack>>,
+        }
+    }
+
+    /// Get the full album by ID.
+    pub async fn album(&self, id: SpotifyId, market: Option<&str>) -> Result<FullAlbum> {
+        let mut req = self.request(Method::GET, &["albums", id.to_string().as_str()]);
+
+        if let Some(market) = market {
+            req.query_param("market", market);
+        }
+
+        req.execute().await?.json()
+    }
+
     /// Search for tracks.
     pub async fn search_track(&self, q: &str, limit: u32) -> Result<SearchTracks> {
         self.request(Method::GET, &["search"])
@@ -4,7 +4,7 @@ mod gql;
 
 pub mod model;
 
-pub mod pubsub;
+pub mod eventsub;
 
 use anyhow::Result;
 use common::stream::Stream;
@@ -22,6 +22,8 @@ const GQL_URL: &str = "https://gql.twitch.tv/gql";
 const GQL_CLIENT_ID: &str = "kimne78kx3ncx6brgo4mv6wki5h1ko";
 /// Common header.
 const BROADCASTER_ID: &str = "broadcaster_id";
+/// Common header for moderation endpoints.
+const MODERATOR_ID: &str = "moderator_id";
 
 #[derive(Debug, Error)]
 pub(crate) enum Error {
@@ -55,6 +57,12 @@ impl Twitch {
         })
     }
 
+    /// Send API requests to the given base URL instead of the Twitch API.
+    pub fn with_api_url(mut self, api_url: &str) -> Result<Self> {
+        self.api_url = str::parse::<Url>(api_url)?;
+        Ok(self)
+    }
+
     /// Access bearer token for the current Twitch client.
     pub fn token(&self) -> &Token {
         &self.token
@@ -161,8 +169,8 @@ impl Twitch {
     pub async fn patch_redemptions(
         &self,
         broadcaster_id: &str,
-        redemption: &pubsub::Redemption,
-        status: pubsub::Status,
+        redemption: &eventsub::Redemption,
+        status: eventsub::Status,
     ) -> Result<()> {
         let mut req = self.new_api(
             Method::PATCH,
@@ -184,7 +192,261 @@ impl Twitch {
 
         #[derive(Serialize)]
         struct UpdateRedemption {
-            status: pubsub::Status,
+            status: eventsub::Status,
+        }
+    }
+
+    /// Create an EventSub subscription.
+    pub async fn create_eventsub_subscription(
+        &self,
+        request: &model::CreateEventSubSubscription<'_>,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(request)?;
+
+        self.new_api(Method::POST, &["eventsub", "subscriptions"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .ok()
+    }
+
+    /// Get the user with the given login.
+    pub async fn user_by_login(&self, login: &str) -> Result<Option<model::User>> {
+        let mut req = self.new_api(Method::GET, &["users"]);
+        req.query_param("login", login);
+        let data = req.execute().await?.json::<Data<Vec<model::User>>>()?;
+        Ok(data.data.into_iter().next())
+    }
+
+    /// Delete a single chat message.
+    pub async fn delete_chat_message(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        message_id: &str,
+    ) -> Result<()> {
+        self.new_api(Method::DELETE, &["moderation", "chat"])
+            .query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id)
+            .query_param("message_id", message_id)
+            .empty_body()
+            .execute()
+            .await?
+            .ok()
+    }
+
+    /// Delete all messages in chat.
+    pub async fn clear_chat(&self, broadcaster_id: &str, moderator_id: &str) -> Result<()> {
+        self.new_api(Method::DELETE, &["moderation", "chat"])
+            .query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id)
+            .empty_body()
+            .execute()
+            .await?
+            .ok()
+    }
+
+    /// Ban a user, or time them out if a duration is specified.
+    pub async fn ban_user(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        request: model::BanUserRequest<'_>,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(&Data { data: request })?;
+
+        self.new_api(Method::POST, &["moderation", "bans"])
+            .query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .ok()
+    }
+
+    /// Remove a ban or timeout from a user.
+    pub async fn unban_user(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        user_id: &str,
+    ) -> Result<()> {
+        self.new_api(Method::DELETE, &["moderation", "bans"])
+            .query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id)
+            .query_param("user_id", user_id)
+            .empty_body()
+            .execute()
+            .await?
+            .ok()
+    }
+
+    /// Get the shield mode status of the channel.
+    pub async fn shield_mode(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+    ) -> Result<Option<model::ShieldModeStatus>> {
+        let mut req = self.new_api(Method::GET, &["moderation", "shield_mode"]);
+
+        req.query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id);
+
+        let data = req
+            .execute()
+            .await?
+            .json::<Data<Vec<model::ShieldModeStatus>>>()?;
+
+        Ok(data.data.into_iter().next())
+    }
+
+    /// Activate or deactivate shield mode in the channel.
+    pub async fn update_shield_mode(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        is_active: bool,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(&UpdateShieldMode { is_active })?;
+
+        self.new_api(Method::PUT, &["moderation", "shield_mode"])
+            .query_param(BROADCASTER_ID, broadcaster_id)
+            .query_param(MODERATOR_ID, moderator_id)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .ok()?;
+
+        return Ok(());
+
+        #[derive(Serialize)]
+        struct UpdateShieldMode {
+            is_active: bool,
+        }
+    }
+
+    /// Get polls for the given broadcaster, most recent first.
+    pub fn polls(&self, broadcaster_id: &str) -> impl Stream<Item = Result<model::Poll>> + '_ {
+        let mut req = self.new_api(Method::GET, &["polls"]);
+        req.query_param(BROADCASTER_ID, broadcaster_id);
+        page(req)
+    }
+
+    /// Create a native poll.
+    pub async fn create_poll(
+        &self,
+        request: model::CreatePollRequest<'_>,
+    ) -> Result<Option<model::Poll>> {
+        let body = serde_json::to_vec(&request)?;
+
+        let res = self
+            .new_api(Method::POST, &["polls"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .json::<Data<Vec<model::Poll>>>()?;
+
+        Ok(res.data.into_iter().next())
+    }
+
+    /// End a native poll, either terminating it so that its results are
+    /// shown, or archiving it so that they are hidden.
+    pub async fn end_poll(
+        &self,
+        broadcaster_id: &str,
+        id: &str,
+        status: model::PollStatus,
+    ) -> Result<Option<model::Poll>> {
+        let body = serde_json::to_vec(&EndPoll {
+            broadcaster_id,
+            id,
+            status,
+        })?;
+
+        let res = self
+            .new_api(Method::PATCH, &["polls"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .json::<Data<Vec<model::Poll>>>()?;
+
+        return Ok(res.data.into_iter().next());
+
+        #[derive(Serialize)]
+        struct EndPoll<'a> {
+            broadcaster_id: &'a str,
+            id: &'a str,
+            status: model::PollStatus,
+        }
+    }
+
+    /// Get predictions for the given broadcaster, most recent first.
+    pub fn predictions(
+        &self,
+        broadcaster_id: &str,
+    ) -> impl Stream<Item = Result<model::Prediction>> + '_ {
+        let mut req = self.new_api(Method::GET, &["predictions"]);
+        req.query_param(BROADCASTER_ID, broadcaster_id);
+        page(req)
+    }
+
+    /// Create a native prediction.
+    pub async fn create_prediction(
+        &self,
+        request: model::CreatePredictionRequest<'_>,
+    ) -> Result<Option<model::Prediction>> {
+        let body = serde_json::to_vec(&request)?;
+
+        let res = self
+            .new_api(Method::POST, &["predictions"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .json::<Data<Vec<model::Prediction>>>()?;
+
+        Ok(res.data.into_iter().next())
+    }
+
+    /// Lock, resolve or cancel a native prediction.
+    ///
+    /// A winning outcome must be specified when resolving a prediction.
+    pub async fn end_prediction(
+        &self,
+        broadcaster_id: &str,
+        id: &str,
+        status: model::PredictionStatus,
+        winning_outcome_id: Option<&str>,
+    ) -> Result<Option<model::Prediction>> {
+        let body = serde_json::to_vec(&EndPrediction {
+            broadcaster_id,
+            id,
+            status,
+            winning_outcome_id,
+        })?;
+
+        let res = self
+            .new_api(Method::PATCH, &["predictions"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .json::<Data<Vec<model::Prediction>>>()?;
+
+        return Ok(res.data.into_iter().next());
+
+        #[derive(Serialize)]
+        struct EndPrediction<'a> {
+            broadcaster_id: &'a str,
+            id: &'a str,
+            status: model::PredictionStatus,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            winning_outcome_id: Option<&'a str>,
         }
     }
 
@@ -0,0 +1,1066 @@
+//! Websocket EventSub integration for twitch.
+//!
+//! See <https://dev.twitch.tv/docs/eventsub/handling-websocket-events/>.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_fuse::Fuse;
+use async_injector::{Injector, Key};
+use backoff::backoff::Backoff;
+use chrono::{DateTime, Utc};
+use common::stream::Stream;
+use common::{tags, BoxStream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::time::{self, Sleep};
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::Instrument;
+
+use crate::twitch::model;
+
+const URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+
+/// How long we wait for a welcome message after connecting.
+const WELCOME_TIMEOUT: Duration = Duration::from_secs(10);
+/// Grace period added on top of the keepalive timeout advertised by the
+/// session.
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(5);
+
+/// Subscription types.
+const REDEMPTION: &str = "channel.channel_points_custom_reward_redemption.add";
+const FOLLOW: &str = "channel.follow";
+const SUBSCRIBE: &str = "channel.subscribe";
+const SUBSCRIPTION_GIFT: &str = "channel.subscription.gift";
+const CHEER: &str = "channel.cheer";
+const RAID: &str = "channel.raid";
+const STREAM_ONLINE: &str = "stream.online";
+const STREAM_OFFLINE: &str = "stream.offline";
+const POLL_BEGIN: &str = "channel.poll.begin";
+const POLL_PROGRESS: &str = "channel.poll.progress";
+const POLL_END: &str = "channel.poll.end";
+const PREDICTION_BEGIN: &str = "channel.prediction.begin";
+const PREDICTION_PROGRESS: &str = "channel.prediction.progress";
+const PREDICTION_LOCK: &str = "channel.prediction.lock";
+const PREDICTION_END: &str = "channel.prediction.end";
+
+/// Websocket EventSub integration for twitch.
+#[derive(Clone)]
+pub struct TwitchEventSub {
+    inner: Arc<Inner>,
+}
+
+impl TwitchEventSub {
+    fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                events: broadcast::channel(1024).0,
+            }),
+        }
+    }
+
+    /// Subscribe for all events.
+    pub fn events(&self) -> TwitchStream<Event> {
+        self.filtered(Some)
+    }
+
+    /// Subscribe for redemptions.
+    pub fn redemptions(&self) -> TwitchStream<Redemption> {
+        self.filtered(|event| match event {
+            Event::Redemption(redemption) => Some(redemption),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for follows.
+    pub fn follows(&self) -> TwitchStream<Follow> {
+        self.filtered(|event| match event {
+            Event::Follow(follow) => Some(follow),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for new subscriptions.
+    pub fn subscriptions(&self) -> TwitchStream<Subscribe> {
+        self.filtered(|event| match event {
+            Event::Subscribe(subscribe) => Some(subscribe),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for gifted subscriptions.
+    pub fn subscription_gifts(&self) -> TwitchStream<SubscriptionGift> {
+        self.filtered(|event| match event {
+            Event::SubscriptionGift(gift) => Some(gift),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for cheers.
+    pub fn cheers(&self) -> TwitchStream<Cheer> {
+        self.filtered(|event| match event {
+            Event::Cheer(cheer) => Some(cheer),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for incoming raids.
+    pub fn raids(&self) -> TwitchStream<Raid> {
+        self.filtered(|event| match event {
+            Event::Raid(raid) => Some(raid),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for the stream going online.
+    pub fn stream_online(&self) -> TwitchStream<StreamOnline> {
+        self.filtered(|event| match event {
+            Event::StreamOnline(online) => Some(online),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for the stream going offline.
+    pub fn stream_offline(&self) -> TwitchStream<StreamOffline> {
+        self.filtered(|event| match event {
+            Event::StreamOffline(offline) => Some(offline),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for updates to native polls.
+    pub fn polls(&self) -> TwitchStream<PollUpdate> {
+        self.filtered(|event| match event {
+            Event::PollBegin(poll) => Some(PollUpdate::Begin(poll)),
+            Event::PollProgress(poll) => Some(PollUpdate::Progress(poll)),
+            Event::PollEnd(poll) => Some(PollUpdate::End(poll)),
+            _ => None,
+        })
+    }
+
+    /// Subscribe for updates to native predictions.
+    pub fn predictions(&self) -> TwitchStream<PredictionUpdate> {
+        self.filtered(|event| match event {
+            Event::PredictionBegin(prediction) => Some(PredictionUpdate::Begin(prediction)),
+            Event::PredictionProgress(prediction) => Some(PredictionUpdate::Progress(prediction)),
+            Event::PredictionLock(prediction) => Some(PredictionUpdate::Lock(prediction)),
+            Event::PredictionEnd(prediction) => Some(PredictionUpdate::End(prediction)),
+            _ => None,
+        })
+    }
+
+    /// Construct a stream of events matching the given filter.
+    fn filtered<T>(&self, filter: fn(Event) -> Option<T>) -> TwitchStream<T>
+    where
+        T: 'static + Send,
+    {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut s = self.inner.events.subscribe();
+
+        TwitchStream {
+            stream: Box::pin(async_stream::stream! {
+                loop {
+                    match s.recv().await {
+                        Ok(event) => {
+                            if let Some(item) = filter(event) {
+                                yield item;
+                            }
+                        }
+                        Err(RecvError::Closed) => break,
+                        Err(RecvError::Lagged(..)) => (),
+                    }
+                }
+            }),
+        }
+    }
+}
+
+pub struct TwitchStream<T> {
+    stream: BoxStream<'static, T>,
+}
+
+impl<T> Stream for TwitchStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+struct Client {
+    stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+}
+
+impl Client {
+    /// Connect to the given websocket url.
+    async fn connect(url: &str) -> Result<Self> {
+        let uri = str::parse::<Uri>(url)?;
+        let req = uri.into_client_request()?;
+        let (stream, _) = tokio_tungstenite::connect_async(req).await?;
+        Ok(Self { stream })
+    }
+
+    /// Receive the next text message.
+    async fn recv(&mut self) -> Option<Result<String>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<String>>> {
+        loop {
+            let message = match Pin::new(&mut self.as_mut().stream).poll_next(cx)? {
+                Poll::Ready(message) => message,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let message = match message {
+                Some(message) => message,
+                None => return Poll::Ready(None),
+            };
+
+            let text = match message {
+                tungstenite::Message::Text(text) => text,
+                tungstenite::Message::Close(..) => return Poll::Ready(None),
+                // NB: pings are answered by tungstenite.
+                tungstenite::Message::Ping(..) | tungstenite::Message::Pong(..) => continue,
+                message => {
+                    tracing::warn!("Unhandled websocket message: {:?}", message);
+                    continue;
+                }
+            };
+
+            return Poll::Ready(Some(Ok(text)));
+        }
+    }
+}
+
+/// Connect to the EventSub websocket once available.
+#[tracing::instrument(skip_all)]
+pub fn connect<S>(
+    settings: &settings::Settings<S>,
+    injector: &Injector,
+) -> impl Future<Output = Result<()>>
+where
+    S: settings::Scope,
+{
+    task(settings.clone(), injector.clone()).in_current_span()
+}
+
+/// A single step performed by the connection state.
+enum Step {
+    /// A message, error, or end of the websocket stream.
+    Message(Option<Result<String>>),
+    /// We haven't heard from the server within the keepalive timeout.
+    KeepaliveExpired,
+    /// Time to attempt to reconnect.
+    Reconnect,
+}
+
+struct State {
+    enabled: bool,
+    url: String,
+    eventsub: TwitchEventSub,
+    client: Fuse<Client>,
+    session_id: Option<String>,
+    streamer: Option<crate::TwitchAndUser>,
+    keepalive_timeout: Duration,
+    keepalive_deadline: Fuse<Pin<Box<Sleep>>>,
+    reconnect: Fuse<Pin<Box<Sleep>>>,
+    reconnect_backoff: backoff::ExponentialBackoff,
+}
+
+impl State {
+    fn new(eventsub: TwitchEventSub, url: String) -> Self {
+        Self {
+            enabled: false,
+            url,
+            eventsub,
+            client: Fuse::empty(),
+            session_id: None,
+            streamer: None,
+            keepalive_timeout: WELCOME_TIMEOUT,
+            keepalive_deadline: Fuse::empty(),
+            reconnect: Fuse::empty(),
+            reconnect_backoff: {
+                let mut backoff = backoff::ExponentialBackoff::default();
+                backoff.current_interval = Duration::from_secs(5);
+                backoff.initial_interval = Duration::from_secs(5);
+                backoff.max_elapsed_time = None;
+                backoff
+            },
+        }
+    }
+
+    /// Disconnect and clear client (if connected).
+    async fn disconnect(&mut self) {
+        if let Some(client) = self.client.as_inner_mut() {
+            if let Err(e) = client.stream.close(None).await {
+                common::log_error!(e, "Error when closing stream");
+            }
+
+            tracing::info!("Disconnected from Twitch EventSub!");
+        }
+
+        self.client.clear();
+        self.session_id = None;
+    }
+
+    /// Clear state.
+    async fn clear(&mut self) {
+        self.disconnect().await;
+        self.keepalive_deadline.clear();
+        self.reconnect.clear();
+    }
+
+    /// An error happened, try to automatically recover the connection.
+    async fn recover(&mut self) {
+        self.clear().await;
+
+        // NB: if still enabled, set a reconnect.
+        if self.enabled {
+            tracing::info!("Attempting to reconnect");
+            let backoff = self.reconnect_backoff.next_backoff().unwrap_or_default();
+            tracing::warn!("Reconnecting in {:?}", backoff);
+            self.reconnect.set(Box::pin(time::sleep(backoff)));
+        }
+    }
+
+    /// Push the keepalive deadline into the future.
+    fn reset_keepalive(&mut self) {
+        self.keepalive_deadline
+            .set(Box::pin(time::sleep(self.keepalive_timeout)));
+    }
+
+    // Rebuild state from the current configuration.
+    async fn build(&mut self) {
+        if !self.enabled || self.streamer.is_none() {
+            self.clear().await;
+            return;
+        }
+
+        if let Err(e) = self.connect().await {
+            common::log_error!(e, "Failed to build EventSub client");
+            self.recover().await;
+        }
+    }
+
+    /// Open a new connection, expecting a welcome message to follow.
+    async fn connect(&mut self) -> Result<()> {
+        self.clear().await;
+
+        tracing::trace!("Connecting to Twitch EventSub");
+
+        let client = Client::connect(&self.url).await?;
+        self.client.set(client);
+        self.keepalive_timeout = WELCOME_TIMEOUT;
+        self.reset_keepalive();
+        Ok(())
+    }
+
+    /// Wait for the next thing to happen to the connection.
+    async fn next(&mut self) -> Step {
+        tokio::select! {
+            message = self.client.as_pin_mut().poll_stream(Client::poll_next) => {
+                Step::Message(message)
+            }
+            _ = &mut self.keepalive_deadline => {
+                Step::KeepaliveExpired
+            }
+            _ = &mut self.reconnect => {
+                Step::Reconnect
+            }
+        }
+    }
+
+    /// Handle a step produced by [State::next].
+    async fn handle(&mut self, step: Step) {
+        match step {
+            Step::Message(Some(Ok(message))) => {
+                if let Err(e) = self.handle_frame(&message).await {
+                    common::log_error!(e, "Failed to handle message");
+                }
+            }
+            Step::Message(Some(Err(e))) => {
+                common::log_error!(e, "Error in websocket");
+                self.recover().await;
+            }
+            Step::Message(None) => {
+                tracing::error!("End of websocket stream");
+                self.recover().await;
+            }
+            Step::KeepaliveExpired => {
+                tracing::warn!("Did not receive keepalive in time!");
+                self.recover().await;
+            }
+            Step::Reconnect => {
+                self.build().await;
+            }
+        }
+    }
+
+    fn deserialize_frame(text: &str) -> Result<self::transport::Frame> {
+        let frame = serde_json::from_str::<self::transport::Frame>(text);
+
+        match frame {
+            Ok(frame) => Ok(frame),
+            Err(e) => {
+                tracing::trace!("<< raw: {}", text);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Handle an incoming message as a frame.
+    async fn handle_frame(&mut self, text: &str) -> Result<()> {
+        use self::transport::MessageType;
+
+        let frame = Self::deserialize_frame(text)?;
+        tracing::trace!("<< {:?}", frame);
+
+        // NB: any message from the server counts as a keepalive.
+        self.reset_keepalive();
+
+        match frame.metadata.message_type {
+            MessageType::SessionWelcome => {
+                let session = frame.payload.session.ok_or(Missing::Session)?;
+                self.welcome(session).await;
+            }
+            MessageType::SessionKeepalive => {}
+            MessageType::Notification => {
+                let subscription = frame.payload.subscription.ok_or(Missing::Subscription)?;
+                let event = frame.payload.event.ok_or(Missing::Event)?;
+                self.handle_notification(&subscription.ty, event)?;
+            }
+            MessageType::SessionReconnect => {
+                let session = frame.payload.session.ok_or(Missing::Session)?;
+                let url = session.reconnect_url.ok_or(Missing::ReconnectUrl)?;
+                self.reconnect_to(&url).await?;
+            }
+            MessageType::Revocation => {
+                let subscription = frame.payload.subscription.ok_or(Missing::Subscription)?;
+
+                tracing::warn!(
+                    "Subscription `{}` was revoked: {}",
+                    subscription.ty,
+                    subscription.status
+                );
+            }
+            MessageType::Unknown => {
+                bail!("Unsupported frame: {:?}", text);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle the welcome message of a newly established session.
+    async fn welcome(&mut self, session: self::transport::Session) {
+        self.set_session(&session);
+        tracing::info!("Connected to Twitch EventSub!");
+
+        let streamer = match &self.streamer {
+            Some(streamer) => streamer,
+            None => return,
+        };
+
+        for request in subscriptions(&streamer.user.id, &session.id) {
+            if let Err(e) = streamer.client.create_eventsub_subscription(&request).await {
+                common::log_error!(e, "Failed to subscribe to `{}`", request.ty);
+            }
+        }
+    }
+
+    /// Adopt the given session.
+    fn set_session(&mut self, session: &self::transport::Session) {
+        if let Some(seconds) = session.keepalive_timeout_seconds {
+            self.keepalive_timeout = Duration::from_secs(seconds) + KEEPALIVE_GRACE;
+        }
+
+        self.session_id = Some(session.id.clone());
+        self.reconnect_backoff.reset();
+        self.reset_keepalive();
+    }
+
+    /// Migrate to the given url as requested by the server.
+    ///
+    /// Subscriptions carry over to the new session, so they don't have to be
+    /// created again. The old connection is kept until the new one has been
+    /// welcomed.
+    async fn reconnect_to(&mut self, url: &str) -> Result<()> {
+        use self::transport::MessageType;
+
+        tracing::info!("Reconnecting to Twitch EventSub as requested");
+
+        let mut client = Client::connect(url).await?;
+
+        let text = match time::timeout(WELCOME_TIMEOUT, client.recv()).await {
+            Ok(Some(text)) => text?,
+            Ok(None) => bail!("Connection closed before welcome"),
+            Err(..) => bail!("Did not receive welcome in time"),
+        };
+
+        let frame = Self::deserialize_frame(&text)?;
+        tracing::trace!("<< {:?}", frame);
+
+        let session = match (frame.metadata.message_type, frame.payload.session) {
+            (MessageType::SessionWelcome, Some(session)) => session,
+            _ => bail!("Expected welcome, but got: {:?}", text),
+        };
+
+        if let Some(old) = self.client.as_inner_mut() {
+            if let Err(e) = old.stream.close(None).await {
+                common::log_error!(e, "Error when closing old stream");
+            }
+        }
+
+        self.client.set(client);
+        self.set_session(&session);
+        tracing::info!("Reconnected to Twitch EventSub!");
+        Ok(())
+    }
+
+    /// Handle a notification for the given subscription type.
+    fn handle_notification(&mut self, ty: &str, event: serde_json::Value) -> Result<()> {
+        let event = match ty {
+            REDEMPTION => Event::Redemption(serde_json::from_value(event)?),
+            FOLLOW => Event::Follow(serde_json::from_value(event)?),
+            SUBSCRIBE => Event::Subscribe(serde_json::from_value(event)?),
+            SUBSCRIPTION_GIFT => Event::SubscriptionGift(serde_json::from_value(event)?),
+            CHEER => Event::Cheer(serde_json::from_value(event)?),
+            RAID => Event::Raid(serde_json::from_value(event)?),
+            STREAM_ONLINE => Event::StreamOnline(serde_json::from_value(event)?),
+            STREAM_OFFLINE => Event::StreamOffline(serde_json::from_value(event)?),
+            POLL_BEGIN => Event::PollBegin(serde_json::from_value(event)?),
+            POLL_PROGRESS => Event::PollProgress(serde_json::from_value(event)?),
+            POLL_END => Event::PollEnd(serde_json::from_value(event)?),
+            PREDICTION_BEGIN => Event::PredictionBegin(serde_json::from_value(event)?),
+            PREDICTION_PROGRESS => Event::PredictionProgress(serde_json::from_value(event)?),
+            PREDICTION_LOCK => Event::PredictionLock(serde_json::from_value(event)?),
+            PREDICTION_END => Event::PredictionEnd(serde_json::from_value(event)?),
+            other => bail!("Unsupported subscription type `{}`", other),
+        };
+
+        let _ = self.eventsub.inner.events.send(event);
+        Ok(())
+    }
+}
+
+/// The subscriptions to create for a new session.
+fn subscriptions<'a>(
+    user_id: &'a str,
+    session_id: &'a str,
+) -> Vec<model::CreateEventSubSubscription<'a>> {
+    let broadcaster = model::EventSubCondition {
+        broadcaster_user_id: Some(user_id),
+        ..Default::default()
+    };
+
+    // NB: follows require a moderator of the channel, which the broadcaster
+    // is.
+    let moderator = model::EventSubCondition {
+        broadcaster_user_id: Some(user_id),
+        moderator_user_id: Some(user_id),
+        ..Default::default()
+    };
+
+    let raided = model::EventSubCondition {
+        to_broadcaster_user_id: Some(user_id),
+        ..Default::default()
+    };
+
+    let transport = model::EventSubTransport {
+        method: "websocket",
+        session_id,
+    };
+
+    [
+        (REDEMPTION, "1", broadcaster),
+        (FOLLOW, "2", moderator),
+        (SUBSCRIBE, "1", broadcaster),
+        (SUBSCRIPTION_GIFT, "1", broadcaster),
+        (CHEER, "1", broadcaster),
+        (RAID, "1", raided),
+        (STREAM_ONLINE, "1", broadcaster),
+        (STREAM_OFFLINE, "1", broadcaster),
+        (POLL_BEGIN, "1", broadcaster),
+        (POLL_PROGRESS, "1", broadcaster),
+        (POLL_END, "1", broadcaster),
+        (PREDICTION_BEGIN, "1", broadcaster),
+        (PREDICTION_PROGRESS, "1", broadcaster),
+        (PREDICTION_LOCK, "1", broadcaster),
+        (PREDICTION_END, "1", broadcaster),
+    ]
+    .into_iter()
+    .map(
+        |(ty, version, condition)| model::CreateEventSubSubscription {
+            ty,
+            version,
+            condition,
+            transport,
+        },
+    )
+    .collect()
+}
+
+async fn task<S>(settings: settings::Settings<S>, injector: Injector) -> Result<()>
+where
+    S: settings::Scope,
+{
+    let settings = settings.scoped("eventsub");
+
+    let (mut enabled_stream, enabled) = settings.stream::<bool>("enabled").or_default().await?;
+
+    let eventsub = TwitchEventSub::new();
+    injector.update(eventsub.clone()).await;
+
+    let streamer_key = Key::<crate::TwitchAndUser>::tagged(tags::Twitch::Streamer)?;
+    let (mut streamer_stream, streamer) = injector.stream_key(&streamer_key).await;
+
+    let mut state = State::new(eventsub, URL.to_string());
+    state.enabled = enabled;
+    state.streamer = streamer;
+    state.build().await;
+
+    loop {
+        tokio::select! {
+            step = state.next() => {
+                state.handle(step).await;
+            }
+            enabled = enabled_stream.recv() => {
+                state.enabled = enabled;
+                state.build().await;
+            }
+            streamer = streamer_stream.recv() => {
+                state.streamer = streamer;
+                state.build().await;
+            }
+        }
+    }
+}
+
+struct Inner {
+    events: broadcast::Sender<Event>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Missing {
+    #[error("missing session in payload")]
+    Session,
+    #[error("missing subscription in payload")]
+    Subscription,
+    #[error("missing event in payload")]
+    Event,
+    #[error("missing reconnect url in session")]
+    ReconnectUrl,
+}
+
+pub mod transport {
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Frame {
+        pub metadata: Metadata,
+        #[serde(default)]
+        pub payload: Payload,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Metadata {
+        pub message_id: String,
+        pub message_type: MessageType,
+        pub message_timestamp: DateTime<Utc>,
+        #[serde(default)]
+        pub subscription_type: Option<String>,
+        #[serde(default)]
+        pub subscription_version: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MessageType {
+        SessionWelcome,
+        SessionKeepalive,
+        Notification,
+        SessionReconnect,
+        Revocation,
+        #[serde(other)]
+        Unknown,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct Payload {
+        #[serde(default)]
+        pub session: Option<Session>,
+        #[serde(default)]
+        pub subscription: Option<Subscription>,
+        #[serde(default)]
+        pub event: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Session {
+        pub id: String,
+        pub status: String,
+        #[serde(default)]
+        pub keepalive_timeout_seconds: Option<u64>,
+        #[serde(default)]
+        pub reconnect_url: Option<String>,
+        pub connected_at: DateTime<Utc>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Subscription {
+        pub id: String,
+        pub status: String,
+        #[serde(rename = "type")]
+        pub ty: String,
+        pub version: String,
+    }
+}
+
+/// An event received over EventSub.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Redemption(Redemption),
+    Follow(Follow),
+    Subscribe(Subscribe),
+    SubscriptionGift(SubscriptionGift),
+    Cheer(Cheer),
+    Raid(Raid),
+    StreamOnline(StreamOnline),
+    StreamOffline(StreamOffline),
+    PollBegin(ChannelPoll),
+    PollProgress(ChannelPoll),
+    PollEnd(ChannelPoll),
+    PredictionBegin(ChannelPrediction),
+    PredictionProgress(ChannelPrediction),
+    PredictionLock(ChannelPrediction),
+    PredictionEnd(ChannelPrediction),
+}
+
+/// An update to a native poll.
+#[derive(Debug, Clone)]
+pub enum PollUpdate {
+    Begin(ChannelPoll),
+    Progress(ChannelPoll),
+    End(ChannelPoll),
+}
+
+/// An update to a native prediction.
+#[derive(Debug, Clone)]
+pub enum PredictionUpdate {
+    Begin(ChannelPrediction),
+    Progress(ChannelPrediction),
+    Lock(ChannelPrediction),
+    End(ChannelPrediction),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reward {
+    pub id: String,
+    pub title: String,
+    pub cost: i64,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redemption {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    #[serde(default, deserialize_with = "empty_string")]
+    pub user_input: Option<String>,
+    pub status: Status,
+    pub reward: Reward,
+    pub redeemed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "FULFILLED", alias = "fulfilled")]
+    Fulfilled,
+    #[serde(rename = "UNFULFILLED", alias = "unfulfilled")]
+    Unfulfilled,
+    #[serde(rename = "CANCELED", alias = "canceled")]
+    Canceled,
+    #[serde(rename = "UNKNOWN", alias = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Follow {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub followed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscribe {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub tier: String,
+    pub is_gift: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionGift {
+    /// The gifting user, unless the gift is anonymous.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub user_login: Option<String>,
+    #[serde(default)]
+    pub user_name: Option<String>,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub total: u32,
+    pub tier: String,
+    #[serde(default)]
+    pub cumulative_total: Option<u32>,
+    pub is_anonymous: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cheer {
+    pub is_anonymous: bool,
+    /// The cheering user, unless the cheer is anonymous.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub user_login: Option<String>,
+    #[serde(default)]
+    pub user_name: Option<String>,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub message: String,
+    pub bits: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Raid {
+    pub from_broadcaster_user_id: String,
+    pub from_broadcaster_user_login: String,
+    pub from_broadcaster_user_name: String,
+    pub to_broadcaster_user_id: String,
+    pub to_broadcaster_user_login: String,
+    pub to_broadcaster_user_name: String,
+    pub viewers: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOnline {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOffline {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPollChoice {
+    pub id: String,
+    pub title: String,
+    /// Total number of votes, which is only present once voting has begun.
+    #[serde(default)]
+    pub votes: u64,
+    #[serde(default)]
+    pub channel_points_votes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPoll {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub title: String,
+    pub choices: Vec<ChannelPollChoice>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// How the poll ended, only present when it has.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPredictionOutcome {
+    pub id: String,
+    pub title: String,
+    pub color: String,
+    #[serde(default)]
+    pub users: u64,
+    #[serde(default)]
+    pub channel_points: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPrediction {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub title: String,
+    pub outcomes: Vec<ChannelPredictionOutcome>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub locks_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub locked_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub winning_outcome_id: Option<String>,
+    /// How the prediction ended, only present when it has.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// Deserializes an empty string as `None`.
+fn empty_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    Ok(match <Option<String>>::deserialize(deserializer)? {
+        Some(string) if !string.is_empty() => Some(string),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use common::sink::SinkExt;
+    use common::stream::StreamExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::{Event, State, TwitchEventSub};
+
+    /// Frames recorded from an EventSub session, up until it is asked to
+    /// reconnect.
+    const SESSION: &str = include_str!("eventsub/session.jsonl");
+    /// Frames recorded from the session we were asked to reconnect to.
+    const RECONNECTED: &str = include_str!("eventsub/reconnected.jsonl");
+
+    /// Serve a single connection by replaying the given frames.
+    async fn replay(listener: TcpListener, frames: Vec<String>) -> Result<()> {
+        let (stream, _) = listener.accept().await?;
+        let mut ws = tokio_tungstenite::accept_async(stream).await?;
+
+        for frame in frames {
+            ws.send(Message::Text(frame)).await?;
+        }
+
+        // NB: keep the connection open until the client goes away.
+        while let Some(message) = ws.next().await {
+            if message?.is_close() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn frames(recorded: &str, reconnect_url: &str) -> Vec<String> {
+        recorded
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.replace("{reconnect_url}", reconnect_url))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_replay_session() -> Result<()> {
+        let first = TcpListener::bind("127.0.0.1:0").await?;
+        let second = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", first.local_addr()?);
+        let reconnect_url = format!("ws://{}", second.local_addr()?);
+
+        tokio::spawn(replay(first, frames(SESSION, &reconnect_url)));
+        tokio::spawn(replay(second, frames(RECONNECTED, &reconnect_url)));
+
+        let eventsub = TwitchEventSub::new();
+        let mut events = eventsub.events();
+        let mut redemptions = eventsub.redemptions();
+        let mut state = State::new(eventsub, url);
+        state.enabled = true;
+        state.connect().await?;
+
+        let mut received = Vec::new();
+
+        let run = async {
+            while received.len() < 10 {
+                tokio::select! {
+                    step = state.next() => {
+                        state.handle(step).await;
+                    }
+                    Some(event) = events.next() => {
+                        received.push(event);
+                    }
+                }
+            }
+        };
+
+        tokio::time::timeout(std::time::Duration::from_secs(10), run).await?;
+
+        assert_eq!(state.session_id.as_deref(), Some("session-reconnected"));
+
+        let redemption = redemptions.next().await.expect("redemption");
+        assert_eq!(redemption.user_login, "cool_user");
+        assert_eq!(redemption.reward.title, "Request Song");
+        assert_eq!(
+            redemption.user_input.as_deref(),
+            Some("queen we will rock you")
+        );
+
+        assert!(matches!(&received[0], Event::Redemption(..)));
+        assert!(matches!(&received[1], Event::Follow(e) if e.user_login == "cool_user"));
+        assert!(matches!(&received[2], Event::Subscribe(e) if e.tier == "1000"));
+        assert!(matches!(&received[3], Event::SubscriptionGift(e) if e.total == 5));
+        assert!(matches!(&received[4], Event::Cheer(e) if e.bits == 100));
+        assert!(matches!(&received[5], Event::Raid(e) if e.viewers == 42));
+        assert!(matches!(&received[6], Event::StreamOnline(..)));
+        assert!(matches!(&received[7], Event::StreamOffline(..)));
+        assert!(
+            matches!(&received[8], Event::PollProgress(e) if e.choices.iter().map(|c| c.votes).sum::<u64>() == 19)
+        );
+        assert!(
+            matches!(&received[9], Event::PredictionLock(e) if e.outcomes[0].channel_points == 15000)
+        );
+        Ok(())
+    }
+}
@@ -115,3 +115,150 @@ pub struct ModifyChannelRequest<'a> {
     pub title: Option<&'a str>,
     pub game_id: Option<&'a str>,
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateEventSubSubscription<'a> {
+    #[serde(rename = "type")]
+    pub ty: &'a str,
+    pub version: &'a str,
+    pub condition: EventSubCondition<'a>,
+    pub transport: EventSubTransport<'a>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct EventSubCondition<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broadcaster_user_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderator_user_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_broadcaster_user_id: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EventSubTransport<'a> {
+    pub method: &'a str,
+    pub session_id: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BanUserRequest<'a> {
+    pub user_id: &'a str,
+    /// Duration of a timeout in seconds. No duration means a permanent ban.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShieldModeStatus {
+    pub is_active: bool,
+    pub moderator_id: String,
+    pub moderator_login: String,
+    pub moderator_name: String,
+    #[serde(default)]
+    pub last_activated_at: Option<String>,
+}
+
+/// The status of a native Twitch poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PollStatus {
+    Active,
+    Completed,
+    Terminated,
+    Archived,
+    Moderated,
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatePollRequest<'a> {
+    pub broadcaster_id: &'a str,
+    pub title: &'a str,
+    pub choices: Vec<PollChoiceTitle<'a>>,
+    /// How long the poll runs for in seconds, between 15 and 1800.
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PollChoiceTitle<'a> {
+    pub title: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Poll {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub title: String,
+    pub choices: Vec<PollChoice>,
+    pub status: PollStatus,
+    pub duration: u32,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PollChoice {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub votes: u64,
+    #[serde(default)]
+    pub channel_points_votes: u64,
+}
+
+/// The status of a native Twitch prediction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PredictionStatus {
+    Active,
+    Resolved,
+    Canceled,
+    Locked,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatePredictionRequest<'a> {
+    pub broadcaster_id: &'a str,
+    pub title: &'a str,
+    pub outcomes: Vec<PredictionOutcomeTitle<'a>>,
+    /// How long users may make predictions for in seconds, between 30 and
+    /// 1800.
+    pub prediction_window: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PredictionOutcomeTitle<'a> {
+    pub title: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Prediction {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub winning_outcome_id: Option<String>,
+    pub outcomes: Vec<PredictionOutcome>,
+    pub prediction_window: u32,
+    pub status: PredictionStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub locked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PredictionOutcome {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub users: u64,
+    #[serde(default)]
+    pub channel_points: u64,
+    pub color: String,
+}
@@ -3,8 +3,8 @@ use std::fmt;
 use std::iter;
 use std::sync::Arc;
 
-use anyhow::{Context, Error, Result};
-use chrono::{DateTime, Utc};
+use anyhow::{bail, Context, Error, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use common::{Cooldown, Duration};
 use diesel::backend::Backend;
 use diesel::prelude::*;
@@ -49,7 +49,7 @@ impl std::str::FromStr for RoleOrUser {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(s) = s.strip_prefix('@') {
+        if s.starts_with('@') {
             let role = Role::from_str(s)?;
             return Ok(RoleOrUser::Role(role));
         }
@@ -59,12 +59,41 @@ impl std::str::FromStr for RoleOrUser {
 }
 
 /// The kind of temporary grant.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TemporaryKind {
     Allow,
     Deny,
 }
 
+impl TemporaryKind {
+    /// Get the kind as a string, as it's stored in the database.
+    fn as_str(self) -> &'static str {
+        match self {
+            TemporaryKind::Allow => "allow",
+            TemporaryKind::Deny => "deny",
+        }
+    }
+}
+
+impl fmt::Display for TemporaryKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+impl std::str::FromStr for TemporaryKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(TemporaryKind::Allow),
+            "deny" => Ok(TemporaryKind::Deny),
+            other => bail!("bad kind of temporary grant: {}", other),
+        }
+    }
+}
+
 /// A grant that has been temporarily given.
 struct Temporary {
     pub(crate) scope: Scope,
@@ -74,18 +103,67 @@ struct Temporary {
 }
 
 impl Temporary {
+    /// Parse a temporary grant as it's stored in the database.
+    fn parse(scope: Scope, principal: &str, kind: &str, expires_at: NaiveDateTime) -> Result<Self> {
+        if matches!(scope, Scope::Unknown) {
+            bail!("unknown scope");
+        }
+
+        let principal = str::parse(principal)?;
+
+        if matches!(principal, RoleOrUser::Role(Role::Unknown)) {
+            bail!("unknown role");
+        }
+
+        Ok(Self {
+            scope,
+            principal,
+            expires_at: DateTime::from_naive_utc_and_offset(expires_at, Utc),
+            kind: str::parse(kind)?,
+        })
+    }
+
     /// Test if the grant is expired.
     pub(crate) fn is_expired(&self, now: &DateTime<Utc>) -> bool {
         *now >= self.expires_at
     }
 }
 
+/// Information about an active temporary grant.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemporaryInfo {
+    pub scope: Scope,
+    pub principal: String,
+    pub kind: TemporaryKind,
+    pub expires_at: DateTime<Utc>,
+    /// Time remaining until the grant expires.
+    pub remaining: Duration,
+}
+
+/// A role defined by the user with an explicit list of members.
+struct CustomRole {
+    /// Documentation for the role.
+    doc: Option<String>,
+    /// The users who are members of the role.
+    members: HashSet<String>,
+}
+
+/// Information about a custom role.
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomRoleInfo {
+    pub role: Role,
+    pub doc: Option<String>,
+    pub members: Vec<String>,
+}
+
 struct Inner {
     db: db::Database,
     /// Schema for every corresponding scope.
     schema: Schema,
     /// Assignments.
     grants: RwLock<HashSet<(Scope, Role)>>,
+    /// Custom roles.
+    custom_roles: RwLock<HashMap<Role, CustomRole>>,
     /// Temporary grants.
     temporary: RwLock<Vec<Temporary>>,
 }
@@ -112,12 +190,16 @@ impl Auth {
             })
             .await?;
 
+        let custom_roles = Self::load_custom_roles(&db).await?;
+        let temporary = Self::load_temporary(&db).await?;
+
         let auth = Auth {
             inner: Arc::new(Inner {
                 db,
                 schema,
                 grants: RwLock::new(grants),
-                temporary: Default::default(),
+                custom_roles: RwLock::new(custom_roles),
+                temporary: RwLock::new(temporary),
             }),
         };
 
@@ -126,6 +208,75 @@ impl Auth {
         Ok(auth)
     }
 
+    /// Load all custom roles and their members from the database.
+    async fn load_custom_roles(db: &db::Database) -> Result<HashMap<Role, CustomRole>> {
+        use db::schema::custom_role_members::dsl as members;
+        use db::schema::custom_roles::dsl;
+
+        db.asyncify(move |c| {
+            let mut out = HashMap::new();
+
+            for (role, doc) in dsl::custom_roles
+                .select((dsl::role, dsl::doc))
+                .load::<(Role, Option<String>)>(c)?
+            {
+                out.insert(
+                    role,
+                    CustomRole {
+                        doc,
+                        members: HashSet::new(),
+                    },
+                );
+            }
+
+            for (role, user) in members::custom_role_members
+                .select((members::role, members::user))
+                .load::<(Role, String)>(c)?
+            {
+                if let Some(custom) = out.get_mut(&role) {
+                    custom.members.insert(user);
+                }
+            }
+
+            Ok::<_, Error>(out)
+        })
+        .await
+    }
+
+    /// Load all temporary grants which have not yet expired from the
+    /// database.
+    async fn load_temporary(db: &db::Database) -> Result<Vec<Temporary>> {
+        use db::schema::temporary_grants::dsl;
+
+        let now = Utc::now();
+
+        db.asyncify(move |c| {
+            let rows = dsl::temporary_grants
+                .select((dsl::scope, dsl::principal, dsl::kind, dsl::expires_at))
+                .filter(dsl::expires_at.gt(now.naive_utc()))
+                .load::<(Scope, String, String, NaiveDateTime)>(c)?;
+
+            let mut out = Vec::with_capacity(rows.len());
+
+            for (scope, principal, kind, expires_at) in rows {
+                match Temporary::parse(scope, &principal, &kind, expires_at) {
+                    Ok(temporary) => out.push(temporary),
+                    Err(e) => {
+                        common::log_warn!(
+                            e,
+                            "Ignoring temporary grant of {} to {}",
+                            scope,
+                            principal
+                        );
+                    }
+                }
+            }
+
+            Ok::<_, Error>(out)
+        })
+        .await
+    }
+
     /// Return all temporary scopes belonging to the specified user.
     async fn temporary_scopes(&self, now: &DateTime<Utc>, principal: RoleOrUser) -> Vec<Scope> {
         let mut out = Vec::new();
@@ -151,7 +302,9 @@ impl Auth {
     /// Return all temporary scopes belonging to the specified user.
     pub async fn scopes_for_role(&self, needle: Role) -> Vec<Scope> {
         let now = Utc::now();
-        let mut out = self.temporary_scopes(&now, RoleOrUser::Role(needle)).await;
+        let mut out = self
+            .temporary_scopes(&now, RoleOrUser::Role(needle.clone()))
+            .await;
 
         let grants = self.inner.grants.read().await;
 
@@ -212,7 +365,7 @@ impl Auth {
 
         for (key, data) in to_insert {
             for allow in &data.allow {
-                self.insert(key, *allow).await?;
+                self.insert(key, allow.clone()).await?;
             }
 
             let version = data.version.clone();
@@ -231,16 +384,57 @@ impl Auth {
         Ok(())
     }
 
-    /// Insert a temporary grant.
+    /// Check that the given role can be granted scopes, which requires
+    /// custom roles to be defined.
+    async fn check_role(&self, role: &Role) -> Result<()> {
+        if matches!(role, Role::Unknown) {
+            bail!("cannot grant to an unknown role");
+        }
+
+        if matches!(role, Role::Custom(..))
+            && !self.inner.custom_roles.read().await.contains_key(role)
+        {
+            bail!("no such role: {}", role);
+        }
+
+        Ok(())
+    }
+
+    /// Insert a temporary grant, replacing any existing grant for the same
+    /// scope and principal.
     pub async fn insert_temporary(
         &self,
         scope: Scope,
         principal: RoleOrUser,
         expires_at: DateTime<Utc>,
         kind: TemporaryKind,
-    ) {
+    ) -> Result<()> {
+        use db::schema::temporary_grants::dsl;
+
+        if let RoleOrUser::Role(role) = &principal {
+            self.check_role(role).await?;
+        }
+
         let mut grants = self.inner.temporary.write().await;
 
+        let insert = principal.to_string();
+        let insert_expires_at = expires_at.naive_utc();
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::temporary_grants)
+                    .values((
+                        dsl::scope.eq(scope),
+                        dsl::principal.eq(insert),
+                        dsl::kind.eq(kind.as_str()),
+                        dsl::expires_at.eq(insert_expires_at),
+                    ))
+                    .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
         if let Some(existing) = grants
             .iter_mut()
             .find(|g| g.scope == scope && g.principal == principal)
@@ -255,17 +449,109 @@ impl Auth {
                 kind,
             });
         }
+
+        Ok(())
+    }
+
+    /// Delete a temporary grant before it expires.
+    ///
+    /// Returns `false` if there is no such grant.
+    pub async fn delete_temporary(&self, scope: Scope, principal: &RoleOrUser) -> Result<bool> {
+        use db::schema::temporary_grants::dsl;
+
+        let now = Utc::now();
+        let mut grants = self.inner.temporary.write().await;
+
+        let Some(index) = grants
+            .iter()
+            .position(|g| g.scope == scope && g.principal == *principal && !g.is_expired(&now))
+        else {
+            return Ok(false);
+        };
+
+        let delete = principal.to_string();
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::temporary_grants
+                        .filter(dsl::scope.eq(scope).and(dsl::principal.eq(delete))),
+                )
+                .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        grants.swap_remove(index);
+        Ok(true)
+    }
+
+    /// Get a list of all active temporary grants, ordered by the time they
+    /// expire.
+    pub async fn temporary(&self) -> Vec<TemporaryInfo> {
+        let now = Utc::now();
+        let grants = self.inner.temporary.read().await;
+
+        let mut out = grants
+            .iter()
+            .filter(|g| !g.is_expired(&now))
+            .map(|g| {
+                let remaining = (g.expires_at - now).num_seconds().max(0);
+
+                TemporaryInfo {
+                    scope: g.scope,
+                    principal: g.principal.to_string(),
+                    kind: g.kind,
+                    expires_at: g.expires_at,
+                    remaining: Duration::seconds(remaining as u64),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        out.sort_by_key(|a| a.expires_at);
+        out
+    }
+
+    /// Remove all temporary grants which have expired.
+    ///
+    /// Returns the number of grants removed from the database.
+    pub async fn sweep_temporary(&self) -> Result<usize> {
+        use db::schema::temporary_grants::dsl;
+
+        let now = Utc::now();
+
+        self.inner
+            .temporary
+            .write()
+            .await
+            .retain(|g| !g.is_expired(&now));
+
+        let now = now.naive_utc();
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                Ok(
+                    diesel::delete(dsl::temporary_grants.filter(dsl::expires_at.le(now)))
+                        .execute(c)?,
+                )
+            })
+            .await
     }
 
     /// Insert an assignment.
     pub async fn insert(&self, scope: Scope, role: Role) -> Result<()> {
         use db::schema::grants::dsl;
 
+        self.check_role(&role).await?;
+        let insert = role.clone();
+
         self.inner
             .db
             .asyncify(move |c| {
                 diesel::insert_into(dsl::grants)
-                    .values((dsl::scope.eq(scope), dsl::role.eq(role)))
+                    .values((dsl::scope.eq(scope), dsl::role.eq(insert)))
                     .execute(c)?;
                 Ok::<_, Error>(())
             })
@@ -279,7 +565,13 @@ impl Auth {
     pub async fn delete(&self, scope: Scope, role: Role) -> Result<()> {
         use db::schema::grants::dsl;
 
-        if self.inner.grants.write().await.remove(&(scope, role)) {
+        if self
+            .inner
+            .grants
+            .write()
+            .await
+            .remove(&(scope, role.clone()))
+        {
             self.inner
                 .db
                 .asyncify(move |c| {
@@ -295,6 +587,216 @@ impl Auth {
         Ok(())
     }
 
+    /// Get a list of all custom roles and their members.
+    pub async fn custom_roles(&self) -> Vec<CustomRoleInfo> {
+        let custom_roles = self.inner.custom_roles.read().await;
+
+        let mut out = custom_roles
+            .iter()
+            .map(|(role, custom)| {
+                let mut members = custom.members.iter().cloned().collect::<Vec<_>>();
+                members.sort();
+
+                CustomRoleInfo {
+                    role: role.clone(),
+                    doc: custom.doc.clone(),
+                    members,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        out.sort_by(|a, b| a.role.cmp(&b.role));
+        out
+    }
+
+    /// Get the custom role with the given name.
+    pub async fn custom_role(&self, role: &Role) -> Option<CustomRoleInfo> {
+        let custom_roles = self.inner.custom_roles.read().await;
+        let custom = custom_roles.get(role)?;
+
+        let mut members = custom.members.iter().cloned().collect::<Vec<_>>();
+        members.sort();
+
+        Some(CustomRoleInfo {
+            role: role.clone(),
+            doc: custom.doc.clone(),
+            members,
+        })
+    }
+
+    /// Create a custom role.
+    ///
+    /// Returns `false` if the role already exists.
+    pub async fn insert_role(&self, role: Role, doc: Option<String>) -> Result<bool> {
+        use db::schema::custom_roles::dsl;
+
+        if !matches!(role, Role::Custom(..)) {
+            bail!("not a valid name for a custom role: {}", role);
+        }
+
+        let mut custom_roles = self.inner.custom_roles.write().await;
+
+        if custom_roles.contains_key(&role) {
+            return Ok(false);
+        }
+
+        let insert = role.clone();
+        let insert_doc = doc.clone();
+        let created_at = Utc::now().naive_utc();
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::custom_roles)
+                    .values((
+                        dsl::role.eq(insert),
+                        dsl::doc.eq(insert_doc),
+                        dsl::created_at.eq(created_at),
+                    ))
+                    .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        custom_roles.insert(
+            role,
+            CustomRole {
+                doc,
+                members: HashSet::new(),
+            },
+        );
+
+        Ok(true)
+    }
+
+    /// Delete a custom role together with its members and grants.
+    ///
+    /// Returns `false` if there is no such role.
+    pub async fn delete_role(&self, role: &Role) -> Result<bool> {
+        use db::schema::custom_role_members::dsl as members;
+        use db::schema::custom_roles::dsl;
+        use db::schema::grants::dsl as grants;
+        use db::schema::temporary_grants::dsl as temporary;
+
+        let mut custom_roles = self.inner.custom_roles.write().await;
+
+        if !custom_roles.contains_key(role) {
+            return Ok(false);
+        }
+
+        let delete = role.clone();
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                c.transaction::<_, Error, _>(|c| {
+                    diesel::delete(grants::grants.filter(grants::role.eq(&delete))).execute(c)?;
+                    diesel::delete(members::custom_role_members.filter(members::role.eq(&delete)))
+                        .execute(c)?;
+                    diesel::delete(dsl::custom_roles.filter(dsl::role.eq(&delete))).execute(c)?;
+                    diesel::delete(
+                        temporary::temporary_grants
+                            .filter(temporary::principal.eq(delete.to_string())),
+                    )
+                    .execute(c)?;
+                    Ok(())
+                })
+            })
+            .await?;
+
+        custom_roles.remove(role);
+        self.inner.grants.write().await.retain(|(_, r)| r != role);
+        self.inner
+            .temporary
+            .write()
+            .await
+            .retain(|t| t.principal != RoleOrUser::Role(role.clone()));
+        Ok(true)
+    }
+
+    /// Add a user as a member of a custom role.
+    ///
+    /// Returns `false` if the user is already a member.
+    pub async fn insert_member(&self, role: &Role, user: &str) -> Result<bool> {
+        use db::schema::custom_role_members::dsl;
+
+        let mut custom_roles = self.inner.custom_roles.write().await;
+
+        let Some(custom) = custom_roles.get_mut(role) else {
+            bail!("no such role: {}", role);
+        };
+
+        let user = db::user_id(user);
+
+        if custom.members.contains(&user) {
+            return Ok(false);
+        }
+
+        let insert = (role.clone(), user.clone());
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                let (role, user) = insert;
+
+                diesel::insert_into(dsl::custom_role_members)
+                    .values((dsl::role.eq(role), dsl::user.eq(user)))
+                    .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        custom.members.insert(user);
+        Ok(true)
+    }
+
+    /// Remove a user from the members of a custom role.
+    ///
+    /// Returns `false` if the user isn't a member.
+    pub async fn delete_member(&self, role: &Role, user: &str) -> Result<bool> {
+        use db::schema::custom_role_members::dsl;
+
+        let mut custom_roles = self.inner.custom_roles.write().await;
+
+        let Some(custom) = custom_roles.get_mut(role) else {
+            bail!("no such role: {}", role);
+        };
+
+        let user = db::user_id(user);
+
+        if !custom.members.remove(&user) {
+            return Ok(false);
+        }
+
+        let delete = (role.clone(), user);
+
+        self.inner
+            .db
+            .asyncify(move |c| {
+                let (role, user) = delete;
+
+                diesel::delete(
+                    dsl::custom_role_members.filter(dsl::role.eq(role).and(dsl::user.eq(user))),
+                )
+                .execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Get the custom roles the given user is a member of.
+    pub async fn custom_roles_for_user(&self, user: &str) -> Vec<Role> {
+        let custom_roles = self.inner.custom_roles.read().await;
+
+        custom_roles
+            .iter()
+            .filter(|(_, custom)| custom.members.contains(user))
+            .map(|(role, _)| role.clone())
+            .collect()
+    }
+
     /// Test if there are any temporary grants matching the given user or role.
     async fn test_temporary(
         &self,
@@ -341,12 +843,13 @@ impl Auth {
         S: AsRef<Scope>,
     {
         let scope = scope.as_ref();
-        let roles = roles.into_iter().collect::<Vec<_>>();
+        let mut roles = roles.into_iter().collect::<Vec<_>>();
+        roles.extend(self.custom_roles_for_user(user).await);
 
         let now = Utc::now();
 
         let against = iter::once(RoleOrUser::User(user.to_string()))
-            .chain(roles.iter().copied().map(RoleOrUser::Role));
+            .chain(roles.iter().cloned().map(RoleOrUser::Role));
 
         let (grant, expired) = self.test_temporary(&now, scope, against).await;
 
@@ -363,7 +866,7 @@ impl Auth {
             if !matches!(grant, Some(TemporaryKind::Deny)) {
                 let grants = self.inner.grants.read().await;
 
-                if roles.iter().any(|r| grants.contains(&(*scope, *r))) {
+                if roles.iter().any(|r| grants.contains(&(*scope, r.clone()))) {
                     break 'outcome true;
                 }
             }
@@ -403,8 +906,8 @@ impl Auth {
         out
     }
 
-    /// Get a list of roles.
-    pub fn roles(&self) -> Vec<RoleInfo> {
+    /// Get a list of roles, including custom roles.
+    pub async fn roles(&self) -> Vec<RoleInfo> {
         let mut out = Vec::new();
 
         for role in Role::list() {
@@ -416,6 +919,17 @@ impl Auth {
             out.push(RoleInfo {
                 role,
                 data: data.clone(),
+                members: None,
+            });
+        }
+
+        for custom in self.custom_roles().await {
+            out.push(RoleInfo {
+                role: custom.role,
+                data: RoleData {
+                    doc: custom.doc.unwrap_or_default(),
+                },
+                members: Some(custom.members),
             });
         }
 
@@ -520,20 +1034,19 @@ macro_rules! roles {
     #[derive(
         Debug,
         Clone,
-        Copy,
         PartialEq,
         Eq,
         PartialOrd,
         Ord,
         Hash,
-        Serialize,
-        Deserialize,
         FromSqlRow,
         AsExpression,
     )]
     #[diesel(sql_type = diesel::sql_types::Text)]
     pub enum Role {
-        $(#[serde(rename = $role)] $variant,)*
+        $($variant,)*
+        /// A role defined by the user, stored without the leading `@`.
+        Custom(Box<str>),
         Unknown,
     }
 
@@ -548,8 +1061,9 @@ macro_rules! roles {
 
     impl fmt::Display for Role {
         fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-            match *self {
+            match self {
                 $(Role::$variant => $role.fmt(fmt),)*
+                Role::Custom(name) => write!(fmt, "@{}", name),
                 Role::Unknown => "unknown".fmt(fmt),
             }
         }
@@ -561,11 +1075,33 @@ macro_rules! roles {
         fn from_str(s: &str) -> Result<Self, Self::Err> {
             match s {
                 $($role => Ok(Role::$variant),)*
-                _ => Ok(Role::Unknown),
+                _ => match s.strip_prefix('@') {
+                    Some(name) if is_custom_role_name(name) => Ok(Role::Custom(name.into())),
+                    _ => Ok(Role::Unknown),
+                },
             }
         }
     }
 
+    impl Serialize for Role {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Role {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            str::parse(&s).map_err(serde::de::Error::custom)
+        }
+    }
+
     impl ToSql<diesel::sql_types::Text, Sqlite> for Role {
         fn to_sql(&self, out: &mut diesel::serialize::Output<'_, '_, Sqlite>) -> diesel::serialize::Result {
             out.set_value(self.to_string());
@@ -586,6 +1122,14 @@ macro_rules! roles {
     }
 }
 
+/// Test if the given string is a valid name for a custom role.
+fn is_custom_role_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
 /// The risk of a given scope.
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, Default)]
 pub(crate) enum Risk {
@@ -603,12 +1147,18 @@ scopes! {
     (Song, "song"),
     (SongYouTube, "song/youtube"),
     (SongSpotify, "song/spotify"),
+    (SongLocal, "song/local"),
     (SongBypassConstraints, "song/bypass-constraints"),
     (SongTheme, "song/theme"),
     (SongEditQueue, "song/edit-queue"),
     (SongListLimit, "song/list-limit"),
     (SongVolume, "song/volume"),
     (SongPlaybackControl, "song/playback-control"),
+    (SongVoteSkip, "song/voteskip"),
+    (SongLike, "song/like"),
+    (SongBan, "song/ban"),
+    (SongBump, "song/bump"),
+    (SongPlaylist, "song/playlist"),
     (SwearJar, "swearjar"),
     (Uptime, "uptime"),
     (Game, "game"),
@@ -620,6 +1170,8 @@ scopes! {
     (EightBall, "8ball"),
     (Command, "command"),
     (CommandEdit, "command/edit"),
+    (Counter, "counter"),
+    (CounterEdit, "counter/edit"),
     (ThemeEdit, "theme/edit"),
     (PromoEdit, "promo/edit"),
     (AliasEdit, "alias/edit"),
@@ -632,10 +1184,23 @@ scopes! {
     (CurrencyWindfall, "currency/windfall"),
     (WaterUndo, "water/undo"),
     (AuthPermit, "auth/permit"),
+    (AuthRoles, "auth/roles"),
     (ChatBypassUrlWhitelist, "chat/bypass-url-whitelist"),
+    (ChatBypassSpamCaps, "chat/bypass-spam/caps"),
+    (ChatBypassSpamSymbols, "chat/bypass-spam/symbols"),
+    (ChatBypassSpamEmotes, "chat/bypass-spam/emotes"),
+    (ChatBypassSpamLength, "chat/bypass-spam/length"),
+    (ChatBypassSpamRepeatedCharacters, "chat/bypass-spam/repeated-characters"),
+    (ChatBypassSpamRepeatedMessages, "chat/bypass-spam/repeated-messages"),
     (Time, "time"),
     (Poll, "poll"),
+    (Prediction, "prediction"),
+    (Gamble, "gamble"),
+    (Bet, "bet"),
+    (BetEdit, "bet/edit"),
+    (Duel, "duel"),
     (Weather, "weather"),
+    (Strikes, "strikes"),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -675,6 +1240,9 @@ pub struct RoleInfo {
     role: Role,
     #[serde(flatten)]
     data: RoleData,
+    /// Members of the role if it's a custom role.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    members: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -682,3 +1250,265 @@ pub(crate) struct RoleData {
     /// Documentation for this role.
     pub(crate) doc: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Utc;
+    use diesel::prelude::*;
+
+    use super::{Auth, Role, RoleOrUser, Schema, Scope, TemporaryKind};
+
+    async fn auth(db: &db::Database) -> Auth {
+        let schema = Schema::load_static(b"roles: {}\nscopes: {}").unwrap();
+        Auth::new(db.clone(), schema).await.unwrap()
+    }
+
+    fn database() -> db::Database {
+        db::Database::open(Path::new(":memory:")).unwrap()
+    }
+
+    async fn temporary_rows(db: &db::Database) -> i64 {
+        use db::schema::temporary_grants::dsl;
+
+        db.asyncify(|c| Ok::<_, anyhow::Error>(dsl::temporary_grants.count().get_result(c)?))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_role() {
+        assert_eq!("@vip".parse::<Role>().unwrap(), Role::Vip);
+        assert_eq!(
+            "@regulars".parse::<Role>().unwrap(),
+            Role::Custom("regulars".into())
+        );
+        assert_eq!("@Regulars".parse::<Role>().unwrap(), Role::Unknown);
+        assert_eq!("@".parse::<Role>().unwrap(), Role::Unknown);
+        assert_eq!("regulars".parse::<Role>().unwrap(), Role::Unknown);
+        assert_eq!(Role::Custom("regulars".into()).to_string(), "@regulars");
+    }
+
+    #[test]
+    fn test_parse_role_or_user() {
+        assert_eq!(
+            "@moderator".parse::<RoleOrUser>().unwrap(),
+            RoleOrUser::Role(Role::Moderator)
+        );
+        assert_eq!(
+            "@editors".parse::<RoleOrUser>().unwrap(),
+            RoleOrUser::Role(Role::Custom("editors".into()))
+        );
+        assert_eq!(
+            "SomeUser".parse::<RoleOrUser>().unwrap(),
+            RoleOrUser::User(String::from("someuser"))
+        );
+    }
+
+    #[test]
+    fn test_parse_temporary_kind() {
+        for kind in [TemporaryKind::Allow, TemporaryKind::Deny] {
+            assert_eq!(kind.to_string().parse::<TemporaryKind>().unwrap(), kind);
+        }
+
+        assert!("grant".parse::<TemporaryKind>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_role_grants() {
+        let db = database();
+        let auth = auth(&db).await;
+        let editors = Role::Custom("editors".into());
+
+        assert!(auth.insert_role(editors.clone(), None).await.unwrap());
+        auth.insert(Scope::SongEditQueue, editors.clone())
+            .await
+            .unwrap();
+        assert!(auth.insert_member(&editors, "Alice").await.unwrap());
+
+        assert!(
+            auth.test_any(Scope::SongEditQueue, "alice", [Role::Everyone])
+                .await
+        );
+        assert!(
+            !auth
+                .test_any(Scope::SongEditQueue, "bob", [Role::Everyone])
+                .await
+        );
+        assert!(!auth.test_any(Scope::Admin, "alice", [Role::Everyone]).await);
+
+        assert!(auth.delete_member(&editors, "alice").await.unwrap());
+        assert!(
+            !auth
+                .test_any(Scope::SongEditQueue, "alice", [Role::Everyone])
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_undefined_role() {
+        let db = database();
+        let auth = auth(&db).await;
+        let undefined = Role::Custom("undefined".into());
+
+        assert!(auth.insert(Scope::Admin, Role::Unknown).await.is_err());
+        assert!(auth.insert(Scope::Admin, undefined.clone()).await.is_err());
+        assert!(auth
+            .insert_temporary(
+                Scope::Admin,
+                RoleOrUser::Role(undefined),
+                Utc::now() + chrono::Duration::hours(1),
+                TemporaryKind::Allow,
+            )
+            .await
+            .is_err());
+        assert!(auth.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_role() {
+        use db::schema::custom_role_members::dsl as members;
+        use db::schema::grants::dsl as grants;
+
+        let db = database();
+        let auth = auth(&db).await;
+        let editors = Role::Custom("editors".into());
+
+        assert!(auth.insert_role(editors.clone(), None).await.unwrap());
+        auth.insert(Scope::SongEditQueue, editors.clone())
+            .await
+            .unwrap();
+        auth.insert(Scope::SongEditQueue, Role::Moderator)
+            .await
+            .unwrap();
+        assert!(auth.insert_member(&editors, "alice").await.unwrap());
+
+        assert!(auth.delete_role(&editors).await.unwrap());
+        assert!(!auth.delete_role(&editors).await.unwrap());
+
+        assert!(auth.custom_role(&editors).await.is_none());
+        assert!(auth.custom_roles_for_user("alice").await.is_empty());
+        assert_eq!(auth.list().await, [(Scope::SongEditQueue, Role::Moderator)]);
+
+        let (grants, members) = db
+            .asyncify(|c| {
+                let grants = grants::grants.count().get_result::<i64>(c)?;
+                let members = members::custom_role_members.count().get_result::<i64>(c)?;
+                Ok::<_, anyhow::Error>((grants, members))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!((grants, members), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_temporary_reload() {
+        let db = database();
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+
+        auth(&db)
+            .await
+            .insert_temporary(
+                Scope::Admin,
+                RoleOrUser::User(String::from("alice")),
+                expires_at,
+                TemporaryKind::Allow,
+            )
+            .await
+            .unwrap();
+
+        let auth = auth(&db).await;
+        assert!(auth.test_any(Scope::Admin, "alice", []).await);
+        assert!(!auth.test_any(Scope::Admin, "bob", []).await);
+
+        let temporary = auth.temporary().await;
+        assert_eq!(temporary.len(), 1);
+        assert_eq!(temporary[0].principal, "alice");
+        assert_eq!(temporary[0].kind, TemporaryKind::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_load_bad_temporary() {
+        use db::schema::temporary_grants::dsl;
+
+        let db = database();
+        let expires_at = (Utc::now() + chrono::Duration::hours(1)).naive_utc();
+
+        db.asyncify(move |c| {
+            diesel::insert_into(dsl::temporary_grants)
+                .values(&vec![
+                    (
+                        dsl::scope.eq("admin"),
+                        dsl::principal.eq("alice"),
+                        dsl::kind.eq("bogus"),
+                        dsl::expires_at.eq(expires_at),
+                    ),
+                    (
+                        dsl::scope.eq("admin"),
+                        dsl::principal.eq("bob"),
+                        dsl::kind.eq("allow"),
+                        dsl::expires_at.eq(expires_at),
+                    ),
+                ])
+                .execute(c)?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+        .unwrap();
+
+        let auth = auth(&db).await;
+        assert!(!auth.test_any(Scope::Admin, "alice", []).await);
+        assert!(auth.test_any(Scope::Admin, "bob", []).await);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_temporary() {
+        let db = database();
+        let auth = auth(&db).await;
+        let now = Utc::now();
+
+        for (user, expires_at) in [
+            ("alice", now - chrono::Duration::minutes(1)),
+            ("bob", now + chrono::Duration::hours(1)),
+        ] {
+            auth.insert_temporary(
+                Scope::Admin,
+                RoleOrUser::User(user.to_string()),
+                expires_at,
+                TemporaryKind::Allow,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(temporary_rows(&db).await, 2);
+        assert_eq!(auth.sweep_temporary().await.unwrap(), 1);
+        assert_eq!(temporary_rows(&db).await, 1);
+        assert_eq!(auth.temporary().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_temporary() {
+        let db = database();
+        let auth = auth(&db).await;
+        let alice = RoleOrUser::User(String::from("alice"));
+
+        auth.insert_temporary(
+            Scope::Admin,
+            alice.clone(),
+            Utc::now() + chrono::Duration::hours(1),
+            TemporaryKind::Allow,
+        )
+        .await
+        .unwrap();
+
+        assert!(auth.delete_temporary(Scope::Admin, &alice).await.unwrap());
+        assert!(!auth.delete_temporary(Scope::Admin, &alice).await.unwrap());
+
+        assert!(!auth.test_any(Scope::Admin, "alice", []).await);
+        assert!(auth.temporary().await.is_empty());
+        assert_eq!(temporary_rows(&db).await, 0);
+    }
+}
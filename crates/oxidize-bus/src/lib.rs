@@ -111,6 +111,10 @@ pub enum YouTubeEvent {
     /// Stop the player.
     #[serde(rename = "stop")]
     Stop,
+    /// Buffer the video which is going to play next, so that the switch to it
+    /// is seamless.
+    #[serde(rename = "preload")]
+    Preload { video_id: String },
 }
 
 /// Events for driving the YouTube player.
@@ -122,6 +126,8 @@ pub enum YouTube {
     YouTubeCurrent { event: YouTubeEvent },
     #[serde(rename = "youtube/volume")]
     YouTubeVolume { volume: u32 },
+    #[serde(rename = "youtube/next")]
+    YouTubeNext { event: YouTubeEvent },
 }
 
 impl Message for YouTube {
@@ -132,6 +138,49 @@ impl Message for YouTube {
         match *self {
             YouTubeCurrent { .. } => Some("youtube/current"),
             YouTubeVolume { .. } => Some("youtube/volume"),
+            YouTubeNext { .. } => Some("youtube/next"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum LocalAudioEvent {
+    /// Play a track from the local library.
+    #[serde(rename = "play")]
+    Play {
+        path: String,
+        elapsed: u64,
+        duration: u64,
+    },
+    /// Pause the player.
+    #[serde(rename = "pause")]
+    Pause,
+    /// Stop the player.
+    #[serde(rename = "stop")]
+    Stop,
+}
+
+/// Events for driving the local audio player.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum LocalAudio {
+    #[serde(rename = "local/current")]
+    LocalCurrent { event: LocalAudioEvent },
+    #[serde(rename = "local/volume")]
+    LocalVolume { volume: u32 },
+}
+
+impl Message for LocalAudio {
+    /// Whether a message should be cached or not and under what key.
+    fn id(&self) -> Option<&'static str> {
+        use self::LocalAudio::*;
+
+        match *self {
+            LocalCurrent { .. } => Some("local/current"),
+            LocalVolume { .. } => Some("local/volume"),
         }
     }
 }
@@ -161,6 +210,45 @@ pub enum Global {
     },
     #[serde(rename = "song/modified")]
     SongModified,
+    /// Votes cast on the current song.
+    #[serde(rename = "song/votes")]
+    SongVotes {
+        track_id: TrackId,
+        skip_votes: u32,
+        skip_threshold: u32,
+        likes: u64,
+        dislikes: u64,
+    },
+    /// Live results of the current poll.
+    #[serde(rename = "poll")]
+    Poll {
+        id: i32,
+        question: String,
+        round: i32,
+        options: Vec<PollOption>,
+        closed: bool,
+    },
+    /// Live results of a native Twitch poll.
+    #[serde(rename = "twitch/poll")]
+    TwitchPoll {
+        id: String,
+        title: String,
+        choices: Vec<TwitchPollChoice>,
+        closed: bool,
+    },
+    /// Live state of a native Twitch prediction.
+    #[serde(rename = "twitch/prediction")]
+    TwitchPrediction {
+        id: String,
+        title: String,
+        outcomes: Vec<TwitchPredictionOutcome>,
+        /// One of `active`, `locked`, `resolved` or `canceled`.
+        status: String,
+        winning_outcome_id: Option<String>,
+    },
+    /// The value of a counter changed.
+    #[serde(rename = "counter")]
+    Counter { name: String, count: i64 },
 }
 
 impl Message for Global {
@@ -171,6 +259,10 @@ impl Message for Global {
         match *self {
             SongProgress { .. } => Some("song/progress"),
             SongCurrent { .. } => Some("song/current"),
+            SongVotes { .. } => Some("song/votes"),
+            Poll { .. } => Some("poll"),
+            TwitchPoll { .. } => Some("twitch/poll"),
+            TwitchPrediction { .. } => Some("twitch/prediction"),
             _ => None,
         }
     }
@@ -224,6 +316,34 @@ impl Global {
     }
 }
 
+/// A single option in a poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct PollOption {
+    /// The keyword used to vote for the option.
+    pub key: String,
+    /// Description of the option.
+    pub description: Option<String>,
+    /// The weighted number of votes cast for the option.
+    pub votes: u64,
+}
+
+/// A single choice in a native Twitch poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct TwitchPollChoice {
+    pub title: String,
+    pub votes: u64,
+}
+
+/// A single outcome in a native Twitch prediction.
+#[derive(Debug, Clone, Serialize)]
+pub struct TwitchPredictionOutcome {
+    pub id: String,
+    pub title: String,
+    pub color: String,
+    pub users: u64,
+    pub channel_points: u64,
+}
+
 /// Events for running commands externally.
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type")]
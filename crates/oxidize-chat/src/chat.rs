@@ -17,7 +17,7 @@ use irc::proto::message::{Message, Tag};
 use irc::proto::Prefix;
 use notify::{recommended_watcher, RecommendedWatcher, Watcher};
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use tokio::sync::{mpsc, Notify};
 
@@ -26,12 +26,16 @@ use crate::command;
 use crate::currency_admin;
 use crate::idle;
 use crate::messages;
+use crate::moderation;
 use crate::module;
 use crate::reward_loop;
 use crate::script;
 use crate::sender;
+use crate::spam;
 use crate::stream_info;
+use crate::strikes;
 use crate::task;
+use crate::template_helpers;
 use crate::utils;
 
 const SERVER: &str = "irc.chat.twitch.tv";
@@ -134,6 +138,8 @@ struct Setup {
     #[dependency]
     bad_words: db::Words,
     #[dependency]
+    strikes: db::Strikes,
+    #[dependency]
     message_log: messagelog::MessageLog,
     #[dependency]
     command_bus: bus::Bus<bus::Command>,
@@ -163,6 +169,7 @@ impl ChatLoop<'_> {
             streamer,
             auth,
             bad_words,
+            strikes,
             message_log,
             command_bus,
             global_bus,
@@ -207,18 +214,26 @@ impl ChatLoop<'_> {
 
         let url_whitelist_enabled = chat_settings.var("url-whitelist/enabled", true).await?;
         let bad_words_enabled = chat_settings.var("bad-words/enabled", false).await?;
+        let bad_words_normalize = chat_settings.var("bad-words/normalize", false).await?;
         let sender_ty = chat_settings.var("sender-type", sender::Type::Chat).await?;
         let threshold = chat_settings.var("idle-detection/threshold", 5).await?;
         let idle = idle::Idle::new(threshold);
+        let ladder = strikes::Ladder::new(&chat_settings.scoped("strikes")).await?;
+        let spam = spam::Chain::new(&chat_settings.scoped("spam")).await?;
 
         let nightbot = injector.var::<api::NightBot>().await;
 
         let sender =
             sender::Sender::new(sender_ty, chat_channel.clone(), client.sender(), nightbot)?;
 
+        let moderation = moderation::Moderation::new(sender.clone(), bot.clone(), streamer.clone());
+
         let (stream_info, stream_info_future) =
             stream_info::setup(streamer.clone(), stream_state_tx.clone());
 
+        let template_helpers =
+            template_helpers::TemplateHelpers::new(injector, &settings, &stream_info).await?;
+
         let context_inner = Arc::new(command::ContextInner::new(
             sender.clone(),
             auth.scope_cooldowns(),
@@ -261,9 +276,11 @@ impl ChatLoop<'_> {
                     handlers: &mut handlers,
                     tasks: &mut hook_futures,
                     stream_info: &stream_info,
+                    template_helpers: &template_helpers,
                     idle: &idle,
                     streamer: &streamer,
                     sender: &sender,
+                    moderation: &moderation,
                     settings: &settings,
                     injector,
                 })
@@ -333,9 +350,14 @@ impl ChatLoop<'_> {
         let mut handler = Handler {
             streamer: &streamer,
             sender: sender.clone(),
+            moderation: &moderation,
             whitelisted_hosts,
             commands,
+            command_cooldowns: CommandCooldowns::default(),
             bad_words: &bad_words,
+            strikes: &strikes,
+            ladder: &ladder,
+            spam: &spam,
             global_bus: &global_bus,
             aliases,
             api_url: Arc::new(api_url),
@@ -347,10 +369,12 @@ impl ChatLoop<'_> {
             bot: &bot,
             handler_shutdown: false,
             stream_info: &stream_info,
+            template_helpers: &template_helpers,
             auth: &auth,
             currency_handler: &currency_handler,
             url_whitelist_enabled,
             bad_words_enabled,
+            bad_words_normalize,
             chat_log: chat_log_builder.build()?,
             messages: &messages,
             context_inner: &context_inner,
@@ -498,18 +522,95 @@ impl ChatLoop<'_> {
     }
 }
 
+/// The scope or role required by a custom command.
+#[derive(Debug, PartialEq, Eq)]
+enum RequiredScope {
+    Role(Role),
+    Scope(Scope),
+    Unknown,
+}
+
+impl RequiredScope {
+    /// Parse a restriction like `song` or `@subscriber`.
+    fn parse(scope: &str) -> Result<Self> {
+        if scope.starts_with('@') {
+            return Ok(match str::parse::<Role>(scope)? {
+                Role::Unknown => RequiredScope::Unknown,
+                role => RequiredScope::Role(role),
+            });
+        }
+
+        Ok(match str::parse::<Scope>(scope)? {
+            Scope::Unknown => RequiredScope::Unknown,
+            scope => RequiredScope::Scope(scope),
+        })
+    }
+}
+
+/// Cooldowns of custom commands, both for everyone and per user.
+#[derive(Default)]
+struct CommandCooldowns {
+    /// When the cooldown of each custom command ends.
+    global: HashMap<db::Key, time::Instant>,
+    /// When the cooldown of each custom command ends for a specific user.
+    user: HashMap<(db::Key, String), time::Instant>,
+}
+
+impl CommandCooldowns {
+    /// Get the remaining cooldown for the given command and user, if any.
+    fn remaining(&self, key: &db::Key, user: &str, now: time::Instant) -> Option<time::Duration> {
+        let user_key = (key.clone(), user.to_string());
+
+        [self.global.get(key), self.user.get(&user_key)]
+            .into_iter()
+            .flatten()
+            .filter_map(|until| until.checked_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+            .max()
+    }
+
+    /// Start the cooldowns of the given command after it's been used.
+    fn start(
+        &mut self,
+        key: &db::Key,
+        user: &str,
+        restrictions: &db::commands::Restrictions,
+        now: time::Instant,
+    ) {
+        if let Some(cooldown) = restrictions.cooldown {
+            self.global.insert(key.clone(), now + cooldown.as_std());
+        }
+
+        if let Some(cooldown) = restrictions.user_cooldown {
+            self.user.retain(|_, until| *until > now);
+            self.user
+                .insert((key.clone(), user.to_string()), now + cooldown.as_std());
+        }
+    }
+}
+
 /// Handler for incoming messages.
 struct Handler<'a> {
     /// Current Streamer.
     streamer: &'a api::TwitchAndUser,
     /// Queue for sending messages.
     sender: sender::Sender,
+    /// Moderation actions.
+    moderation: &'a moderation::Moderation,
     /// Whitelisted hosts for links.
     whitelisted_hosts: HashSet<String>,
     /// All registered commands.
     commands: Option<db::Commands>,
+    /// Cooldowns of custom commands.
+    command_cooldowns: CommandCooldowns,
     /// Bad words.
     bad_words: &'a db::Words,
+    /// Strikes issued to users.
+    strikes: &'a db::Strikes,
+    /// Punishments for repeated offences.
+    ladder: &'a strikes::Ladder,
+    /// Spam filters.
+    spam: &'a spam::Chain,
     /// For sending notifications.
     global_bus: &'a bus::Bus<bus::Global>,
     /// Aliases.
@@ -532,11 +633,14 @@ struct Handler<'a> {
     handler_shutdown: bool,
     /// Stream information.
     stream_info: &'a stream_info::StreamInfo,
+    /// Resolves data for template helpers.
+    template_helpers: &'a template_helpers::TemplateHelpers,
     /// Information about auth.
     auth: &'a Auth,
     /// Handler for currencies.
     currency_handler: &'a currency_admin::Handler,
     bad_words_enabled: settings::Var<bool>,
+    bad_words_normalize: settings::Var<bool>,
     url_whitelist_enabled: settings::Var<bool>,
     /// Handler for chat logs.
     chat_log: Option<chat_log::ChatLog>,
@@ -674,23 +778,12 @@ async fn process_command<'a>(
 }
 
 impl<'a> Handler<'a> {
-    /// Delete the given message.
-    fn delete_message(&self, user: &User) -> Result<()> {
-        let id = match &user.inner.tags.id {
-            Some(id) => id,
-            None => return Ok(()),
-        };
-
-        tracing::info!("Attempting to delete message: {}", id);
-        user.inner.sender.delete(id);
-        Ok(())
-    }
-
-    /// Test if the message should be deleted.
-    async fn should_be_deleted(&self, user: &User, message: &str) -> bool {
+    /// Test if the message violates the rules of the channel, in which case it
+    /// should be deleted.
+    async fn violation(&self, user: &User, message: &str) -> Option<Violation> {
         // Moderators can say whatever they want.
         if user.is_moderator() {
-            return false;
+            return None;
         }
 
         if self.bad_words_enabled.load().await {
@@ -711,7 +804,7 @@ impl<'a> Handler<'a> {
                     }
                 }
 
-                return true;
+                return Some(Violation::BadWord);
             }
         }
 
@@ -719,23 +812,23 @@ impl<'a> Handler<'a> {
             && self.url_whitelist_enabled.load().await
             && self.has_bad_link(message)
         {
-            return true;
+            return Some(Violation::BadLink);
         }
 
-        false
+        if let Some(user) = user.real() {
+            if let Some(rule) = self.spam.test(&user, message).await {
+                return Some(Violation::Spam(rule));
+            }
+        }
+
+        None
     }
 
     /// Test the message for bad words.
     async fn test_bad_words(&self, message: &str) -> Option<Arc<db::Word>> {
+        let normalize = self.bad_words_normalize.load().await;
         let tester = self.bad_words.tester().await;
-
-        for word in common::words::trimmed(message) {
-            if let Some(word) = tester.test(word) {
-                return Some(word);
-            }
-        }
-
-        None
+        tester.test_message(message, normalize)
     }
 
     /// Check if the given iterator has URLs that need to be
@@ -825,24 +918,47 @@ impl<'a> Handler<'a> {
         let mut it = common::words::split(message.clone());
         let first = it.next();
 
-        if let Some(commands) = self.commands.as_ref() {
+        // NB: cloned since checking restrictions requires exclusive access.
+        if let Some(commands) = self.commands.clone() {
             if let Some((command, captures)) = commands
                 .resolve(user.sender().channel(), first.as_deref(), &it)
                 .await
             {
-                if command.has_var("count") {
-                    commands.increment(&command).await?;
-                }
+                if self.check_restrictions(user, &command).await? {
+                    let args = it.clone().collect::<Vec<_>>();
+
+                    let helpers = self
+                        .template_helpers
+                        .resolve(
+                            &command.template,
+                            user.sender().channel(),
+                            user.real().map(|real| real.login()),
+                            args,
+                        )
+                        .await?;
+
+                    let streamer = self.streamer;
+
+                    let vars = CommandVars {
+                        name: user.display_name(),
+                        target: &streamer.user.login,
+                        // NB: the count as it will be once the command has
+                        // been counted below.
+                        count: command.count() + i32::from(command.has_var("count")),
+                        helpers,
+                        captures,
+                    };
 
-                let vars = CommandVars {
-                    name: user.display_name(),
-                    target: &self.streamer.user.login,
-                    count: command.count(),
-                    captures,
-                };
+                    let charge = self.charge(user, &command);
 
-                let response = command.render(&vars)?;
-                self.sender.privmsg(response).await;
+                    if let Some(response) = render_and_charge(&command, &vars, charge).await? {
+                        if command.has_var("count") {
+                            commands.increment(&command).await?;
+                        }
+
+                        self.sender.privmsg(response).await;
+                    }
+                }
             }
         }
 
@@ -873,13 +989,111 @@ impl<'a> Handler<'a> {
             }
         }
 
-        if self.should_be_deleted(user, &message).await {
-            self.delete_message(user)?;
+        if let Some(violation) = self.violation(user, &message).await {
+            hooks.push(Box::pin(enforce(
+                self.moderation,
+                self.ladder,
+                self.strikes,
+                user.clone(),
+                violation,
+            )));
         }
 
         Ok(())
     }
 
+    /// Check that the user is allowed to use the given custom command.
+    ///
+    /// Responds to the user and returns `false` if they are not allowed to
+    /// use it.
+    async fn check_restrictions(
+        &mut self,
+        user: &User,
+        command: &db::commands::Command,
+    ) -> Result<bool> {
+        let restrictions = &command.restrictions;
+
+        // NB: injected commands are not restricted.
+        let Some(real) = user.real() else {
+            return Ok(true);
+        };
+
+        if let Some(scope) = restrictions.scope.as_deref() {
+            let allowed = match RequiredScope::parse(scope)? {
+                RequiredScope::Role(role) => real.has_role(&role).await,
+                RequiredScope::Scope(scope) => real.has_scope(scope).await,
+                RequiredScope::Unknown => false,
+            };
+
+            if !allowed {
+                respond!(real, "You are not allowed to use that command");
+                return Ok(false);
+            }
+        }
+
+        let now = time::Instant::now();
+
+        if let Some(remaining) = self
+            .command_cooldowns
+            .remaining(&command.key, real.login(), now)
+        {
+            respond!(
+                real,
+                "Cooldown in effect, please wait at least {}!",
+                common::display::compact_duration(remaining)
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Charge the user for using the given custom command and start its
+    /// cooldowns.
+    ///
+    /// Responds to the user and returns `false` if they can't afford it.
+    async fn charge(&mut self, user: &User, command: &db::commands::Command) -> Result<bool> {
+        let restrictions = &command.restrictions;
+
+        // NB: injected commands are not restricted.
+        let Some(real) = user.real() else {
+            return Ok(true);
+        };
+
+        if let Some(cost) = restrictions.cost.filter(|cost| *cost > 0) {
+            let Some(currency) = self.currency_handler.currency.load().await else {
+                respond!(real, "No currency configured");
+                return Ok(false);
+            };
+
+            let channel = user.sender().channel();
+
+            if !currency.balance_debit(channel, real.login(), cost).await? {
+                let balance = currency
+                    .balance_of(channel, real.login())
+                    .await?
+                    .unwrap_or_default();
+
+                respond!(
+                    real,
+                    "You need {cost} {currency} to use that command, you have {balance} {currency}",
+                    cost = cost,
+                    currency = currency.name,
+                    balance = balance.balance,
+                );
+                return Ok(false);
+            }
+        }
+
+        self.command_cooldowns.start(
+            &command.key,
+            real.login(),
+            restrictions,
+            time::Instant::now(),
+        );
+        Ok(true)
+    }
+
     /// Run the given raw command.
     pub(crate) async fn raw(
         &mut self,
@@ -930,6 +1144,7 @@ impl<'a> Handler<'a> {
                 };
 
                 let login = Box::<str>::from(login);
+                self.stream_info.observe_chatter(&login);
 
                 if let Some(chat_log) = self.chat_log.as_ref().cloned() {
                     let tags = tags.clone();
@@ -1028,6 +1243,75 @@ impl<'a> Handler<'a> {
     }
 }
 
+/// Render the response to a custom command, and only charge the user for it
+/// once that succeeded so that a broken template doesn't cost anything.
+///
+/// Returns `None` if the user couldn't be charged.
+async fn render_and_charge<F>(
+    command: &db::commands::Command,
+    vars: &CommandVars<'_>,
+    charge: F,
+) -> Result<Option<String>>
+where
+    F: std::future::Future<Output = Result<bool>>,
+{
+    let response = command.render(vars)?;
+
+    if !charge.await? {
+        return Ok(None);
+    }
+
+    Ok(Some(response))
+}
+
+/// Delete the given message and issue a strike against its author.
+///
+/// Both go through the Twitch API, so this is queued up as a hook to run
+/// alongside chat instead of holding up the messages behind this one.
+async fn enforce(
+    moderation: &moderation::Moderation,
+    ladder: &strikes::Ladder,
+    strikes: &db::Strikes,
+    user: User,
+    violation: Violation,
+) -> Result<()> {
+    if let Some(id) = &user.inner.tags.id {
+        tracing::info!("Attempting to delete message: {}", id);
+        moderation.delete_message(id).await;
+    }
+
+    if let Some(user) = user.real() {
+        ladder
+            .strike(strikes, moderation, &user, violation.reason())
+            .await
+            .context("Failed to issue strike")?;
+    }
+
+    Ok(())
+}
+
+/// A rule of the channel which was violated by a message.
+#[derive(Debug, Clone, Copy)]
+enum Violation {
+    /// The message contained a bad word.
+    BadWord,
+    /// The message contained a link to a host which is not whitelisted.
+    BadLink,
+    /// The message was caught by a spam filter.
+    Spam(spam::Rule),
+}
+
+impl Violation {
+    /// The reason recorded for the violation.
+    fn reason(self) -> &'static str {
+        match self {
+            Violation::BadWord => "bad word",
+            Violation::BadLink => "disallowed link",
+            Violation::Spam(rule) => rule.reason(),
+        }
+    }
+}
+
 /// Struct representing a real user.
 ///
 /// For example, an injected command does not have a real user associated with it.
@@ -1047,11 +1331,26 @@ impl<'a> RealUser<'a> {
         self.login
     }
 
+    /// Get the id of the user, if known.
+    pub fn id(&self) -> Option<&'a str> {
+        self.tags.user_id.as_deref()
+    }
+
     /// Get the display name of the user.
     pub fn display_name(&self) -> &'a str {
         self.tags.display_name.as_deref().unwrap_or(self.login)
     }
 
+    /// Get the channel the user is in.
+    pub fn channel(&self) -> &'a Channel {
+        self.sender.channel()
+    }
+
+    /// Get the tags associated with the message sent by the user.
+    pub(crate) fn tags(&self) -> &'a Tags {
+        self.tags
+    }
+
     /// Respond to the user with a message.
     pub async fn respond(&self, m: impl fmt::Display) {
         self.sender
@@ -1115,6 +1414,18 @@ impl<'a> RealUser<'a> {
     {
         self.auth.test_any(scope, self.login, self.roles()).await
     }
+
+    /// Test if the current user has the given role, including custom roles.
+    pub async fn has_role(&self, role: &Role) -> bool {
+        match role {
+            Role::Custom(..) => self
+                .auth
+                .custom_roles_for_user(self.login)
+                .await
+                .contains(role),
+            role => self.roles().contains(role),
+        }
+    }
 }
 
 /// Information about the user.
@@ -1262,6 +1573,14 @@ impl User {
 
         user.has_scope(scope).await
     }
+
+    /// Test if the current user has the given role, including custom roles.
+    pub async fn has_role(&self, role: &Role) -> bool {
+        match self.real() {
+            Some(user) => user.has_role(role).await,
+            None => self.roles().contains(role),
+        }
+    }
 }
 
 struct PartitionResponse<'a, I> {
@@ -1384,6 +1703,7 @@ pub(crate) struct CommandVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
     count: i32,
+    helpers: template::HelperData,
     #[serde(flatten)]
     captures: db::Captures<'a>,
 }
@@ -1456,3 +1776,277 @@ async fn refresh_roles(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time;
+
+    use async_injector::Injector;
+    use auth::{Auth, Role, Scope};
+    use common::irc::Tags;
+    use common::stream::StreamExt;
+    use common::{Channel, Duration};
+    use irc::client::{self, Client};
+    use tokio::sync::Notify;
+
+    use crate::{command, moderation, sender, stream_info, strikes};
+
+    use super::{
+        enforce, render_and_charge, CommandCooldowns, CommandVars, Principal, RequiredScope, User,
+        UserInner, Violation,
+    };
+
+    fn key(name: &str) -> db::Key {
+        db::Key {
+            channel: Channel::new("#channel").to_owned(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(
+            RequiredScope::parse("song").unwrap(),
+            RequiredScope::Scope(Scope::Song)
+        );
+        assert_eq!(
+            RequiredScope::parse("@subscriber").unwrap(),
+            RequiredScope::Role(Role::Subscriber)
+        );
+        assert_eq!(
+            RequiredScope::parse("@regulars").unwrap(),
+            RequiredScope::Role(Role::Custom("regulars".into()))
+        );
+        assert_eq!(
+            RequiredScope::parse("not-a-scope").unwrap(),
+            RequiredScope::Unknown
+        );
+        assert_eq!(
+            RequiredScope::parse("@NotARole").unwrap(),
+            RequiredScope::Unknown
+        );
+    }
+
+    #[test]
+    fn test_command_cooldowns() {
+        let other = key("other");
+        let key = key("hello");
+        let now = time::Instant::now();
+
+        let mut cooldowns = CommandCooldowns::default();
+        assert_eq!(cooldowns.remaining(&key, "alice", now), None);
+
+        let restrictions = db::commands::Restrictions {
+            cooldown: Some(Duration::seconds(10)),
+            user_cooldown: Some(Duration::seconds(60)),
+            ..Default::default()
+        };
+
+        cooldowns.start(&key, "alice", &restrictions, now);
+
+        let later = now + time::Duration::from_secs(5);
+        assert_eq!(
+            cooldowns.remaining(&key, "alice", later),
+            Some(time::Duration::from_secs(55))
+        );
+        assert_eq!(
+            cooldowns.remaining(&key, "bob", later),
+            Some(time::Duration::from_secs(5))
+        );
+        assert_eq!(cooldowns.remaining(&other, "alice", later), None);
+
+        // global cooldown has expired, but not alice's cooldown.
+        let later = now + time::Duration::from_secs(10);
+        assert_eq!(cooldowns.remaining(&key, "bob", later), None);
+        assert_eq!(
+            cooldowns.remaining(&key, "alice", later),
+            Some(time::Duration::from_secs(50))
+        );
+
+        let later = now + time::Duration::from_secs(60);
+        assert_eq!(cooldowns.remaining(&key, "alice", later), None);
+    }
+
+    #[test]
+    fn test_command_without_cooldowns() {
+        let key = key("hello");
+        let now = time::Instant::now();
+
+        let mut cooldowns = CommandCooldowns::default();
+        cooldowns.start(&key, "alice", &Default::default(), now);
+        assert_eq!(cooldowns.remaining(&key, "alice", now), None);
+    }
+
+    const STRIKES_SCHEMA: &[u8] = br#"
+types:
+  chat/strikes/enabled: {doc: "", type: {id: bool}}
+  chat/strikes/decay: {doc: "", type: {id: duration}}
+  chat/strikes/timeout: {doc: "", type: {id: duration}}
+  chat/strikes/long-timeout: {doc: "", type: {id: duration}}
+"#;
+
+    /// Set up a user who sent a message with the given id.
+    async fn user(sender: &sender::Sender, id: &str) -> User {
+        let db = db::Database::open(Path::new(":memory:")).unwrap();
+        let schema = auth::Schema::load_static(b"roles: {}\nscopes: {}").unwrap();
+
+        User {
+            inner: Arc::new(UserInner {
+                tags: Tags {
+                    id: Some(id.to_string()),
+                    ..Tags::default()
+                },
+                sender: sender.clone(),
+                principal: Principal::User {
+                    login: "alice".into(),
+                },
+                streamer_login: "streamer".to_string(),
+                stream_info: stream_info::StreamInfo {
+                    data: Default::default(),
+                },
+                auth: Auth::new(db, schema).await.unwrap(),
+                context: Arc::new(command::ContextInner::new(
+                    sender.clone(),
+                    HashMap::new(),
+                    Arc::new(Notify::new()),
+                )),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_moderation() {
+        // Helix which accepts requests but never gets around to responding.
+        let helix = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", helix.local_addr().unwrap());
+
+        let token = api::Token::new();
+        token.set("token", "client-id");
+
+        let twitch = |id: &str| api::TwitchAndUser {
+            user: Arc::new(api::User {
+                id: id.to_string(),
+                login: id.to_string(),
+                display_name: id.to_string(),
+            }),
+            client: api::Twitch::new("test", token.clone())
+                .unwrap()
+                .with_api_url(&api_url)
+                .unwrap(),
+        };
+
+        let config = client::data::config::Config {
+            server: Some("irc.example.com".to_string()),
+            use_mock_connection: true,
+            ..client::data::config::Config::default()
+        };
+
+        let client = Client::from_config(config).await.unwrap();
+        let nightbot = Injector::new().var::<api::NightBot>().await;
+        let sender = sender::Sender::new(
+            settings::Var::new(sender::Type::Chat),
+            "#streamer".to_string(),
+            client.sender(),
+            nightbot,
+        )
+        .unwrap();
+
+        let moderation =
+            moderation::Moderation::new(sender.clone(), twitch("bot"), twitch("streamer"));
+
+        let db = db::Database::open(Path::new(":memory:")).unwrap();
+        let schema = settings::Schema::load_bytes(STRIKES_SCHEMA).unwrap();
+        let settings = settings::Settings::new(db.clone(), schema);
+        let ladder = strikes::Ladder::new(&settings.scoped("chat/strikes"))
+            .await
+            .unwrap();
+        let strikes = db::Strikes::load(db).await.unwrap();
+
+        let mut hooks = common::Futures::default();
+
+        for id in ["first", "second"] {
+            hooks.push(Box::pin(enforce(
+                &moderation,
+                &ladder,
+                &strikes,
+                user(&sender, id).await,
+                Violation::BadWord,
+            )));
+        }
+
+        // The second message is deleted while the first delete is still
+        // waiting on Helix.
+        let accept = async {
+            let first = helix.accept().await.unwrap();
+            let second = helix.accept().await.unwrap();
+            (first, second)
+        };
+
+        let _connections = tokio::select! {
+            _ = hooks.next() => panic!("moderation should still be waiting on helix"),
+            connections = accept => connections,
+            _ = tokio::time::sleep(time::Duration::from_secs(5)) => {
+                panic!("second message was held up by the first")
+            }
+        };
+
+        assert_eq!(hooks.len(), 2);
+    }
+
+    /// Set up a custom command with the given template.
+    async fn command(template: &str) -> Arc<db::commands::Command> {
+        let channel = Channel::new("#channel");
+        let db = db::Database::open(Path::new(":memory:")).unwrap();
+        let commands = db::Commands::load(db).await.unwrap();
+        let template = template::Template::compile(template).unwrap();
+        commands.edit(channel, "test", template).await.unwrap();
+
+        let it = common::words::split(Arc::new(String::from("test")));
+        let (command, _) = commands.resolve(channel, Some("test"), &it).await.unwrap();
+        command
+    }
+
+    fn vars() -> CommandVars<'static> {
+        CommandVars {
+            name: Some("alice"),
+            target: "streamer",
+            count: 0,
+            helpers: Default::default(),
+            captures: db::Captures::Prefix { rest: "" },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_and_charge() {
+        let charged = Cell::new(false);
+
+        let charge = |ok| {
+            let charged = &charged;
+
+            async move {
+                charged.set(true);
+                Ok(ok)
+            }
+        };
+
+        let hello = command("Hello {{name}}").await;
+        let response = render_and_charge(&hello, &vars(), charge(true)).await;
+        assert_eq!(response.unwrap().as_deref(), Some("Hello alice"));
+        assert!(charged.take());
+
+        // the user couldn't afford it.
+        let response = render_and_charge(&hello, &vars(), charge(false)).await;
+        assert_eq!(response.unwrap(), None);
+        assert!(charged.take());
+
+        // a template which fails to render doesn't cost anything.
+        let broken = command("You rolled {{random}}").await;
+        let response = render_and_charge(&broken, &vars(), charge(true)).await;
+        assert!(response.is_err());
+        assert!(!charged.take());
+    }
+}
@@ -115,6 +115,34 @@ impl ContextInner {
     pub(crate) fn notify(&self) -> &ContextNotify {
         &self.notify
     }
+
+    /// Remove the specified hook.
+    async fn remove_hook(&self, id: HookId) {
+        let mut hooks = self.message_hooks.write().await;
+
+        if hooks.contains(id.0) {
+            let _ = hooks.remove(id.0);
+        }
+    }
+}
+
+/// A handle to a hook which has been inserted.
+#[derive(Clone)]
+pub struct HookHandle {
+    id: HookId,
+    inner: Arc<ContextInner>,
+}
+
+impl HookHandle {
+    /// Get the identifier of the hook.
+    pub fn id(&self) -> HookId {
+        self.id
+    }
+
+    /// Remove the hook.
+    pub async fn remove(&self) {
+        self.inner.remove_hook(self.id).await;
+    }
 }
 
 /// Context for a single command invocation.
@@ -160,10 +188,15 @@ impl<'a> Context<'a> {
 
     /// Setup the specified hook.
     pub async fn remove_hook(&self, id: HookId) {
-        let mut hooks = self.inner.message_hooks.write().await;
+        self.inner.remove_hook(id).await;
+    }
 
-        if hooks.contains(id.0) {
-            let _ = hooks.remove(id.0);
+    /// Get a handle to the specified hook, which can be used to remove it
+    /// after the current command has completed.
+    pub fn hook_handle(&self, id: HookId) -> HookHandle {
+        HookHandle {
+            id,
+            inner: self.inner.clone(),
         }
     }
 
@@ -5,6 +5,7 @@ use async_injector::Injector;
 use async_trait::async_trait;
 use auth::Scope;
 use common::display;
+use currency::{TOP_DEFAULT, TOP_MAX};
 
 use crate::command;
 
@@ -84,6 +85,53 @@ impl command::Handler for Handler {
                     }
                 }
             }
+            Some("top") => {
+                let n = ctx
+                    .next_parse_optional::<usize>()?
+                    .unwrap_or(TOP_DEFAULT)
+                    .clamp(1, TOP_MAX);
+
+                let balances = currency
+                    .balances_top(ctx.channel(), currency::BalanceOrder::Balance, 0, n)
+                    .await?;
+
+                let top = balances
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| format!("{}. {} ({})", i + 1, b.user, b.amount))
+                    .collect::<Vec<_>>();
+
+                if top.is_empty() {
+                    respond!(ctx, "Nobody has any {name} yet", name = currency.name);
+                } else {
+                    respond!(
+                        ctx,
+                        "Top {name}: {top}",
+                        name = currency.name,
+                        top = top.join(", ")
+                    );
+                }
+            }
+            Some("rank") => {
+                let user = ctx
+                    .user
+                    .real()
+                    .ok_or(respond_err!("Only real users can check their rank"))?;
+
+                match currency.balance_rank(ctx.channel(), user.login()).await? {
+                    Some(rank) => {
+                        respond!(
+                            user,
+                            "You are ranked #{rank} by {name}.",
+                            rank = rank,
+                            name = currency.name
+                        );
+                    }
+                    None => {
+                        respond!(user, "You don't have any {name} yet.", name = currency.name);
+                    }
+                }
+            }
             Some("give") => {
                 let taker = db::user_id(&ctx.next_str("<user> <amount>")?);
                 let amount: i64 = ctx.next_parse("<user> <amount>")?;
@@ -207,6 +255,8 @@ impl command::Handler for Handler {
             Some(..) => {
                 let mut alts = Vec::new();
 
+                alts.push("top");
+                alts.push("rank");
                 alts.push("give");
 
                 if ctx.user.has_scope(Scope::CurrencyBoost).await {
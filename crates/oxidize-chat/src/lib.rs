@@ -23,9 +23,15 @@ pub mod messages;
 
 mod chat_log;
 mod currency_admin;
+mod moderation;
+pub use self::moderation::Moderation;
 mod reward_loop;
 mod sender;
 pub use self::sender::Sender;
+mod spam;
+mod strikes;
+mod template_helpers;
+pub use self::template_helpers::TemplateHelpers;
 
 mod respond;
 pub use self::respond::{respond, RespondErr};
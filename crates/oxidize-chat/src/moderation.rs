@@ -0,0 +1,206 @@
+//! Moderation actions performed through the Twitch API.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use api::twitch::model;
+use common::{Cooldown, Duration};
+
+use crate::sender;
+
+/// Service used to perform moderation actions in chat.
+///
+/// Actions are performed on behalf of the bot, which needs to be a moderator
+/// in the channel. Every action is logged, and failures are also reported
+/// back to chat.
+#[derive(Clone)]
+pub struct Moderation {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    sender: sender::Sender,
+    bot: api::TwitchAndUser,
+    streamer: api::TwitchAndUser,
+    /// Limits how often failures are reported to chat.
+    failure_cooldown: parking_lot::Mutex<Cooldown>,
+}
+
+impl Moderation {
+    /// Construct a new moderation service.
+    pub(crate) fn new(
+        sender: sender::Sender,
+        bot: api::TwitchAndUser,
+        streamer: api::TwitchAndUser,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                sender,
+                bot,
+                streamer,
+                failure_cooldown: parking_lot::Mutex::new(Cooldown::from_duration(
+                    Duration::seconds(60),
+                )),
+            }),
+        }
+    }
+
+    /// Look up the id of the user with the given login.
+    pub async fn user_id(&self, login: &str) -> Result<Option<String>> {
+        let user = self.inner.bot.client.user_by_login(login).await?;
+        Ok(user.map(|u| u.id))
+    }
+
+    /// Delete the message with the given id.
+    ///
+    /// Returns `true` if the message was deleted.
+    pub async fn delete_message(&self, id: &str) -> bool {
+        let result = self
+            .inner
+            .bot
+            .client
+            .delete_chat_message(self.broadcaster_id(), self.moderator_id(), id)
+            .await;
+
+        self.report(format!("delete message `{}`", id), result)
+            .await
+    }
+
+    /// Delete all messages in chat.
+    ///
+    /// Returns `true` if chat was cleared.
+    pub async fn clear_chat(&self) -> bool {
+        let result = self
+            .inner
+            .bot
+            .client
+            .clear_chat(self.broadcaster_id(), self.moderator_id())
+            .await;
+
+        self.report("clear chat".to_string(), result).await
+    }
+
+    /// Time out the user with the given id for the specified duration.
+    ///
+    /// Returns `true` if the user was timed out.
+    pub async fn timeout(&self, user_id: &str, duration: Duration, reason: Option<&str>) -> bool {
+        let request = model::BanUserRequest {
+            user_id,
+            duration: Some(u32::try_from(duration.num_seconds()).unwrap_or(u32::MAX)),
+            reason,
+        };
+
+        let result = self
+            .inner
+            .bot
+            .client
+            .ban_user(self.broadcaster_id(), self.moderator_id(), request)
+            .await;
+
+        self.report(
+            format!("time out user `{}` for {}", user_id, duration),
+            result,
+        )
+        .await
+    }
+
+    /// Ban the user with the given id.
+    ///
+    /// Returns `true` if the user was banned.
+    pub async fn ban(&self, user_id: &str, reason: Option<&str>) -> bool {
+        let request = model::BanUserRequest {
+            user_id,
+            duration: None,
+            reason,
+        };
+
+        let result = self
+            .inner
+            .bot
+            .client
+            .ban_user(self.broadcaster_id(), self.moderator_id(), request)
+            .await;
+
+        self.report(format!("ban user `{}`", user_id), result).await
+    }
+
+    /// Remove a ban or timeout from the user with the given id.
+    ///
+    /// Returns `true` if the user was unbanned.
+    pub async fn unban(&self, user_id: &str) -> bool {
+        let result = self
+            .inner
+            .bot
+            .client
+            .unban_user(self.broadcaster_id(), self.moderator_id(), user_id)
+            .await;
+
+        self.report(format!("unban user `{}`", user_id), result)
+            .await
+    }
+
+    /// Test if shield mode is active.
+    pub async fn is_shield_mode_active(&self) -> Result<bool> {
+        let status = self
+            .inner
+            .bot
+            .client
+            .shield_mode(self.broadcaster_id(), self.moderator_id())
+            .await?;
+
+        Ok(status.map(|s| s.is_active).unwrap_or_default())
+    }
+
+    /// Activate or deactivate shield mode.
+    ///
+    /// Returns `true` if shield mode was updated.
+    pub async fn shield_mode(&self, is_active: bool) -> bool {
+        let result = self
+            .inner
+            .bot
+            .client
+            .update_shield_mode(self.broadcaster_id(), self.moderator_id(), is_active)
+            .await;
+
+        let what = if is_active {
+            "activate shield mode"
+        } else {
+            "deactivate shield mode"
+        };
+
+        self.report(what.to_string(), result).await
+    }
+
+    fn broadcaster_id(&self) -> &str {
+        &self.inner.streamer.user.id
+    }
+
+    fn moderator_id(&self) -> &str {
+        &self.inner.bot.user.id
+    }
+
+    /// Report the result of an action.
+    async fn report(&self, what: String, result: Result<()>) -> bool {
+        let e = match result {
+            Ok(()) => {
+                tracing::info!("Moderation: {}", what);
+                return true;
+            }
+            Err(e) => e,
+        };
+
+        common::log_error!(e, "Moderation: failed to {}", what);
+
+        if self.inner.failure_cooldown.lock().is_open() {
+            self.inner
+                .sender
+                .privmsg(format!(
+                    "Failed to {}, is {} a moderator? :(",
+                    what, self.inner.bot.user.display_name
+                ))
+                .await;
+        }
+
+        false
+    }
+}
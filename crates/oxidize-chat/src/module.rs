@@ -6,8 +6,10 @@ use std::sync::Arc;
 
 use crate::command;
 use crate::idle;
+use crate::moderation;
 use crate::sender;
 use crate::stream_info;
+use crate::template_helpers;
 
 use anyhow::Result;
 
@@ -34,9 +36,11 @@ impl Handlers {
 pub struct HookContext<'a, 'task> {
     pub injector: &'a Injector,
     pub stream_info: &'a stream_info::StreamInfo,
+    pub template_helpers: &'a template_helpers::TemplateHelpers,
     pub idle: &'a idle::Idle,
     pub streamer: &'a api::TwitchAndUser,
     pub sender: &'a sender::Sender,
+    pub moderation: &'a moderation::Moderation,
     pub settings: &'a settings::Settings<::auth::Scope>,
     pub handlers: &'a mut Handlers,
     pub tasks: &'a mut Vec<BoxFuture<'task, Result<()>>>,
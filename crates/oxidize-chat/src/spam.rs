@@ -0,0 +1,582 @@
+//! Heuristics used to detect spam in chat.
+//!
+//! Each heuristic is implemented as a [Filter], which are tested in order by a
+//! [Chain]. Every filter can be enabled individually, and users can be
+//! exempted from it through its corresponding [Scope].
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use auth::Scope;
+use common::irc::Tags;
+use common::Duration;
+
+use crate::chat::RealUser;
+
+/// A rule enforced by a spam filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rule {
+    /// Too large a portion of the message is in upper case.
+    Caps,
+    /// Too large a portion of the message is symbols.
+    Symbols,
+    /// The message contains too many emotes.
+    Emotes,
+    /// The message is too long.
+    Length,
+    /// The same character is repeated too many times in a row.
+    RepeatedCharacters,
+    /// The same message was sent too many times in a row.
+    RepeatedMessages,
+}
+
+impl Rule {
+    /// The reason recorded for violating the rule.
+    pub(crate) fn reason(self) -> &'static str {
+        match self {
+            Rule::Caps => "excessive caps",
+            Rule::Symbols => "excessive symbols",
+            Rule::Emotes => "too many emotes",
+            Rule::Length => "message too long",
+            Rule::RepeatedCharacters => "repeated characters",
+            Rule::RepeatedMessages => "repeated message",
+        }
+    }
+
+    /// The scope which exempts a user from the rule.
+    fn scope(self) -> Scope {
+        match self {
+            Rule::Caps => Scope::ChatBypassSpamCaps,
+            Rule::Symbols => Scope::ChatBypassSpamSymbols,
+            Rule::Emotes => Scope::ChatBypassSpamEmotes,
+            Rule::Length => Scope::ChatBypassSpamLength,
+            Rule::RepeatedCharacters => Scope::ChatBypassSpamRepeatedCharacters,
+            Rule::RepeatedMessages => Scope::ChatBypassSpamRepeatedMessages,
+        }
+    }
+}
+
+/// A message being tested.
+pub(crate) struct Message<'a> {
+    /// Login of the user who sent the message.
+    pub(crate) login: &'a str,
+    /// Text of the message.
+    pub(crate) text: &'a str,
+    /// Tags associated with the message.
+    pub(crate) tags: &'a Tags,
+}
+
+impl Message<'_> {
+    /// Iterate over the characters of the message which are not part of a
+    /// Twitch emote, since emotes like `LUL` or `<3` would otherwise count as
+    /// caps or symbols.
+    fn chars_without_emotes(&self) -> impl Iterator<Item = char> + '_ {
+        let spans = emotes::spans(self.tags);
+
+        self.text
+            .chars()
+            .enumerate()
+            .filter(move |(n, _)| !spans.iter().any(|(s, e)| (*s..=*e).contains(n)))
+            .map(|(_, c)| c)
+    }
+}
+
+/// A single spam heuristic.
+#[async_trait]
+pub(crate) trait Filter: Send + Sync {
+    /// The rule enforced by this filter.
+    fn rule(&self) -> Rule;
+
+    /// Test if the given message violates the rule.
+    async fn test(&self, message: &Message<'_>) -> bool;
+}
+
+struct Entry {
+    enabled: settings::Var<bool>,
+    filter: Box<dyn Filter>,
+}
+
+/// A chain of spam filters.
+pub(crate) struct Chain {
+    entries: Vec<Entry>,
+}
+
+impl Chain {
+    /// Set up the default chain of filters from the given `chat/spam` settings.
+    pub(crate) async fn new(settings: &settings::Settings<::auth::Scope>) -> Result<Self> {
+        let mut chain = Self {
+            entries: Vec::new(),
+        };
+
+        let s = settings.scoped("caps");
+        chain
+            .push(
+                &s,
+                Caps {
+                    min_length: s.var("min-length", 10).await?,
+                    max: s.var("max%", 70).await?,
+                },
+            )
+            .await?;
+
+        let s = settings.scoped("symbols");
+        chain
+            .push(
+                &s,
+                Symbols {
+                    min_length: s.var("min-length", 10).await?,
+                    max: s.var("max%", 50).await?,
+                },
+            )
+            .await?;
+
+        let s = settings.scoped("emotes");
+        chain
+            .push(
+                &s,
+                Emotes {
+                    max: s.var("max", 10).await?,
+                },
+            )
+            .await?;
+
+        let s = settings.scoped("length");
+        chain
+            .push(
+                &s,
+                Length {
+                    max: s.var("max", 300).await?,
+                },
+            )
+            .await?;
+
+        let s = settings.scoped("repeated-characters");
+        chain
+            .push(
+                &s,
+                RepeatedCharacters {
+                    max: s.var("max", 10).await?,
+                },
+            )
+            .await?;
+
+        let s = settings.scoped("repeated-messages");
+        chain
+            .push(
+                &s,
+                RepeatedMessages {
+                    max: s.var("max", 3).await?,
+                    window: s.var("window", Duration::seconds(30)).await?,
+                    seen: Default::default(),
+                },
+            )
+            .await?;
+
+        Ok(chain)
+    }
+
+    /// Add a filter to the chain, which is enabled through the `enabled` key
+    /// of the given settings.
+    pub(crate) async fn push<F>(
+        &mut self,
+        settings: &settings::Settings<::auth::Scope>,
+        filter: F,
+    ) -> Result<()>
+    where
+        F: 'static + Filter,
+    {
+        self.entries.push(Entry {
+            enabled: settings.var("enabled", false).await?,
+            filter: Box::new(filter),
+        });
+
+        Ok(())
+    }
+
+    /// Test the message sent by the given user against every filter in the
+    /// chain, returning the first rule which was violated.
+    pub(crate) async fn test(&self, user: &RealUser<'_>, text: &str) -> Option<Rule> {
+        let message = Message {
+            login: user.login(),
+            text,
+            tags: user.tags(),
+        };
+
+        for entry in &self.entries {
+            if !entry.enabled.load().await {
+                continue;
+            }
+
+            let rule = entry.filter.rule();
+
+            if entry.filter.test(&message).await && !user.has_scope(rule.scope()).await {
+                return Some(rule);
+            }
+        }
+
+        None
+    }
+}
+
+/// Filter limiting the portion of a message that is in upper case.
+struct Caps {
+    min_length: settings::Var<u32>,
+    max: settings::Var<u32>,
+}
+
+#[async_trait]
+impl Filter for Caps {
+    fn rule(&self) -> Rule {
+        Rule::Caps
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        let mut letters = 0;
+        let mut upper = 0;
+
+        for c in message.chars_without_emotes().filter(|c| c.is_alphabetic()) {
+            letters += 1;
+
+            if c.is_uppercase() {
+                upper += 1;
+            }
+        }
+
+        letters >= self.min_length.load().await as usize
+            && percent(upper, letters) > self.max.load().await
+    }
+}
+
+/// Filter limiting the portion of a message that is symbols.
+struct Symbols {
+    min_length: settings::Var<u32>,
+    max: settings::Var<u32>,
+}
+
+#[async_trait]
+impl Filter for Symbols {
+    fn rule(&self) -> Rule {
+        Rule::Symbols
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        let mut total = 0;
+        let mut symbols = 0;
+
+        for c in message
+            .chars_without_emotes()
+            .filter(|c| !c.is_whitespace())
+        {
+            total += 1;
+
+            if !c.is_alphanumeric() {
+                symbols += 1;
+            }
+        }
+
+        total >= self.min_length.load().await as usize
+            && percent(symbols, total) > self.max.load().await
+    }
+}
+
+/// Filter limiting the number of emotes in a message.
+struct Emotes {
+    max: settings::Var<u32>,
+}
+
+#[async_trait]
+impl Filter for Emotes {
+    fn rule(&self) -> Rule {
+        Rule::Emotes
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        emotes::count(message.tags) > self.max.load().await as usize
+    }
+}
+
+/// Filter limiting the length of a message.
+struct Length {
+    max: settings::Var<u32>,
+}
+
+#[async_trait]
+impl Filter for Length {
+    fn rule(&self) -> Rule {
+        Rule::Length
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        message.text.chars().count() > self.max.load().await as usize
+    }
+}
+
+/// Filter limiting how many times a character can be repeated in a row.
+struct RepeatedCharacters {
+    max: settings::Var<u32>,
+}
+
+#[async_trait]
+impl Filter for RepeatedCharacters {
+    fn rule(&self) -> Rule {
+        Rule::RepeatedCharacters
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        longest_run(message.text) > self.max.load().await as usize
+    }
+}
+
+/// Filter limiting how many times a user can send the same message in a row.
+struct RepeatedMessages {
+    max: settings::Var<u32>,
+    window: settings::Var<Duration>,
+    /// The last message seen by each user, when it was last seen, and the
+    /// number of times it has been repeated.
+    seen: parking_lot::Mutex<HashMap<String, (String, Instant, u32)>>,
+}
+
+#[async_trait]
+impl Filter for RepeatedMessages {
+    fn rule(&self) -> Rule {
+        Rule::RepeatedMessages
+    }
+
+    async fn test(&self, message: &Message<'_>) -> bool {
+        let max = self.max.load().await;
+        let window = self.window.load().await.as_std();
+        let now = Instant::now();
+        let text = message.text.trim().to_lowercase();
+
+        let mut seen = self.seen.lock();
+        seen.retain(|_, (_, last, _)| now.duration_since(*last) < window);
+
+        match seen.get_mut(message.login) {
+            Some((last_text, last, count)) if *last_text == text => {
+                *last = now;
+                *count += 1;
+                *count > max
+            }
+            _ => {
+                seen.insert(message.login.to_string(), (text, now, 1));
+                false
+            }
+        }
+    }
+}
+
+/// Calculate how large a percentage `part` is of `total`.
+fn percent(part: usize, total: usize) -> u32 {
+    if total == 0 {
+        return 0;
+    }
+
+    ((part * 100) / total) as u32
+}
+
+/// Find the longest run of a single repeated non-whitespace character.
+fn longest_run(text: &str) -> usize {
+    let mut longest = 0;
+    let mut current = None;
+    let mut run = 0;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            current = None;
+            run = 0;
+            continue;
+        }
+
+        if current == Some(c) {
+            run += 1;
+        } else {
+            current = Some(c);
+            run = 1;
+        }
+
+        longest = usize::max(longest, run);
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::irc::Tags;
+    use common::Duration;
+
+    use super::{longest_run, percent, Caps, Emotes, Filter, Message, RepeatedMessages, Symbols};
+
+    fn tags(emotes: Option<&str>) -> Tags {
+        Tags {
+            emotes: emotes.map(String::from),
+            ..Tags::default()
+        }
+    }
+
+    /// Test a message without emotes from the given user.
+    async fn test<F>(filter: &F, login: &str, text: &str) -> bool
+    where
+        F: Filter,
+    {
+        let tags = tags(None);
+
+        filter
+            .test(&Message {
+                login,
+                text,
+                tags: &tags,
+            })
+            .await
+    }
+
+    #[test]
+    pub(crate) fn test_percent() {
+        assert_eq!(0, percent(0, 0));
+        assert_eq!(50, percent(5, 10));
+        assert_eq!(100, percent(3, 3));
+    }
+
+    #[test]
+    pub(crate) fn test_longest_run() {
+        assert_eq!(0, longest_run(""));
+        assert_eq!(1, longest_run("abc"));
+        assert_eq!(4, longest_run("heyyyy there"));
+        assert_eq!(3, longest_run("!!! ! !"));
+        assert_eq!(2, longest_run("aa     aa"));
+    }
+
+    #[tokio::test]
+    async fn test_caps() {
+        let caps = Caps {
+            min_length: settings::Var::new(10),
+            max: settings::Var::new(70),
+        };
+
+        // short messages are never caps, no matter how loud.
+        assert!(!test(&caps, "a", "").await);
+        assert!(!test(&caps, "a", "HELLO!!!").await);
+        // only letters count towards the minimum length.
+        assert!(!test(&caps, "a", "HELLO 1234567890").await);
+        assert!(test(&caps, "a", "HELLO THERE").await);
+        // the limit itself is allowed, only exceeding it is not.
+        assert!(!test(&caps, "a", "HELLOTHere").await);
+        assert!(test(&caps, "a", "HELLOTHERe").await);
+        assert!(!test(&caps, "a", "hello there").await);
+    }
+
+    #[tokio::test]
+    async fn test_caps_emotes() {
+        let caps = Caps {
+            min_length: settings::Var::new(10),
+            max: settings::Var::new(70),
+        };
+
+        let text = "LUL LUL LUL LUL ok";
+        let emotes = tags(Some("425618:0-2,4-6,8-10,12-14"));
+
+        let message = Message {
+            login: "a",
+            text,
+            tags: &emotes,
+        };
+
+        // emotes don't count as caps, which leaves a short message.
+        assert!(!caps.test(&message).await);
+        assert!(test(&caps, "a", text).await);
+    }
+
+    #[tokio::test]
+    async fn test_symbols() {
+        let symbols = Symbols {
+            min_length: settings::Var::new(10),
+            max: settings::Var::new(50),
+        };
+
+        assert!(!test(&symbols, "a", "!!!!!!!!!").await);
+        assert!(test(&symbols, "a", "!!!!!!!!!!").await);
+        // whitespace doesn't count towards the length.
+        assert!(!test(&symbols, "a", "! ! ! ! ! ! ! ! !").await);
+        // the limit itself is allowed, only exceeding it is not.
+        assert!(!test(&symbols, "a", "hello!!!!!").await);
+        assert!(test(&symbols, "a", "hell!!!!!!").await);
+
+        let text = ":) :) :) :) :) :) hi";
+        let emotes = tags(Some("1:0-1,3-4,6-7,9-10,12-13,15-16"));
+
+        let message = Message {
+            login: "a",
+            text,
+            tags: &emotes,
+        };
+
+        assert!(!symbols.test(&message).await);
+        assert!(test(&symbols, "a", text).await);
+    }
+
+    #[tokio::test]
+    async fn test_emotes() {
+        let filter = Emotes {
+            max: settings::Var::new(2),
+        };
+
+        let text = "Kappa Kappa Kappa";
+
+        for (emotes, expected) in [
+            (None, false),
+            (Some("25:0-4,6-10"), false),
+            (Some("25:0-4,6-10,12-16"), true),
+        ] {
+            let tags = tags(emotes);
+
+            let message = Message {
+                login: "a",
+                text,
+                tags: &tags,
+            };
+
+            assert_eq!(filter.test(&message).await, expected, "{:?}", emotes);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_messages() {
+        let filter = RepeatedMessages {
+            max: settings::Var::new(2),
+            window: settings::Var::new(Duration::seconds(30)),
+            seen: parking_lot::Mutex::new(HashMap::new()),
+        };
+
+        assert!(!test(&filter, "a", "hello").await);
+        // repeats are compared case insensitively and trimmed.
+        assert!(!test(&filter, "a", " Hello ").await);
+        assert!(test(&filter, "a", "HELLO").await);
+        assert!(test(&filter, "a", "hello").await);
+
+        // other users are tracked separately.
+        assert!(!test(&filter, "b", "hello").await);
+
+        // a different message resets the count.
+        assert!(!test(&filter, "a", "bye").await);
+        assert!(!test(&filter, "a", "hello").await);
+        assert!(!test(&filter, "a", "hello").await);
+        assert!(test(&filter, "a", "hello").await);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_messages_window() {
+        let filter = RepeatedMessages {
+            max: settings::Var::new(1),
+            window: settings::Var::new(Duration::default()),
+            seen: parking_lot::Mutex::new(HashMap::new()),
+        };
+
+        // with an empty window, earlier messages are forgotten immediately.
+        assert!(!test(&filter, "a", "hello").await);
+        assert!(!test(&filter, "a", "hello").await);
+    }
+}
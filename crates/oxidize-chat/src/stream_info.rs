@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::pin;
 use std::sync::Arc;
@@ -17,6 +17,8 @@ pub struct Data {
     pub game: Option<String>,
     pub subs: Vec<api::twitch::model::Subscription>,
     pub subs_set: HashSet<String>,
+    /// When each user last sent a message in chat.
+    pub chatters: HashMap<String, time::Instant>,
 }
 
 /// Notify on changes in stream state.
@@ -31,6 +33,44 @@ pub struct StreamInfo {
 }
 
 impl StreamInfo {
+    /// How long chatters are remembered after they last sent a message.
+    const CHATTER_RETENTION: time::Duration = time::Duration::from_secs(60 * 60);
+
+    /// Record that the given user sent a message in chat.
+    pub(crate) fn observe_chatter(&self, login: &str) {
+        let now = time::Instant::now();
+        let mut data = self.data.write();
+
+        if let Some(seen) = data.chatters.get_mut(login) {
+            *seen = now;
+        } else {
+            data.chatters.insert(login.to_string(), now);
+        }
+    }
+
+    /// Count the users who have sent a message in chat within the given
+    /// window.
+    pub fn active_chatters(&self, window: time::Duration) -> usize {
+        let now = time::Instant::now();
+
+        self.data
+            .read()
+            .chatters
+            .values()
+            .filter(|seen| now.duration_since(**seen) <= window)
+            .count()
+    }
+
+    /// Forget about chatters who haven't been active for a while.
+    fn prune_chatters(&self) {
+        let now = time::Instant::now();
+
+        self.data
+            .write()
+            .chatters
+            .retain(|_, seen| now.duration_since(*seen) <= Self::CHATTER_RETENTION);
+    }
+
     /// Check if a name is a subscriber.
     pub(crate) fn is_subscriber(&self, name: &str) -> bool {
         self.data.read().subs_set.contains(name)
@@ -140,6 +180,8 @@ pub(crate) fn setup(
                     }
                 }
                 _ = stream_interval.tick() => {
+                    stream_info.prune_chatters();
+
                     let stream = stream_info
                         .refresh_stream(&streamer, &stream_state_tx);
                     let channel = stream_info
@@ -0,0 +1,279 @@
+//! Escalating punishments for users who break the rules of the channel.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::{Channel, Duration};
+
+use crate::chat::RealUser;
+use crate::moderation;
+
+/// The action taken in response to a strike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Warn the user.
+    Warn,
+    /// Time the user out for the given duration.
+    Timeout(Duration),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Warn => "warn".fmt(fmt),
+            Action::Timeout(duration) => write!(fmt, "timeout {}", duration),
+        }
+    }
+}
+
+/// Ladder of punishments issued for repeated offences.
+///
+/// The first offence is a warning, the second a timeout, and any subsequent
+/// offence a longer timeout. Strikes older than the configured decay no longer
+/// count towards the next punishment.
+pub(crate) struct Ladder {
+    enabled: settings::Var<bool>,
+    decay: settings::Var<Duration>,
+    timeout: settings::Var<Duration>,
+    long_timeout: settings::Var<Duration>,
+    /// Locks held while striking a user, by login.
+    ///
+    /// Strikes against the same user are issued one at a time, so that quick
+    /// offences each see the strikes issued before them.
+    locks: parking_lot::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Ladder {
+    /// Set up the ladder from the given `chat/strikes` settings.
+    pub(crate) async fn new(settings: &settings::Settings<::auth::Scope>) -> Result<Self> {
+        Ok(Self {
+            enabled: settings.var("enabled", false).await?,
+            decay: settings.var("decay", Duration::hours(1)).await?,
+            timeout: settings.var("timeout", Duration::seconds(60)).await?,
+            long_timeout: settings
+                .var("long-timeout", Duration::seconds(10 * 60))
+                .await?,
+            locks: parking_lot::Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Pick the action to take against a user who has the given number of
+    /// strikes which have not yet decayed.
+    async fn action(&self, previous: usize) -> Action {
+        match previous {
+            0 => Action::Warn,
+            1 => Action::Timeout(self.timeout.load().await),
+            _ => Action::Timeout(self.long_timeout.load().await),
+        }
+    }
+
+    /// Issue a strike against the given user and punish them accordingly.
+    pub(crate) async fn strike(
+        &self,
+        strikes: &db::Strikes,
+        moderation: &moderation::Moderation,
+        user: &RealUser<'_>,
+        reason: &str,
+    ) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        self.escalate(strikes, user.channel(), user.login(), reason, |action| {
+            punish(moderation, user, reason, action)
+        })
+        .await
+    }
+
+    /// Pick the action for the next strike against the given user, carry it
+    /// out with `punish` and record the strike if that succeeded.
+    async fn escalate<F, O>(
+        &self,
+        strikes: &db::Strikes,
+        channel: &Channel,
+        login: &str,
+        reason: &str,
+        punish: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(Action) -> O,
+        O: Future<Output = Result<bool>>,
+    {
+        let lock = self
+            .locks
+            .lock()
+            .entry(login.to_string())
+            .or_default()
+            .clone();
+
+        let result = async {
+            let _guard = lock.lock().await;
+
+            let decay = self.decay.load().await;
+            let previous = strikes.list_by_user(channel, login, &decay).await?.len();
+            let action = self.action(previous).await;
+
+            // NB: only count strikes which were actually acted on, since the
+            // failure has already been reported.
+            if punish(action).await? {
+                strikes
+                    .insert(channel, login, reason, &action.to_string())
+                    .await?;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        let mut locks = self.locks.lock();
+
+        // NB: the only other reference is the one in the map, so nobody else
+        // is waiting on the lock.
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(login);
+        }
+
+        result
+    }
+}
+
+/// Punish the user with the given action.
+///
+/// Returns `true` if the action was taken.
+async fn punish(
+    moderation: &moderation::Moderation,
+    user: &RealUser<'_>,
+    reason: &str,
+    action: Action,
+) -> Result<bool> {
+    match action {
+        Action::Warn => {
+            user.respond(format!(
+                "Your message was removed ({}), further offences will lead to a timeout.",
+                reason
+            ))
+            .await;
+
+            Ok(true)
+        }
+        Action::Timeout(duration) => {
+            let user_id = match user.id() {
+                Some(user_id) => user_id.to_string(),
+                None => match moderation.user_id(user.login()).await? {
+                    Some(user_id) => user_id,
+                    None => {
+                        tracing::warn!("No user id for {} to time out", user.login());
+                        return Ok(false);
+                    }
+                },
+            };
+
+            Ok(moderation.timeout(&user_id, duration, Some(reason)).await)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use common::{Channel, Duration};
+
+    use super::{Action, Ladder};
+
+    fn ladder() -> Ladder {
+        Ladder {
+            enabled: settings::Var::new(true),
+            decay: settings::Var::new(Duration::hours(1)),
+            timeout: settings::Var::new(Duration::seconds(60)),
+            long_timeout: settings::Var::new(Duration::seconds(600)),
+            locks: parking_lot::Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_escalation() {
+        let ladder = ladder();
+
+        assert_eq!(ladder.action(0).await, Action::Warn);
+        assert_eq!(
+            ladder.action(1).await,
+            Action::Timeout(Duration::seconds(60))
+        );
+        assert_eq!(
+            ladder.action(2).await,
+            Action::Timeout(Duration::seconds(600))
+        );
+        assert_eq!(
+            ladder.action(10).await,
+            Action::Timeout(Duration::seconds(600))
+        );
+
+        // changes to the settings are picked up.
+        *ladder.long_timeout.write().await = Duration::seconds(3600);
+        assert_eq!(
+            ladder.action(2).await,
+            Action::Timeout(Duration::seconds(3600))
+        );
+    }
+
+    #[test]
+    fn test_action_display() {
+        assert_eq!(Action::Warn.to_string(), "warn");
+        assert_eq!(
+            Action::Timeout(Duration::seconds(90)).to_string(),
+            "timeout 1m30s"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_strikes() {
+        let ladder = ladder();
+        let db = db::Database::open(Path::new(":memory:")).unwrap();
+        let strikes = db::Strikes::load(db).await.unwrap();
+        let channel = Channel::new("#channel");
+        let actions = parking_lot::Mutex::new(Vec::new());
+        let actions_ref = &actions;
+
+        let strike = |login| {
+            ladder.escalate(&strikes, channel, login, "spam", move |action| async move {
+                // Punishing goes through the API, so give the other strikes a
+                // chance to run in the meantime.
+                tokio::task::yield_now().await;
+                actions_ref.lock().push((login, action));
+                Ok(true)
+            })
+        };
+
+        tokio::try_join!(
+            strike("alice"),
+            strike("alice"),
+            strike("bob"),
+            strike("alice")
+        )
+        .unwrap();
+
+        let actions = actions.into_inner();
+        let alice = actions
+            .iter()
+            .filter(|(login, _)| *login == "alice")
+            .map(|(_, action)| *action)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            alice,
+            [
+                Action::Warn,
+                Action::Timeout(Duration::seconds(60)),
+                Action::Timeout(Duration::seconds(600)),
+            ]
+        );
+        assert!(actions.contains(&("bob", Action::Warn)));
+        assert_eq!(strikes.list_recent(channel, 10).await.unwrap().len(), 4);
+        assert!(ladder.locks.lock().is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use async_injector::{Injector, Ref};
+use chrono::{Offset, TimeZone, Utc};
+use chrono_tz::{Etc, Tz};
+use common::models::Song;
+use common::Channel;
+use template::{HelperData, StreamData, Template};
+
+use crate::stream_info;
+
+/// Resolves the data used by template helpers ahead of rendering.
+///
+/// Only data which is used by the template is resolved.
+#[derive(Clone)]
+pub struct TemplateHelpers {
+    stream_info: stream_info::StreamInfo,
+    timezone: settings::Var<Tz>,
+    song: Ref<Song>,
+    currency: Ref<currency::Currency>,
+    counters: Ref<db::Counters>,
+}
+
+impl TemplateHelpers {
+    pub(crate) async fn new(
+        injector: &Injector,
+        settings: &settings::Settings<::auth::Scope>,
+        stream_info: &stream_info::StreamInfo,
+    ) -> Result<Self> {
+        Ok(Self {
+            stream_info: stream_info.clone(),
+            timezone: settings.var("time/timezone", Etc::UTC).await?,
+            song: injector.var().await,
+            currency: injector.var().await,
+            counters: injector.var().await,
+        })
+    }
+
+    /// Resolve helper data for the given template.
+    ///
+    /// `user` is the user the template is being rendered for, if any, and
+    /// `args` are the arguments passed to it.
+    pub async fn resolve(
+        &self,
+        template: &Template,
+        channel: &Channel,
+        user: Option<&str>,
+        args: Vec<String>,
+    ) -> Result<HelperData> {
+        let vars = template.vars();
+
+        let mut data = HelperData {
+            args,
+            ..HelperData::default()
+        };
+
+        if vars.contains("date") || vars.contains("uptime") {
+            let tz = self.timezone.load().await;
+            let now = Utc::now();
+            let offset = tz.offset_from_utc_datetime(&now.naive_utc()).fix();
+            data.now = Some(now.with_timezone(&offset));
+        }
+
+        {
+            let stream_info = self.stream_info.data.read();
+
+            data.stream = StreamData {
+                title: stream_info.title.clone(),
+                game: stream_info.game.clone(),
+                started_at: stream_info.stream.as_ref().map(|s| s.started_at),
+            };
+        }
+
+        if vars.contains("song") {
+            data.song = self.song.load().await.map(|song| song.item().what());
+        }
+
+        if vars.contains("balance") || vars.contains("watch_time") {
+            if let (Some(user), Some(currency)) = (user, self.currency.load().await) {
+                let balance = currency
+                    .balance_of(channel, user)
+                    .await?
+                    .unwrap_or_default();
+                data.balance = Some(balance.balance);
+                data.watch_time = Some(balance.watch_time().num_seconds());
+            }
+        }
+
+        if vars.contains("counter") {
+            if let Some(counters) = self.counters.load().await {
+                data.counters = counters
+                    .list(channel)
+                    .await?
+                    .into_iter()
+                    .map(|c| (c.name, c.count))
+                    .collect();
+            }
+        }
+
+        Ok(data)
+    }
+}
@@ -45,6 +45,10 @@ impl Item {
                 },
                 None => String::from("*Some YouTube Video*"),
             },
+            Track::Local { track } => match track.artist.as_ref() {
+                Some(artist) => format!("\"{}\" by {}", track.title(), artist),
+                None => format!("\"{}\"", track.title()),
+            },
         }
     }
 
@@ -53,6 +57,7 @@ impl Item {
         match &self.track {
             Track::Spotify { track } => track.is_playable.unwrap_or(true),
             Track::YouTube { video: _ } => true,
+            Track::Local { track: _ } => true,
         }
     }
 
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A track in the local music library.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LocalTrack {
+    /// Path of the track, relative to the root of the library and using `/`
+    /// as a separator.
+    pub path: String,
+    /// Title as read from tags.
+    pub title: Option<String>,
+    /// Artist as read from tags.
+    pub artist: Option<String>,
+    /// Album as read from tags.
+    pub album: Option<String>,
+    /// Duration of the track.
+    pub duration: std::time::Duration,
+}
+
+impl LocalTrack {
+    /// Get the title of the track, falling back to its file name.
+    pub fn title(&self) -> &str {
+        if let Some(title) = self.title.as_deref() {
+            return title;
+        }
+
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+
+        match name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => name,
+        }
+    }
+}
@@ -2,5 +2,6 @@
 pub enum PlayerKind {
     Spotify,
     YouTube,
+    Local,
     None,
 }
@@ -96,6 +96,7 @@ impl Song {
         match self.item.track_id() {
             TrackId::Spotify(..) => PlayerKind::Spotify,
             TrackId::YouTube(..) => PlayerKind::YouTube,
+            TrackId::Local(..) => PlayerKind::Local,
         }
     }
 
@@ -14,6 +14,10 @@ pub enum Track {
     YouTube {
         video: Box<crate::models::youtube::Video>,
     },
+    #[serde(rename = "local")]
+    Local {
+        track: Box<crate::models::local::LocalTrack>,
+    },
 }
 
 impl Track {
@@ -22,6 +26,7 @@ impl Track {
         match self {
             Self::Spotify { track } => display::human_artists(&track.artists),
             Self::YouTube { video } => video.snippet.as_ref().and_then(|s| s.channel_title.clone()),
+            Self::Local { track } => track.artist.clone(),
         }
     }
 
@@ -35,6 +40,7 @@ impl Track {
                 .map(|s| s.title.as_str())
                 .unwrap_or("no name")
                 .to_string(),
+            Self::Local { track } => track.title().to_string(),
         }
     }
 }
@@ -19,6 +19,9 @@ pub enum TrackId {
     Spotify(SpotifyId),
     /// A YouTube track.
     YouTube(String),
+    /// A track in the local music library, identified by its path relative to
+    /// the root of the library.
+    Local(String),
 }
 
 #[derive(Debug, Error)]
@@ -37,7 +40,9 @@ pub enum FromStrError {
     /// Failed to parse an ID.
     #[error("bad spotify track id (expected base62): {}", _0)]
     BadBase62(String),
-    #[error("missing uri prefix, expected youtube:video:<id>, or spotify:track:<id>")]
+    #[error(
+        "missing uri prefix, expected youtube:video:<id>, spotify:track:<id>, or local:track:<path>"
+    )]
     MissingUriPrefix,
 }
 
@@ -51,6 +56,10 @@ impl std::str::FromStr for TrackId {
             return Ok(video_id);
         }
 
+        if let Some(path) = s.strip_prefix("local:track:") {
+            return Ok(TrackId::Local(path.to_string()));
+        }
+
         if s.starts_with("spotify:track:") {
             let mut id = s.trim_start_matches("spotify:track:");
             //Trim parameters
@@ -71,6 +80,7 @@ impl fmt::Display for TrackId {
         match self {
             TrackId::Spotify(id) => write!(fmt, "spotify:track:{}", id.to_base62()),
             TrackId::YouTube(id) => write!(fmt, "youtube:video:{}", id),
+            TrackId::Local(path) => write!(fmt, "local:track:{}", path),
         }
     }
 }
@@ -81,6 +91,9 @@ impl TrackId {
         match self {
             TrackId::Spotify(id) => format!("{}/{}", SPOTIFY_URL, id.to_base62()),
             TrackId::YouTube(id) => format!("{}/{}", YOUTUBE_URL, id),
+            // NB: local tracks are not publicly accessible, so the best we can
+            // do is to show the URI.
+            TrackId::Local(..) => self.to_string(),
         }
     }
 
@@ -95,77 +108,6 @@ impl TrackId {
             other => other,
         }
     }
-
-    /// Parse by trying  URL forms first.
-    pub fn parse_with_urls(s: &str) -> Result<Self, FromStrError> {
-        // Parse a track id from a URL or URI.
-        if let Ok(url) = str::parse::<url::Url>(s) {
-            match url.host() {
-                Some(url::Host::Domain("open.spotify.com")) => {
-                    let parts = url.path().split('/').collect::<Vec<_>>();
-
-                    let id = match parts.as_slice() {
-                        ["", "track", id] => SpotifyId::from_base62(id)
-                            .map_err(|_| FromStrError::BadBase62((*id).to_string()))?,
-                        _ => return Err(FromStrError::BadUrl(url.to_string())),
-                    };
-
-                    return Ok(TrackId::Spotify(id));
-                }
-                Some(host) if is_long_youtube(&host) => {
-                    let parts = url.path().split('/').collect::<Vec<_>>();
-
-                    if parts.as_slice() != ["", "watch"] {
-                        return Err(FromStrError::BadUrl(url.to_string()));
-                    }
-
-                    let mut video_id = None;
-
-                    for (n, value) in url.query_pairs() {
-                        if n == "v" {
-                            video_id = Some(value.to_string());
-                        }
-                    }
-
-                    let video_id = match video_id {
-                        Some(video_id) => video_id,
-                        None => return Err(FromStrError::BadUrl(url.to_string())),
-                    };
-
-                    return Ok(TrackId::YouTube(video_id));
-                }
-                Some(host) if is_short_youtube(&host) => {
-                    let parts = url.path().split('/').collect::<Vec<_>>();
-
-                    let video_id = match parts.as_slice() {
-                        ["", video_id] => *video_id,
-                        _ => return Err(FromStrError::BadUrl(url.to_string())),
-                    };
-
-                    return Ok(TrackId::YouTube(video_id.to_string()));
-                }
-                Some(..) => {
-                    return Err(FromStrError::BadHost(url.to_string()));
-                }
-                None => (),
-            }
-        }
-
-        return str::parse(s);
-
-        #[inline]
-        fn is_long_youtube(host: &url::Host<&str>) -> bool {
-            matches!(
-                host,
-                url::Host::Domain("youtube.com") | url::Host::Domain("www.youtube.com")
-            )
-        }
-
-        #[inline]
-        fn is_short_youtube(host: &url::Host<&str>) -> bool {
-            matches!(host, url::Host::Domain("youtu.be"))
-        }
-    }
 }
 
 impl<DB> ToSql<Text, DB> for TrackId
@@ -208,3 +150,37 @@ impl<'de> serde::Deserialize<'de> for TrackId {
         TrackId::parse_with_prefix_fallback(&s).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FromStrError, TrackId};
+
+    #[test]
+    fn test_parse_local() {
+        let track_id = str::parse::<TrackId>("local:track:rock/one.mp3").expect("track id");
+        assert_eq!(track_id, TrackId::Local(String::from("rock/one.mp3")));
+        assert_eq!(track_id.to_string(), "local:track:rock/one.mp3");
+        assert_eq!(track_id.url(), "local:track:rock/one.mp3");
+
+        // NB: the path is kept verbatim, including spaces and colons.
+        assert_eq!(
+            str::parse::<TrackId>("local:track:a b/c:d.flac").expect("track id"),
+            TrackId::Local(String::from("a b/c:d.flac"))
+        );
+
+        assert!(matches!(
+            str::parse::<TrackId>("local:rock/one.mp3"),
+            Err(FromStrError::MissingUriPrefix)
+        ));
+    }
+
+    #[test]
+    fn test_parse_local_with_prefix_fallback() {
+        // local tracks stored in the database must not be mistaken for bare
+        // spotify ids.
+        assert_eq!(
+            TrackId::parse_with_prefix_fallback("local:track:one.mp3").expect("track id"),
+            TrackId::Local(String::from("one.mp3"))
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use std::fmt;
 
 /// Format the given part and whole as a percentage.
-pub fn percentage(part: u32, total: u32) -> impl fmt::Display {
+pub fn percentage(part: u64, total: u64) -> impl fmt::Display {
     Percentage(part, total)
 }
 
 #[derive(Clone, Copy)]
-struct Percentage(u32, u32);
+struct Percentage(u64, u64);
 
 impl fmt::Display for Percentage {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -23,6 +23,8 @@ pub enum Uri {
     SpotifyTrack(SpotifyId),
     /// A Spotify playlist.
     SpotifyPlaylist(SpotifyId),
+    /// A Spotify album.
+    SpotifyAlbum(SpotifyId),
     /// A YouTube video.
     YouTubeVideo(String),
 }
@@ -60,6 +62,11 @@ impl std::str::FromStr for Uri {
                         .map_err(|_| FromStrError::BadBase62(id.to_string()))?;
                     return Ok(Uri::SpotifyPlaylist(id));
                 }
+                (Some("album"), Some(id)) => {
+                    let id = SpotifyId::from_base62(id)
+                        .map_err(|_| FromStrError::BadBase62(id.to_string()))?;
+                    return Ok(Uri::SpotifyAlbum(id));
+                }
                 _ => (),
             },
             _ => (),
@@ -74,6 +81,7 @@ impl fmt::Display for Uri {
         match self {
             Uri::SpotifyTrack(id) => write!(fmt, "spotify:track:{}", id.to_base62()),
             Uri::SpotifyPlaylist(id) => write!(fmt, "spotify:playlist:{}", id.to_base62()),
+            Uri::SpotifyAlbum(id) => write!(fmt, "spotify:album:{}", id.to_base62()),
             Uri::YouTubeVideo(id) => write!(fmt, "youtube:video:{}", id),
         }
     }
@@ -1,11 +1,13 @@
 //! Module for the built-in currency which uses the regular databse support.
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use common::Channel;
 use db::{models, schema, user_id, Database};
 use diesel::prelude::*;
 
-use crate::{BalanceOf, BalanceTransferError};
+use crate::{BalanceOf, BalanceOrder, BalanceTransferError};
 
 pub(crate) struct Backend {
     db: Database,
@@ -70,6 +72,95 @@ impl Backend {
             .await
     }
 
+    /// Get a page of balances, ordered from highest to lowest.
+    pub(crate) async fn balances_top(
+        &self,
+        channel: &Channel,
+        order: BalanceOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<models::Balance>> {
+        use self::schema::balances::dsl;
+
+        let channel = channel.to_owned();
+        let offset = i64::try_from(offset)?;
+        let limit = i64::try_from(limit)?;
+
+        self.db
+            .asyncify(move |c| {
+                let query = dsl::balances
+                    .filter(dsl::channel.eq(channel))
+                    .offset(offset)
+                    .limit(limit);
+
+                let balances = match order {
+                    BalanceOrder::Balance => query
+                        .order((dsl::amount.desc(), dsl::user.asc()))
+                        .load::<models::Balance>(c)?,
+                    BalanceOrder::WatchTime => query
+                        .order((dsl::watch_time.desc(), dsl::user.asc()))
+                        .load::<models::Balance>(c)?,
+                };
+
+                Ok(balances)
+            })
+            .await
+    }
+
+    /// Find the 1-based rank of the user by balance.
+    pub(crate) async fn balance_rank(&self, channel: &Channel, user: &str) -> Result<Option<u64>> {
+        use self::schema::balances::dsl;
+
+        let channel = channel.to_owned();
+        let user = user_id(user);
+
+        self.db
+            .asyncify(move |c| {
+                let amount = dsl::balances
+                    .select(dsl::amount)
+                    .filter(dsl::channel.eq(&channel).and(dsl::user.eq(user)))
+                    .first::<i64>(c)
+                    .optional()?;
+
+                let Some(amount) = amount else {
+                    return Ok(None);
+                };
+
+                let above = dsl::balances
+                    .filter(dsl::channel.eq(&channel).and(dsl::amount.gt(amount)))
+                    .count()
+                    .get_result::<i64>(c)?;
+
+                Ok(Some(u64::try_from(above)? + 1))
+            })
+            .await
+    }
+
+    /// Find the balances of the given users in one query.
+    ///
+    /// Users without a balance are left out.
+    pub(crate) async fn balances_of(
+        &self,
+        channel: &Channel,
+        users: Vec<String>,
+    ) -> Result<HashMap<String, i64>> {
+        use self::schema::balances::dsl;
+
+        let channel = channel.to_owned();
+        let users = users.iter().map(|u| user_id(u)).collect::<Vec<_>>();
+
+        self.db
+            .asyncify(move |c| {
+                let balances = dsl::balances
+                    .select((dsl::user, dsl::amount))
+                    .filter(dsl::channel.eq(channel).and(dsl::user.eq_any(users)))
+                    .load::<(String, i64)>(c)?;
+
+                Ok(balances.into_iter().collect())
+            })
+            .await
+    }
+
     /// Import balances for all users.
     pub(crate) async fn import_balances(&self, balances: Vec<models::Balance>) -> Result<()> {
         use self::schema::balances::dsl;
@@ -151,6 +242,37 @@ impl Backend {
             .await
     }
 
+    /// Subtract from the balance of a single user, but only if they can
+    /// afford it.
+    pub(crate) async fn balance_debit(
+        &self,
+        channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<bool> {
+        use self::schema::balances::dsl;
+
+        let channel = channel.to_owned();
+        let user = user_id(user);
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::balances.filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::user.eq(&user))
+                            .and(dsl::amount.ge(amount)),
+                    ),
+                )
+                .set(dsl::amount.eq(dsl::amount - amount))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
     /// Add balance to users.
     pub(crate) async fn balances_increment<I>(
         &self,
@@ -242,3 +364,149 @@ fn modify_balance(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::Channel;
+    use db::Database;
+
+    use super::Backend;
+    use crate::BalanceOrder;
+
+    fn backend() -> Backend {
+        Backend::new(Database::open(Path::new(":memory:")).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_balances_of() {
+        let backend = backend();
+        let channel = Channel::new("#channel");
+
+        backend.balance_add(channel, "alice", 10).await.unwrap();
+        backend.balance_add(channel, "bob", 20).await.unwrap();
+        backend
+            .balance_add(Channel::new("#other"), "carol", 30)
+            .await
+            .unwrap();
+
+        let users = vec![
+            String::from("Alice"),
+            String::from("bob"),
+            String::from("carol"),
+        ];
+
+        let balances = backend.balances_of(channel, users).await.unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances.get("alice"), Some(&10));
+        assert_eq!(balances.get("bob"), Some(&20));
+
+        let balances = backend.balances_of(channel, Vec::new()).await.unwrap();
+        assert!(balances.is_empty());
+    }
+
+    fn users(balances: &[db::models::Balance]) -> Vec<&str> {
+        balances.iter().map(|b| b.user.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_balances_top() {
+        let backend = backend();
+        let channel = Channel::new("#channel");
+
+        backend.balance_add(channel, "bob", 20).await.unwrap();
+        backend.balance_add(channel, "alice", 10).await.unwrap();
+        backend.balance_add(channel, "carol", 20).await.unwrap();
+        backend.balance_add(channel, "dave", 5).await.unwrap();
+        backend
+            .balance_add(Channel::new("#other"), "erin", 100)
+            .await
+            .unwrap();
+
+        let top = backend
+            .balances_top(channel, BalanceOrder::Balance, 0, 10)
+            .await
+            .unwrap();
+        // Ties are broken by user name.
+        assert_eq!(users(&top), ["bob", "carol", "alice", "dave"]);
+
+        let page = backend
+            .balances_top(channel, BalanceOrder::Balance, 1, 2)
+            .await
+            .unwrap();
+        assert_eq!(users(&page), ["carol", "alice"]);
+
+        backend
+            .balances_increment(channel, vec![String::from("dave")], 0, 60)
+            .await
+            .unwrap();
+        backend
+            .balances_increment(channel, vec![String::from("alice")], 0, 30)
+            .await
+            .unwrap();
+
+        let top = backend
+            .balances_top(channel, BalanceOrder::WatchTime, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(users(&top), ["dave", "alice", "bob", "carol"]);
+    }
+
+    #[tokio::test]
+    async fn test_balance_rank() {
+        let backend = backend();
+        let channel = Channel::new("#channel");
+
+        backend.balance_add(channel, "alice", 10).await.unwrap();
+        backend.balance_add(channel, "bob", 20).await.unwrap();
+        backend.balance_add(channel, "carol", 20).await.unwrap();
+        backend.balance_add(channel, "dave", 5).await.unwrap();
+        backend
+            .balance_add(Channel::new("#other"), "erin", 100)
+            .await
+            .unwrap();
+
+        // Tied users share a rank, and the next one skips past them.
+        assert_eq!(backend.balance_rank(channel, "bob").await.unwrap(), Some(1));
+        assert_eq!(
+            backend.balance_rank(channel, "Carol").await.unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            backend.balance_rank(channel, "alice").await.unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            backend.balance_rank(channel, "dave").await.unwrap(),
+            Some(4)
+        );
+
+        assert_eq!(backend.balance_rank(channel, "frank").await.unwrap(), None);
+        assert_eq!(backend.balance_rank(channel, "erin").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_balance_debit() {
+        let backend = backend();
+        let channel = Channel::new("#channel");
+
+        backend.balance_add(channel, "alice", 10).await.unwrap();
+
+        assert!(backend.balance_debit(channel, "alice", 4).await.unwrap());
+        assert!(backend.balance_debit(channel, "Alice", 6).await.unwrap());
+        assert!(!backend.balance_debit(channel, "alice", 1).await.unwrap());
+
+        let balance = backend.balance_of(channel, "alice").await.unwrap().unwrap();
+        assert_eq!(balance.balance, 0);
+    }
+
+    #[tokio::test]
+    async fn test_balance_debit_missing_user() {
+        let backend = backend();
+        let channel = Channel::new("#channel");
+
+        assert!(!backend.balance_debit(channel, "bob", 1).await.unwrap());
+        assert!(backend.balance_of(channel, "bob").await.unwrap().is_none());
+    }
+}
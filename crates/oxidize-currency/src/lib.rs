@@ -1,6 +1,6 @@
 //! Stream currency configuration.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::pin::pin;
 use std::sync::Arc;
 
@@ -34,6 +34,20 @@ impl BalanceOf {
     }
 }
 
+/// What to rank users by when listing the top balances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BalanceOrder {
+    #[default]
+    Balance,
+    WatchTime,
+}
+
+/// Number of users listed by the top balances if no number is specified.
+pub const TOP_DEFAULT: usize = 5;
+/// Maximum number of users listed by the top balances.
+pub const TOP_MAX: usize = 10;
+
 /// Helper struct to construct a currency.
 pub struct CurrencyBuilder {
     streamer: api::TwitchAndUser,
@@ -204,6 +218,32 @@ impl Backend {
         }
     }
 
+    /// Get a page of balances, ordered from highest to lowest.
+    async fn balances_top(
+        &self,
+        channel: &Channel,
+        order: BalanceOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Balance>> {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balances_top(channel, order, offset, limit).await,
+            MySql(backend) => backend.balances_top(channel, order, offset, limit).await,
+        }
+    }
+
+    /// Find the 1-based rank of the user by balance.
+    async fn balance_rank(&self, channel: &Channel, user: &str) -> Result<Option<u64>> {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balance_rank(channel, user).await,
+            MySql(backend) => backend.balance_rank(channel, user).await,
+        }
+    }
+
     /// Find user balance.
     async fn balance_of(&self, channel: &Channel, user: &str) -> Result<Option<BalanceOf>> {
         use self::Backend::*;
@@ -214,6 +254,20 @@ impl Backend {
         }
     }
 
+    /// Find the balances of the given users.
+    async fn balances_of(
+        &self,
+        channel: &Channel,
+        users: Vec<String>,
+    ) -> Result<HashMap<String, i64>> {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balances_of(channel, users).await,
+            MySql(backend) => backend.balances_of(channel, users).await,
+        }
+    }
+
     /// Add (or subtract) from the balance for a single user.
     async fn balance_add(&self, channel: &Channel, user: &str, amount: i64) -> Result<()> {
         use self::Backend::*;
@@ -224,6 +278,16 @@ impl Backend {
         }
     }
 
+    /// Subtract from the balance of a single user if they can afford it.
+    async fn balance_debit(&self, channel: &Channel, user: &str, amount: i64) -> Result<bool> {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balance_debit(channel, user, amount).await,
+            MySql(backend) => backend.balance_debit(channel, user, amount).await,
+        }
+    }
+
     /// Add balance to users.
     #[tracing::instrument(skip(self, users))]
     pub async fn balances_increment<I>(
@@ -321,16 +385,64 @@ impl Currency {
         self.inner.backend.import_balances(balances).await
     }
 
+    /// Test if the backend keeps track of watch time.
+    pub fn tracks_watch_time(&self) -> bool {
+        matches!(self.inner.backend, Backend::BuiltIn(..))
+    }
+
+    /// Get a page of balances, ordered from highest to lowest.
+    pub async fn balances_top(
+        &self,
+        channel: &Channel,
+        order: BalanceOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Balance>> {
+        self.inner
+            .backend
+            .balances_top(channel, order, offset, limit)
+            .await
+    }
+
+    /// Find the 1-based rank of the user by balance, or `None` if the user
+    /// has no balance.
+    pub async fn balance_rank(&self, channel: &Channel, user: &str) -> Result<Option<u64>> {
+        self.inner.backend.balance_rank(channel, user).await
+    }
+
     /// Find user balance.
     pub async fn balance_of(&self, channel: &Channel, user: &str) -> Result<Option<BalanceOf>> {
         self.inner.backend.balance_of(channel, user).await
     }
 
+    /// Find the balances of many users at once, by their normalized login.
+    ///
+    /// Users without a balance are left out.
+    pub async fn balances_of(
+        &self,
+        channel: &Channel,
+        users: Vec<String>,
+    ) -> Result<HashMap<String, i64>> {
+        self.inner.backend.balances_of(channel, users).await
+    }
+
     /// Add (or subtract) from the balance for a single user.
     pub async fn balance_add(&self, channel: &Channel, user: &str, amount: i64) -> Result<()> {
         self.inner.backend.balance_add(channel, user, amount).await
     }
 
+    /// Subtract `amount` from the balance of a single user in one step, but
+    /// only if they have at least that much.
+    ///
+    /// Returns `false` if the user couldn't afford it, in which case the
+    /// balance is left untouched.
+    pub async fn balance_debit(&self, channel: &Channel, user: &str, amount: i64) -> Result<bool> {
+        self.inner
+            .backend
+            .balance_debit(channel, user, amount)
+            .await
+    }
+
     /// Add balance to users.
     pub async fn balances_increment<I>(
         &self,
@@ -7,6 +7,7 @@
 //! 1) Name the table to use.
 //! 2) Name the fields holding channel, user, and amount.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
@@ -17,7 +18,7 @@ use mysql::prelude::*;
 use mysql_async as mysql;
 use serde::{Deserialize, Serialize};
 
-use crate::{BalanceOf, BalanceTransferError};
+use crate::{BalanceOf, BalanceOrder, BalanceTransferError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Schema {
@@ -62,6 +63,64 @@ impl Queries {
         Ok(results)
     }
 
+    /// Select a page of balances, ordered from highest to lowest.
+    #[tracing::instrument(skip(self, tx))]
+    async fn select_top_balances<Tx>(
+        &self,
+        tx: &mut Tx,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<(String, i32)>>
+    where
+        Tx: Queryable,
+    {
+        tracing::trace!("Select top balances");
+
+        let query = format!(
+            "SELECT `{user_column}`, `{balance_column}` \
+             FROM `{table}` \
+             ORDER BY `{balance_column}` DESC, `{user_column}` ASC \
+             LIMIT :limit OFFSET :offset",
+            table = self.schema.table,
+            balance_column = self.schema.balance_column,
+            user_column = self.schema.user_column,
+        );
+
+        let params = params! {
+            "limit" => limit,
+            "offset" => offset,
+        };
+
+        let results = tx
+            .exec_map(query.as_str(), params, mysql::from_row::<(String, i32)>)
+            .await?;
+        Ok(results)
+    }
+
+    /// Count the number of balances which are larger than the given one.
+    #[tracing::instrument(skip(self, tx))]
+    async fn count_balances_above<Tx>(&self, tx: &mut Tx, balance: i32) -> Result<u64>
+    where
+        Tx: Queryable,
+    {
+        tracing::trace!("Count balances above");
+
+        let query = format!(
+            "SELECT COUNT(*) FROM `{table}` WHERE `{balance_column}` > :balance",
+            table = self.schema.table,
+            balance_column = self.schema.balance_column,
+        );
+
+        let params = params! {
+            "balance" => balance,
+        };
+
+        Ok(tx
+            .exec_first(query.as_str(), params)
+            .await?
+            .unwrap_or_default())
+    }
+
     /// Select the given balance.
     #[tracing::instrument(skip(self, tx))]
     async fn select_balance<Tx>(&self, tx: &mut Tx, user: &str) -> Result<Option<i32>>
@@ -128,6 +187,35 @@ impl Queries {
         Ok(())
     }
 
+    /// Subtract from the given balance, but only if it is large enough.
+    ///
+    /// Returns `false` if the balance was not modified.
+    #[tracing::instrument(skip(self, tx))]
+    async fn debit_balance(
+        &self,
+        tx: &mut mysql::Transaction<'_>,
+        user: &str,
+        amount: i32,
+    ) -> Result<bool> {
+        tracing::trace!("Debit balance");
+
+        let query = format!(
+            "UPDATE `{table}` SET `{balance_column}` = `{balance_column}` - :amount \
+             WHERE `{user_column}` = :user AND `{balance_column}` >= :amount",
+            table = self.schema.table,
+            balance_column = self.schema.balance_column,
+            user_column = self.schema.user_column,
+        );
+
+        let params = params! {
+            "amount" => amount,
+            "user" => user,
+        };
+
+        tx.exec_drop(query.as_str(), params).await?;
+        Ok(tx.affected_rows() == 1)
+    }
+
     /// Insert the given balance.
     #[tracing::instrument(skip(self, tx))]
     async fn insert_balance<Tx>(&self, tx: &mut Tx, user: &str, balance: i32) -> Result<()>
@@ -256,6 +344,53 @@ impl Backend {
         Ok(output)
     }
 
+    /// Get a page of balances, ordered from highest to lowest.
+    pub(crate) async fn balances_top(
+        &self,
+        _channel: &Channel,
+        order: BalanceOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Balance>> {
+        if order == BalanceOrder::WatchTime {
+            anyhow::bail!("watch time is not tracked by the mysql backend");
+        }
+
+        let channel = self.channel.to_owned();
+
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        let balances = self
+            .queries
+            .select_top_balances(&mut tx, offset.try_into()?, limit.try_into()?)
+            .await?;
+
+        Ok(balances
+            .into_iter()
+            .map(|(user, balance)| Balance {
+                channel: (*channel).to_owned(),
+                user,
+                amount: balance as i64,
+                watch_time: 0,
+            })
+            .collect())
+    }
+
+    /// Find the 1-based rank of the user by balance.
+    pub(crate) async fn balance_rank(&self, _channel: &Channel, user: &str) -> Result<Option<u64>> {
+        let user = user_id(user);
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        let Some(balance) = self.queries.select_balance(&mut tx, &user).await? else {
+            return Ok(None);
+        };
+
+        let above = self.queries.count_balances_above(&mut tx, balance).await?;
+        Ok(Some(above + 1))
+    }
+
     /// Import balances for all users.
     pub(crate) async fn import_balances(&self, balances: Vec<Balance>) -> Result<()> {
         let opts = mysql::TxOpts::new();
@@ -299,6 +434,32 @@ impl Backend {
         }))
     }
 
+    /// Find the balances of the given users.
+    ///
+    /// Users without a balance are left out.
+    pub(crate) async fn balances_of(
+        &self,
+        _channel: &Channel,
+        users: Vec<String>,
+    ) -> Result<HashMap<String, i64>> {
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        let mut balances = HashMap::new();
+
+        // NB: the schema is configurable and only supports looking up one
+        // user at a time, so the best we can do is a single transaction.
+        for user in users {
+            let user = user_id(&user);
+
+            if let Some(balance) = self.queries.select_balance(&mut tx, &user).await? {
+                balances.insert(user, i64::from(balance));
+            }
+        }
+
+        Ok(balances)
+    }
+
     /// Add (or subtract) from the balance for a single user.
     pub(crate) async fn balance_add(
         &self,
@@ -316,6 +477,24 @@ impl Backend {
         Ok(())
     }
 
+    /// Subtract from the balance of a single user, but only if they can
+    /// afford it.
+    pub(crate) async fn balance_debit(
+        &self,
+        _channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<bool> {
+        let user = user_id(user);
+        let amount = amount.try_into()?;
+
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+        let debited = self.queries.debit_balance(&mut tx, &user, amount).await?;
+        tx.commit().await?;
+        Ok(debited)
+    }
+
     /// Add balance to users.
     pub(crate) async fn balances_increment<I>(
         &self,
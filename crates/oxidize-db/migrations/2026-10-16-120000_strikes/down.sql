@@ -0,0 +1 @@
+DROP TABLE strikes;
\ No newline at end of file
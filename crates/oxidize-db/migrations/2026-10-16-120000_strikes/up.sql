@@ -0,0 +1,10 @@
+CREATE TABLE strikes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    reason VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_strikes_channel_user_created_at ON strikes(channel, user, created_at);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE bad_words ADD COLUMN kind VARCHAR NOT NULL DEFAULT 'word';
\ No newline at end of file
@@ -0,0 +1,19 @@
+CREATE TABLE polls (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    round INTEGER NOT NULL,
+    parent_id INTEGER,
+    created_at TIMESTAMP NOT NULL,
+    closed_at TIMESTAMP
+);
+
+CREATE INDEX idx_polls_channel_created_at ON polls(channel, created_at);
+
+CREATE TABLE poll_options (
+    poll_id INTEGER NOT NULL,
+    key VARCHAR NOT NULL,
+    description VARCHAR,
+    votes BIGINT NOT NULL,
+    PRIMARY KEY (poll_id, key)
+);
\ No newline at end of file
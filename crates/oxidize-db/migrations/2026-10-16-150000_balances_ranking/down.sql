@@ -0,0 +1,2 @@
+DROP INDEX idx_balances_channel_watch_time;
+DROP INDEX idx_balances_channel_amount;
\ No newline at end of file
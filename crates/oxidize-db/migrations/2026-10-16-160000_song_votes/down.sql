@@ -0,0 +1,2 @@
+DROP TABLE song_skips;
+DROP TABLE song_likes;
\ No newline at end of file
@@ -0,0 +1,17 @@
+CREATE TABLE song_likes (
+    track_id VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    liked BOOLEAN NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (track_id, user)
+);
+
+CREATE TABLE song_skips (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    track_id VARCHAR NOT NULL,
+    user VARCHAR,
+    votes INTEGER NOT NULL,
+    skipped_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_song_skips_user_skipped_at ON song_skips(user, skipped_at);
\ No newline at end of file
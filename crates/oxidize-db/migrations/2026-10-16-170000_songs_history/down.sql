@@ -0,0 +1,2 @@
+DROP INDEX idx_songs_played_added_at;
+DROP INDEX idx_songs_user_added_at;
\ No newline at end of file
@@ -0,0 +1,2 @@
+CREATE INDEX idx_songs_user_added_at ON songs(user, added_at);
+CREATE INDEX idx_songs_played_added_at ON songs(played, added_at);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE song_bans;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE song_bans (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    kind VARCHAR NOT NULL,
+    pattern VARCHAR NOT NULL,
+    reason VARCHAR,
+    banned_by VARCHAR,
+    banned_at TIMESTAMP NOT NULL,
+    UNIQUE (kind, pattern)
+);
\ No newline at end of file
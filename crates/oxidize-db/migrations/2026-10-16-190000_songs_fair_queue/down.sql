@@ -0,0 +1,2 @@
+ALTER TABLE songs DROP COLUMN turn;
+ALTER TABLE songs DROP COLUMN weight;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE songs ADD COLUMN weight INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE songs ADD COLUMN turn BIGINT NOT NULL DEFAULT 0;
\ No newline at end of file
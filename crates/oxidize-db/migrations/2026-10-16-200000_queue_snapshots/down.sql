@@ -0,0 +1 @@
+DROP TABLE queue_snapshots;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE queue_snapshots (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    position INTEGER NOT NULL,
+    track_id VARCHAR NOT NULL,
+    user VARCHAR,
+    saved_at TIMESTAMP NOT NULL,
+    UNIQUE (name, position)
+);
\ No newline at end of file
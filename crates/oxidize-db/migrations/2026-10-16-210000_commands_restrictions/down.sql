@@ -0,0 +1,4 @@
+ALTER TABLE commands DROP COLUMN cost;
+ALTER TABLE commands DROP COLUMN user_cooldown;
+ALTER TABLE commands DROP COLUMN cooldown;
+ALTER TABLE commands DROP COLUMN scope;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE commands ADD COLUMN scope VARCHAR;
+ALTER TABLE commands ADD COLUMN cooldown BIGINT;
+ALTER TABLE commands ADD COLUMN user_cooldown BIGINT;
+ALTER TABLE commands ADD COLUMN cost BIGINT;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE counters (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    scope VARCHAR,
+    PRIMARY KEY (channel, name)
+);
\ No newline at end of file
@@ -0,0 +1,2 @@
+DROP TABLE custom_role_members;
+DROP TABLE custom_roles;
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE custom_roles (
+    role VARCHAR NOT NULL PRIMARY KEY,
+    doc VARCHAR,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE custom_role_members (
+    role VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    PRIMARY KEY (role, user)
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE temporary_grants;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE temporary_grants (
+    scope VARCHAR NOT NULL,
+    principal VARCHAR NOT NULL,
+    kind VARCHAR NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (scope, principal)
+);
\ No newline at end of file
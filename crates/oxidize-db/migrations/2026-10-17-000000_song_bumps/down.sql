@@ -0,0 +1,2 @@
+DROP TABLE song_bumps;
+ALTER TABLE songs DROP COLUMN queued_at;
\ No newline at end of file
@@ -0,0 +1,10 @@
+ALTER TABLE songs ADD COLUMN queued_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE songs SET queued_at = added_at;
+
+CREATE TABLE song_bumps (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    track_id VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    paid_at TIMESTAMP NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE bets;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE bets (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    placed_at TIMESTAMP NOT NULL
+);
\ No newline at end of file
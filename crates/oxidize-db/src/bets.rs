@@ -0,0 +1,118 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::Bet;
+
+/// Wagers which have been debited for a betting pool that is still running,
+/// kept around so that they can be refunded if the pool is lost.
+#[derive(Clone)]
+pub struct Bets {
+    db: crate::Database,
+}
+
+impl Bets {
+    /// Open the bets database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all outstanding bets, oldest first.
+    pub async fn list(&self) -> Result<Vec<Bet>> {
+        use self::schema::bets::dsl;
+
+        self.db
+            .asyncify(move |c| Ok(dsl::bets.order(dsl::id.asc()).load::<models::Bet>(c)?))
+            .await
+    }
+
+    /// Record that the given user wagered the given amount.
+    pub async fn insert(&self, channel: &Channel, user: &str, amount: i64) -> Result<()> {
+        use self::schema::bets::dsl;
+
+        let bet = models::InsertBet {
+            channel: channel.to_owned(),
+            user: crate::user_id(user),
+            amount,
+            placed_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::bets).values(&bet).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove all bets in the given channel, once they have been paid out or
+    /// refunded.
+    pub async fn clear(&self, channel: &Channel) -> Result<usize> {
+        use self::schema::bets::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(diesel::delete(dsl::bets.filter(dsl::channel.eq(&channel))).execute(c)?)
+            })
+            .await
+    }
+
+    /// Remove the bets with the given ids.
+    pub async fn delete(&self, ids: Vec<i32>) -> Result<usize> {
+        use self::schema::bets::dsl;
+
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.db
+            .asyncify(
+                move |c| Ok(diesel::delete(dsl::bets.filter(dsl::id.eq_any(ids))).execute(c)?),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::Channel;
+
+    use super::Bets;
+    use crate::Database;
+
+    #[tokio::test]
+    async fn test_clear() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let bets = Bets::load(db).await.unwrap();
+
+        let a = Channel::new("#a");
+        let b = Channel::new("#b");
+
+        bets.insert(a, "Alice", 10).await.unwrap();
+        bets.insert(a, "bob", 20).await.unwrap();
+        bets.insert(b, "alice", 5).await.unwrap();
+
+        let all = bets.list().await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].user, "alice");
+        assert_eq!(all[0].amount, 10);
+
+        assert_eq!(bets.clear(a).await.unwrap(), 2);
+
+        let left = bets.list().await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(&*left[0].channel, b);
+
+        let ids = left.iter().map(|b| b.id).collect();
+        assert_eq!(bets.delete(ids).await.unwrap(), 1);
+        assert!(bets.list().await.unwrap().is_empty());
+    }
+}
@@ -5,9 +5,9 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Error, Result};
 use common::words;
-use common::Channel;
+use common::{Channel, Duration};
 use diesel::prelude::*;
-use serde::{ser, Serialize};
+use serde::{ser, Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 /// Local database wrapper.
@@ -39,6 +39,10 @@ impl Database {
                             text: text.to_string(),
                             group: None,
                             disabled: false,
+                            scope: None,
+                            cooldown: None,
+                            user_cooldown: None,
+                            cost: None,
                         };
 
                         diesel::insert_into(dsl::commands)
@@ -80,6 +84,32 @@ impl Database {
             .await
     }
 
+    /// Edit the restrictions of a command.
+    async fn edit_restrictions(&self, key: &crate::Key, restrictions: &Restrictions) -> Result<()> {
+        use crate::schema::commands::dsl;
+
+        let key = key.clone();
+        let restrictions = restrictions.clone();
+
+        self.0
+            .asyncify(move |c| {
+                diesel::update(
+                    dsl::commands
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set((
+                    dsl::scope.eq(restrictions.scope.as_deref()),
+                    dsl::cooldown.eq(restrictions.cooldown.map(seconds)),
+                    dsl::user_cooldown.eq(restrictions.user_cooldown.map(seconds)),
+                    dsl::cost.eq(restrictions.cost),
+                ))
+                .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
     /// Increment the given key.
     async fn increment(&self, key: &crate::Key) -> Result<bool, Error> {
         use crate::schema::commands::dsl;
@@ -142,6 +172,7 @@ impl Commands {
             inner.remove(&key);
         } else {
             let vars = template.vars();
+            let restrictions = Restrictions::from_db(&command);
 
             let command = Arc::new(Command {
                 key: key.clone(),
@@ -151,6 +182,7 @@ impl Commands {
                 vars,
                 group: command.group,
                 disabled: command.disabled,
+                restrictions,
             });
 
             inner.insert(key, command);
@@ -174,6 +206,34 @@ impl Commands {
         }))
     }
 
+    /// Edit the restrictions for the given command.
+    ///
+    /// Returns `false` if there is no such command.
+    pub async fn edit_restrictions(
+        &self,
+        channel: &Channel,
+        name: &str,
+        edit: impl FnOnce(&mut Restrictions),
+    ) -> Result<bool> {
+        let key = crate::Key::new(channel, name);
+
+        let mut inner = self.inner.write().await;
+
+        let Some(command) = self.db.fetch(&key).await? else {
+            return Ok(false);
+        };
+
+        let mut restrictions = Restrictions::from_db(&command);
+        edit(&mut restrictions);
+        self.db.edit_restrictions(&key, &restrictions).await?;
+
+        inner.modify(key, |command| {
+            command.restrictions = restrictions;
+        });
+
+        Ok(true)
+    }
+
     /// Increment the specified command.
     pub async fn increment(&self, command: &Command) -> Result<(), Error> {
         self.db.increment(&command.key).await?;
@@ -209,6 +269,80 @@ pub struct Command {
     vars: HashSet<String>,
     pub group: Option<String>,
     pub disabled: bool,
+    #[serde(flatten)]
+    pub restrictions: Restrictions,
+}
+
+/// Restrictions on who can use a command and how often.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Restrictions {
+    /// The scope required to use the command, or a role like `@subscriber`.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// The cooldown between uses of the command by anyone.
+    #[serde(default)]
+    pub cooldown: Option<Duration>,
+    /// The cooldown between uses of the command by the same user.
+    #[serde(default)]
+    pub user_cooldown: Option<Duration>,
+    /// The amount of currency it costs to use the command.
+    #[serde(default)]
+    pub cost: Option<i64>,
+}
+
+impl Restrictions {
+    /// Load restrictions from a database command.
+    fn from_db(command: &crate::models::Command) -> Self {
+        let duration = |seconds: i64| Duration::seconds(u64::try_from(seconds).unwrap_or_default());
+
+        Self {
+            scope: command.scope.clone(),
+            cooldown: command.cooldown.map(duration),
+            user_cooldown: command.user_cooldown.map(duration),
+            cost: command.cost,
+        }
+    }
+
+    /// Test if there are no restrictions.
+    pub fn is_empty(&self) -> bool {
+        self.scope.is_none()
+            && self.cooldown.is_none()
+            && self.user_cooldown.is_none()
+            && self.cost.is_none()
+    }
+}
+
+impl fmt::Display for Restrictions {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "scope = {scope}, cooldown = {cooldown}, user cooldown = {user_cooldown}, cost = {cost}",
+            scope = self.scope.as_deref().unwrap_or("*none*"),
+            cooldown = Optional(self.cooldown.as_ref()),
+            user_cooldown = Optional(self.user_cooldown.as_ref()),
+            cost = Optional(self.cost.as_ref()),
+        )
+    }
+}
+
+/// Helper to display an optional value.
+struct Optional<'a, T>(Option<&'a T>);
+
+impl<T> fmt::Display for Optional<'_, T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(value) => value.fmt(fmt),
+            None => "*none*".fmt(fmt),
+        }
+    }
+}
+
+/// Convert a duration into seconds as stored in the database.
+fn seconds(duration: Duration) -> i64 {
+    i64::try_from(duration.num_seconds()).unwrap_or(i64::MAX)
 }
 
 /// Serialize the atomic count.
@@ -245,6 +379,7 @@ impl Command {
             vars,
             group: command.group.clone(),
             disabled: command.disabled,
+            restrictions: Restrictions::from_db(command),
         })
     }
 
@@ -286,6 +421,76 @@ impl fmt::Display for Command {
             pattern = self.pattern,
             group = self.group.as_deref().unwrap_or("*none*"),
             disabled = self.disabled,
-        )
+        )?;
+
+        if !self.restrictions.is_empty() {
+            write!(fmt, ", {}", self.restrictions)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Channel, Duration};
+
+    use super::Restrictions;
+    use crate::models;
+
+    fn command() -> models::Command {
+        models::Command {
+            channel: Channel::new("#channel").to_owned(),
+            name: String::from("hello"),
+            pattern: None,
+            count: 0,
+            text: String::from("Hello!"),
+            group: None,
+            disabled: false,
+            scope: None,
+            cooldown: None,
+            user_cooldown: None,
+            cost: None,
+        }
+    }
+
+    #[test]
+    fn test_restrictions_from_db() {
+        let restrictions = Restrictions::from_db(&command());
+        assert!(restrictions.is_empty());
+        assert_eq!(
+            restrictions.to_string(),
+            "scope = *none*, cooldown = *none*, user cooldown = *none*, cost = *none*"
+        );
+
+        let restrictions = Restrictions::from_db(&models::Command {
+            scope: Some(String::from("@subscriber")),
+            cooldown: Some(90),
+            user_cooldown: Some(-1),
+            cost: Some(10),
+            ..command()
+        });
+
+        assert!(!restrictions.is_empty());
+        assert_eq!(restrictions.scope.as_deref(), Some("@subscriber"));
+        assert_eq!(restrictions.cooldown, Some(Duration::seconds(90)));
+        // negative durations in the database are clamped to zero.
+        assert_eq!(restrictions.user_cooldown, Some(Duration::seconds(0)));
+        assert_eq!(restrictions.cost, Some(10));
+        assert_eq!(
+            restrictions.to_string(),
+            "scope = @subscriber, cooldown = 1m30s, user cooldown = 0s, cost = 10"
+        );
+    }
+
+    #[test]
+    fn test_restrictions_deserialize() {
+        let restrictions =
+            serde_yaml::from_str::<Restrictions>("{scope: song, cooldown: 5m, cost: 25}").unwrap();
+
+        assert_eq!(restrictions.scope.as_deref(), Some("song"));
+        assert_eq!(restrictions.cooldown, Some(Duration::seconds(300)));
+        assert_eq!(restrictions.user_cooldown, None);
+        assert_eq!(restrictions.cost, Some(25));
     }
 }
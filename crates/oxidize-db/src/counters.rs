@@ -0,0 +1,351 @@
+use std::fmt;
+use std::str;
+
+use anyhow::{bail, Result};
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::Counter;
+
+/// An operation which modifies a counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterOp {
+    /// Add to the counter, or subtract from it if negative.
+    Add(i64),
+    /// Set the counter to the given value.
+    Set(i64),
+}
+
+impl CounterOp {
+    /// Apply the operation to the given count.
+    fn apply(self, count: i64) -> i64 {
+        match self {
+            CounterOp::Add(n) => count.saturating_add(n),
+            CounterOp::Set(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for CounterOp {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CounterOp::Add(n) if n < 0 => write!(fmt, "{}", n),
+            CounterOp::Add(n) => write!(fmt, "+{}", n),
+            CounterOp::Set(n) => write!(fmt, "={}", n),
+        }
+    }
+}
+
+impl str::FromStr for CounterOp {
+    type Err = anyhow::Error;
+
+    /// Parse an operation like `+`, `-`, `+5`, `-5`, `=10` or `reset`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "+" => CounterOp::Add(1),
+            "-" => CounterOp::Add(-1),
+            "reset" => CounterOp::Set(0),
+            s => {
+                if let Some(n) = s.strip_prefix('=') {
+                    CounterOp::Set(parse_count(n)?)
+                } else if let Some(n) = s.strip_prefix('+') {
+                    CounterOp::Add(parse_count(n)?)
+                } else if let Some(n) = s.strip_prefix('-') {
+                    CounterOp::Add(parse_count(n)?.saturating_neg())
+                } else {
+                    bail!("expected one of `+`, `-`, `+<n>`, `-<n>`, `=<n>`, or `reset`")
+                }
+            }
+        })
+    }
+}
+
+/// Parse a count in an operation.
+fn parse_count(s: &str) -> Result<i64> {
+    match str::parse::<i64>(s) {
+        Ok(n) => Ok(n),
+        Err(e) => bail!("bad number `{}`: {}", s, e),
+    }
+}
+
+/// Normalize the name of a counter.
+fn normalize(name: &str) -> Result<String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        bail!("counter name must not be empty");
+    }
+
+    Ok(name.to_lowercase())
+}
+
+/// Named counters, like the number of deaths or wins on stream.
+#[derive(Clone)]
+pub struct Counters {
+    db: crate::Database,
+}
+
+impl Counters {
+    /// Open the counters database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all counters in the given channel.
+    pub async fn list(&self, channel: &Channel) -> Result<Vec<Counter>> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counters
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::name.asc())
+                    .load::<models::Counter>(c)?)
+            })
+            .await
+    }
+
+    /// Get the counter with the given name.
+    pub async fn get(&self, channel: &Channel, name: &str) -> Result<Option<Counter>> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_owned();
+        let name = normalize(name)?;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counters
+                    .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name)))
+                    .first::<models::Counter>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Modify the counter with the given name, creating it if it doesn't
+    /// exist.
+    ///
+    /// Returns the modified counter.
+    pub async fn modify(&self, channel: &Channel, name: &str, op: CounterOp) -> Result<Counter> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_owned();
+        let name = normalize(name)?;
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|c| {
+                    let counter = dsl::counters
+                        .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name)))
+                        .first::<models::Counter>(c)
+                        .optional()?;
+
+                    let counter = match counter {
+                        Some(mut counter) => {
+                            counter.count = op.apply(counter.count);
+
+                            diesel::update(
+                                dsl::counters
+                                    .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name))),
+                            )
+                            .set(dsl::count.eq(counter.count))
+                            .execute(c)?;
+
+                            counter
+                        }
+                        None => {
+                            let counter = models::Counter {
+                                channel,
+                                name,
+                                count: op.apply(0),
+                                scope: None,
+                            };
+
+                            diesel::insert_into(dsl::counters)
+                                .values(&counter)
+                                .execute(c)?;
+
+                            counter
+                        }
+                    };
+
+                    Ok(counter)
+                })
+            })
+            .await
+    }
+
+    /// Set the scope or role which also allows users to modify the counter with
+    /// the given name, on top of those with `counter/edit`.
+    ///
+    /// Returns `false` if there is no such counter.
+    pub async fn edit_scope(
+        &self,
+        channel: &Channel,
+        name: &str,
+        scope: Option<String>,
+    ) -> Result<bool> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_owned();
+        let name = normalize(name)?;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::counters.filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name))),
+                )
+                .set(dsl::scope.eq(scope))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Delete the counter with the given name.
+    ///
+    /// Returns `false` if there is no such counter.
+    pub async fn delete(&self, channel: &Channel, name: &str) -> Result<bool> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_owned();
+        let name = normalize(name)?;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::counters.filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name))),
+                )
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::Channel;
+
+    use super::{CounterOp, Counters};
+    use crate::Database;
+
+    async fn counters() -> Counters {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        Counters::load(db).await.unwrap()
+    }
+
+    #[test]
+    fn test_parse_op() {
+        assert_eq!("+".parse::<CounterOp>().unwrap(), CounterOp::Add(1));
+        assert_eq!("-".parse::<CounterOp>().unwrap(), CounterOp::Add(-1));
+        assert_eq!("+5".parse::<CounterOp>().unwrap(), CounterOp::Add(5));
+        assert_eq!("-5".parse::<CounterOp>().unwrap(), CounterOp::Add(-5));
+        assert_eq!("=10".parse::<CounterOp>().unwrap(), CounterOp::Set(10));
+        assert_eq!("=-1".parse::<CounterOp>().unwrap(), CounterOp::Set(-1));
+        assert_eq!("reset".parse::<CounterOp>().unwrap(), CounterOp::Set(0));
+        assert!("5".parse::<CounterOp>().is_err());
+        assert!("=".parse::<CounterOp>().is_err());
+        assert!("+x".parse::<CounterOp>().is_err());
+    }
+
+    #[test]
+    fn test_apply_op() {
+        assert_eq!(CounterOp::Add(2).apply(3), 5);
+        assert_eq!(CounterOp::Add(-5).apply(3), -2);
+        assert_eq!(CounterOp::Set(7).apply(3), 7);
+        assert_eq!(CounterOp::Add(1).apply(i64::MAX), i64::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let counters = counters().await;
+        let channel = Channel::new("#channel");
+        let other = Channel::new("#other");
+
+        assert!(counters.get(channel, "deaths").await.unwrap().is_none());
+
+        // modifying a missing counter creates it.
+        let counter = counters
+            .modify(channel, "Deaths", CounterOp::Add(1))
+            .await
+            .unwrap();
+        assert_eq!(counter.name, "deaths");
+        assert_eq!(counter.count, 1);
+
+        let counter = counters
+            .modify(channel, " deaths ", CounterOp::Add(-3))
+            .await
+            .unwrap();
+        assert_eq!(counter.count, -2);
+
+        counters
+            .modify(channel, "wins", CounterOp::Set(10))
+            .await
+            .unwrap();
+        counters
+            .modify(other, "deaths", CounterOp::Set(100))
+            .await
+            .unwrap();
+
+        let list = counters.list(channel).await.unwrap();
+        let list = list
+            .iter()
+            .map(|c| (c.name.as_str(), c.count))
+            .collect::<Vec<_>>();
+        assert_eq!(list, [("deaths", -2), ("wins", 10)]);
+
+        assert!(counters.delete(channel, "DEATHS").await.unwrap());
+        assert!(!counters.delete(channel, "deaths").await.unwrap());
+        assert!(counters.get(channel, "deaths").await.unwrap().is_none());
+
+        let counter = counters.get(other, "deaths").await.unwrap().unwrap();
+        assert_eq!(counter.count, 100);
+
+        assert!(counters.get(channel, " ").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_edit_scope() {
+        let counters = counters().await;
+        let channel = Channel::new("#channel");
+
+        assert!(!counters
+            .edit_scope(channel, "deaths", Some(String::from("@subscriber")))
+            .await
+            .unwrap());
+
+        counters
+            .modify(channel, "deaths", CounterOp::Add(1))
+            .await
+            .unwrap();
+
+        assert!(counters
+            .edit_scope(channel, "Deaths", Some(String::from("@subscriber")))
+            .await
+            .unwrap());
+
+        let counter = counters.get(channel, "deaths").await.unwrap().unwrap();
+        assert_eq!(counter.scope.as_deref(), Some("@subscriber"));
+
+        // the scope is kept when the counter is modified.
+        let counter = counters
+            .modify(channel, "deaths", CounterOp::Add(1))
+            .await
+            .unwrap();
+        assert_eq!(counter.scope.as_deref(), Some("@subscriber"));
+
+        assert!(counters.edit_scope(channel, "deaths", None).await.unwrap());
+        let counter = counters.get(channel, "deaths").await.unwrap().unwrap();
+        assert_eq!(counter.scope, None);
+    }
+}
@@ -10,14 +10,23 @@ pub use self::after_streams::AfterStreams;
 mod aliases;
 pub use self::aliases::Aliases;
 
+mod bets;
+pub use self::bets::{Bet, Bets};
+
 pub mod commands;
 pub use self::commands::Commands;
 
+mod counters;
+pub use self::counters::{Counter, CounterOp, Counters};
+
 mod matcher;
 pub use self::matcher::{Captures, Key, Matchable, Matcher, Pattern};
 
 pub mod models;
 
+mod polls;
+pub use self::polls::{PollResult, Polls};
+
 mod promotions;
 pub use self::promotions::{Promotion, Promotions};
 
@@ -26,19 +35,34 @@ mod script_storage;
 #[cfg(feature = "scripting")]
 pub use self::script_storage::ScriptStorage;
 
+mod queue_snapshots;
+pub use self::queue_snapshots::{QueueSnapshot, QueueSnapshotSong, QueueSnapshots};
+
+mod song_bans;
+pub use self::song_bans::{SongBan, SongBanKind, SongBans};
+
+mod song_bumps;
+pub use self::song_bumps::{SongBump, SongBumps};
+
+mod song_votes;
+pub use self::song_votes::{SongLikes, SongVotes};
+
+mod strikes;
+pub use self::strikes::Strikes;
+
 mod task;
 
 mod themes;
 pub use self::themes::Themes;
 
 mod words;
-pub use self::words::{Word, Words};
+pub use self::words::{normalize, Word, WordKind, Words, WordsFormat};
 
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use common::models::TrackId;
 use diesel::prelude::*;
 use diesel_migrations::{EmbeddedMigrations, HarnessWithOutput, MigrationHarness};
@@ -109,7 +133,7 @@ impl Database {
         self.asyncify(move |c| {
             let songs = dsl::songs
                 .filter(dsl::deleted.eq(false).and(dsl::played.eq(false)))
-                .order((dsl::promoted_at.desc(), dsl::added_at.asc()))
+                .order((dsl::promoted_at.desc(), dsl::queued_at.asc()))
                 .load::<models::Song>(c)?;
             Ok(songs)
         })
@@ -173,6 +197,34 @@ impl Database {
         .await
     }
 
+    /// Restore the most recently deleted song with the given ID.
+    pub async fn player_restore_song(&self, track_id: &TrackId) -> Result<bool> {
+        use self::schema::songs::dsl;
+
+        let track_id = track_id.clone();
+
+        self.asyncify(move |c| {
+            let ids: Vec<i32> = dsl::songs
+                .select(dsl::id)
+                .filter(
+                    dsl::played
+                        .eq(false)
+                        .and(dsl::deleted.eq(true))
+                        .and(dsl::track_id.eq(&track_id)),
+                )
+                .order(dsl::added_at.desc())
+                .limit(1)
+                .load(c)?;
+
+            let count = diesel::update(dsl::songs.filter(dsl::id.eq_any(ids)))
+                .set(dsl::deleted.eq(false))
+                .execute(c)?;
+
+            Ok(count == 1)
+        })
+        .await
+    }
+
     /// Promote the track with the given ID.
     pub async fn player_promote_song(
         &self,
@@ -209,6 +261,94 @@ impl Database {
         .await
     }
 
+    /// Move the track with the given ID in front of the track `before` by
+    /// taking over its turn, and by queueing it just before it.
+    ///
+    /// Queues are restored in the order songs were queued in, so this causes
+    /// the new position to persist. The time the song was added at is left
+    /// untouched so that history and statistics stay correct.
+    pub async fn player_bump_song(
+        &self,
+        track_id: &TrackId,
+        before: &TrackId,
+        turn: i64,
+    ) -> Result<bool> {
+        use self::schema::songs::dsl;
+
+        let track_id = track_id.clone();
+        let before = before.clone();
+
+        self.asyncify(move |c| {
+            let queued_at: Option<NaiveDateTime> = dsl::songs
+                .select(dsl::queued_at)
+                .filter(
+                    dsl::played
+                        .eq(false)
+                        .and(dsl::deleted.eq(false))
+                        .and(dsl::track_id.eq(&before)),
+                )
+                .order(dsl::added_at.desc())
+                .first(c)
+                .optional()?;
+
+            let queued_at = match queued_at {
+                Some(queued_at) => queued_at - chrono::Duration::milliseconds(1),
+                None => return Ok(false),
+            };
+
+            let ids: Vec<i32> = dsl::songs
+                .select(dsl::id)
+                .filter(
+                    dsl::played
+                        .eq(false)
+                        .and(dsl::deleted.eq(false))
+                        .and(dsl::track_id.eq(&track_id)),
+                )
+                .order(dsl::added_at.desc())
+                .limit(1)
+                .load(c)?;
+
+            let count = diesel::update(dsl::songs.filter(dsl::id.eq_any(ids)))
+                .set((dsl::queued_at.eq(queued_at), dsl::turn.eq(turn)))
+                .execute(c)?;
+
+            Ok(count == 1)
+        })
+        .await
+    }
+
+    /// Move the queued track with the given ID to the back of the queue with
+    /// the given turn, undoing any bumps.
+    pub async fn player_requeue_song(&self, track_id: &TrackId, turn: i64) -> Result<bool> {
+        use self::schema::songs::dsl;
+
+        let track_id = track_id.clone();
+
+        self.asyncify(move |c| {
+            let ids: Vec<i32> = dsl::songs
+                .select(dsl::id)
+                .filter(
+                    dsl::played
+                        .eq(false)
+                        .and(dsl::deleted.eq(false))
+                        .and(dsl::track_id.eq(&track_id)),
+                )
+                .order(dsl::added_at.desc())
+                .limit(1)
+                .load(c)?;
+
+            let count = diesel::update(dsl::songs.filter(dsl::id.eq_any(ids)))
+                .set((
+                    dsl::queued_at.eq(Utc::now().naive_utc()),
+                    dsl::turn.eq(turn),
+                ))
+                .execute(c)?;
+
+            Ok(count == 1)
+        })
+        .await
+    }
+
     /// Test if the song has been played within a given duration.
     pub async fn player_last_song_within(
         &self,
@@ -241,6 +381,142 @@ impl Database {
         })
         .await
     }
+
+    /// List the most recent song requests which haven't been deleted,
+    /// optionally limited to the given user, newest first.
+    pub async fn player_history(
+        &self,
+        user: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<models::Song>> {
+        use self::schema::songs::dsl;
+
+        let user = user.map(user_id);
+
+        self.asyncify(move |c| {
+            let mut query = dsl::songs.filter(dsl::deleted.eq(false)).into_boxed();
+
+            if let Some(user) = user {
+                query = query.filter(dsl::user.eq(user));
+            }
+
+            let songs = query
+                .order(dsl::added_at.desc())
+                .limit(limit)
+                .load::<models::Song>(c)?;
+
+            Ok(songs)
+        })
+        .await
+    }
+
+    /// List the most requested tracks, optionally only counting requests
+    /// made within the given duration.
+    pub async fn player_top_tracks(
+        &self,
+        within: Option<&common::Duration>,
+        limit: i64,
+    ) -> Result<Vec<models::TrackCount>> {
+        use self::schema::songs::dsl;
+        use diesel::dsl::count_star;
+
+        // NB: without a duration every request since the epoch is counted.
+        let since = within.map_or(NaiveDateTime::UNIX_EPOCH, |within| {
+            (Utc::now() - within.as_chrono()).naive_utc()
+        });
+
+        self.asyncify(move |c| {
+            let tracks = dsl::songs
+                .filter(
+                    dsl::deleted
+                        .eq(false)
+                        .and(dsl::user.is_not_null())
+                        .and(dsl::added_at.gt(since)),
+                )
+                .group_by(dsl::track_id)
+                .select((dsl::track_id, count_star()))
+                .order((count_star().desc(), dsl::track_id.asc()))
+                .limit(limit)
+                .load::<models::TrackCount>(c)?;
+
+            Ok(tracks)
+        })
+        .await
+    }
+
+    /// List the users who have requested the most songs, optionally only
+    /// counting requests made within the given duration.
+    pub async fn player_top_requesters(
+        &self,
+        within: Option<&common::Duration>,
+        limit: i64,
+    ) -> Result<Vec<models::RequesterCount>> {
+        use self::schema::songs::dsl;
+        use diesel::dsl::count_star;
+
+        // NB: without a duration every request since the epoch is counted.
+        let since = within.map_or(NaiveDateTime::UNIX_EPOCH, |within| {
+            (Utc::now() - within.as_chrono()).naive_utc()
+        });
+
+        self.asyncify(move |c| {
+            let requesters = dsl::songs
+                .filter(
+                    dsl::deleted
+                        .eq(false)
+                        .and(dsl::user.is_not_null())
+                        .and(dsl::added_at.gt(since)),
+                )
+                .group_by(dsl::user)
+                .select((dsl::user.assume_not_null(), count_star()))
+                .order((count_star().desc(), dsl::user.asc()))
+                .limit(limit)
+                .load::<models::RequesterCount>(c)?;
+
+            Ok(requesters)
+        })
+        .await
+    }
+
+    /// List the songs played during the most recent stream, in the order
+    /// they were requested.
+    ///
+    /// Streams are not recorded, so the most recent stream is taken to be the
+    /// latest run of played songs which were requested no more than `gap`
+    /// apart.
+    pub async fn player_last_stream(
+        &self,
+        gap: &common::Duration,
+        limit: i64,
+    ) -> Result<Vec<models::Song>> {
+        use self::schema::songs::dsl;
+
+        let gap = gap.as_chrono();
+
+        self.asyncify(move |c| {
+            let songs = dsl::songs
+                .filter(dsl::played.eq(true))
+                .order(dsl::added_at.desc())
+                .limit(limit)
+                .load::<models::Song>(c)?;
+
+            let mut out = Vec::new();
+
+            for song in songs {
+                if let Some(last) = out.last().map(|s: &models::Song| s.added_at) {
+                    if last - song.added_at > gap {
+                        break;
+                    }
+                }
+
+                out.push(song);
+            }
+
+            out.reverse();
+            Ok(out)
+        })
+        .await
+    }
 }
 
 /// Convert a user display name into a user id.
@@ -260,10 +536,177 @@ pub enum RenameError {
 
 #[cfg(test)]
 mod tests {
-    use super::user_id;
+    use std::path::Path;
+
+    use chrono::{Duration, NaiveDateTime, Utc};
+    use common::models::TrackId;
+
+    use super::{models, user_id, Database};
+
+    fn database() -> Database {
+        Database::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn track(name: &str) -> TrackId {
+        TrackId::Local(format!("{name}.mp3"))
+    }
+
+    async fn push(db: &Database, name: &str, user: Option<&str>, added_at: NaiveDateTime) {
+        db.player_push_back(&models::AddSong {
+            track_id: track(name),
+            added_at,
+            user: user.map(String::from),
+            weight: 1,
+            turn: 0,
+            queued_at: added_at,
+        })
+        .await
+        .unwrap();
+    }
 
     #[test]
     fn test_user_id() {
         assert_eq!("oxidizebot", user_id("@OxidizeBot"));
     }
+
+    #[tokio::test]
+    async fn test_player_history() {
+        let db = database();
+        let now = Utc::now().naive_utc();
+
+        push(&db, "a", Some("alice"), now - Duration::minutes(3)).await;
+        push(&db, "b", Some("bob"), now - Duration::minutes(2)).await;
+        push(&db, "c", Some("alice"), now - Duration::minutes(1)).await;
+        push(&db, "d", Some("bob"), now).await;
+        assert!(db.player_remove_song(&track("d"), false).await.unwrap());
+
+        let ids =
+            |songs: Vec<models::Song>| songs.into_iter().map(|s| s.track_id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(db.player_history(None, 10).await.unwrap()),
+            [track("c"), track("b"), track("a")]
+        );
+        assert_eq!(
+            ids(db.player_history(Some("@Alice"), 10).await.unwrap()),
+            [track("c"), track("a")]
+        );
+        assert_eq!(ids(db.player_history(None, 1).await.unwrap()), [track("c")]);
+    }
+
+    #[tokio::test]
+    async fn test_player_bump_song() {
+        let db = database();
+        let now = Utc::now().naive_utc();
+
+        push(&db, "a", Some("alice"), now - Duration::minutes(2)).await;
+        push(&db, "b", Some("bob"), now - Duration::minutes(1)).await;
+        push(&db, "c", Some("carol"), now).await;
+
+        assert!(db
+            .player_bump_song(&track("c"), &track("a"), 0)
+            .await
+            .unwrap());
+        assert!(!db
+            .player_bump_song(&track("c"), &track("x"), 0)
+            .await
+            .unwrap());
+
+        let songs = db.player_list().await.unwrap();
+
+        assert_eq!(
+            songs.iter().map(|s| s.track_id.clone()).collect::<Vec<_>>(),
+            [track("c"), track("a"), track("b")]
+        );
+
+        // NB: bumping only changes the queue order, not when it was added.
+        assert_eq!(songs[0].added_at, now);
+        assert!(songs[0].queued_at < songs[1].queued_at);
+    }
+
+    #[tokio::test]
+    async fn test_player_top() {
+        let db = database();
+        let now = Utc::now().naive_utc();
+
+        push(&db, "a", Some("alice"), now - Duration::days(30)).await;
+        push(&db, "a", Some("alice"), now - Duration::days(30)).await;
+        push(&db, "a", Some("alice"), now - Duration::days(30)).await;
+        push(&db, "b", Some("bob"), now - Duration::minutes(2)).await;
+        push(&db, "b", Some("carol"), now - Duration::minutes(1)).await;
+        push(&db, "c", Some("bob"), now).await;
+        // songs without a user are not requests.
+        push(&db, "c", None, now).await;
+        push(&db, "c", None, now).await;
+
+        let tracks = |tracks: Vec<models::TrackCount>| {
+            tracks
+                .into_iter()
+                .map(|t| (t.track_id, t.count))
+                .collect::<Vec<_>>()
+        };
+
+        let users = |users: Vec<models::RequesterCount>| {
+            users
+                .into_iter()
+                .map(|u| (u.user, u.count))
+                .collect::<Vec<_>>()
+        };
+
+        let week = common::Duration::hours(24 * 7);
+
+        assert_eq!(
+            tracks(db.player_top_tracks(None, 10).await.unwrap()),
+            [(track("a"), 3), (track("b"), 2), (track("c"), 1)]
+        );
+        assert_eq!(
+            tracks(db.player_top_tracks(Some(&week), 10).await.unwrap()),
+            [(track("b"), 2), (track("c"), 1)]
+        );
+        assert_eq!(
+            tracks(db.player_top_tracks(None, 1).await.unwrap()),
+            [(track("a"), 3)]
+        );
+
+        assert_eq!(
+            users(db.player_top_requesters(None, 10).await.unwrap()),
+            [
+                (String::from("alice"), 3),
+                (String::from("bob"), 2),
+                (String::from("carol"), 1)
+            ]
+        );
+        assert_eq!(
+            users(db.player_top_requesters(Some(&week), 10).await.unwrap()),
+            [(String::from("bob"), 2), (String::from("carol"), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_player_last_stream() {
+        let db = database();
+        let now = Utc::now().naive_utc();
+
+        // previous stream.
+        push(&db, "a", Some("alice"), now - Duration::days(2)).await;
+        // last stream.
+        push(&db, "b", Some("bob"), now - Duration::hours(2)).await;
+        push(&db, "c", Some("carol"), now - Duration::hours(1)).await;
+        // not played yet.
+        push(&db, "d", Some("dave"), now).await;
+
+        for name in ["a", "b", "c"] {
+            assert!(db.player_remove_song(&track(name), true).await.unwrap());
+        }
+
+        let gap = common::Duration::hours(6);
+        let songs = db.player_last_stream(&gap, 100).await.unwrap();
+
+        assert_eq!(
+            songs.into_iter().map(|s| s.track_id).collect::<Vec<_>>(),
+            [track("b"), track("c")]
+        );
+
+        assert!(db.player_last_stream(&gap, 0).await.unwrap().is_empty());
+    }
 }
@@ -5,7 +5,9 @@ use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 
 use crate::schema::{
-    after_streams, aliases, bad_words, balances, commands, promotions, script_keys, songs, themes,
+    after_streams, aliases, bad_words, balances, bets, commands, counters, poll_options, polls,
+    promotions, queue_snapshots, script_keys, song_bans, song_bumps, song_likes, song_skips, songs,
+    strikes, themes,
 };
 
 #[derive(Serialize, Deserialize, Queryable, Insertable)]
@@ -46,6 +48,14 @@ pub struct Command {
     pub group: Option<String>,
     /// If the command is disabled.
     pub disabled: bool,
+    /// The scope or role required to use the command, if any.
+    pub scope: Option<String>,
+    /// The cooldown in seconds between uses of the command by anyone.
+    pub cooldown: Option<i64>,
+    /// The cooldown in seconds between uses of the command by the same user.
+    pub user_cooldown: Option<i64>,
+    /// The amount of currency it costs to use the command.
+    pub cost: Option<i64>,
 }
 
 #[derive(Debug, Clone, Default, diesel::AsChangeset)]
@@ -113,20 +123,24 @@ pub struct InsertAfterStream {
     pub text: String,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Queryable, Insertable)]
 pub struct BadWord {
+    /// The word, phrase, glob or regular expression to match.
     pub word: String,
+    /// Message to send when the word is used.
     pub why: Option<String>,
+    /// How the word is matched.
+    pub kind: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Queryable)]
 pub struct Song {
     /// ID of the song request.
     pub id: i32,
-    /// If the request already played or not.
-    pub played: bool,
     /// If the request was deleted or not.
     pub deleted: bool,
+    /// If the request already played or not.
+    pub played: bool,
     /// The track id of the song.
     pub track_id: TrackId,
     /// When the song was added.
@@ -137,6 +151,26 @@ pub struct Song {
     pub promoted_by: Option<String>,
     /// The user that requested the song.
     pub user: Option<String>,
+    /// The weight of the request when fair queueing is enabled.
+    pub weight: i32,
+    /// The turn in which the song is played when fair queueing is enabled.
+    pub turn: i64,
+    /// Ordering key of the song in the queue, moved around by bumps.
+    pub queued_at: NaiveDateTime,
+}
+
+/// The number of times a track has been requested.
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct TrackCount {
+    pub track_id: TrackId,
+    pub count: i64,
+}
+
+/// The number of songs requested by a user.
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct RequesterCount {
+    pub user: String,
+    pub count: i64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Insertable)]
@@ -148,6 +182,12 @@ pub struct AddSong {
     pub added_at: NaiveDateTime,
     /// The user that requested the song.
     pub user: Option<String>,
+    /// The weight of the request when fair queueing is enabled.
+    pub weight: i32,
+    /// The turn in which the song is played when fair queueing is enabled.
+    pub turn: i64,
+    /// Ordering key of the song in the queue, moved around by bumps.
+    pub queued_at: NaiveDateTime,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
@@ -218,3 +258,217 @@ pub struct ScriptKey {
 pub struct SetScriptKeyValue<'a> {
     pub value: &'a [u8],
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct Strike {
+    /// The unique identifier of the strike.
+    pub id: i32,
+    /// The channel the strike was issued in.
+    pub channel: OwnedChannel,
+    /// The user the strike was issued to.
+    pub user: String,
+    /// Why the strike was issued.
+    pub reason: String,
+    /// The action taken as a result of the strike.
+    pub action: String,
+    /// When the strike was issued.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for strikes.
+#[derive(Insertable)]
+#[diesel(table_name = strikes)]
+pub struct InsertStrike {
+    pub channel: String,
+    pub user: String,
+    pub reason: String,
+    pub action: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct Poll {
+    /// The unique identifier of the poll.
+    pub id: i32,
+    /// The channel the poll was run in.
+    pub channel: OwnedChannel,
+    /// The question being asked.
+    pub question: String,
+    /// The round of the poll, starting at one.
+    pub round: i32,
+    /// The poll whose leading options this round was run off from.
+    pub parent_id: Option<i32>,
+    /// When the poll was started.
+    pub created_at: NaiveDateTime,
+    /// When the poll was closed.
+    pub closed_at: Option<NaiveDateTime>,
+}
+
+/// Insert model for polls.
+#[derive(Insertable)]
+#[diesel(table_name = polls)]
+pub struct InsertPoll {
+    pub channel: String,
+    pub question: String,
+    pub round: i32,
+    pub parent_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = poll_options)]
+pub struct PollOption {
+    /// The poll the option belongs to.
+    #[serde(skip)]
+    pub poll_id: i32,
+    /// The keyword used to vote for the option.
+    pub key: String,
+    /// Description of the option.
+    pub description: Option<String>,
+    /// The weighted number of votes cast for the option.
+    pub votes: i64,
+}
+
+/// Whether a user likes or dislikes a track.
+#[derive(Debug, Clone, Queryable, Insertable)]
+#[diesel(table_name = song_likes)]
+pub struct SongLike {
+    /// The track which was voted on.
+    pub track_id: TrackId,
+    /// The user who voted.
+    pub user: String,
+    /// If the user likes the track, otherwise they dislike it.
+    pub liked: bool,
+    /// When the user last voted.
+    pub updated_at: NaiveDateTime,
+}
+
+/// Insert model for songs which have been skipped by vote.
+#[derive(Insertable)]
+#[diesel(table_name = song_skips)]
+pub struct InsertSongSkip {
+    pub track_id: TrackId,
+    pub user: Option<String>,
+    pub votes: i32,
+    pub skipped_at: NaiveDateTime,
+}
+
+/// A ban on requesting songs matching a pattern.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct SongBan {
+    /// The unique identifier of the ban.
+    pub id: i32,
+    /// The kind of ban, which determines how the pattern is matched.
+    pub kind: String,
+    /// The pattern being banned.
+    pub pattern: String,
+    /// Why the pattern was banned.
+    pub reason: Option<String>,
+    /// The user who added the ban.
+    pub banned_by: Option<String>,
+    /// When the ban was added.
+    pub banned_at: NaiveDateTime,
+}
+
+/// Insert model for song bans.
+#[derive(Insertable)]
+#[diesel(table_name = song_bans)]
+pub struct InsertSongBan {
+    pub kind: String,
+    pub pattern: String,
+    pub reason: Option<String>,
+    pub banned_by: Option<String>,
+    pub banned_at: NaiveDateTime,
+}
+
+/// A single song in a saved queue snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct QueueSnapshotSong {
+    /// The unique identifier of the entry.
+    pub id: i32,
+    /// The name of the snapshot.
+    pub name: String,
+    /// The position of the song in the queue.
+    pub position: i32,
+    /// The track which was queued.
+    pub track_id: TrackId,
+    /// The user who requested the song.
+    pub user: Option<String>,
+    /// When the snapshot was saved.
+    pub saved_at: NaiveDateTime,
+}
+
+/// Insert model for queue snapshot songs.
+#[derive(Insertable)]
+#[diesel(table_name = queue_snapshots)]
+pub struct InsertQueueSnapshotSong {
+    pub name: String,
+    pub position: i32,
+    pub track_id: TrackId,
+    pub user: Option<String>,
+    pub saved_at: NaiveDateTime,
+}
+
+/// A named counter, like the number of deaths on stream.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[diesel(table_name = counters)]
+pub struct Counter {
+    /// The channel the counter belongs to.
+    pub channel: OwnedChannel,
+    /// The name of the counter.
+    pub name: String,
+    /// The current value of the counter.
+    pub count: i64,
+    /// The scope or role required to modify the counter.
+    pub scope: Option<String>,
+}
+
+/// A bump which has been paid for and is refundable.
+#[derive(Debug, Clone, Queryable)]
+pub struct SongBump {
+    /// The unique identifier of the bump.
+    pub id: i32,
+    /// The track that was bumped.
+    pub track_id: TrackId,
+    /// The user who paid for the bump.
+    pub user: String,
+    /// The amount paid.
+    pub amount: i64,
+    /// When the bump was paid for.
+    pub paid_at: NaiveDateTime,
+}
+
+/// Insert model for song bumps.
+#[derive(Insertable)]
+#[diesel(table_name = song_bumps)]
+pub struct InsertSongBump {
+    pub track_id: TrackId,
+    pub user: String,
+    pub amount: i64,
+    pub paid_at: NaiveDateTime,
+}
+
+/// A wager which has been debited for a betting pool which is still running.
+#[derive(Debug, Clone, Queryable)]
+pub struct Bet {
+    /// The unique identifier of the bet.
+    pub id: i32,
+    /// The channel the bet was placed in.
+    pub channel: OwnedChannel,
+    /// The user who placed the bet.
+    pub user: String,
+    /// The amount wagered.
+    pub amount: i64,
+    /// When the bet was placed.
+    pub placed_at: NaiveDateTime,
+}
+
+/// Insert model for bets.
+#[derive(Insertable)]
+#[diesel(table_name = bets)]
+pub struct InsertBet {
+    pub channel: OwnedChannel,
+    pub user: String,
+    pub amount: i64,
+    pub placed_at: NaiveDateTime,
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use common::Channel;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::{Poll, PollOption};
+
+/// A poll together with the options which were voted on.
+#[derive(Debug, Clone, Serialize)]
+pub struct PollResult {
+    #[serde(flatten)]
+    pub poll: Poll,
+    pub options: Vec<PollOption>,
+}
+
+/// History of polls which have been run in a channel.
+#[derive(Clone)]
+pub struct Polls {
+    db: crate::Database,
+}
+
+impl Polls {
+    /// Open the polls database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record a newly started poll with the given options, which are provided
+    /// as pairs of keywords and optional descriptions.
+    pub async fn insert(
+        &self,
+        channel: &Channel,
+        question: &str,
+        round: i32,
+        parent_id: Option<i32>,
+        options: &[(String, Option<String>)],
+    ) -> Result<Poll> {
+        use self::schema::poll_options::dsl as o;
+        use self::schema::polls::dsl;
+
+        let poll = models::InsertPoll {
+            channel: channel.to_string(),
+            question: question.to_string(),
+            round,
+            parent_id,
+            created_at: Utc::now().naive_utc(),
+        };
+
+        let options = options.to_vec();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|c| {
+                    diesel::insert_into(dsl::polls).values(&poll).execute(c)?;
+
+                    // NB: we have exclusive access to the connection, so the
+                    // most recent poll is the one we just inserted.
+                    let poll = dsl::polls.order(dsl::id.desc()).first::<models::Poll>(c)?;
+
+                    let options = options
+                        .into_iter()
+                        .map(|(key, description)| models::PollOption {
+                            poll_id: poll.id,
+                            key,
+                            description,
+                            votes: 0,
+                        })
+                        .collect::<Vec<_>>();
+
+                    diesel::insert_into(o::poll_options)
+                        .values(&options)
+                        .execute(c)?;
+
+                    Ok(poll)
+                })
+            })
+            .await
+    }
+
+    /// Close the given poll, storing the number of votes cast for each
+    /// option.
+    pub async fn close(&self, id: i32, votes: &[(String, u64)]) -> Result<()> {
+        use self::schema::poll_options::dsl as o;
+        use self::schema::polls::dsl;
+
+        let votes = votes.to_vec();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|c| {
+                    diesel::update(dsl::polls.filter(dsl::id.eq(id)))
+                        .set(dsl::closed_at.eq(Utc::now().naive_utc()))
+                        .execute(c)?;
+
+                    for (key, votes) in votes {
+                        diesel::update(
+                            o::poll_options.filter(o::poll_id.eq(id).and(o::key.eq(key))),
+                        )
+                        .set(o::votes.eq(i64::try_from(votes).unwrap_or(i64::MAX)))
+                        .execute(c)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Close all polls created before the given time which are still open.
+    ///
+    /// Running polls only live in memory, so this is used at startup to close
+    /// polls which were abandoned when the bot stopped. Their votes are lost.
+    pub async fn close_abandoned(&self, before: NaiveDateTime) -> Result<usize> {
+        use self::schema::polls::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let filter = dsl::closed_at.is_null().and(dsl::created_at.lt(before));
+
+                Ok(diesel::update(dsl::polls.filter(filter))
+                    .set(dsl::closed_at.eq(Utc::now().naive_utc()))
+                    .execute(c)?)
+            })
+            .await
+    }
+
+    /// Get the poll with the given id in the given channel.
+    pub async fn get(&self, channel: &Channel, id: i32) -> Result<Option<PollResult>> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let poll = dsl::polls
+                    .filter(dsl::channel.eq(channel).and(dsl::id.eq(id)))
+                    .first::<models::Poll>(c)
+                    .optional()?;
+
+                Ok(with_options(c, poll.into_iter().collect())?.pop())
+            })
+            .await
+    }
+
+    /// Get the most recently closed poll in the given channel.
+    pub async fn last_closed(&self, channel: &Channel) -> Result<Option<PollResult>> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let poll = dsl::polls
+                    .filter(dsl::channel.eq(channel).and(dsl::closed_at.is_not_null()))
+                    .order(dsl::closed_at.desc())
+                    .first::<models::Poll>(c)
+                    .optional()?;
+
+                Ok(with_options(c, poll.into_iter().collect())?.pop())
+            })
+            .await
+    }
+
+    /// List the most recent polls in the given channel, newest first.
+    pub async fn list_recent(&self, channel: &Channel, limit: i64) -> Result<Vec<PollResult>> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let polls = dsl::polls
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::created_at.desc())
+                    .limit(limit)
+                    .load::<models::Poll>(c)?;
+
+                with_options(c, polls)
+            })
+            .await
+    }
+}
+
+/// Load the options associated with the given polls.
+fn with_options(c: &mut SqliteConnection, polls: Vec<Poll>) -> Result<Vec<PollResult>> {
+    use self::schema::poll_options::dsl;
+
+    let ids = polls.iter().map(|p| p.id).collect::<Vec<_>>();
+
+    let mut options = HashMap::<i32, Vec<PollOption>>::new();
+
+    for option in dsl::poll_options
+        .filter(dsl::poll_id.eq_any(ids))
+        .order(dsl::votes.desc())
+        .load::<models::PollOption>(c)?
+    {
+        options.entry(option.poll_id).or_default().push(option);
+    }
+
+    Ok(polls
+        .into_iter()
+        .map(|poll| PollResult {
+            options: options.remove(&poll.id).unwrap_or_default(),
+            poll,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Utc;
+    use common::Channel;
+
+    use super::Polls;
+    use crate::Database;
+
+    #[tokio::test]
+    async fn test_close_abandoned() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let polls = Polls::load(db).await.unwrap();
+        let channel = Channel::new("#channel");
+
+        let options = [(String::from("a"), None), (String::from("b"), None)];
+
+        let closed = polls
+            .insert(channel, "closed?", 1, None, &options)
+            .await
+            .unwrap();
+        polls
+            .close(closed.id, &[(String::from("b"), 3), (String::from("a"), 1)])
+            .await
+            .unwrap();
+
+        let abandoned = polls
+            .insert(channel, "abandoned?", 1, None, &options)
+            .await
+            .unwrap();
+        // NB: make sure the abandoned poll is strictly older than the cutoff.
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let before = Utc::now().naive_utc();
+        let running = polls
+            .insert(channel, "running?", 1, None, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(polls.close_abandoned(before).await.unwrap(), 1);
+
+        let abandoned = polls.get(channel, abandoned.id).await.unwrap().unwrap();
+        assert!(abandoned.poll.closed_at.is_some());
+        assert!(abandoned.options.iter().all(|o| o.votes == 0));
+
+        let running = polls.get(channel, running.id).await.unwrap().unwrap();
+        assert!(running.poll.closed_at.is_none());
+
+        // results of polls which were closed properly are left alone.
+        let closed = polls.get(channel, closed.id).await.unwrap().unwrap();
+        let votes = closed
+            .options
+            .iter()
+            .map(|o| (o.key.as_str(), o.votes))
+            .collect::<Vec<_>>();
+        assert_eq!(votes, [("b", 3), ("a", 1)]);
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use chrono::{NaiveDateTime, Utc};
+use common::models::TrackId;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::QueueSnapshotSong;
+
+/// Summary of a saved queue snapshot.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    /// The name of the snapshot.
+    pub name: String,
+    /// The number of songs in the snapshot.
+    pub songs: usize,
+    /// When the snapshot was saved.
+    pub saved_at: NaiveDateTime,
+}
+
+/// Named snapshots of the song request queue.
+#[derive(Clone)]
+pub struct QueueSnapshots {
+    db: crate::Database,
+}
+
+impl QueueSnapshots {
+    /// Open the queue snapshots database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all snapshots, ordered by name.
+    pub async fn list(&self) -> Result<Vec<QueueSnapshot>> {
+        use self::schema::queue_snapshots::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let songs = dsl::queue_snapshots
+                    .order((dsl::name, dsl::position))
+                    .load::<models::QueueSnapshotSong>(c)?;
+
+                let mut snapshots = BTreeMap::<String, QueueSnapshot>::new();
+
+                for song in songs {
+                    let snapshot =
+                        snapshots
+                            .entry(song.name.clone())
+                            .or_insert_with(|| QueueSnapshot {
+                                name: song.name,
+                                songs: 0,
+                                saved_at: song.saved_at,
+                            });
+
+                    snapshot.songs += 1;
+                }
+
+                Ok(snapshots.into_values().collect())
+            })
+            .await
+    }
+
+    /// Get the songs in the snapshot with the given name, in queue order.
+    ///
+    /// Returns `None` if there is no such snapshot.
+    pub async fn get(&self, name: &str) -> Result<Option<Vec<QueueSnapshotSong>>> {
+        use self::schema::queue_snapshots::dsl;
+
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let songs = dsl::queue_snapshots
+                    .filter(dsl::name.eq(name))
+                    .order(dsl::position)
+                    .load::<models::QueueSnapshotSong>(c)?;
+
+                if songs.is_empty() {
+                    return Ok(None);
+                }
+
+                Ok(Some(songs))
+            })
+            .await
+    }
+
+    /// Save the given songs as a snapshot, replacing any existing snapshot
+    /// with the same name.
+    pub async fn save(&self, name: &str, songs: &[(TrackId, Option<String>)]) -> Result<()> {
+        use self::schema::queue_snapshots::dsl;
+
+        let name = name.trim();
+
+        if name.is_empty() {
+            bail!("snapshot name must not be empty");
+        }
+
+        let saved_at = Utc::now().naive_utc();
+
+        let songs = songs
+            .iter()
+            .enumerate()
+            .map(
+                |(position, (track_id, user))| models::InsertQueueSnapshotSong {
+                    name: name.to_string(),
+                    position: i32::try_from(position).unwrap_or(i32::MAX),
+                    track_id: track_id.clone(),
+                    user: user.clone(),
+                    saved_at,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|c| {
+                    diesel::delete(dsl::queue_snapshots.filter(dsl::name.eq(&name))).execute(c)?;
+
+                    diesel::insert_into(dsl::queue_snapshots)
+                        .values(&songs)
+                        .execute(c)?;
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
+    /// Delete the snapshot with the given name.
+    ///
+    /// Returns `false` if there is no such snapshot.
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        use self::schema::queue_snapshots::dsl;
+
+        let name = name.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let count =
+                    diesel::delete(dsl::queue_snapshots.filter(dsl::name.eq(name))).execute(c)?;
+                Ok(count > 0)
+            })
+            .await
+    }
+}
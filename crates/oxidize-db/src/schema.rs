@@ -18,6 +18,10 @@ table! {
         text -> Text,
         group -> Nullable<Text>,
         disabled -> Bool,
+        scope -> Nullable<Text>,
+        cooldown -> Nullable<BigInt>,
+        user_cooldown -> Nullable<BigInt>,
+        cost -> Nullable<BigInt>,
     }
 }
 
@@ -35,6 +39,7 @@ table! {
     bad_words (word) {
         word -> Text,
         why -> Nullable<Text>,
+        kind -> Text,
     }
 }
 
@@ -48,6 +53,9 @@ table! {
         promoted_at -> Nullable<Timestamp>,
         promoted_by -> Nullable<Text>,
         user -> Nullable<Text>,
+        weight -> Integer,
+        turn -> BigInt,
+        queued_at -> Timestamp,
     }
 }
 
@@ -116,3 +124,129 @@ table! {
         value -> Binary,
     }
 }
+
+table! {
+    strikes (id) {
+        id -> Integer,
+        channel -> Text,
+        user -> Text,
+        reason -> Text,
+        action -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    polls (id) {
+        id -> Integer,
+        channel -> Text,
+        question -> Text,
+        round -> Integer,
+        parent_id -> Nullable<Integer>,
+        created_at -> Timestamp,
+        closed_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    poll_options (poll_id, key) {
+        poll_id -> Integer,
+        key -> Text,
+        description -> Nullable<Text>,
+        votes -> BigInt,
+    }
+}
+
+table! {
+    song_likes (track_id, user) {
+        track_id -> Text,
+        user -> Text,
+        liked -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    song_skips (id) {
+        id -> Integer,
+        track_id -> Text,
+        user -> Nullable<Text>,
+        votes -> Integer,
+        skipped_at -> Timestamp,
+    }
+}
+
+table! {
+    song_bans (id) {
+        id -> Integer,
+        kind -> Text,
+        pattern -> Text,
+        reason -> Nullable<Text>,
+        banned_by -> Nullable<Text>,
+        banned_at -> Timestamp,
+    }
+}
+
+table! {
+    queue_snapshots (id) {
+        id -> Integer,
+        name -> Text,
+        position -> Integer,
+        track_id -> Text,
+        user -> Nullable<Text>,
+        saved_at -> Timestamp,
+    }
+}
+
+table! {
+    counters (channel, name) {
+        channel -> Text,
+        name -> Text,
+        count -> BigInt,
+        scope -> Nullable<Text>,
+    }
+}
+
+table! {
+    custom_roles (role) {
+        role -> Text,
+        doc -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    custom_role_members (role, user) {
+        role -> Text,
+        user -> Text,
+    }
+}
+
+table! {
+    temporary_grants (scope, principal) {
+        scope -> Text,
+        principal -> Text,
+        kind -> Text,
+        expires_at -> Timestamp,
+    }
+}
+
+table! {
+    song_bumps (id) {
+        id -> Integer,
+        track_id -> Text,
+        user -> Text,
+        amount -> BigInt,
+        paid_at -> Timestamp,
+    }
+}
+
+table! {
+    bets (id) {
+        id -> Integer,
+        channel -> Text,
+        user -> Text,
+        amount -> BigInt,
+        placed_at -> Timestamp,
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::str;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use common::models::{Track, TrackId};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::SongBan;
+
+/// What a song ban matches against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SongBanKind {
+    /// Match a single track by its id.
+    Track,
+    /// Match tracks by an artist, either by name or by Spotify artist id.
+    Artist,
+    /// Match videos uploaded by a YouTube channel, either by name or by id.
+    Channel,
+    /// Match tracks whose title contains a keyword.
+    Keyword,
+}
+
+impl SongBanKind {
+    /// Get the kind as it's stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SongBanKind::Track => "track",
+            SongBanKind::Artist => "artist",
+            SongBanKind::Channel => "channel",
+            SongBanKind::Keyword => "keyword",
+        }
+    }
+
+    /// Normalize the given pattern for this kind of ban.
+    fn normalize(self, pattern: &str) -> Result<String> {
+        let pattern = pattern.trim();
+
+        if pattern.is_empty() {
+            bail!("pattern must not be empty");
+        }
+
+        Ok(match self {
+            SongBanKind::Track => match str::parse::<TrackId>(pattern) {
+                Ok(track_id) => track_id.to_string(),
+                Err(e) => bail!("bad track: {}", e),
+            },
+            _ => pattern.to_lowercase(),
+        })
+    }
+
+    /// Test if the given normalized pattern matches the given track.
+    fn matches(self, pattern: &str, track_id: &TrackId, track: &Track) -> bool {
+        match self {
+            SongBanKind::Track => track_id.to_string() == pattern,
+            SongBanKind::Artist => match track {
+                Track::Spotify { track } => track.artists.iter().any(|a| {
+                    a.name.to_lowercase() == pattern
+                        || a.id.as_deref().map(str::to_lowercase).as_deref() == Some(pattern)
+                }),
+                Track::Local { track } => {
+                    track.artist.as_deref().map(str::to_lowercase).as_deref() == Some(pattern)
+                }
+                Track::YouTube { .. } => false,
+            },
+            SongBanKind::Channel => match track {
+                Track::YouTube { video } => match &video.snippet {
+                    Some(snippet) => {
+                        snippet.channel_id.to_lowercase() == pattern
+                            || snippet
+                                .channel_title
+                                .as_deref()
+                                .map(str::to_lowercase)
+                                .as_deref()
+                                == Some(pattern)
+                    }
+                    None => false,
+                },
+                _ => false,
+            },
+            SongBanKind::Keyword => track.name().to_lowercase().contains(pattern),
+        }
+    }
+}
+
+impl fmt::Display for SongBanKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+impl str::FromStr for SongBanKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "track" => SongBanKind::Track,
+            "artist" => SongBanKind::Artist,
+            "channel" => SongBanKind::Channel,
+            "keyword" => SongBanKind::Keyword,
+            other => bail!("bad kind of song ban: {}", other),
+        })
+    }
+}
+
+/// Bans on requesting songs by track, artist, YouTube channel, or keyword.
+#[derive(Clone)]
+pub struct SongBans {
+    db: crate::Database,
+}
+
+impl SongBans {
+    /// Open the song bans database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all song bans, newest first.
+    pub async fn list(&self) -> Result<Vec<SongBan>> {
+        use self::schema::song_bans::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::song_bans
+                    .order(dsl::banned_at.desc())
+                    .load::<models::SongBan>(c)?)
+            })
+            .await
+    }
+
+    /// Ban the given pattern.
+    ///
+    /// Returns `false` if the pattern is already banned.
+    pub async fn insert(
+        &self,
+        kind: SongBanKind,
+        pattern: &str,
+        reason: Option<&str>,
+        banned_by: Option<&str>,
+    ) -> Result<bool> {
+        use self::schema::song_bans::dsl;
+
+        let ban = models::InsertSongBan {
+            kind: kind.as_str().to_string(),
+            pattern: kind.normalize(pattern)?,
+            reason: reason.map(str::to_string),
+            banned_by: banned_by.map(crate::user_id),
+            banned_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::insert_or_ignore_into(dsl::song_bans)
+                    .values(&ban)
+                    .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Remove the ban on the given pattern.
+    ///
+    /// Returns `false` if the pattern wasn't banned.
+    pub async fn delete(&self, kind: SongBanKind, pattern: &str) -> Result<bool> {
+        use self::schema::song_bans::dsl;
+
+        let pattern = kind.normalize(pattern)?;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::song_bans
+                        .filter(dsl::kind.eq(kind.as_str()).and(dsl::pattern.eq(pattern))),
+                )
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Remove the ban with the given id.
+    ///
+    /// Returns `false` if there is no such ban.
+    pub async fn delete_by_id(&self, id: i32) -> Result<bool> {
+        use self::schema::song_bans::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(dsl::song_bans.filter(dsl::id.eq(id))).execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Find the first ban which matches the given track, if any.
+    pub async fn find(&self, track_id: &TrackId, track: &Track) -> Result<Option<SongBan>> {
+        let bans = self.list().await?;
+        Ok(find(bans, track_id, track))
+    }
+}
+
+/// Find the first of the given bans which matches the given track.
+fn find(bans: Vec<SongBan>, track_id: &TrackId, track: &Track) -> Option<SongBan> {
+    bans.into_iter()
+        .find(|ban| match str::parse::<SongBanKind>(&ban.kind) {
+            Ok(kind) => kind.matches(&ban.pattern, track_id, track),
+            Err(e) => {
+                tracing::warn!("Ignoring song ban {}: {}", ban.id, e);
+                false
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::models::local::LocalTrack;
+    use common::models::{Track, TrackId};
+
+    use super::{find, SongBan, SongBanKind};
+
+    fn ban(id: i32, kind: SongBanKind, pattern: &str) -> SongBan {
+        SongBan {
+            id,
+            kind: kind.as_str().to_string(),
+            pattern: kind.normalize(pattern).unwrap(),
+            reason: None,
+            banned_by: None,
+            banned_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    fn local(path: &str, title: &str, artist: &str) -> (TrackId, Track) {
+        let track = LocalTrack {
+            path: path.to_string(),
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            album: None,
+            duration: Duration::from_secs(60),
+        };
+
+        (
+            TrackId::Local(path.to_string()),
+            Track::Local {
+                track: Box::new(track),
+            },
+        )
+    }
+
+    #[test]
+    fn test_find() {
+        let bans = vec![
+            ban(1, SongBanKind::Track, "local:track:rock/one.mp3"),
+            ban(2, SongBanKind::Artist, "Nickelback"),
+            ban(3, SongBanKind::Keyword, "Nightcore"),
+            ban(4, SongBanKind::Channel, "Some Channel"),
+        ];
+
+        let (track_id, track) = local("rock/one.mp3", "One", "Someone");
+        assert_eq!(find(bans.clone(), &track_id, &track).map(|b| b.id), Some(1));
+
+        let (track_id, track) = local("rock/two.mp3", "Photograph", "nickelback");
+        assert_eq!(find(bans.clone(), &track_id, &track).map(|b| b.id), Some(2));
+
+        let (track_id, track) = local("pop/three.mp3", "Song (NIGHTCORE remix)", "Someone");
+        assert_eq!(find(bans.clone(), &track_id, &track).map(|b| b.id), Some(3));
+
+        // NB: channel bans only apply to YouTube videos.
+        let (track_id, track) = local("pop/four.mp3", "Four", "Some Channel");
+        assert_eq!(find(bans, &track_id, &track).map(|b| b.id), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert!(SongBanKind::Track.normalize("not a track").is_err());
+        assert!(SongBanKind::Keyword.normalize("  ").is_err());
+        assert_eq!(
+            SongBanKind::Artist.normalize(" Nickelback ").unwrap(),
+            "nickelback"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::models::TrackId;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::SongBump;
+
+/// Bumps which viewers have paid for, kept around so that they can be
+/// refunded if the bumped song is removed before it plays.
+#[derive(Clone)]
+pub struct SongBumps {
+    db: crate::Database,
+}
+
+impl SongBumps {
+    /// Open the song bumps database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all paid bumps, oldest first.
+    pub async fn list(&self) -> Result<Vec<SongBump>> {
+        use self::schema::song_bumps::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::song_bumps
+                    .order(dsl::id.asc())
+                    .load::<models::SongBump>(c)?)
+            })
+            .await
+    }
+
+    /// Record that the given user paid the given amount to bump a track.
+    pub async fn insert(&self, track_id: &TrackId, user: &str, amount: i64) -> Result<()> {
+        use self::schema::song_bumps::dsl;
+
+        let bump = models::InsertSongBump {
+            track_id: track_id.clone(),
+            user: crate::user_id(user),
+            amount,
+            paid_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::song_bumps)
+                    .values(&bump)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove all bumps paid by the given user for the given track, returning
+    /// the total amount which should be refunded.
+    pub async fn take(&self, track_id: &TrackId, user: &str) -> Result<i64> {
+        use self::schema::song_bumps::dsl;
+
+        let track_id = track_id.clone();
+        let user = crate::user_id(user);
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction::<_, anyhow::Error, _>(|c| {
+                    let filter = dsl::track_id.eq(&track_id).and(dsl::user.eq(&user));
+
+                    let amounts = dsl::song_bumps
+                        .select(dsl::amount)
+                        .filter(filter)
+                        .load::<i64>(c)?;
+
+                    diesel::delete(dsl::song_bumps.filter(filter)).execute(c)?;
+                    Ok(amounts.into_iter().sum())
+                })
+            })
+            .await
+    }
+
+    /// Remove the bumps with the given ids without refunding them.
+    pub async fn delete(&self, ids: Vec<i32>) -> Result<usize> {
+        use self::schema::song_bumps::dsl;
+
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.db
+            .asyncify(move |c| {
+                Ok(diesel::delete(dsl::song_bumps.filter(dsl::id.eq_any(ids))).execute(c)?)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use common::models::TrackId;
+
+    use super::SongBumps;
+    use crate::Database;
+
+    fn track(name: &str) -> TrackId {
+        TrackId::Local(format!("{name}.mp3"))
+    }
+
+    #[tokio::test]
+    async fn test_take() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let bumps = SongBumps::load(db).await.unwrap();
+
+        bumps.insert(&track("a"), "Alice", 10).await.unwrap();
+        bumps.insert(&track("a"), "alice", 20).await.unwrap();
+        bumps.insert(&track("a"), "bob", 5).await.unwrap();
+        bumps.insert(&track("b"), "alice", 7).await.unwrap();
+
+        assert_eq!(bumps.take(&track("a"), "alice").await.unwrap(), 30);
+        assert_eq!(bumps.take(&track("a"), "alice").await.unwrap(), 0);
+
+        let left = bumps.list().await.unwrap();
+        assert_eq!(left.len(), 2);
+
+        let ids = left.iter().map(|b| b.id).collect();
+        assert_eq!(bumps.delete(ids).await.unwrap(), 2);
+        assert!(bumps.list().await.unwrap().is_empty());
+    }
+}
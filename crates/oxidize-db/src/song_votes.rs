@@ -0,0 +1,123 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::models::TrackId;
+use common::Duration;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::models;
+use crate::schema;
+
+/// The number of users who like and dislike a track.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SongLikes {
+    pub likes: u64,
+    pub dislikes: u64,
+}
+
+/// Votes cast by viewers on songs, like likes and vote skips.
+#[derive(Clone)]
+pub struct SongVotes {
+    db: crate::Database,
+}
+
+impl SongVotes {
+    /// Open the song votes database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record that the given user likes or dislikes the given track,
+    /// replacing any earlier vote by the same user.
+    pub async fn set_like(&self, track_id: &TrackId, user: &str, liked: bool) -> Result<()> {
+        use self::schema::song_likes::dsl;
+
+        let like = models::SongLike {
+            track_id: track_id.clone(),
+            user: crate::user_id(user),
+            liked,
+            updated_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::song_likes)
+                    .values(&like)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Count the likes and dislikes of the given track.
+    pub async fn likes(&self, track_id: &TrackId) -> Result<SongLikes> {
+        use self::schema::song_likes::dsl;
+
+        let track_id = track_id.clone();
+
+        self.db
+            .asyncify(move |c| {
+                let votes = dsl::song_likes
+                    .select(dsl::liked)
+                    .filter(dsl::track_id.eq(track_id))
+                    .load::<bool>(c)?;
+
+                let likes = votes.iter().filter(|liked| **liked).count() as u64;
+
+                Ok(SongLikes {
+                    likes,
+                    dislikes: votes.len() as u64 - likes,
+                })
+            })
+            .await
+    }
+
+    /// Record that the given track, requested by the given user, was skipped
+    /// by vote.
+    pub async fn insert_skip(
+        &self,
+        track_id: &TrackId,
+        user: Option<&str>,
+        votes: u32,
+    ) -> Result<()> {
+        use self::schema::song_skips::dsl;
+
+        let skip = models::InsertSongSkip {
+            track_id: track_id.clone(),
+            user: user.map(crate::user_id),
+            votes: i32::try_from(votes).unwrap_or(i32::MAX),
+            skipped_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::song_skips)
+                    .values(&skip)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Count the songs requested by the given user which have been skipped by
+    /// vote within the given duration.
+    pub async fn skips_within(&self, user: &str, within: &Duration) -> Result<u32> {
+        use self::schema::song_skips::dsl;
+
+        let user = crate::user_id(user);
+        let since = (Utc::now() - within.as_chrono()).naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                let count = dsl::song_skips
+                    .filter(dsl::user.eq(user).and(dsl::skipped_at.gt(since)))
+                    .count()
+                    .get_result::<i64>(c)?;
+
+                Ok(u32::try_from(count).unwrap_or(u32::MAX))
+            })
+            .await
+    }
+}
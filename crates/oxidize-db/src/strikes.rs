@@ -0,0 +1,195 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::{Channel, Duration};
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::Strike;
+
+/// Strikes issued to users who have broken the rules of the channel.
+#[derive(Clone)]
+pub struct Strikes {
+    db: crate::Database,
+}
+
+impl Strikes {
+    /// Open the strikes database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record a strike against the given user.
+    pub async fn insert(
+        &self,
+        channel: &Channel,
+        user: &str,
+        reason: &str,
+        action: &str,
+    ) -> Result<()> {
+        use self::schema::strikes::dsl;
+
+        let strike = models::InsertStrike {
+            channel: channel.to_string(),
+            user: crate::user_id(user),
+            reason: reason.to_string(),
+            action: action.to_string(),
+            created_at: Utc::now().naive_utc(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::strikes)
+                    .values(&strike)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// List strikes issued to the given user within the given duration,
+    /// newest first.
+    pub async fn list_by_user(
+        &self,
+        channel: &Channel,
+        user: &str,
+        within: &Duration,
+    ) -> Result<Vec<Strike>> {
+        use self::schema::strikes::dsl;
+
+        let channel = channel.to_string();
+        let user = crate::user_id(user);
+        let since = (Utc::now() - within.as_chrono()).naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::strikes
+                    .filter(
+                        dsl::channel
+                            .eq(channel)
+                            .and(dsl::user.eq(user))
+                            .and(dsl::created_at.gt(since)),
+                    )
+                    .order(dsl::created_at.desc())
+                    .load::<models::Strike>(c)?)
+            })
+            .await
+    }
+
+    /// List the most recent strikes in the given channel, newest first.
+    pub async fn list_recent(&self, channel: &Channel, limit: i64) -> Result<Vec<Strike>> {
+        use self::schema::strikes::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::strikes
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::created_at.desc())
+                    .limit(limit)
+                    .load::<models::Strike>(c)?)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Utc;
+    use common::{Channel, Duration};
+    use diesel::prelude::*;
+
+    use super::Strikes;
+    use crate::{models, schema, Database};
+
+    /// Insert a strike which was issued the given duration ago.
+    async fn insert_old(db: &Database, channel: &Channel, user: &str, ago: Duration) {
+        use schema::strikes::dsl;
+
+        let strike = models::InsertStrike {
+            channel: channel.to_string(),
+            user: user.to_string(),
+            reason: String::from("old"),
+            action: String::from("warn"),
+            created_at: (Utc::now() - ago.as_chrono()).naive_utc(),
+        };
+
+        db.asyncify(move |c| {
+            diesel::insert_into(dsl::strikes)
+                .values(&strike)
+                .execute(c)?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_by_user() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let strikes = Strikes::load(db.clone()).await.unwrap();
+        let channel = Channel::new("#channel");
+        let hour = Duration::hours(1);
+
+        insert_old(&db, channel, "alice", Duration::hours(2)).await;
+        insert_old(&db, channel, "alice", Duration::seconds(30 * 60)).await;
+        strikes
+            .insert(channel, "Alice", "caps", "timeout 1m")
+            .await
+            .unwrap();
+        strikes
+            .insert(channel, "bob", "spam", "warn")
+            .await
+            .unwrap();
+        strikes
+            .insert(Channel::new("#other"), "alice", "spam", "warn")
+            .await
+            .unwrap();
+
+        // strikes outside of the decay window no longer count.
+        let list = strikes.list_by_user(channel, "alice", &hour).await.unwrap();
+        let reasons = list.iter().map(|s| s.reason.as_str()).collect::<Vec<_>>();
+        assert_eq!(reasons, ["caps", "old"]);
+        assert_eq!(list[0].user, "alice");
+        assert_eq!(list[0].action, "timeout 1m");
+
+        let list = strikes
+            .list_by_user(channel, "@ALICE", &Duration::hours(3))
+            .await
+            .unwrap();
+        assert_eq!(list.len(), 3);
+
+        let list = strikes.list_by_user(channel, "carol", &hour).await.unwrap();
+        assert!(list.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_recent() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let strikes = Strikes::load(db.clone()).await.unwrap();
+        let channel = Channel::new("#channel");
+
+        insert_old(&db, channel, "alice", Duration::hours(2)).await;
+        strikes
+            .insert(channel, "bob", "spam", "warn")
+            .await
+            .unwrap();
+        strikes
+            .insert(Channel::new("#other"), "carol", "spam", "warn")
+            .await
+            .unwrap();
+
+        let list = strikes.list_recent(channel, 10).await.unwrap();
+        let users = list.iter().map(|s| s.user.as_str()).collect::<Vec<_>>();
+        assert_eq!(users, ["bob", "alice"]);
+
+        let list = strikes.list_recent(channel, 1).await.unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].user, "bob");
+    }
+}
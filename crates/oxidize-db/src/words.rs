@@ -1,8 +1,12 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{RwLock, RwLockReadGuard};
 
 /// Tokenize the given word.
@@ -11,29 +15,135 @@ pub(crate) fn tokenize(word: &str) -> String {
     inflector::string::singularize::to_singular(&word)
 }
 
+/// How a bad word is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordKind {
+    /// Match a single word, including words which sound similar.
+    #[default]
+    Word,
+    /// Match a sequence of words.
+    Phrase,
+    /// Match a single word using a glob, where `*` matches any number of
+    /// characters and `?` matches a single character.
+    Glob,
+    /// Match a regular expression anywhere in the message.
+    Regex,
+}
+
+impl WordKind {
+    /// Get the kind as it's stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WordKind::Word => "word",
+            WordKind::Phrase => "phrase",
+            WordKind::Glob => "glob",
+            WordKind::Regex => "regex",
+        }
+    }
+}
+
+impl fmt::Display for WordKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+impl str::FromStr for WordKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "word" => WordKind::Word,
+            "phrase" => WordKind::Phrase,
+            "glob" => WordKind::Glob,
+            "regex" => WordKind::Regex,
+            other => bail!("bad kind of bad word: {}", other),
+        })
+    }
+}
+
+/// Format used when importing or exporting bad words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordsFormat {
+    /// Comma-separated values with a `word,why,kind` header.
+    Csv,
+    /// A YAML list of words.
+    Yaml,
+}
+
+impl WordsFormat {
+    /// Guess the format to use from the extension of the given path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(WordsFormat::Csv),
+            "yaml" | "yml" => Some(WordsFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+impl str::FromStr for WordsFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "csv" => WordsFormat::Csv,
+            "yaml" => WordsFormat::Yaml,
+            other => bail!("unsupported format: {}", other),
+        })
+    }
+}
+
+/// A single word being imported.
+#[derive(Debug, Deserialize)]
+struct ImportWord {
+    word: String,
+    #[serde(default)]
+    why: Option<String>,
+    #[serde(default)]
+    kind: WordKind,
+}
+
 #[derive(Debug, Default)]
 struct Inner {
     hashed: HashMap<eudex::Hash, Arc<Word>>,
     exact: HashMap<String, Arc<Word>>,
+    /// Globs, matched against individual words.
+    globs: Vec<Arc<Word>>,
+    /// Phrases and regular expressions, matched against the whole message.
+    patterns: Vec<Arc<Word>>,
 }
 
 impl Inner {
     /// Insert a bad word.
-    fn insert(&mut self, word: &str, why: Option<&str>) -> Result<()> {
-        let word = Word {
-            word: tokenize(word),
-            why: why.map(template::Template::compile).transpose()?,
-        };
+    fn insert(&mut self, word: Word) {
+        self.remove(&word.word);
 
         let word = Arc::new(word);
-        self.hashed
-            .insert(eudex::Hash::new(&word.word), Arc::clone(&word));
-        self.exact.insert(word.word.to_string(), Arc::clone(&word));
-        Ok(())
+
+        match word.kind {
+            WordKind::Word => {
+                let token = tokenize(&word.word);
+                self.hashed
+                    .insert(eudex::Hash::new(&token), Arc::clone(&word));
+                self.exact.insert(token, word);
+            }
+            WordKind::Glob => {
+                self.globs.push(word);
+            }
+            WordKind::Phrase | WordKind::Regex => {
+                self.patterns.push(word);
+            }
+        }
     }
 
     /// Insert a bad word.
     fn remove(&mut self, word: &str) {
+        self.globs.retain(|w| w.word != word);
+        self.patterns.retain(|w| w.word != word);
+
         let word = tokenize(word);
 
         // TODO: there might be hash conflicts. Deal with them.
@@ -55,12 +165,14 @@ impl Database {
             .await
     }
 
-    /// Insert or update an existing word.
-    async fn edit(&self, word: &str, why: Option<&str>) -> Result<()> {
+    /// Insert or update an existing word, returning the message it ends up
+    /// with.
+    async fn edit(&self, word: &str, why: Option<&str>, kind: WordKind) -> Result<Option<String>> {
         use crate::schema::bad_words::dsl;
 
         let word = word.to_string();
         let why = why.map(|w| w.to_string());
+        let kind = kind.to_string();
 
         self.0
             .asyncify(move |c| {
@@ -69,20 +181,28 @@ impl Database {
 
                 match b {
                     None => {
-                        let bad_word = crate::models::BadWord { word, why };
+                        let bad_word = crate::models::BadWord {
+                            word,
+                            why: why.clone(),
+                            kind,
+                        };
 
                         diesel::insert_into(dsl::bad_words)
                             .values(&bad_word)
                             .execute(c)?;
+
+                        Ok(why)
                     }
-                    Some(_) => {
+                    Some(b) => {
+                        let why = why.or(b.why);
+
                         diesel::update(filter)
-                            .set(why.map(|w| dsl::why.eq(w)))
+                            .set((dsl::why.eq(&why), dsl::kind.eq(kind)))
                             .execute(c)?;
+
+                        Ok(why)
                     }
                 }
-
-                Ok(())
             })
             .await
     }
@@ -116,7 +236,13 @@ impl Words {
         let mut inner = Inner::default();
 
         for word in db.list().await? {
-            inner.insert(&word.word, word.why.as_deref())?;
+            let result = str::parse(&word.kind)
+                .and_then(|kind| Word::new(&word.word, word.why.as_deref(), kind))
+                .map(|w| inner.insert(w));
+
+            if let Err(e) = result {
+                common::log_error!(e, "Failed to load bad word `{}`", word.word);
+            }
         }
 
         Ok(Words {
@@ -125,18 +251,74 @@ impl Words {
         })
     }
 
-    /// Insert a word into the bad words list.
-    #[allow(unused)]
-    pub(crate) async fn edit(&self, word: &str, why: Option<&str>) -> Result<()> {
-        self.db.edit(word, why).await?;
+    /// List all words in the bad words list.
+    pub async fn list(&self) -> Result<Vec<crate::models::BadWord>> {
+        let mut words = self.db.list().await?;
+        words.sort();
+        Ok(words)
+    }
+
+    /// Import words in the given format into the bad words list, replacing
+    /// any existing words with the same name.
+    ///
+    /// Returns the number of imported words. Nothing is imported if any of the
+    /// words are invalid.
+    pub async fn import(&self, format: WordsFormat, input: &[u8]) -> Result<usize> {
+        let words: Vec<ImportWord> = match format {
+            WordsFormat::Csv => csv::Reader::from_reader(input)
+                .deserialize()
+                .collect::<Result<_, _>>()?,
+            WordsFormat::Yaml => serde_yaml::from_slice(input)?,
+        };
+
+        for w in &words {
+            Word::new(&w.word, w.why.as_deref(), w.kind)
+                .with_context(|| anyhow!("bad word `{}`", w.word))?;
+        }
+
+        for w in &words {
+            self.edit(&w.word, w.why.as_deref(), w.kind).await?;
+        }
+
+        Ok(words.len())
+    }
+
+    /// Export all words in the bad words list in the given format.
+    pub async fn export(&self, format: WordsFormat) -> Result<Vec<u8>> {
+        let words = self.list().await?;
+
+        Ok(match format {
+            WordsFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+
+                for word in &words {
+                    writer.serialize(word)?;
+                }
+
+                writer.into_inner()?
+            }
+            WordsFormat::Yaml => serde_yaml::to_string(&words)?.into_bytes(),
+        })
+    }
+
+    /// Insert a word into the bad words list, or update an existing word.
+    ///
+    /// The existing message of a word is kept if `why` is `None`.
+    pub async fn edit(&self, word: &str, why: Option<&str>, kind: WordKind) -> Result<()> {
+        // NB: compile the word first so that we never store a bad pattern.
+        let mut compiled = Word::new(word, why, kind)?;
+        let why = self.db.edit(word, why, kind).await?;
+        compiled.why = why
+            .as_deref()
+            .map(template::Template::compile)
+            .transpose()?;
         let mut inner = self.inner.write().await;
-        inner.insert(word, why)?;
+        inner.insert(compiled);
         Ok(())
     }
 
     /// Remove a word from the bad words list.
-    #[allow(unused)]
-    pub(crate) async fn delete(&self, word: &str) -> Result<bool> {
+    pub async fn delete(&self, word: &str) -> Result<bool> {
         if !self.db.delete(word).await? {
             return Ok(false);
         }
@@ -162,16 +344,52 @@ pub struct Tester<'a> {
 impl Tester<'_> {
     /// Test the given word.
     pub fn test(&self, word: &str) -> Option<Arc<Word>> {
-        let word = tokenize(word);
+        let token = tokenize(word);
 
-        if let Some(w) = self.inner.hashed.get(&eudex::Hash::new(&word)) {
+        if let Some(w) = self.inner.hashed.get(&eudex::Hash::new(&token)) {
             return Some(Arc::clone(w));
         }
 
-        if let Some(w) = self.inner.exact.get(&word) {
+        if let Some(w) = self.inner.exact.get(&token) {
             return Some(Arc::clone(w));
         }
 
+        for glob in &self.inner.globs {
+            if glob.is_match(word) {
+                return Some(Arc::clone(glob));
+            }
+        }
+
+        None
+    }
+
+    /// Test every word in the given message, as well as any phrases or regular
+    /// expressions which should be matched against the whole message.
+    ///
+    /// If `normalize` is set, the message is passed through [normalize] before
+    /// it's tested.
+    pub fn test_message(&self, message: &str, normalize: bool) -> Option<Arc<Word>> {
+        let normalized;
+
+        let message = if normalize {
+            normalized = self::normalize(message);
+            normalized.as_str()
+        } else {
+            message
+        };
+
+        for word in common::words::trimmed(message) {
+            if let Some(word) = self.test(word) {
+                return Some(word);
+            }
+        }
+
+        for pattern in &self.inner.patterns {
+            if pattern.is_match(message) {
+                return Some(Arc::clone(pattern));
+            }
+        }
+
         None
     }
 }
@@ -179,5 +397,371 @@ impl Tester<'_> {
 #[derive(Debug)]
 pub struct Word {
     pub(crate) word: String,
+    pub(crate) kind: WordKind,
+    /// Compiled pattern for words which are not matched by token.
+    regex: Option<regex::Regex>,
     pub why: Option<template::Template>,
 }
+
+impl Word {
+    /// Construct a new bad word.
+    fn new(word: &str, why: Option<&str>, kind: WordKind) -> Result<Self> {
+        let source = match kind {
+            WordKind::Word => None,
+            WordKind::Phrase => {
+                let words = word.split_whitespace().map(regex::escape);
+                Some(format!(r"\b{}\b", words.collect::<Vec<_>>().join(r"\W+")))
+            }
+            WordKind::Glob => {
+                let mut source = String::from("^");
+
+                for c in word.chars() {
+                    match c {
+                        '*' => source.push_str(".*"),
+                        '?' => source.push('.'),
+                        c => source.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                    }
+                }
+
+                source.push('$');
+                Some(source)
+            }
+            WordKind::Regex => Some(word.to_string()),
+        };
+
+        let regex = match source {
+            Some(source) => Some(
+                regex::RegexBuilder::new(&source)
+                    .case_insensitive(true)
+                    .build()?,
+            ),
+            None => None,
+        };
+
+        Ok(Word {
+            word: word.to_string(),
+            kind,
+            regex,
+            why: why.map(template::Template::compile).transpose()?,
+        })
+    }
+
+    /// Test if the word's pattern matches the given text.
+    fn is_match(&self, text: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(text),
+            None => false,
+        }
+    }
+}
+
+/// Normalize the given text so that it's harder to dodge bad words.
+///
+/// Characters which look like latin letters, such as accented letters, their
+/// cyrillic or greek lookalikes, and fullwidth forms, are mapped to the letter
+/// they resemble. Leetspeak is mapped back to letters in words which contain
+/// letters, and invisible characters are removed. Finally, words which are
+/// spelled out one character at a time like `b a d` are joined together.
+pub fn normalize(text: &str) -> String {
+    let mut words = Vec::new();
+    let mut spelled = String::new();
+
+    for word in text.split_whitespace() {
+        let word = word
+            .chars()
+            .filter(|c| !is_invisible(*c))
+            .flat_map(char::to_lowercase)
+            .map(confusable)
+            .collect::<String>();
+
+        match word.chars().count() {
+            0 => continue,
+            1 => spelled.push_str(&word),
+            _ => {
+                push_spelled(&mut words, &mut spelled);
+                words.push(word);
+            }
+        }
+    }
+
+    push_spelled(&mut words, &mut spelled);
+
+    let words = words.iter().map(|word| {
+        // NB: numbers are left alone.
+        if word.chars().all(|c| c.is_ascii_digit()) {
+            return word.clone();
+        }
+
+        let last = word.chars().count() - 1;
+
+        word.chars()
+            .enumerate()
+            .map(|(n, c)| match leetspeak(c) {
+                // NB: trailing punctuation like `!` is left alone.
+                Some(_) if n == last && c.is_ascii_punctuation() => c,
+                Some(l) => l,
+                None => c,
+            })
+            .collect::<String>()
+    });
+
+    return words.collect::<Vec<_>>().join(" ");
+
+    /// Push characters which were spelled out, which are joined into a single
+    /// word if there are enough of them.
+    fn push_spelled(words: &mut Vec<String>, spelled: &mut String) {
+        if spelled.chars().count() >= 3 {
+            words.push(std::mem::take(spelled));
+        } else {
+            words.extend(spelled.drain(..).map(String::from));
+        }
+    }
+}
+
+/// Test if the given character is invisible, or a combining mark which would
+/// otherwise be used to decorate a letter.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{ad}' | '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}' | '\u{300}'..='\u{36f}'
+    )
+}
+
+/// Map a lowercase character to the latin letter or digit it resembles.
+fn confusable(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'а' | 'α' => 'a',
+        'β' => 'b',
+        'ç' | 'с' => 'c',
+        'ԁ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'е' | 'ё' | 'ε' => 'e',
+        'н' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'м' => 'm',
+        'ñ' | 'η' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'υ' => 'u',
+        'ν' => 'v',
+        'ԝ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'ý' | 'ÿ' | 'у' => 'y',
+        // fullwidth forms.
+        'ａ'..='ｚ' | '０'..='９' => {
+            let base = if c >= 'ａ' {
+                ('a', 'ａ')
+            } else {
+                ('0', '０')
+            };
+            char::from_u32(base.0 as u32 + (c as u32 - base.1 as u32)).unwrap_or(c)
+        }
+        c => c,
+    }
+}
+
+/// Map a leetspeak character to the letter it represents.
+fn leetspeak(c: char) -> Option<char> {
+    Some(match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        '8' => 'b',
+        '9' => 'g',
+        '|' => 'l',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{normalize, ImportWord, Inner, Tester, Word, WordKind, Words, WordsFormat};
+
+    async fn words() -> Words {
+        let db = crate::Database::open(Path::new(":memory:")).unwrap();
+        Words::load(db).await.unwrap()
+    }
+
+    #[test]
+    pub(crate) fn test_normalize_confusables() {
+        assert_eq!("bad word", normalize("bad word"));
+        assert_eq!("bad word", normalize("BÄD WÖRD"));
+        // cyrillic and greek lookalikes.
+        assert_eq!("bad word", normalize("bаd wοrd"));
+        // fullwidth forms.
+        assert_eq!("bad word", normalize("ｂａｄ ｗｏｒｄ"));
+        // invisible characters and combining marks.
+        assert_eq!("badword", normalize("bad\u{200b}word"));
+        assert_eq!("bad", normalize("ba\u{301}d"));
+    }
+
+    #[test]
+    pub(crate) fn test_normalize_leetspeak() {
+        assert_eq!("bad word", normalize("b4d w0rd"));
+        assert_eq!("asshole", normalize("@$$h0le"));
+        assert_eq!("eeet", normalize("3e3t"));
+        // numbers and trailing punctuation are left alone.
+        assert_eq!("1337 bad!", normalize("1337 bad!"));
+    }
+
+    #[test]
+    pub(crate) fn test_normalize_spelled_out() {
+        assert_eq!("badword", normalize("b a d w o r d"));
+        assert_eq!("say badword now", normalize("say b a d w o r d now"));
+        assert_eq!("a b", normalize("a   b"));
+    }
+
+    #[test]
+    pub(crate) fn test_import_formats() {
+        let csv = "word,why\nfoo,\nbar baz,no phrases please\n";
+        let words = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize::<ImportWord>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(2, words.len());
+        assert_eq!(("foo", None, WordKind::Word), entry(&words[0]));
+        assert_eq!(
+            ("bar baz", Some("no phrases please"), WordKind::Word),
+            entry(&words[1])
+        );
+
+        let yaml = "- word: foo\n- word: b*r\n  kind: glob\n";
+        let words = serde_yaml::from_str::<Vec<ImportWord>>(yaml).unwrap();
+
+        assert_eq!(2, words.len());
+        assert_eq!(("foo", None, WordKind::Word), entry(&words[0]));
+        assert_eq!(("b*r", None, WordKind::Glob), entry(&words[1]));
+
+        fn entry(w: &ImportWord) -> (&str, Option<&str>, WordKind) {
+            (w.word.as_str(), w.why.as_deref(), w.kind)
+        }
+    }
+
+    #[test]
+    pub(crate) fn test_kinds() {
+        let mut inner = Inner::default();
+
+        for (word, kind) in [
+            ("badword", WordKind::Word),
+            ("very bad phrase", WordKind::Phrase),
+            ("bad*ness", WordKind::Glob),
+            (r"b+a+d+", WordKind::Regex),
+        ] {
+            inner.insert(Word::new(word, None, kind).unwrap());
+        }
+
+        let inner = tokio::sync::RwLock::new(inner);
+        let tester = Tester {
+            inner: inner.try_read().unwrap(),
+        };
+
+        let test = |m: &str, n: bool| tester.test_message(m, n).map(|w| w.word.clone());
+
+        assert_eq!(Some("badword".into()), test("such Badwords", false));
+        assert_eq!(
+            Some("very bad phrase".into()),
+            test("a VERY  bad, phrase", false)
+        );
+        assert_eq!(Some("bad*ness".into()), test("so much badderness", false));
+        assert_eq!(Some(r"b+a+d+".into()), test("baaaaaad", false));
+        assert_eq!(None, test("hello there", false));
+        assert_eq!(None, test("v e r y b4d phrase", false));
+        assert_eq!(Some("badword".into()), test("b 4 d w 0 r d", true));
+    }
+
+    #[tokio::test]
+    pub(crate) async fn test_import() {
+        let words = words().await;
+
+        let csv = "word,why,kind\nfoo,,word\nb*r,no bars please,glob\n";
+        assert_eq!(
+            2,
+            words
+                .import(WordsFormat::Csv, csv.as_bytes())
+                .await
+                .unwrap()
+        );
+
+        let mut list = words.list().await.unwrap();
+        list.sort_by(|a, b| a.word.cmp(&b.word));
+
+        assert_eq!(2, list.len());
+        assert_eq!(("b*r", Some("no bars please"), "glob"), entry(&list[0]));
+        assert_eq!(("foo", None, "word"), entry(&list[1]));
+
+        assert!(words
+            .tester()
+            .await
+            .test_message("a bazar", false)
+            .is_some());
+
+        // NB: an invalid word means that nothing is imported.
+        let yaml = "- word: baz\n- word: \"(\"\n  kind: regex\n";
+        assert!(words
+            .import(WordsFormat::Yaml, yaml.as_bytes())
+            .await
+            .is_err());
+        assert_eq!(2, words.list().await.unwrap().len());
+        assert!(words.tester().await.test("baz").is_none());
+
+        fn entry(w: &crate::models::BadWord) -> (&str, Option<&str>, &str) {
+            (w.word.as_str(), w.why.as_deref(), w.kind.as_str())
+        }
+    }
+
+    #[tokio::test]
+    pub(crate) async fn test_export() {
+        let words = words().await;
+        words
+            .edit("foo", Some("no foo"), WordKind::Word)
+            .await
+            .unwrap();
+        words.edit(r"b+a+r", None, WordKind::Regex).await.unwrap();
+
+        let csv = String::from_utf8(words.export(WordsFormat::Csv).await.unwrap()).unwrap();
+        let mut lines = csv.lines().collect::<Vec<_>>();
+        lines[1..].sort();
+        assert_eq!(lines, ["word,why,kind", "b+a+r,,regex", "foo,no foo,word"]);
+
+        for format in [WordsFormat::Csv, WordsFormat::Yaml] {
+            let exported = words.export(format).await.unwrap();
+
+            let other = self::words().await;
+            assert_eq!(2, other.import(format, &exported).await.unwrap());
+            assert_eq!(
+                words.export(format).await.unwrap(),
+                other.export(format).await.unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    pub(crate) async fn test_edit_keeps_why() {
+        let words = words().await;
+        words
+            .edit("foo", Some("no foo"), WordKind::Word)
+            .await
+            .unwrap();
+        words.edit("foo", None, WordKind::Glob).await.unwrap();
+
+        let list = words.list().await.unwrap();
+        assert_eq!(1, list.len());
+        assert_eq!(Some("no foo"), list[0].why.as_deref());
+        assert_eq!("glob", list[0].kind);
+
+        let tester = words.tester().await;
+        let word = tester.test_message("foo", false).unwrap();
+        assert_eq!(WordKind::Glob, word.kind);
+        assert!(word.why.is_some());
+    }
+}
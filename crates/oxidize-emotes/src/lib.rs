@@ -816,6 +816,45 @@ impl Rendered {
     }
 }
 
+/// Count the number of Twitch emotes used in a message, based on its tags.
+///
+/// Every use of an emote is counted, so a message repeating the same emote
+/// three times counts as three emotes.
+pub fn count(tags: &irc::Tags) -> usize {
+    let emotes = match tags.emotes.as_deref() {
+        Some(emotes) => emotes,
+        None => return 0,
+    };
+
+    // 300354391:8-16,18-26/28087:0-6
+    emotes
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .map(|(_, spans)| spans.split(',').filter(|s| !s.is_empty()).count())
+        .sum()
+}
+
+/// Find the character positions of every Twitch emote used in a message,
+/// based on its tags.
+///
+/// Spans are inclusive, and index characters rather than bytes.
+pub fn spans(tags: &irc::Tags) -> Vec<(usize, usize)> {
+    let emotes = match tags.emotes.as_deref() {
+        Some(emotes) => emotes,
+        None => return Vec::new(),
+    };
+
+    emotes
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .flat_map(|(_, spans)| spans.split(','))
+        .filter_map(|span| {
+            let (s, e) = span.split_once('-')?;
+            Some((str::parse(s).ok()?, str::parse(e).ok()?))
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub(crate) struct Words<'a> {
     string: &'a str,
@@ -866,7 +905,38 @@ impl<'a> Iterator for Words<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::Words;
+    use common::irc::Tags;
+
+    use super::{count, spans, Words};
+
+    #[test]
+    pub(crate) fn test_count() {
+        let tags = |emotes: Option<&str>| Tags {
+            emotes: emotes.map(String::from),
+            ..Tags::default()
+        };
+
+        assert_eq!(0, count(&tags(None)));
+        assert_eq!(0, count(&tags(Some(""))));
+        assert_eq!(1, count(&tags(Some("28087:0-6"))));
+        assert_eq!(3, count(&tags(Some("300354391:8-16,18-26/28087:0-6"))));
+    }
+
+    #[test]
+    pub(crate) fn test_spans() {
+        let tags = |emotes: Option<&str>| Tags {
+            emotes: emotes.map(String::from),
+            ..Tags::default()
+        };
+
+        assert!(spans(&tags(None)).is_empty());
+        assert!(spans(&tags(Some(""))).is_empty());
+        assert!(spans(&tags(Some("28087:x-6"))).is_empty());
+        assert_eq!(
+            vec![(8, 16), (18, 26), (0, 6)],
+            spans(&tags(Some("300354391:8-16,18-26/28087:0-6")))
+        );
+    }
 
     #[test]
     pub(crate) fn test_words() {
@@ -1,11 +1,41 @@
 use std::future::Future;
 use std::sync::Arc;
 
-use anyhow::{bail, Error, Result};
+use anyhow::{bail, Context, Error, Result};
+use async_trait::async_trait;
 use common::models::spotify::device::Device;
-use common::models::SpotifyId;
+use common::models::track_id::FromStrError;
+use common::models::{PlayerKind, SpotifyId, Track, TrackId};
+use common::Uri;
 use thiserror::Error;
 use tokio::sync::mpsc;
+use url::Url;
+
+use crate::provider::TrackProvider;
+
+/// Parse a Spotify playlist or album from a URL or a URI.
+pub(super) fn parse_collection(s: &str) -> Option<Uri> {
+    if let Ok(url) = str::parse::<Url>(s) {
+        if url.host().is_some() {
+            if url.host_str() != Some("open.spotify.com") {
+                return None;
+            }
+
+            let parts = url.path().split('/').collect::<Vec<_>>();
+
+            return match parts.as_slice() {
+                ["", "playlist", id] => SpotifyId::from_base62(id).ok().map(Uri::SpotifyPlaylist),
+                ["", "album", id] => SpotifyId::from_base62(id).ok().map(Uri::SpotifyAlbum),
+                _ => None,
+            };
+        }
+    }
+
+    match str::parse::<Uri>(s) {
+        Ok(uri @ (Uri::SpotifyPlaylist(..) | Uri::SpotifyAlbum(..))) => Some(uri),
+        _ => None,
+    }
+}
 
 /// Setup a player.
 pub(super) async fn setup(
@@ -210,6 +240,129 @@ impl ConnectPlayer {
     }
 }
 
+/// Provides tracks from Spotify, played through Spotify Connect.
+pub(super) struct SpotifyProvider {
+    pub(super) spotify: Arc<api::Spotify>,
+    pub(super) player: ConnectPlayer,
+    /// The market of the streamer, which tracks are looked up in.
+    pub(super) market: tokio::sync::OnceCell<Option<String>>,
+}
+
+impl SpotifyProvider {
+    /// Get the market of the streamer, which is only looked up once.
+    async fn market(&self) -> Result<Option<&str>> {
+        let market = self
+            .market
+            .get_or_try_init(|| async {
+                let streamer = self.spotify.me().await?;
+                Ok::<_, anyhow::Error>(streamer.country)
+            })
+            .await?;
+
+        Ok(market.as_deref())
+    }
+}
+
+#[async_trait]
+impl TrackProvider for SpotifyProvider {
+    fn kind(&self) -> PlayerKind {
+        PlayerKind::Spotify
+    }
+
+    fn search_prefix(&self) -> &'static str {
+        "spotify"
+    }
+
+    fn handles(&self, track_id: &TrackId) -> bool {
+        matches!(track_id, TrackId::Spotify(..))
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<Result<TrackId, FromStrError>> {
+        if url.host_str() != Some("open.spotify.com") {
+            return None;
+        }
+
+        let parts = url.path().split('/').collect::<Vec<_>>();
+
+        Some(match parts.as_slice() {
+            ["", "track", id] => SpotifyId::from_base62(id)
+                .map(TrackId::Spotify)
+                .map_err(|_| FromStrError::BadBase62((*id).to_string())),
+            _ => Err(FromStrError::BadUrl(url.to_string())),
+        })
+    }
+
+    fn is_ready(&self) -> bool {
+        self.spotify.token().is_ready()
+    }
+
+    async fn lookup(&self, track_id: &TrackId) -> Result<(Track, std::time::Duration)> {
+        let TrackId::Spotify(id) = track_id else {
+            bail!("not a spotify track: {}", track_id);
+        };
+
+        let market = self.market().await?;
+        let track = self.spotify.track(id.to_base62(), market).await?;
+        let duration = std::time::Duration::from_millis(track.duration_ms.into());
+
+        Ok((
+            Track::Spotify {
+                track: Box::new(track),
+            },
+            duration,
+        ))
+    }
+
+    async fn search(&self, q: &str) -> Result<Option<TrackId>> {
+        let page = self.spotify.search_track(q, 1).await?;
+
+        let Some(id) = page.tracks.items.into_iter().flat_map(|t| t.id).next() else {
+            return Ok(None);
+        };
+
+        let track_id = SpotifyId::from_base62(id).context("Malformed id from search result")?;
+        Ok(Some(TrackId::Spotify(track_id)))
+    }
+
+    async fn play(
+        &self,
+        track_id: &TrackId,
+        elapsed: std::time::Duration,
+        _duration: std::time::Duration,
+    ) {
+        if let &TrackId::Spotify(id) = track_id {
+            self.player.play(Some(id), Some(elapsed)).await;
+        }
+    }
+
+    async fn pause(&self) {
+        self.player.pause().await;
+    }
+
+    async fn stop(&self) {
+        self.player.stop().await;
+    }
+
+    async fn volume(&self, modify: crate::ModifyVolume) -> u32 {
+        self.player.volume(modify).await
+    }
+
+    async fn current_volume(&self) -> u32 {
+        self.player.current_volume().await
+    }
+
+    async fn queue(&self, track_id: &TrackId) -> Result<(), crate::AddTrackError> {
+        let &TrackId::Spotify(id) = track_id else {
+            return Err(crate::AddTrackError::UnsupportedPlaybackMode);
+        };
+
+        self.player
+            .queue(id)
+            .await
+            .map_err(|e| crate::AddTrackError::Error(e.into()))
+    }
+}
+
 pub(super) struct ConnectStream {
     /// Receiver for configuration events.
     config_rx: mpsc::UnboundedReceiver<ConfigurationEvent>,
@@ -278,3 +431,38 @@ where
         common::log_warn!(e, "Failed to issue connect command");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::models::SpotifyId;
+    use common::Uri;
+
+    use super::parse_collection;
+
+    #[test]
+    fn test_parse_collection() {
+        let id = SpotifyId::from_base62("1ZTlxhxQ4FGJdUMBEd9pn0").unwrap();
+
+        assert_eq!(
+            parse_collection("https://open.spotify.com/playlist/1ZTlxhxQ4FGJdUMBEd9pn0?si=abc"),
+            Some(Uri::SpotifyPlaylist(id))
+        );
+        assert_eq!(
+            parse_collection("https://open.spotify.com/album/1ZTlxhxQ4FGJdUMBEd9pn0"),
+            Some(Uri::SpotifyAlbum(id))
+        );
+        assert_eq!(
+            parse_collection("spotify:album:1ZTlxhxQ4FGJdUMBEd9pn0"),
+            Some(Uri::SpotifyAlbum(id))
+        );
+        assert_eq!(
+            parse_collection("https://open.spotify.com/track/1ZTlxhxQ4FGJdUMBEd9pn0"),
+            None
+        );
+        assert_eq!(
+            parse_collection("spotify:track:1ZTlxhxQ4FGJdUMBEd9pn0"),
+            None
+        );
+        assert_eq!(parse_collection("queen we will rock you"), None);
+    }
+}
@@ -1,29 +1,38 @@
 mod connect;
+mod local;
 mod mixer;
 mod playback_future;
 mod player_internal;
+mod provider;
 mod youtube;
 
 use std::fmt;
 use std::pin::pin;
 use std::sync::Arc;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, Result};
 use async_fuse::Fuse;
 use async_injector::{Injector, Key};
+use common::display;
 use common::models::spotify::device::Device;
-use common::models::{Item, PlayerKind, Song, SpotifyId, Track, TrackId};
+use common::models::track_id::FromStrError;
+use common::models::{Item, PlayerKind, Song, Track, TrackId};
 use common::tags;
-use common::{display, PtDuration};
-use common::{Channel, Duration};
+use common::{Channel, Duration, Uri};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
-use self::connect::{ConnectDevice, ConnectPlayer, ConnectStream};
+use self::connect::{ConnectDevice, ConnectPlayer, ConnectStream, SpotifyProvider};
+pub use self::local::LocalLibrary;
+use self::local::LocalProvider;
 use self::mixer::Mixer;
 use self::playback_future::PlaybackFuture;
 use self::player_internal::{PlayerInitialize, PlayerInternal, PlayerState};
-use self::youtube::YouTubePlayer;
+use self::provider::Providers;
+use self::youtube::YouTubeProvider;
+
+/// The name of the queue snapshot which is saved before the queue is purged.
+pub const PURGE_SNAPSHOT: &str = "last-purge";
 
 /// Event used by player integrations.
 #[derive(Debug)]
@@ -81,75 +90,6 @@ impl ModifyVolume {
     }
 }
 
-/// Converts a track into an Item.
-///
-/// Returns `None` if the service required to convert the item is not
-/// authenticated.
-async fn convert_item(
-    spotify: &api::Spotify,
-    youtube: &api::YouTube,
-    user: Option<&str>,
-    track_id: &TrackId,
-    duration_override: Option<std::time::Duration>,
-    market: Option<&str>,
-) -> Result<Option<Item>> {
-    let (track, duration) = match track_id {
-        TrackId::Spotify(id) => {
-            if !spotify.token().is_ready() {
-                return Ok(None);
-            }
-
-            let track_id_string = id.to_base62();
-            let track = spotify.track(track_id_string, market).await?;
-            let duration = std::time::Duration::from_millis(track.duration_ms.into());
-
-            (
-                Track::Spotify {
-                    track: Box::new(track),
-                },
-                duration,
-            )
-        }
-        TrackId::YouTube(id) => {
-            if !youtube.token().is_ready() {
-                return Ok(None);
-            }
-
-            let video = youtube.videos_by_id(id, "contentDetails,snippet").await?;
-
-            let video = match video {
-                Some(video) => video,
-                None => bail!("no video found for id `{}`", id),
-            };
-
-            let content_details = video
-                .content_details
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("video does not have content details"))?;
-
-            let duration = str::parse::<PtDuration>(&content_details.duration)?;
-            (
-                Track::YouTube {
-                    video: Box::new(video),
-                },
-                duration.into_std(),
-            )
-        }
-    };
-
-    let duration = match duration_override {
-        Some(duration) => duration,
-        None => duration,
-    };
-
-    Ok(Some(Item::new(
-        track_id.clone(),
-        track,
-        user.map(|user| user.to_string()),
-        duration,
-    )))
-}
-
 /// Run the player.
 #[tracing::instrument(skip_all)]
 pub async fn setup(
@@ -158,6 +98,7 @@ pub async fn setup(
     db: db::Database,
     global_bus: bus::Bus<bus::Global>,
     youtube_bus: bus::Bus<bus::YouTube>,
+    local_bus: bus::Bus<bus::LocalAudio>,
     settings: settings::Settings<::auth::Scope>,
 ) -> Result<()> {
     let settings = settings.scoped("player");
@@ -176,6 +117,31 @@ pub async fn setup(
     let (youtube_player, youtube_future) =
         self::youtube::setup(youtube_bus, settings.scoped("youtube")).await?;
 
+    let (local_player, local, local_future) =
+        self::local::setup(local_bus, settings.scoped("local")).await?;
+
+    injector.update(local.clone()).await;
+
+    // NB: Spotify is registered first, since it's used for searches without a
+    // prefix.
+    let mut providers = Providers::default();
+
+    providers.register(SpotifyProvider {
+        spotify: spotify.clone(),
+        player: connect_player.clone(),
+        market: Default::default(),
+    });
+
+    providers.register(YouTubeProvider {
+        youtube,
+        player: youtube_player,
+    });
+
+    providers.register(LocalProvider {
+        library: local,
+        player: local_player,
+    });
+
     let bus = bus::Bus::new();
 
     let (song_update_interval_stream, song_update_interval) = settings
@@ -197,6 +163,7 @@ pub async fn setup(
     let song_switch_feedback = settings.var("song-switch-feedback", true).await?;
     let max_songs_per_user = settings.var("max-songs-per-user", 2).await?;
     let max_queue_length = settings.var("max-queue-length", 30).await?;
+    let fair_queue = settings.var("fair-queue", false).await?;
 
     let mixer = Mixer::new(db.clone());
 
@@ -214,9 +181,8 @@ pub async fn setup(
         closed: Mutex::new(None),
         injector: injector.clone(),
         spotify: spotify.clone(),
-        youtube: youtube.clone(),
+        providers,
         connect_player: connect_player.clone(),
-        youtube_player,
         mixer,
         bus,
         global_bus,
@@ -225,7 +191,10 @@ pub async fn setup(
         max_queue_length,
         max_songs_per_user,
         duplicate_duration,
+        fair_queue,
         themes: injector.var().await,
+        song_bans: injector.var().await,
+        queue_snapshots: injector.var().await,
     });
 
     let playback = PlaybackFuture {
@@ -240,12 +209,14 @@ pub async fn setup(
     let mut playback_future = pin!(playback.run(injector.clone(), settings));
 
     let youtube_future = pin!(youtube_future);
+    let local_future = pin!(local_future);
     let spotify_future = pin!(spotify_future);
     let playback_future = pin!(playback_future);
 
     common::local_join! {
         futures =>
         youtube_future,
+        local_future,
         spotify_future,
         playback_future,
     };
@@ -347,6 +318,24 @@ impl Player {
         Ok(promoted)
     }
 
+    /// Move a song requested by the given user forward in the queue by up to
+    /// the given number of positions, returning the position it was moved to.
+    pub async fn bump_song(
+        &self,
+        user: Option<&str>,
+        n: usize,
+        positions: usize,
+    ) -> Result<(usize, Arc<Item>), BumpError> {
+        let bumped = self.inner.mixer.bump_song(user, n, positions).await?;
+
+        self.inner
+            .modified(Source::Manual)
+            .await
+            .map_err(BumpError::Error)?;
+
+        Ok(bumped)
+    }
+
     /// Toggle playback.
     pub async fn toggle(&self) -> Result<()> {
         self.inner.toggle(Source::Manual).await?;
@@ -383,10 +372,8 @@ impl Player {
             .track_id()
             .clone();
 
-        match track_id {
-            TrackId::Spotify(..) => Some(self.inner.connect_player.current_volume().await),
-            TrackId::YouTube(..) => Some(self.inner.youtube_player.current_volume().await),
-        }
+        let provider = self.inner.providers.for_track(&track_id)?;
+        Some(provider.current_volume().await)
     }
 
     /// Update volume of the player.
@@ -398,10 +385,8 @@ impl Player {
             }
         };
 
-        Some(match track_id {
-            TrackId::Spotify(..) => self.inner.connect_player.volume(modify).await,
-            TrackId::YouTube(..) => self.inner.youtube_player.volume(modify).await,
-        })
+        let provider = self.inner.providers.for_track(&track_id)?;
+        Some(provider.volume(modify).await)
     }
 
     /// Close the player from more requests.
@@ -414,27 +399,50 @@ impl Player {
         *self.inner.closed.lock() = None;
     }
 
-    /// Search for a track.
-    pub async fn search_track(&self, q: &str) -> Result<Option<TrackId>> {
-        if let Some(q) = q.strip_prefix("youtube:") {
-            let results = self.inner.youtube.search(q).await?;
-            let result = results
-                .items
-                .into_iter()
-                .filter(|r| matches!(r.id.kind, common::models::youtube::Kind::Video));
-            let mut result = result.flat_map(|r| r.id.video_id);
-            return Ok(result.next().map(TrackId::YouTube));
-        }
+    /// Parse a track id from a URL or a URI.
+    pub fn parse_track(&self, s: &str) -> Result<TrackId, FromStrError> {
+        self.inner.providers.parse(s)
+    }
 
-        let q = q.strip_prefix("spotify:").unwrap_or(q);
-        let page = self.inner.spotify.search_track(q, 1).await?;
+    /// Parse a Spotify playlist or album from a URL or a URI.
+    pub fn parse_collection(&self, s: &str) -> Option<Uri> {
+        connect::parse_collection(s)
+    }
 
-        let Some(id) = page.tracks.items.into_iter().flat_map(|t| t.id).next() else {
+    /// Load the tracks of a Spotify playlist or album, together with a
+    /// description of it.
+    ///
+    /// At most `limit` tracks are loaded. Returns `None` if Spotify is not
+    /// authenticated.
+    pub async fn load_collection(
+        &self,
+        uri: &Uri,
+        limit: usize,
+    ) -> Result<Option<(String, Vec<Arc<Item>>)>> {
+        if !self.inner.spotify.token().is_ready() {
             return Ok(None);
-        };
+        }
+
+        Ok(Some(self.inner.load_collection(uri, Some(limit)).await?))
+    }
 
-        let track_id = SpotifyId::from_base62(id).context("Malformed id from search result")?;
-        Ok(Some(TrackId::Spotify(track_id)))
+    /// Search for a track.
+    ///
+    /// The query can be prefixed with the name of a provider, like
+    /// `youtube:<query>`, to search using that provider.
+    pub async fn search_track(&self, q: &str) -> Result<Option<TrackId>> {
+        self.inner.providers.search(q).await
+    }
+
+    /// Look up the metadata of the given track.
+    ///
+    /// Returns `None` if the service required to look up the track is not
+    /// authenticated.
+    pub async fn lookup_track(&self, track_id: &TrackId) -> Result<Option<Item>> {
+        self.inner
+            .providers
+            .convert_item(None, track_id, None)
+            .await
     }
 
     /// Play a theme track.
@@ -451,16 +459,12 @@ impl Player {
 
         let duration = theme.end.clone().map(|o| o.as_duration());
 
-        let item = convert_item(
-            &self.inner.spotify,
-            &self.inner.youtube,
-            None,
-            &theme.track_id,
-            duration,
-            None,
-        )
-        .await
-        .map_err(PlayThemeError::Error)?;
+        let item = self
+            .inner
+            .providers
+            .convert_item(None, &theme.track_id, duration)
+            .await
+            .map_err(PlayThemeError::Error)?;
 
         let item = match item {
             Some(item) => item,
@@ -479,6 +483,9 @@ impl Player {
 
     /// Add the given track to the queue.
     ///
+    /// The weight determines how many songs the requester gets to play per
+    /// turn when fair queueing is enabled, where `1` is the default.
+    ///
     /// Returns the item added.
     pub async fn add_track(
         &self,
@@ -486,13 +493,26 @@ impl Player {
         track_id: TrackId,
         bypass_constraints: bool,
         max_duration: Option<Duration>,
+        weight: u32,
     ) -> Result<(Option<usize>, Arc<Item>), AddTrackError> {
         self.inner
-            .add_track(user, track_id, bypass_constraints, max_duration)
+            .add_track(user, track_id, bypass_constraints, max_duration, weight)
             .await
     }
 
+    /// Purge the queue.
+    ///
+    /// A snapshot named [PURGE_SNAPSHOT] of the queue is saved before it's
+    /// purged if queue snapshots are available.
     pub async fn purge(&self) -> Result<Vec<Arc<Item>>> {
+        if let Some(queue_snapshots) = self.inner.queue_snapshots.load().await {
+            let songs = self.snapshot_songs().await;
+
+            if !songs.is_empty() {
+                queue_snapshots.save(PURGE_SNAPSHOT, &songs).await?;
+            }
+        }
+
         let purged = self.inner.mixer.purge().await?;
 
         if !purged.is_empty() {
@@ -535,6 +555,89 @@ impl Player {
         Ok(removed)
     }
 
+    /// Restore the songs removed by the last destructive queue operation,
+    /// which is either a purge or the removal of a single song.
+    ///
+    /// Returns the restored items.
+    pub async fn undo(&self) -> Result<Vec<Arc<Item>>> {
+        let fair = self.inner.fair_queue.load().await;
+        let restored = self.inner.mixer.undo(fair).await?;
+
+        if !restored.is_empty() {
+            self.inner.modified(Source::Manual).await?;
+        }
+
+        Ok(restored)
+    }
+
+    /// Save the current queue as a snapshot with the given name, replacing
+    /// any existing snapshot with the same name.
+    ///
+    /// Returns the number of songs saved.
+    pub async fn save_snapshot(&self, name: &str) -> Result<usize> {
+        let queue_snapshots = self
+            .inner
+            .queue_snapshots
+            .load()
+            .await
+            .ok_or_else(|| anyhow!("queue snapshots not configured"))?;
+
+        let songs = self.snapshot_songs().await;
+        queue_snapshots.save(name, &songs).await?;
+        Ok(songs.len())
+    }
+
+    /// Add the songs in the snapshot with the given name to the queue.
+    ///
+    /// Songs are requested by whoever originally requested them, or `user` if
+    /// that isn't known. Songs which can't be added, like songs which are
+    /// already queued or have been banned since the snapshot was saved, are
+    /// skipped.
+    ///
+    /// Returns the number of songs added and skipped, or `None` if there is
+    /// no snapshot with the given name.
+    pub async fn load_snapshot(&self, name: &str, user: &str) -> Result<Option<(usize, usize)>> {
+        let queue_snapshots = self
+            .inner
+            .queue_snapshots
+            .load()
+            .await
+            .ok_or_else(|| anyhow!("queue snapshots not configured"))?;
+
+        let Some(songs) = queue_snapshots.get(name).await? else {
+            return Ok(None);
+        };
+
+        let mut added = 0;
+        let mut skipped = 0;
+
+        for song in songs {
+            let user = song.user.as_deref().unwrap_or(user);
+
+            match self.add_track(user, song.track_id, true, None, 1).await {
+                Ok(..) => added += 1,
+                Err(AddTrackError::Error(e)) => {
+                    common::log_warn!(e, "Failed to add song from snapshot");
+                    skipped += 1;
+                }
+                Err(..) => skipped += 1,
+            }
+        }
+
+        Ok(Some((added, skipped)))
+    }
+
+    /// Collect the songs in the queue to store in a snapshot.
+    async fn snapshot_songs(&self) -> Vec<(TrackId, Option<String>)> {
+        self.inner
+            .mixer
+            .queue()
+            .await
+            .iter()
+            .map(|item| (item.track_id().clone(), item.user().cloned()))
+            .collect()
+    }
+
     /// Find the next item that matches the given predicate and how long until it plays.
     pub async fn find(
         &self,
@@ -601,6 +704,29 @@ pub enum PlayThemeError {
     Error(anyhow::Error),
 }
 
+/// Error raised when trying to bump a song.
+pub enum BumpError {
+    /// No song at the given position.
+    NoSuchSong,
+    /// Song was not requested by the given user.
+    NotRequester,
+    /// Song can't be moved any further forward.
+    CannotMove,
+    /// Other generic error happened.
+    Error(anyhow::Error),
+}
+
+impl fmt::Display for BumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BumpError::NoSuchSong => write!(f, "No such song to bump"),
+            BumpError::NotRequester => write!(f, "You can only bump your own songs"),
+            BumpError::CannotMove => write!(f, "That song can't be bumped any further"),
+            BumpError::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
 /// Error raised when trying to add track.
 pub enum AddTrackError {
     /// Queue is full.
@@ -623,6 +749,8 @@ pub enum AddTrackError {
     UnsupportedPlaybackMode,
     /// Song cannot be played in the streamer's region
     NotPlayable,
+    /// Song matches a ban.
+    Banned(db::SongBan),
     /// Other generic error happened.
     Error(anyhow::Error),
 }
@@ -704,6 +832,27 @@ impl fmt::Display for AddTrackError {
             AddTrackError::NotPlayable => {
                 write!(f, "This song is not available in the streamer's region :(")
             }
+            AddTrackError::Banned(ban) => {
+                match str::parse::<db::SongBanKind>(&ban.kind) {
+                    Ok(db::SongBanKind::Artist) => {
+                        write!(f, "Songs by {} are banned", ban.pattern)?;
+                    }
+                    Ok(db::SongBanKind::Channel) => {
+                        write!(f, "Videos from {} are banned", ban.pattern)?;
+                    }
+                    Ok(db::SongBanKind::Keyword) => {
+                        write!(f, "Songs matching \"{}\" are banned", ban.pattern)?;
+                    }
+                    _ => {
+                        write!(f, "That song is banned")?;
+                    }
+                }
+
+                match &ban.reason {
+                    Some(reason) => write!(f, ": {}", reason),
+                    None => write!(f, " :("),
+                }
+            }
             AddTrackError::Error(e) => {
                 write!(f, "{}", e)
             }
@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use common::models::local::LocalTrack;
+use common::models::{PlayerKind, Track, TrackId};
+use lofty::prelude::*;
+use parking_lot::RwLock;
+
+use crate::provider::TrackProvider;
+
+/// File extensions which are indexed as audio files.
+const EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "opus", "wav", "m4a"];
+
+/// Setup a player.
+pub(super) async fn setup(
+    bus: bus::Bus<bus::LocalAudio>,
+    settings: settings::Settings<::auth::Scope>,
+) -> Result<(LocalPlayer, LocalLibrary, impl Future<Output = Result<()>>)> {
+    tracing::trace!("Setting up local audio player");
+
+    let (mut path_stream, path) = settings.stream::<String>("path").optional().await?;
+    let (mut volume_scale_stream, mut volume_scale) =
+        settings.stream("volume-scale").or_with(100).await?;
+    let (mut volume_stream, volume) = settings.stream("volume").or_with(50).await?;
+    let mut scaled_volume = (volume * volume_scale) / 100u32;
+    let volume = settings::Var::new(volume);
+
+    let library = LocalLibrary::default();
+
+    // NB: index eagerly, so that queued local tracks can be loaded when the
+    // player is initialized.
+    if let Err(e) = library.reindex(path.map(PathBuf::from)).await {
+        common::log_error!(e, "Failed to index local music library");
+    }
+
+    let player = LocalPlayer {
+        bus,
+        settings,
+        volume: volume.clone(),
+    };
+
+    let returned_player = player.clone();
+    let returned_library = library.clone();
+
+    let future = async move {
+        player.volume_update(scaled_volume).await;
+
+        loop {
+            tokio::select! {
+                path = path_stream.recv() => {
+                    if let Err(e) = library.reindex(path.map(PathBuf::from)).await {
+                        common::log_error!(e, "Failed to index local music library");
+                    }
+                }
+                update = volume_scale_stream.recv() => {
+                    volume_scale = update;
+                    scaled_volume = (volume.load().await * volume_scale) / 100u32;
+                    player.volume_update(scaled_volume).await;
+                }
+                update = volume_stream.recv() => {
+                    *volume.write().await = update;
+                    scaled_volume = (volume.load().await * volume_scale) / 100u32;
+                    player.volume_update(scaled_volume).await;
+                }
+            }
+        }
+    };
+
+    Ok((returned_player, returned_library, future))
+}
+
+#[derive(Default)]
+struct Index {
+    root: Option<PathBuf>,
+    tracks: BTreeMap<String, LocalTrack>,
+}
+
+/// An indexed library of audio files in a local music directory.
+#[derive(Clone, Default)]
+pub struct LocalLibrary {
+    index: Arc<RwLock<Index>>,
+}
+
+impl LocalLibrary {
+    /// Get the indexed track with the given path.
+    pub fn get(&self, path: &str) -> Option<LocalTrack> {
+        self.index.read().tracks.get(path).cloned()
+    }
+
+    /// Resolve the file system path of an indexed track.
+    ///
+    /// Only tracks which are part of the index can be resolved, so this never
+    /// points outside of the music directory.
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let index = self.index.read();
+        let root = index.root.as_ref()?;
+
+        if !index.tracks.contains_key(path) {
+            return None;
+        }
+
+        Some(path.split('/').fold(root.clone(), |p, c| p.join(c)))
+    }
+
+    /// Get the number of indexed tracks.
+    pub fn len(&self) -> usize {
+        self.index.read().tracks.len()
+    }
+
+    /// Test if the library is empty.
+    pub fn is_empty(&self) -> bool {
+        self.index.read().tracks.is_empty()
+    }
+
+    /// Search the library for the track which best matches the given query.
+    ///
+    /// Every term in the query has to match either the title, artist, album,
+    /// or path of the track. Among matching tracks, the one with the shortest
+    /// description wins since it's the most specific match.
+    pub fn search(&self, q: &str) -> Option<LocalTrack> {
+        let terms = q
+            .split_whitespace()
+            .map(|t| t.to_lowercase())
+            .collect::<Vec<_>>();
+
+        if terms.is_empty() {
+            return None;
+        }
+
+        let index = self.index.read();
+
+        index
+            .tracks
+            .values()
+            .filter_map(|track| {
+                let haystack = [
+                    Some(track.title()),
+                    track.artist.as_deref(),
+                    track.album.as_deref(),
+                    Some(track.path.as_str()),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+
+                if terms.iter().all(|t| haystack.contains(t.as_str())) {
+                    Some((haystack.len(), track))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|(len, _)| *len)
+            .map(|(_, track)| track.clone())
+    }
+
+    /// Rebuild the index from the given music directory, or clear it if
+    /// `None`.
+    async fn reindex(&self, root: Option<PathBuf>) -> Result<()> {
+        let tracks = match &root {
+            Some(root) => {
+                let root = root.clone();
+                tokio::task::spawn_blocking(move || index(&root)).await??
+            }
+            None => BTreeMap::new(),
+        };
+
+        tracing::info!("Indexed {} tracks in local music library", tracks.len());
+        *self.index.write() = Index { root, tracks };
+        Ok(())
+    }
+}
+
+/// Walk the given directory and index all audio files in it.
+fn index(root: &Path) -> Result<BTreeMap<String, LocalTrack>> {
+    let mut tracks = BTreeMap::new();
+    let mut queue = vec![root.to_path_buf()];
+
+    while let Some(dir) = queue.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .with_context(|| anyhow::anyhow!("failed to read directory: {}", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                queue.push(path);
+                continue;
+            }
+
+            let is_audio = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| EXTENSIONS.iter().any(|x| x.eq_ignore_ascii_case(e)))
+                .unwrap_or_default();
+
+            if !is_audio {
+                continue;
+            }
+
+            match read_track(root, &path) {
+                Ok(track) => {
+                    tracks.insert(track.path.clone(), track);
+                }
+                Err(e) => {
+                    common::log_warn!(e, "Failed to read audio file: {}", path.display());
+                }
+            }
+        }
+    }
+
+    Ok(tracks)
+}
+
+/// Read metadata for a single audio file.
+fn read_track(root: &Path, path: &Path) -> Result<LocalTrack> {
+    let relative = path
+        .strip_prefix(root)?
+        .components()
+        .map(|c| c.as_os_str().to_str().context("path is not valid UTF-8"))
+        .collect::<Result<Vec<_>>>()?
+        .join("/");
+
+    let file = lofty::read_from_path(path)?;
+    let tag = file.primary_tag().or_else(|| file.first_tag());
+
+    Ok(LocalTrack {
+        path: relative,
+        title: tag.and_then(|t| t.title()).map(|s| s.into_owned()),
+        artist: tag.and_then(|t| t.artist()).map(|s| s.into_owned()),
+        album: tag.and_then(|t| t.album()).map(|s| s.into_owned()),
+        duration: file.properties().duration(),
+    })
+}
+
+#[derive(Clone)]
+pub(super) struct LocalPlayer {
+    bus: bus::Bus<bus::LocalAudio>,
+    settings: settings::Settings<::auth::Scope>,
+    volume: settings::Var<u32>,
+}
+
+impl LocalPlayer {
+    /// Update playback information.
+    pub(super) async fn tick(&self, elapsed: Duration, duration: Duration, path: String) {
+        self.play(elapsed, duration, path).await;
+    }
+
+    pub(super) async fn play(&self, elapsed: Duration, duration: Duration, path: String) {
+        let event = bus::LocalAudioEvent::Play {
+            path,
+            elapsed: elapsed.as_secs(),
+            duration: duration.as_secs(),
+        };
+
+        self.bus.send(bus::LocalAudio::LocalCurrent { event }).await;
+    }
+
+    pub(super) async fn pause(&self) {
+        let event = bus::LocalAudioEvent::Pause;
+        self.bus.send(bus::LocalAudio::LocalCurrent { event }).await;
+    }
+
+    pub(super) async fn stop(&self) {
+        let event = bus::LocalAudioEvent::Stop;
+        self.bus.send(bus::LocalAudio::LocalCurrent { event }).await;
+    }
+
+    pub(super) async fn volume(&self, modify: crate::ModifyVolume) -> u32 {
+        let mut volume = self.volume.write().await;
+        let update = modify.apply(*volume);
+        *volume = update;
+        let result = self.settings.set("volume", update).await;
+
+        if let Err(e) = result {
+            common::log_error!(e, "Failed to store updated volume in settings");
+        }
+
+        update
+    }
+
+    pub(super) async fn current_volume(&self) -> u32 {
+        self.volume.load().await
+    }
+
+    async fn volume_update(&self, volume: u32) {
+        self.bus.send(bus::LocalAudio::LocalVolume { volume }).await;
+    }
+}
+
+/// Provides tracks from the local music library, played through the local
+/// audio overlay.
+pub(super) struct LocalProvider {
+    pub(super) library: LocalLibrary,
+    pub(super) player: LocalPlayer,
+}
+
+#[async_trait]
+impl TrackProvider for LocalProvider {
+    fn kind(&self) -> PlayerKind {
+        PlayerKind::Local
+    }
+
+    fn search_prefix(&self) -> &'static str {
+        "local"
+    }
+
+    fn handles(&self, track_id: &TrackId) -> bool {
+        matches!(track_id, TrackId::Local(..))
+    }
+
+    async fn lookup(&self, track_id: &TrackId) -> Result<(Track, Duration)> {
+        let TrackId::Local(path) = track_id else {
+            bail!("not a local track: {}", track_id);
+        };
+
+        let Some(track) = self.library.get(path) else {
+            bail!("no track `{}` in local music library", path);
+        };
+
+        let duration = track.duration;
+
+        Ok((
+            Track::Local {
+                track: Box::new(track),
+            },
+            duration,
+        ))
+    }
+
+    async fn search(&self, q: &str) -> Result<Option<TrackId>> {
+        Ok(self.library.search(q).map(|t| TrackId::Local(t.path)))
+    }
+
+    async fn play(&self, track_id: &TrackId, elapsed: Duration, duration: Duration) {
+        if let TrackId::Local(path) = track_id {
+            self.player.play(elapsed, duration, path.clone()).await;
+        }
+    }
+
+    async fn tick(&self, track_id: &TrackId, elapsed: Duration, duration: Duration) {
+        if let TrackId::Local(path) = track_id {
+            self.player.tick(elapsed, duration, path.clone()).await;
+        }
+    }
+
+    async fn pause(&self) {
+        self.player.pause().await;
+    }
+
+    async fn stop(&self) {
+        self.player.stop().await;
+    }
+
+    async fn volume(&self, modify: crate::ModifyVolume) -> u32 {
+        self.player.volume(modify).await
+    }
+
+    async fn current_volume(&self) -> u32 {
+        self.player.current_volume().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use common::models::local::LocalTrack;
+    use parking_lot::RwLock;
+
+    use super::{Index, LocalLibrary};
+
+    /// Write a second of silence as an 8-bit mono WAV file.
+    fn write_wav(path: &Path) {
+        let data = vec![0x80u8; 8000];
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&8u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+
+        std::fs::write(path, wav).unwrap();
+    }
+
+    fn track(path: &str, title: Option<&str>, artist: Option<&str>) -> LocalTrack {
+        LocalTrack {
+            path: path.to_string(),
+            title: title.map(String::from),
+            artist: artist.map(String::from),
+            album: None,
+            duration: Duration::from_secs(60),
+        }
+    }
+
+    fn library(root: &str, tracks: Vec<LocalTrack>) -> LocalLibrary {
+        let tracks = tracks
+            .into_iter()
+            .map(|t| (t.path.clone(), t))
+            .collect::<BTreeMap<_, _>>();
+
+        LocalLibrary {
+            index: Arc::new(RwLock::new(Index {
+                root: Some(PathBuf::from(root)),
+                tracks,
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex() {
+        let root = std::env::temp_dir().join(format!("oxidize-local-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("rock")).unwrap();
+
+        write_wav(&root.join("rock").join("one.wav"));
+        write_wav(&root.join("two.WAV"));
+        // not audio files, or not readable ones, are skipped.
+        std::fs::write(root.join("notes.txt"), "hello").unwrap();
+        std::fs::write(root.join("broken.mp3"), "not an mp3").unwrap();
+
+        let library = LocalLibrary::default();
+        library.reindex(Some(root.clone())).await.unwrap();
+
+        assert_eq!(library.len(), 2);
+
+        let one = library.get("rock/one.wav").expect("indexed track");
+        assert_eq!(one.title(), "one");
+        assert_eq!(one.duration.as_secs(), 1);
+        assert!(library.get("two.WAV").is_some());
+        assert!(library.get("notes.txt").is_none());
+        assert!(library.get("broken.mp3").is_none());
+
+        library.reindex(None).await.unwrap();
+        assert!(library.is_empty());
+        assert!(library.resolve("rock/one.wav").is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_resolve() {
+        let library = library("/music", vec![track("rock/one.mp3", None, None)]);
+
+        assert_eq!(
+            library.resolve("rock/one.mp3"),
+            Some(Path::new("/music").join("rock").join("one.mp3"))
+        );
+        // only indexed tracks resolve, so paths can't escape the library.
+        assert_eq!(library.resolve("../etc/passwd"), None);
+        assert_eq!(library.resolve("rock/two.mp3"), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let library = library(
+            "/music",
+            vec![
+                track("rock/one.mp3", Some("Hello"), Some("Band")),
+                track("rock/hello-again.mp3", Some("Hello Again"), Some("Band")),
+                track("jazz/three.mp3", None, Some("Trio")),
+            ],
+        );
+
+        let path = |q: &str| library.search(q).map(|t| t.path);
+
+        // the shortest, most specific match wins.
+        assert_eq!(path("hello").as_deref(), Some("rock/one.mp3"));
+        assert_eq!(path("HELLO again").as_deref(), Some("rock/hello-again.mp3"));
+        // terms can match across title, artist, and path.
+        assert_eq!(path("trio jazz").as_deref(), Some("jazz/three.mp3"));
+        assert_eq!(path("three").as_deref(), Some("jazz/three.mp3"));
+        assert_eq!(path("hello trio"), None);
+        assert_eq!(path("   "), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -10,6 +10,8 @@ use common::Duration;
 use tokio::sync::Mutex;
 use tokio::sync::MutexGuard;
 
+use crate::{BumpError, Providers};
+
 #[derive(Default)]
 struct Fallback {
     /// Currently loaded fallback items.
@@ -18,6 +20,174 @@ struct Fallback {
     queue: VecDeque<Arc<Item>>,
 }
 
+/// The amount of turns a request with a weight of one takes up when fair
+/// queueing is enabled.
+///
+/// Requests with a higher weight take up a fraction of a turn, allowing the
+/// requester to have more songs played per turn.
+const TURN: i64 = 1000;
+
+/// An item removed from the queue which can be restored with an undo.
+struct Removed {
+    /// The position the item was removed from.
+    position: usize,
+    /// The removed item.
+    item: Arc<Item>,
+    /// The turn the item had in the queue.
+    turn: i64,
+    /// The weight the item had in the queue.
+    weight: u32,
+    /// If the item had been promoted.
+    promoted: bool,
+    /// The turn the item had before it was bumped, if it was bumped.
+    bumped: Option<i64>,
+}
+
+/// Bookkeeping used to order the queue fairly between requesters.
+#[derive(Default)]
+struct Fair {
+    /// The turn and weight of each queued track.
+    turns: HashMap<TrackId, (i64, u32)>,
+    /// The next turn of each requester.
+    next: HashMap<String, i64>,
+    /// The turn of the most recently played song.
+    current: i64,
+    /// The number of promoted items at the front of the queue, which are not
+    /// subject to fair ordering.
+    promoted: usize,
+    /// The turn each bumped track had before it was bumped.
+    bumped: HashMap<TrackId, i64>,
+}
+
+impl Fair {
+    /// Get the turn of a new request by the given user.
+    fn next_turn(&self, user: Option<&str>) -> i64 {
+        match user.and_then(|user| self.next.get(user)) {
+            Some(next) => (*next).max(self.current),
+            None => self.current,
+        }
+    }
+
+    /// Get the turn of the given queued item.
+    fn turn(&self, item: &Item) -> i64 {
+        self.turns
+            .get(item.track_id())
+            .map(|(turn, _)| *turn)
+            .unwrap_or(self.current)
+    }
+
+    /// Record that the given item has been queued.
+    fn insert(&mut self, item: &Item, turn: i64, weight: u32) {
+        self.turns.insert(item.track_id().clone(), (turn, weight));
+
+        if let Some(user) = item.user() {
+            let next = turn + TURN / i64::from(weight.max(1));
+            let entry = self.next.entry(user.clone()).or_insert(next);
+            *entry = (*entry).max(next);
+        }
+    }
+
+    /// Capture what is needed to restore the item at the given position if
+    /// it is removed.
+    fn removed(&self, position: usize, item: &Arc<Item>) -> Removed {
+        let (turn, weight) = self
+            .turns
+            .get(item.track_id())
+            .copied()
+            .unwrap_or((self.current, 1));
+
+        Removed {
+            position,
+            item: item.clone(),
+            turn,
+            weight,
+            promoted: position < self.promoted,
+            bumped: self.bumped.get(item.track_id()).copied(),
+        }
+    }
+
+    /// Record that the item at the given position has been removed from the
+    /// queue, where `queue` is what remains in it.
+    fn remove(&mut self, queue: &VecDeque<Arc<Item>>, position: usize, item: &Item) {
+        if position < self.promoted {
+            self.promoted -= 1;
+        }
+
+        self.turns.remove(item.track_id());
+        self.bumped.remove(item.track_id());
+
+        let Some(user) = item.user() else {
+            return;
+        };
+
+        // NB: give back the turn which was taken up by the removed item.
+        let next = queue
+            .iter()
+            .filter(|i| i.user() == Some(user))
+            .filter_map(|i| self.turns.get(i.track_id()))
+            .map(|(turn, weight)| turn + TURN / i64::from((*weight).max(1)))
+            .max();
+
+        match next {
+            Some(next) => {
+                self.next.insert(user.clone(), next);
+            }
+            None => {
+                self.next.remove(user);
+            }
+        }
+    }
+
+    /// Record that the given item is being played.
+    fn play(&mut self, item: &Item) {
+        if self.promoted > 0 {
+            self.promoted -= 1;
+        }
+
+        if let Some((turn, _)) = self.turns.remove(item.track_id()) {
+            self.current = self.current.max(turn);
+        }
+
+        self.bumped.remove(item.track_id());
+
+        let current = self.current;
+        self.next.retain(|_, next| *next > current);
+    }
+
+    /// Stably sort the items which haven't been promoted by turn.
+    fn sort(&self, queue: &mut VecDeque<Arc<Item>>) {
+        let promoted = self.promoted.min(queue.len());
+        queue.make_contiguous()[promoted..].sort_by_key(|item| self.turn(item));
+    }
+
+    /// Find the position in a sorted queue at which an item with the given
+    /// turn should be inserted.
+    fn position(&self, queue: &VecDeque<Arc<Item>>, turn: i64) -> usize {
+        let promoted = self.promoted.min(queue.len());
+
+        queue
+            .iter()
+            .skip(promoted)
+            .position(|item| self.turn(item) > turn)
+            .map(|n| n + promoted)
+            .unwrap_or(queue.len())
+    }
+
+    /// Update the turn of the given queued item.
+    fn set_turn(&mut self, item: &Item, turn: i64) {
+        if let Some(entry) = self.turns.get_mut(item.track_id()) {
+            entry.0 = turn;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.turns.clear();
+        self.next.clear();
+        self.promoted = 0;
+        self.bumped.clear();
+    }
+}
+
 /// Mixer decides what song to play next.
 pub(super) struct Mixer {
     /// Database access.
@@ -28,6 +198,10 @@ pub(super) struct Mixer {
     sidelined: parking_lot::Mutex<VecDeque<Song>>,
     /// Fallback queue.
     fallback: Mutex<Fallback>,
+    /// Fair queueing bookkeeping.
+    fair: parking_lot::Mutex<Fair>,
+    /// Items removed by the last destructive queue operation.
+    undo: parking_lot::Mutex<Vec<Removed>>,
     /// Keeping track of queue length.
     len: AtomicUsize,
 }
@@ -43,44 +217,55 @@ impl Mixer {
             queue: Mutex::default(),
             sidelined: parking_lot::Mutex::default(),
             fallback: Mutex::default(),
+            fair: parking_lot::Mutex::default(),
+            undo: parking_lot::Mutex::default(),
             len: AtomicUsize::new(0),
         }
     }
 
     /// Initialize the queue from the database.
+    ///
+    /// If `fair` is set, songs which haven't been promoted are ordered by the
+    /// turn they were assigned when they were requested.
     #[tracing::instrument(skip_all)]
-    pub(super) async fn initialize_queue(
-        &self,
-        spotify: &api::Spotify,
-        youtube: &api::YouTube,
-    ) -> Result<()> {
-        // TODO: cache this value
-        let streamer = spotify.me().await?;
-        let market = streamer.country.as_deref();
+    pub(super) async fn initialize_queue(&self, providers: &Providers, fair: bool) -> Result<()> {
         let mut queue = self.queue.lock().await;
+        let mut state = Fair::default();
+        let mut current = None::<i64>;
 
         // Add tracks from database.
         for song in self.db.player_list().await? {
-            let item = crate::convert_item(
-                spotify,
-                youtube,
-                song.user.as_deref(),
-                &song.track_id,
-                None,
-                market,
-            )
-            .await;
+            let item = providers
+                .convert_item(song.user.as_deref(), &song.track_id, None)
+                .await;
 
             match item {
-                Ok(item) => {
-                    queue.extend(item.map(Arc::new));
+                Ok(Some(item)) => {
+                    let weight = u32::try_from(song.weight).unwrap_or(1);
+
+                    if song.promoted_at.is_some() {
+                        state.promoted += 1;
+                    } else {
+                        current = Some(current.map_or(song.turn, |c| c.min(song.turn)));
+                    }
+
+                    state.insert(&item, song.turn, weight);
+                    queue.push_back(Arc::new(item));
                 }
+                Ok(None) => {}
                 Err(error) => {
                     common::log_warn!(error, "Failed to convert database item");
                 }
             }
         }
 
+        state.current = current.unwrap_or_default();
+
+        if fair {
+            state.sort(&mut queue);
+        }
+
+        *self.fair.lock() = state;
         self.len.store(queue.len(), Ordering::SeqCst);
         Ok(())
     }
@@ -94,24 +279,68 @@ impl Mixer {
         self.queue.lock().await
     }
 
-    /// Push item to back of queue.
-    pub(super) async fn push_back(&self, item: Arc<Item>) -> Result<()> {
+    /// Add an item to the queue with the given weight, returning the position
+    /// it was added at.
+    ///
+    /// If `fair` is set, the item is placed so that every requester gets a
+    /// turn before anyone gets another one. Otherwise it's added to the back.
+    pub(super) async fn push_back(
+        &self,
+        item: Arc<Item>,
+        weight: u32,
+        fair: bool,
+    ) -> Result<usize> {
+        let mut queue = self.queue.lock().await;
+        let turn = self.fair.lock().next_turn(item.user().map(String::as_str));
+
+        let added_at = Utc::now().naive_utc();
+
         self.db
             .player_push_back(&db::models::AddSong {
                 track_id: item.track_id().clone(),
-                added_at: Utc::now().naive_utc(),
+                added_at,
                 user: item.user().cloned(),
+                weight: i32::try_from(weight).unwrap_or(i32::MAX),
+                turn,
+                queued_at: added_at,
             })
             .await?;
 
-        self.queue.lock().await.push_back(item);
+        let position = {
+            let mut state = self.fair.lock();
+            state.insert(&item, turn, weight);
+
+            if fair {
+                state.sort(&mut queue);
+                state.position(&queue, turn)
+            } else {
+                queue.len()
+            }
+        };
+
+        queue.insert(position, item);
         self.len.fetch_add(1, Ordering::SeqCst);
-        Ok(())
+        Ok(position)
     }
 
     /// Purge the song queue.
     pub(super) async fn purge(&self) -> Result<Vec<Arc<Item>>> {
         let purged = self.queue.lock().await.drain(..).collect::<Vec<_>>();
+
+        {
+            let mut state = self.fair.lock();
+
+            if !purged.is_empty() {
+                *self.undo.lock() = purged
+                    .iter()
+                    .enumerate()
+                    .map(|(position, item)| state.removed(position, item))
+                    .collect();
+            }
+
+            state.clear();
+        }
+
         self.len.store(0, Ordering::SeqCst);
 
         if !purged.is_empty() {
@@ -130,7 +359,15 @@ impl Mixer {
                 return Ok(None);
             }
 
-            queue.remove(n)
+            let removed = queue.remove(n);
+
+            if let Some(item) = &removed {
+                let mut state = self.fair.lock();
+                *self.undo.lock() = vec![state.removed(n, item)];
+                state.remove(&queue, n, item);
+            }
+
+            removed
         };
 
         if let Some(item) = next {
@@ -151,7 +388,16 @@ impl Mixer {
                 return Ok(None);
             }
 
-            queue.pop_back()
+            let removed = queue.pop_back();
+
+            if let Some(item) = &removed {
+                let position = queue.len();
+                let mut state = self.fair.lock();
+                *self.undo.lock() = vec![state.removed(position, item)];
+                state.remove(&queue, position, item);
+            }
+
+            removed
         };
 
         if let Some(item) = next {
@@ -176,7 +422,15 @@ impl Mixer {
                 .iter()
                 .rposition(|i| i.user().map(|u| u == user).unwrap_or_default())
             {
-                queue.remove(position)
+                let removed = queue.remove(position);
+
+                if let Some(item) = &removed {
+                    let mut state = self.fair.lock();
+                    *self.undo.lock() = vec![state.removed(position, item)];
+                    state.remove(&queue, position, item);
+                }
+
+                removed
             } else {
                 None
             }
@@ -191,6 +445,66 @@ impl Mixer {
         Ok(None)
     }
 
+    /// Restore the items removed by the last destructive queue operation,
+    /// returning the items that were restored.
+    ///
+    /// Items are put back where they were removed from, unless `fair` is set
+    /// in which case items which haven't been promoted are ordered by turn.
+    /// Items which have been queued again since are skipped.
+    ///
+    /// Bumps are refunded when songs are removed, so bumped items lose their
+    /// bump: they get back the turn they had before they were bumped and are
+    /// otherwise put at the back of the queue.
+    pub(super) async fn undo(&self, fair: bool) -> Result<Vec<Arc<Item>>> {
+        let restored = {
+            let mut queue = self.queue.lock().await;
+            let mut removed = std::mem::take(&mut *self.undo.lock());
+            removed.sort_by_key(|r| r.position);
+
+            let mut state = self.fair.lock();
+            let mut restored = Vec::new();
+
+            for r in removed {
+                if queue.iter().any(|i| i.track_id() == r.item.track_id()) {
+                    continue;
+                }
+
+                let position = if r.promoted {
+                    let position = r.position.min(state.promoted).min(queue.len());
+                    state.promoted += 1;
+                    position
+                } else if r.bumped.is_some() {
+                    queue.len()
+                } else {
+                    r.position
+                        .clamp(state.promoted.min(queue.len()), queue.len())
+                };
+
+                let turn = r.bumped.unwrap_or(r.turn);
+                state.insert(&r.item, turn, r.weight);
+                queue.insert(position, r.item.clone());
+                restored.push((r.item, r.bumped));
+            }
+
+            if fair {
+                state.sort(&mut queue);
+            }
+
+            self.len.store(queue.len(), Ordering::SeqCst);
+            restored
+        };
+
+        for (item, bumped) in &restored {
+            self.db.player_restore_song(item.track_id()).await?;
+
+            if let Some(turn) = bumped {
+                self.db.player_requeue_song(item.track_id(), *turn).await?;
+            }
+        }
+
+        Ok(restored.into_iter().map(|(item, _)| item).collect())
+    }
+
     /// Promote the given song.
     pub(super) async fn promote_song(
         &self,
@@ -207,6 +521,12 @@ impl Mixer {
 
             if let Some(removed) = queue.remove(n) {
                 queue.push_front(removed);
+
+                let mut state = self.fair.lock();
+
+                if n >= state.promoted {
+                    state.promoted += 1;
+                }
             }
 
             queue.front().cloned()
@@ -220,6 +540,63 @@ impl Mixer {
         Ok(None)
     }
 
+    /// Move the song at the given position forward by up to the given number
+    /// of positions, returning the position it was moved to.
+    ///
+    /// Songs are never moved ahead of promoted songs. If `user` is specified,
+    /// the song has to be requested by them.
+    pub(super) async fn bump_song(
+        &self,
+        user: Option<&str>,
+        n: usize,
+        positions: usize,
+    ) -> Result<(usize, Arc<Item>), BumpError> {
+        let (to, item, before, turn) = {
+            let mut queue = self.queue.lock().await;
+
+            let item = match queue.get(n) {
+                Some(item) => item.clone(),
+                None => return Err(BumpError::NoSuchSong),
+            };
+
+            if let Some(user) = user {
+                if item.user().map(String::as_str) != Some(user) {
+                    return Err(BumpError::NotRequester);
+                }
+            }
+
+            let mut state = self.fair.lock();
+            let to = n.saturating_sub(positions).max(state.promoted.min(n));
+
+            if to == n {
+                return Err(BumpError::CannotMove);
+            }
+
+            let before = queue[to].clone();
+
+            // NB: take over the turn of the song we end up in front of, so
+            // that fair ordering preserves the new position.
+            let original = state.turn(&item);
+            let turn = state.turn(&before).min(original);
+            state.set_turn(&item, turn);
+            state
+                .bumped
+                .entry(item.track_id().clone())
+                .or_insert(original);
+
+            queue.remove(n);
+            queue.insert(to, item.clone());
+            (to, item, before, turn)
+        };
+
+        self.db
+            .player_bump_song(item.track_id(), before.track_id(), turn)
+            .await
+            .map_err(BumpError::Error)?;
+
+        Ok((to, item))
+    }
+
     /// Check if a song has been queued within the specified period of time.
     pub(super) async fn last_song_within(
         &self,
@@ -269,9 +646,35 @@ impl Mixer {
         Ok(self.next_fallback_item().await)
     }
 
+    /// Peek at the song which [Mixer::next_song] would return, without
+    /// removing it.
+    ///
+    /// Fallback items are only considered if they have already been shuffled
+    /// into the fallback queue.
+    pub(super) async fn peek_next(&self) -> Option<Arc<Item>> {
+        if let Some(song) = self.sidelined.lock().front() {
+            return Some(song.item().clone());
+        }
+
+        if let Some(item) = self.queue.lock().await.front() {
+            return Some(item.clone());
+        }
+
+        self.fallback.lock().await.queue.front().cloned()
+    }
+
     /// Pop the front of the queue.
     async fn pop_front(&self) -> Result<Option<Arc<Item>>> {
-        let next = self.queue.lock().await.pop_front();
+        let next = {
+            let mut queue = self.queue.lock().await;
+            let next = queue.pop_front();
+
+            if let Some(item) = &next {
+                self.fair.lock().play(item);
+            }
+
+            next
+        };
 
         if let Some(item) = next {
             self.len.fetch_sub(1, Ordering::SeqCst);
@@ -294,3 +697,378 @@ impl Mixer {
         fallback.queue.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use anyhow::{anyhow, Result};
+    use common::models::{Item, Song, TrackId};
+
+    use super::Mixer;
+    use crate::provider::mock::MockProvider;
+    use crate::BumpError;
+    use crate::Providers;
+
+    fn providers() -> Providers {
+        let mut providers = Providers::default();
+        providers.register(
+            MockProvider::default()
+                .with_track("a.mp3", 60)
+                .with_track("b.mp3", 120)
+                .with_track("c.mp3", 180)
+                .with_track("d.mp3", 60)
+                .with_track("e.mp3", 60)
+                .with_track("f.mp3", 60),
+        );
+        providers
+    }
+
+    async fn item(providers: &Providers, user: &str, path: &str) -> Result<Arc<Item>> {
+        let track_id = TrackId::Local(path.to_string());
+        let item = providers
+            .convert_item(Some(user), &track_id, None)
+            .await?
+            .expect("mock provider is always ready");
+        Ok(Arc::new(item))
+    }
+
+    async fn paths(mixer: &Mixer) -> Vec<String> {
+        mixer
+            .queue()
+            .await
+            .iter()
+            .map(|i| i.track_id().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_queue() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db.clone());
+
+        mixer
+            .push_back(item(&providers, "alice", "a.mp3").await?, 1, false)
+            .await?;
+        mixer
+            .push_back(item(&providers, "bob", "b.mp3").await?, 1, false)
+            .await?;
+        mixer
+            .push_back(item(&providers, "alice", "c.mp3").await?, 1, false)
+            .await?;
+        assert_eq!(mixer.len(), 3);
+
+        let promoted = mixer.promote_song(Some("mod"), 1).await?;
+        assert_eq!(
+            promoted.map(|i| i.track_id().clone()),
+            Some(TrackId::Local(String::from("b.mp3")))
+        );
+        assert_eq!(
+            paths(&mixer).await,
+            [
+                "local:track:b.mp3",
+                "local:track:a.mp3",
+                "local:track:c.mp3"
+            ]
+        );
+
+        // A fresh mixer restores the queue from the database.
+        let restored = Mixer::new(db.clone());
+        restored.initialize_queue(&providers, false).await?;
+        assert_eq!(restored.len(), 3);
+        assert_eq!(paths(&restored).await, paths(&mixer).await);
+
+        let removed = mixer.remove_last_by_user("alice").await?;
+        assert_eq!(
+            removed.map(|i| i.track_id().clone()),
+            Some(TrackId::Local(String::from("c.mp3")))
+        );
+        assert_eq!(mixer.len(), 2);
+
+        let next = mixer.next_song().await?.expect("queue is not empty");
+        assert_eq!(
+            next.item().track_id(),
+            &TrackId::Local(String::from("b.mp3"))
+        );
+        assert_eq!(mixer.len(), 1);
+
+        mixer.purge().await?;
+        assert_eq!(mixer.len(), 0);
+        assert!(mixer.next_song().await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fair_queue() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db.clone());
+
+        let mut positions = Vec::new();
+
+        for (user, path, weight) in [
+            ("alice", "a.mp3", 1),
+            ("alice", "b.mp3", 1),
+            ("bob", "c.mp3", 1),
+            ("carol", "d.mp3", 1),
+            ("dave", "e.mp3", 2),
+            ("dave", "f.mp3", 2),
+        ] {
+            let item = item(&providers, user, path).await?;
+            positions.push(mixer.push_back(item, weight, true).await?);
+        }
+
+        // Everyone gets a turn before alice gets her second song, except dave
+        // who gets two songs per turn.
+        assert_eq!(positions, [0, 1, 1, 2, 3, 4]);
+        assert_eq!(
+            paths(&mixer).await,
+            [
+                "local:track:a.mp3",
+                "local:track:c.mp3",
+                "local:track:d.mp3",
+                "local:track:e.mp3",
+                "local:track:f.mp3",
+                "local:track:b.mp3"
+            ]
+        );
+
+        // A fresh mixer restores the fair ordering from the database.
+        let restored = Mixer::new(db.clone());
+        restored.initialize_queue(&providers, true).await?;
+        assert_eq!(paths(&restored).await, paths(&mixer).await);
+
+        // Promoted songs stay in front of the queue.
+        mixer.promote_song(Some("mod"), 5).await?;
+        let next = mixer.next_song().await?.expect("queue is not empty");
+        assert_eq!(
+            next.item().track_id(),
+            &TrackId::Local(String::from("b.mp3"))
+        );
+
+        // Alice's promoted song took her turn, so her next request goes to the
+        // back.
+        mixer.remove_at(0).await?;
+        let position = mixer
+            .push_back(item(&providers, "alice", "a.mp3").await?, 1, true)
+            .await?;
+        assert_eq!(position, 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bump_song() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db.clone());
+
+        for (user, path) in [
+            ("alice", "a.mp3"),
+            ("bob", "b.mp3"),
+            ("carol", "c.mp3"),
+            ("dave", "d.mp3"),
+            ("alice", "e.mp3"),
+        ] {
+            let item = item(&providers, user, path).await?;
+            mixer.push_back(item, 1, true).await?;
+        }
+
+        assert!(matches!(
+            mixer.bump_song(Some("bob"), 4, 2).await,
+            Err(BumpError::NotRequester)
+        ));
+        assert!(matches!(
+            mixer.bump_song(Some("bob"), 5, 2).await,
+            Err(BumpError::NoSuchSong)
+        ));
+
+        let (to, bumped) = mixer
+            .bump_song(Some("alice"), 4, 2)
+            .await
+            .map_err(|_| anyhow!("failed to bump song"))?;
+        assert_eq!(to, 2);
+        assert_eq!(bumped.track_id(), &TrackId::Local(String::from("e.mp3")));
+        assert_eq!(
+            paths(&mixer).await,
+            [
+                "local:track:a.mp3",
+                "local:track:b.mp3",
+                "local:track:e.mp3",
+                "local:track:c.mp3",
+                "local:track:d.mp3"
+            ]
+        );
+
+        // Songs can't be bumped ahead of promoted songs.
+        mixer.promote_song(Some("mod"), 1).await?;
+        let (to, _) = mixer
+            .bump_song(None, 3, 10)
+            .await
+            .map_err(|_| anyhow!("failed to bump song"))?;
+        assert_eq!(to, 1);
+        assert!(matches!(
+            mixer.bump_song(None, 1, 1).await,
+            Err(BumpError::CannotMove)
+        ));
+
+        // Bumped songs keep their position when fair ordering is applied.
+        mixer
+            .push_back(item(&providers, "carol", "f.mp3").await?, 1, true)
+            .await?;
+        assert_eq!(
+            paths(&mixer).await,
+            [
+                "local:track:b.mp3",
+                "local:track:c.mp3",
+                "local:track:a.mp3",
+                "local:track:e.mp3",
+                "local:track:d.mp3",
+                "local:track:f.mp3"
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db.clone());
+
+        for (user, path) in [("alice", "a.mp3"), ("bob", "b.mp3"), ("carol", "c.mp3")] {
+            let item = item(&providers, user, path).await?;
+            mixer.push_back(item, 1, false).await?;
+        }
+
+        let before = paths(&mixer).await;
+
+        mixer.remove_at(1).await?;
+        assert_eq!(mixer.len(), 2);
+
+        let restored = mixer.undo(false).await?;
+        assert_eq!(restored.len(), 1);
+        assert_eq!(paths(&mixer).await, before);
+        assert_eq!(mixer.len(), 3);
+
+        // Nothing left to undo.
+        assert!(mixer.undo(false).await?.is_empty());
+
+        mixer.promote_song(Some("mod"), 2).await?;
+        let before = paths(&mixer).await;
+
+        mixer.purge().await?;
+        assert_eq!(mixer.len(), 0);
+
+        let restored = mixer.undo(false).await?;
+        assert_eq!(restored.len(), 3);
+        assert_eq!(paths(&mixer).await, before);
+
+        // Restored songs are persisted.
+        let fresh = Mixer::new(db.clone());
+        fresh.initialize_queue(&providers, false).await?;
+        assert_eq!(paths(&fresh).await, before);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_bumped() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db.clone());
+
+        for (user, path) in [("alice", "a.mp3"), ("bob", "b.mp3"), ("carol", "c.mp3")] {
+            let item = item(&providers, user, path).await?;
+            mixer.push_back(item, 1, false).await?;
+        }
+
+        mixer
+            .bump_song(Some("carol"), 2, 2)
+            .await
+            .map_err(|_| anyhow!("failed to bump song"))?;
+        assert_eq!(
+            paths(&mixer).await,
+            [
+                "local:track:c.mp3",
+                "local:track:a.mp3",
+                "local:track:b.mp3"
+            ]
+        );
+
+        // The bump is refunded when the song is removed, so it doesn't keep
+        // its bumped position when restored.
+        mixer.remove_at(0).await?;
+        mixer.undo(false).await?;
+
+        let expected = [
+            "local:track:a.mp3",
+            "local:track:b.mp3",
+            "local:track:c.mp3",
+        ];
+        assert_eq!(paths(&mixer).await, expected);
+
+        let fresh = Mixer::new(db.clone());
+        fresh.initialize_queue(&providers, false).await?;
+        assert_eq!(paths(&fresh).await, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fallback() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db);
+
+        let fallback = item(&providers, "streamer", "a.mp3").await?;
+        mixer.update_fallback_items(vec![fallback]).await;
+
+        // Queued songs take priority over fallback items.
+        mixer
+            .push_back(item(&providers, "bob", "b.mp3").await?, 1, false)
+            .await?;
+
+        let next = mixer.next_song().await?.expect("queue is not empty");
+        assert_eq!(
+            next.item().track_id(),
+            &TrackId::Local(String::from("b.mp3"))
+        );
+
+        let next = mixer.next_song().await?.expect("fallback is not empty");
+        assert_eq!(
+            next.item().track_id(),
+            &TrackId::Local(String::from("a.mp3"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_peek_next() -> Result<()> {
+        let db = db::Database::open(Path::new(":memory:"))?;
+        let providers = providers();
+        let mixer = Mixer::new(db);
+
+        assert!(mixer.peek_next().await.is_none());
+
+        mixer
+            .push_back(item(&providers, "bob", "b.mp3").await?, 1, false)
+            .await?;
+
+        let next = mixer.peek_next().await.expect("queue is not empty");
+        assert_eq!(next.track_id(), &TrackId::Local(String::from("b.mp3")));
+
+        // Sidelined songs play before the queue.
+        let sidelined = item(&providers, "alice", "a.mp3").await?;
+        mixer.push_sidelined(Song::new(sidelined, Default::default()));
+
+        let next = mixer.peek_next().await.expect("sidelined is not empty");
+        assert_eq!(next.track_id(), &TrackId::Local(String::from("a.mp3")));
+
+        let song = mixer.next_song().await?.expect("sidelined is not empty");
+        assert_eq!(song.item().track_id(), next.track_id());
+
+        let next = mixer.peek_next().await.expect("queue is not empty");
+        assert_eq!(next.track_id(), &TrackId::Local(String::from("b.mp3")));
+        Ok(())
+    }
+}
@@ -42,12 +42,21 @@ impl PlaybackFuture {
             }
         }
 
+        // NB: the single fallback URI has been replaced with a list.
+        if let Some(fallback_uri) = settings.get::<Uri>("fallback-uri").await? {
+            if settings.get::<Vec<Uri>>("fallback-uris").await?.is_none() {
+                settings.set("fallback-uris", vec![fallback_uri]).await?;
+            }
+
+            settings.clear("fallback-uri").await?;
+        }
+
         let cache = injector
             .get::<storage::Cache>()
             .await
             .context("missing cache")?;
 
-        let (mut fallback_stream, fallback) = settings.stream("fallback-uri").optional().await?;
+        let (mut fallback_stream, fallback) = settings.stream("fallback-uris").or_default().await?;
 
         let mut configure_fallback = pin!(Fuse::new(update_fallback_items_task(
             &self.internal,
@@ -108,19 +117,19 @@ impl PlaybackFuture {
         /// Update fallback item tasks.
         async fn update_fallback_items_task(
             internal: &PlayerInternal,
-            fallback: Option<Uri>,
+            fallback: Vec<Uri>,
             cache: &storage::Cache,
         ) -> Result<()> {
             #[derive(Clone, Copy, Serialize)]
             #[serde(tag = "source")]
             enum Key<'a> {
-                Uri { uri: &'a Uri },
+                Uris { uris: &'a [Uri] },
                 Library,
             }
 
-            let key = match fallback.as_ref() {
-                Some(uri) => Key::Uri { uri },
-                None => Key::Library,
+            let key = match fallback.as_slice() {
+                [] => Key::Library,
+                uris => Key::Uris { uris },
             };
 
             let duration = chrono::Duration::hours(4);
@@ -130,7 +139,7 @@ impl PlaybackFuture {
                 "Loading fallback items", {
                     // NB: I don't know what's up, but for some reason this
                     // future blows up the stack.
-                    let (what, items) = common::debug_box_pin(cache.wrap(key, duration, internal.load_fallback_items(fallback.as_ref()))).await?;
+                    let (what, items) = common::debug_box_pin(cache.wrap(key, duration, internal.load_fallback_items(&fallback))).await?;
 
                     tracing::info!(
                         "Updated fallback queue with {} items from {}.",
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::pin::pin;
 use std::sync::Arc;
 use std::time::Duration;
@@ -7,7 +8,6 @@ use async_injector::Injector;
 use chrono::{DateTime, Utc};
 use common::models::spotify::context::FullPlayingContext;
 use common::models::spotify::track::FullTrack;
-use common::models::spotify::user::PrivateUser;
 use common::models::{SpotifyId, State, TrackId};
 use common::stream::Stream;
 use common::stream::StreamExt;
@@ -15,8 +15,8 @@ use common::Uri;
 use parking_lot::Mutex;
 
 use crate::{
-    convert_item, AddTrackError, ConnectDevice, ConnectPlayer, DuplicateBy, Event,
-    IntegrationEvent, Item, Mixer, PlaybackMode, PlayerKind, Song, Source, Track, YouTubePlayer,
+    AddTrackError, ConnectDevice, ConnectPlayer, DuplicateBy, Event, IntegrationEvent, Item, Mixer,
+    PlaybackMode, PlayerKind, Providers, Song, Source, Track,
 };
 
 #[derive(Default)]
@@ -36,9 +36,9 @@ pub(super) struct PlayerInternal {
     pub(super) injector: Injector,
     /// API clients and streams.
     pub(super) spotify: Arc<api::Spotify>,
-    pub(super) youtube: Arc<api::YouTube>,
+    /// Providers for all supported kinds of tracks.
+    pub(super) providers: Providers,
     pub(super) connect_player: ConnectPlayer,
-    pub(super) youtube_player: YouTubePlayer,
     /// The internal mixer.
     pub(super) mixer: Mixer,
     /// The player bus.
@@ -51,8 +51,13 @@ pub(super) struct PlayerInternal {
     pub(super) max_queue_length: settings::Var<u32>,
     pub(super) max_songs_per_user: settings::Var<u32>,
     pub(super) duplicate_duration: settings::Var<common::Duration>,
+    pub(super) fair_queue: settings::Var<bool>,
     /// Theme songs.
     pub(super) themes: async_injector::Ref<db::Themes>,
+    /// Banned songs.
+    pub(super) song_bans: async_injector::Ref<db::SongBans>,
+    /// Saved queue snapshots.
+    pub(super) queue_snapshots: async_injector::Ref<db::QueueSnapshots>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -159,9 +164,8 @@ impl PlayerInternal {
         }
 
         if !initialize.queue {
-            self.mixer
-                .initialize_queue(&self.spotify, &self.youtube)
-                .await?;
+            let fair = self.fair_queue.load().await;
+            self.mixer.initialize_queue(&self.providers, fair).await?;
 
             initialize.queue = true;
         }
@@ -215,21 +219,19 @@ impl PlayerInternal {
     /// Switch the current player and send the appropriate play commands.
     #[tracing::instrument(skip(self), fields(state = ?self.state()))]
     async fn switch_current_player(&self, player: PlayerKind) -> Result<()> {
-        use common::models::PlayerKind::*;
-
         tracing::trace!("Switch current player");
         let state = self.state();
 
-        match (state.player, player) {
-            (Spotify, Spotify) => (),
-            (YouTube, YouTube) => (),
-            (Spotify, _) | (None, YouTube) => {
-                self.connect_player.stop().await;
-            }
-            (YouTube, _) | (None, Spotify) => {
-                self.youtube_player.stop().await;
+        // NB: stop the previous player, or all other players if we don't know
+        // which one was previously active.
+        if state.player != player {
+            for provider in self.providers.iter() {
+                let kind = provider.kind();
+
+                if state.player == kind || (state.player == PlayerKind::None && player != kind) {
+                    provider.stop().await;
+                }
             }
-            (None, None) => (),
         }
 
         self.state.lock().player = player;
@@ -241,16 +243,9 @@ impl PlayerInternal {
     async fn send_pause_command(&self) {
         tracing::trace!("Sending pause command");
 
-        match self.state().player {
-            PlayerKind::Spotify => {
-                tracing::trace!("Pausing Spotify player");
-                self.connect_player.pause().await;
-            }
-            PlayerKind::YouTube => {
-                tracing::trace!("Pausing YouTube player");
-                self.youtube_player.pause().await;
-            }
-            _ => (),
+        if let Some(provider) = self.providers.for_kind(self.state().player) {
+            tracing::trace!("Pausing {:?} player", provider.kind());
+            provider.pause().await;
         }
     }
 
@@ -259,17 +254,12 @@ impl PlayerInternal {
     async fn send_play_command(&self, song: &Song) {
         tracing::trace!("Sending play command");
 
-        match song.item().track_id().clone() {
-            TrackId::Spotify(id) => {
-                self.connect_player
-                    .play(Some(id), Some(song.elapsed()))
-                    .await;
-            }
-            TrackId::YouTube(id) => {
-                self.youtube_player
-                    .play(song.elapsed(), song.item().duration(), id)
-                    .await;
-            }
+        let track_id = song.item().track_id();
+
+        if let Some(provider) = self.providers.for_track(track_id) {
+            provider
+                .play(track_id, song.elapsed(), song.item().duration())
+                .await;
         }
     }
 
@@ -617,117 +607,96 @@ impl PlayerInternal {
         self.mixer.update_fallback_items(items).await;
     }
 
-    /// Load fallback items with the given uri.
+    /// Load fallback items from the given URIs, or from the streamer's
+    /// library if there are none.
     #[tracing::instrument(skip(self), fields(state = ?self.state()))]
     pub(super) async fn load_fallback_items(
         &self,
-        uri: Option<&Uri>,
+        uris: &[Uri],
     ) -> Result<(String, Vec<Arc<Item>>)> {
         tracing::trace!("Loading fallback items");
 
-        let (what, items) = match uri {
-            Some(uri) => {
-                let id = match uri {
-                    Uri::SpotifyPlaylist(id) => id,
-                    uri => {
-                        return Err(anyhow!(
-                            "Bad fallback URI `{}`, expected Spotify Playlist",
-                            uri
-                        ));
-                    }
-                };
+        if uris.is_empty() {
+            let stream = download_spotify_library(&self.spotify);
+            let items = convert(stream, None).await?;
+            return Ok((String::from("your library"), items));
+        }
 
-                let (name, items) = download_spotify_playlist(&self.spotify, *id).await?;
-                let items = convert(items).await?;
-                (Some(name), items)
-            }
-            None => {
-                let items = download_spotify_library(&self.spotify);
-                let items = convert(items).await?;
-                (None, items)
+        let mut what = Vec::new();
+        let mut items = Vec::new();
+        let mut seen = HashSet::new();
+
+        for uri in uris {
+            let (name, collection) = self.load_collection(uri, None).await?;
+            what.push(name);
+
+            for item in collection {
+                if seen.insert(item.track_id().clone()) {
+                    items.push(item);
+                }
             }
-        };
+        }
 
-        let what = what
-            .as_ref()
-            .map(|u| format!("\"{}\" playlist", u))
-            .unwrap_or_else(|| String::from("your library"));
+        Ok((what.join(", "), items))
+    }
 
-        return Ok((what, items));
+    /// Load the playable tracks of a Spotify playlist or album, together with
+    /// a description of the collection.
+    ///
+    /// If `limit` is specified, at most that many tracks are loaded.
+    pub(super) async fn load_collection(
+        &self,
+        uri: &Uri,
+        limit: Option<usize>,
+    ) -> Result<(String, Vec<Arc<Item>>)> {
+        let streamer = self.spotify.me().await?;
+        let market = streamer.country.as_deref();
 
-        async fn convert(stream: impl Stream<Item = Result<FullTrack>>) -> Result<Vec<Arc<Item>>> {
-            let mut stream = pin!(stream);
+        match uri {
+            Uri::SpotifyPlaylist(id) => {
+                let playlist = self.spotify.playlist(*id, market).await?;
+                let what = format!("\"{}\" playlist", playlist.name);
 
-            let mut items = Vec::new();
+                let stream = async_stream::try_stream! {
+                    let mut playlist_tracks = pin!(self.spotify.page_as_stream(playlist.tracks));
 
-            while let Some(track) = stream.next().await.transpose()? {
-                let track_id = match &track.id {
-                    Some(track_id) => track_id,
-                    None => {
-                        continue;
+                    while let Some(playlist_track) = playlist_tracks.next().await.transpose()? {
+                        yield playlist_track.track;
                     }
                 };
 
-                let track_id = TrackId::Spotify(
-                    SpotifyId::from_base62(track_id)
-                        .map_err(|_| anyhow!("bad spotify id: {}", track_id))?,
-                );
-
-                let duration = Duration::from_millis(track.duration_ms.into());
-
-                let item = Item::new(
-                    track_id,
-                    Track::Spotify {
-                        track: Box::new(track),
-                    },
-                    None,
-                    duration,
-                );
-
-                if item.is_playable() {
-                    items.push(Arc::new(item));
-                }
+                Ok((what, convert(stream, limit).await?))
             }
+            Uri::SpotifyAlbum(id) => {
+                let album = self.spotify.album(*id, market).await?;
+                let what = format!("\"{}\" album", album.name);
 
-            Ok(items)
-        }
+                let mut ids = Vec::new();
+                let mut album_tracks = pin!(self.spotify.page_as_stream(album.tracks));
 
-        /// Download a playlist from Spotify.
-        async fn download_spotify_playlist(
-            spotify: &api::Spotify,
-            playlist: SpotifyId,
-        ) -> Result<(String, impl Stream<Item = Result<FullTrack>> + '_)> {
-            let streamer = spotify.me().await?;
-
-            let playlist = spotify
-                .playlist(playlist, streamer.country.as_deref())
-                .await?;
-
-            let name = playlist.name.to_string();
-
-            let items = async_stream::try_stream! {
-                let mut playlist_tracks = pin!(spotify.page_as_stream(playlist.tracks));
+                while let Some(track) = album_tracks.next().await.transpose()? {
+                    if limit.is_some_and(|limit| ids.len() >= limit) {
+                        break;
+                    }
 
-                while let Some(playlist_track) = playlist_tracks.next().await.transpose()? {
-                    yield playlist_track.track;
+                    ids.extend(track.id);
                 }
-            };
-
-            Ok((name, items))
-        }
 
-        /// Download a spotify library.
-        fn download_spotify_library(
-            spotify: &api::Spotify,
-        ) -> impl Stream<Item = Result<FullTrack>> + '_ {
-            async_stream::try_stream! {
-                let saved_tracks = spotify.my_tracks().await?;
-                let mut saved_tracks = pin!(spotify.page_as_stream(saved_tracks));
+                // NB: album tracks are simplified, so look up the full tracks.
+                let stream = async_stream::try_stream! {
+                    for chunk in ids.chunks(api::spotify::TRACKS_LIMIT) {
+                        for track in self.spotify.tracks(chunk, market).await? {
+                            yield track;
+                        }
+                    }
+                };
 
-                while let Some(track) = saved_tracks.next().await.transpose()? {
-                    yield track.track;
-                }
+                Ok((what, convert(stream, limit).await?))
             }
+            uri => Err(anyhow!(
+                "Bad URI `{}`, expected Spotify playlist or album",
+                uri
+            )),
         }
     }
 
@@ -771,17 +740,10 @@ impl PlayerInternal {
                 };
 
                 // TODO: how do we deal with playback mode on a device transfer?
-                match track_id {
-                    TrackId::Spotify(id) => {
-                        self.connect_player.play(Some(id), Some(elapsed)).await;
-                        self.switch_current_player(PlayerKind::Spotify).await?;
-                        self.injector.update(State::Playing).await;
-                    }
-                    TrackId::YouTube(id) => {
-                        self.youtube_player.play(elapsed, duration, id).await;
-                        self.switch_current_player(PlayerKind::YouTube).await?;
-                        self.injector.update(State::Playing).await;
-                    }
+                if let Some(provider) = self.providers.for_track(&track_id) {
+                    provider.play(&track_id, elapsed, duration).await;
+                    self.switch_current_player(provider.kind()).await?;
+                    self.injector.update(State::Playing).await;
                 }
             }
         }
@@ -797,11 +759,23 @@ impl PlayerInternal {
             self.global_bus.send(bus::Global::song_progress(song)).await;
 
             if let Some(song) = song {
-                if let TrackId::YouTube(id) = song.item().track_id() {
-                    self.youtube_player
-                        .tick(song.elapsed(), song.item().duration(), id.to_string())
+                let track_id = song.item().track_id();
+
+                if let Some(provider) = self.providers.for_track(track_id) {
+                    provider
+                        .tick(track_id, song.elapsed(), song.item().duration())
                         .await;
                 }
+
+                let next = self.mixer.peek_next().await;
+
+                if let Some(next) = next {
+                    let track_id = next.track_id();
+
+                    if let Some(provider) = self.providers.for_track(track_id) {
+                        provider.preload(track_id, song.remaining()).await;
+                    }
+                }
             }
         }
     }
@@ -838,36 +812,65 @@ impl PlayerInternal {
         track_id: TrackId,
         bypass_constraints: bool,
         max_duration: Option<common::Duration>,
+        weight: u32,
     ) -> Result<(Option<usize>, Arc<Item>), AddTrackError> {
-        // TODO: cache this value
-        let streamer: PrivateUser = self.spotify.me().await.map_err(AddTrackError::Error)?;
-        let market = streamer.country.as_deref();
+        let item = self
+            .providers
+            .convert_item(Some(user), &track_id, None)
+            .await
+            .map_err(AddTrackError::Error)?;
+
+        let item = match item {
+            Some(item) => item,
+            None => return Err(AddTrackError::MissingAuth),
+        };
+
+        // NB: bans apply regardless of playback mode and even when bypassing
+        // constraints, since songs which are added on behalf of others like
+        // from snapshots go through here.
+        if let Some(song_bans) = self.song_bans.load().await {
+            if let Some(ban) = song_bans
+                .find(&track_id, item.track())
+                .await
+                .map_err(AddTrackError::Error)?
+            {
+                return Err(AddTrackError::Banned(ban));
+            }
+        }
 
         match self.state().mode {
             PlaybackMode::Default => {
-                self.default_add_track(user, track_id, bypass_constraints, max_duration, market)
-                    .await
+                self.default_add_track(
+                    user,
+                    track_id,
+                    item,
+                    bypass_constraints,
+                    max_duration,
+                    weight,
+                )
+                .await
             }
             PlaybackMode::Queue => {
-                self.queue_add_track(user, track_id, bypass_constraints, max_duration, market)
+                self.queue_add_track(user, track_id, item, bypass_constraints, max_duration)
                     .await
             }
         }
     }
 
     /// Default method for adding a track.
-    #[tracing::instrument(skip(self), fields(state = ?self.state()))]
+    #[tracing::instrument(skip(self, item), fields(state = ?self.state()))]
     async fn default_add_track(
         &self,
         user: &str,
         track_id: TrackId,
+        mut item: Item,
         bypass_constraints: bool,
         max_duration: Option<common::Duration>,
-        market: Option<&str>,
+        weight: u32,
     ) -> Result<(Option<usize>, Arc<Item>), AddTrackError> {
         tracing::trace!("Add track");
 
-        let (user_count, len) = {
+        let user_count = {
             if !bypass_constraints {
                 let closed = (*self.closed.lock()).as_ref().cloned();
 
@@ -912,13 +915,10 @@ impl PlayerInternal {
             }
 
             let mut user_count = 0;
-            let mut len = 0;
 
             let items = self.mixer.queue().await;
 
             for (index, i) in items.iter().enumerate() {
-                len += 1;
-
                 if *i.track_id() == track_id {
                     return Err(AddTrackError::QueueContainsTrack(index));
                 }
@@ -928,7 +928,7 @@ impl PlayerInternal {
                 }
             }
 
-            (user_count, len)
+            user_count
         };
 
         let max_songs_per_user = self.max_songs_per_user.load().await;
@@ -938,22 +938,6 @@ impl PlayerInternal {
             return Err(AddTrackError::TooManyUserTracks(max_songs_per_user));
         }
 
-        let item = convert_item(
-            &self.spotify,
-            &self.youtube,
-            Some(user),
-            &track_id,
-            None,
-            market,
-        )
-        .await
-        .map_err(AddTrackError::Error)?;
-
-        let mut item = match item {
-            Some(item) => item,
-            None => return Err(AddTrackError::MissingAuth),
-        };
-
         if !item.is_playable() {
             return Err(AddTrackError::NotPlayable);
         }
@@ -967,9 +951,11 @@ impl PlayerInternal {
         }
 
         let item = Arc::new(item);
+        let fair = self.fair_queue.load().await;
 
-        self.mixer
-            .push_back(item.clone())
+        let position = self
+            .mixer
+            .push_back(item.clone(), weight, fair)
             .await
             .map_err(AddTrackError::Error)?;
 
@@ -977,49 +963,85 @@ impl PlayerInternal {
             .await
             .map_err(AddTrackError::Error)?;
 
-        Ok((Some(len), item))
+        Ok((Some(position), item))
     }
 
     /// Try to queue up a track.
-    #[tracing::instrument(skip(self), fields(state = ?self.state()))]
+    #[tracing::instrument(skip(self, item), fields(state = ?self.state()))]
     async fn queue_add_track(
         &self,
         user: &str,
         track_id: TrackId,
+        item: Item,
         _bypass_constraints: bool,
         _max_duration: Option<common::Duration>,
-        market: Option<&str>,
     ) -> Result<(Option<usize>, Arc<Item>), AddTrackError> {
         tracing::trace!("Add track");
 
-        let item = convert_item(
-            &self.spotify,
-            &self.youtube,
-            Some(user),
-            &track_id,
-            None,
-            market,
-        )
-        .await
-        .map_err(AddTrackError::Error)?;
+        match self.providers.for_track(&track_id) {
+            Some(provider) => provider.queue(&track_id).await?,
+            None => return Err(AddTrackError::UnsupportedPlaybackMode),
+        }
 
-        let item = match item {
-            Some(item) => item,
-            None => return Err(AddTrackError::MissingAuth),
-        };
+        Ok((None, Arc::new(item)))
+    }
+}
 
-        match track_id {
-            TrackId::Spotify(id) => {
-                self.connect_player
-                    .queue(id)
-                    .await
-                    .map_err(|e| AddTrackError::Error(e.into()))?;
-            }
-            TrackId::YouTube(..) => {
-                return Err(AddTrackError::UnsupportedPlaybackMode);
+/// Convert a stream of Spotify tracks into playable items.
+///
+/// If `limit` is specified, at most that many items are converted.
+async fn convert(
+    stream: impl Stream<Item = Result<FullTrack>>,
+    limit: Option<usize>,
+) -> Result<Vec<Arc<Item>>> {
+    let mut stream = pin!(stream);
+
+    let mut items = Vec::new();
+
+    while let Some(track) = stream.next().await.transpose()? {
+        if limit.is_some_and(|limit| items.len() >= limit) {
+            break;
+        }
+
+        let track_id = match &track.id {
+            Some(track_id) => track_id,
+            None => {
+                continue;
             }
+        };
+
+        let track_id = TrackId::Spotify(
+            SpotifyId::from_base62(track_id)
+                .map_err(|_| anyhow!("bad spotify id: {}", track_id))?,
+        );
+
+        let duration = Duration::from_millis(track.duration_ms.into());
+
+        let item = Item::new(
+            track_id,
+            Track::Spotify {
+                track: Box::new(track),
+            },
+            None,
+            duration,
+        );
+
+        if item.is_playable() {
+            items.push(Arc::new(item));
         }
+    }
 
-        Ok((None, Arc::new(item)))
+    Ok(items)
+}
+
+/// Download a spotify library.
+fn download_spotify_library(spotify: &api::Spotify) -> impl Stream<Item = Result<FullTrack>> + '_ {
+    async_stream::try_stream! {
+        let saved_tracks = spotify.my_tracks().await?;
+        let mut saved_tracks = pin!(spotify.page_as_stream(saved_tracks));
+
+        while let Some(track) = saved_tracks.next().await.transpose()? {
+            yield track.track;
+        }
     }
 }
@@ -0,0 +1,340 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use common::models::track_id::FromStrError;
+use common::models::{Item, PlayerKind, Track, TrackId};
+use url::Url;
+
+use crate::{AddTrackError, ModifyVolume};
+
+/// A source of tracks which can be requested and played by the player.
+///
+/// A provider is responsible for everything which is specific to one kind of
+/// track: recognizing its ids and URLs, looking up metadata, searching, and
+/// driving the backend which performs the actual playback.
+#[async_trait]
+pub(crate) trait TrackProvider: Send + Sync {
+    /// The kind of player used to play tracks from this provider.
+    fn kind(&self) -> PlayerKind;
+
+    /// The prefix used to direct a search to this provider, like `youtube`
+    /// in `youtube:<query>`.
+    fn search_prefix(&self) -> &'static str;
+
+    /// Test if the given track id belongs to this provider.
+    fn handles(&self, track_id: &TrackId) -> bool;
+
+    /// Try to parse a track id out of the given URL.
+    ///
+    /// Returns `None` if the URL is not recognized by this provider.
+    fn parse_url(&self, _url: &Url) -> Option<Result<TrackId, FromStrError>> {
+        None
+    }
+
+    /// Test if the provider is ready to look up tracks, which typically means
+    /// that the service it uses has been authenticated.
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    /// Look up the metadata and duration of the given track.
+    async fn lookup(&self, track_id: &TrackId) -> Result<(Track, Duration)>;
+
+    /// Search for the track which best matches the given query.
+    async fn search(&self, q: &str) -> Result<Option<TrackId>>;
+
+    /// Start playing the given track.
+    async fn play(&self, track_id: &TrackId, elapsed: Duration, duration: Duration);
+
+    /// Update playback information for the given track while it's playing.
+    async fn tick(&self, _track_id: &TrackId, _elapsed: Duration, _duration: Duration) {}
+
+    /// Prepare the given track, which is queued to play after the current
+    /// one, where `remaining` is how long is left of the current track.
+    ///
+    /// Backends use this to buffer the track ahead of time so that it starts
+    /// without delay.
+    async fn preload(&self, _track_id: &TrackId, _remaining: Duration) {}
+
+    /// Pause playback.
+    async fn pause(&self);
+
+    /// Stop playback.
+    async fn stop(&self);
+
+    /// Modify the volume of the backend, returning the updated volume.
+    async fn volume(&self, modify: ModifyVolume) -> u32;
+
+    /// Get the current volume of the backend.
+    async fn current_volume(&self) -> u32;
+
+    /// Enqueue the given track in the backend to play next.
+    ///
+    /// Only used in the `queue` playback mode, which most backends don't
+    /// support.
+    async fn queue(&self, _track_id: &TrackId) -> Result<(), AddTrackError> {
+        Err(AddTrackError::UnsupportedPlaybackMode)
+    }
+}
+
+/// The collection of providers available to the player.
+#[derive(Clone, Default)]
+pub(crate) struct Providers {
+    providers: Vec<Arc<dyn TrackProvider>>,
+}
+
+impl Providers {
+    /// Register a provider.
+    ///
+    /// The first provider registered is used for searches without a prefix.
+    pub(crate) fn register(&mut self, provider: impl TrackProvider + 'static) {
+        self.providers.push(Arc::new(provider));
+    }
+
+    /// Iterate over all registered providers.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &dyn TrackProvider> {
+        self.providers.iter().map(|p| &**p)
+    }
+
+    /// Get the provider responsible for the given track.
+    pub(crate) fn for_track(&self, track_id: &TrackId) -> Option<&dyn TrackProvider> {
+        self.iter().find(|p| p.handles(track_id))
+    }
+
+    /// Get the provider which plays using the given kind of player.
+    pub(crate) fn for_kind(&self, kind: PlayerKind) -> Option<&dyn TrackProvider> {
+        self.iter().find(|p| p.kind() == kind)
+    }
+
+    /// Parse a track id, trying URLs recognized by any provider before falling
+    /// back to URIs like `spotify:track:<id>`.
+    pub(crate) fn parse(&self, s: &str) -> Result<TrackId, FromStrError> {
+        if let Ok(url) = str::parse::<Url>(s) {
+            if url.host().is_some() {
+                return match self.iter().find_map(|p| p.parse_url(&url)) {
+                    Some(result) => result,
+                    None => Err(FromStrError::BadHost(url.to_string())),
+                };
+            }
+        }
+
+        str::parse(s)
+    }
+
+    /// Search for a track, using the provider matching the prefix of the query
+    /// if there is one.
+    pub(crate) async fn search(&self, q: &str) -> Result<Option<TrackId>> {
+        for provider in self.iter() {
+            let prefix = provider.search_prefix();
+
+            if let Some(q) = q.strip_prefix(prefix).and_then(|q| q.strip_prefix(':')) {
+                return provider.search(q).await;
+            }
+        }
+
+        match self.iter().next() {
+            Some(provider) => provider.search(q).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Converts a track into an Item.
+    ///
+    /// Returns `None` if the provider required to convert the item is not
+    /// ready.
+    pub(crate) async fn convert_item(
+        &self,
+        user: Option<&str>,
+        track_id: &TrackId,
+        duration_override: Option<Duration>,
+    ) -> Result<Option<Item>> {
+        let Some(provider) = self.for_track(track_id) else {
+            bail!("no provider for track `{}`", track_id);
+        };
+
+        if !provider.is_ready() {
+            return Ok(None);
+        }
+
+        let (track, duration) = provider.lookup(track_id).await?;
+
+        Ok(Some(Item::new(
+            track_id.clone(),
+            track,
+            user.map(|user| user.to_string()),
+            duration_override.unwrap_or(duration),
+        )))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use anyhow::{bail, Result};
+    use async_trait::async_trait;
+    use common::models::local::LocalTrack;
+    use common::models::{PlayerKind, Track, TrackId};
+    use parking_lot::Mutex;
+
+    use super::TrackProvider;
+    use crate::ModifyVolume;
+
+    /// A provider serving a fixed set of tracks from memory.
+    ///
+    /// Tracks are identified as local tracks so that they can be stored in
+    /// the database like any other track.
+    #[derive(Default)]
+    pub(crate) struct MockProvider {
+        tracks: HashMap<String, Duration>,
+        volume: Mutex<u32>,
+    }
+
+    impl MockProvider {
+        /// Add a track with the given duration in seconds.
+        pub(crate) fn with_track(mut self, path: &str, secs: u64) -> Self {
+            self.tracks
+                .insert(path.to_string(), Duration::from_secs(secs));
+            self
+        }
+    }
+
+    #[async_trait]
+    impl TrackProvider for MockProvider {
+        fn kind(&self) -> PlayerKind {
+            PlayerKind::Local
+        }
+
+        fn search_prefix(&self) -> &'static str {
+            "mock"
+        }
+
+        fn handles(&self, track_id: &TrackId) -> bool {
+            matches!(track_id, TrackId::Local(..))
+        }
+
+        async fn lookup(&self, track_id: &TrackId) -> Result<(Track, Duration)> {
+            let TrackId::Local(path) = track_id else {
+                bail!("not a mock track: {}", track_id);
+            };
+
+            let Some(duration) = self.tracks.get(path).copied() else {
+                bail!("no mock track `{}`", path);
+            };
+
+            let track = LocalTrack {
+                path: path.clone(),
+                title: Some(path.clone()),
+                artist: None,
+                album: None,
+                duration,
+            };
+
+            Ok((
+                Track::Local {
+                    track: Box::new(track),
+                },
+                duration,
+            ))
+        }
+
+        async fn search(&self, q: &str) -> Result<Option<TrackId>> {
+            let mut paths = self
+                .tracks
+                .keys()
+                .filter(|p| p.contains(q))
+                .collect::<Vec<_>>();
+            paths.sort();
+            Ok(paths.first().map(|p| TrackId::Local(p.to_string())))
+        }
+
+        async fn play(&self, _track_id: &TrackId, _elapsed: Duration, _duration: Duration) {}
+
+        async fn pause(&self) {}
+
+        async fn stop(&self) {}
+
+        async fn volume(&self, modify: ModifyVolume) -> u32 {
+            let mut volume = self.volume.lock();
+            *volume = modify.apply(*volume);
+            *volume
+        }
+
+        async fn current_volume(&self) -> u32 {
+            *self.volume.lock()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::models::track_id::FromStrError;
+    use common::models::TrackId;
+
+    use super::mock::MockProvider;
+    use super::Providers;
+
+    fn providers() -> Providers {
+        let mut providers = Providers::default();
+        providers.register(
+            MockProvider::default()
+                .with_track("rock/one.mp3", 120)
+                .with_track("jazz/two.mp3", 60),
+        );
+        providers
+    }
+
+    #[tokio::test]
+    async fn test_search() -> anyhow::Result<()> {
+        let providers = providers();
+
+        assert_eq!(
+            providers.search("mock:two").await?,
+            Some(TrackId::Local(String::from("jazz/two.mp3")))
+        );
+        assert_eq!(
+            providers.search("one").await?,
+            Some(TrackId::Local(String::from("rock/one.mp3")))
+        );
+        assert_eq!(providers.search("three").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_convert_item() -> anyhow::Result<()> {
+        let providers = providers();
+        let track_id = TrackId::Local(String::from("rock/one.mp3"));
+
+        let item = providers
+            .convert_item(Some("user"), &track_id, None)
+            .await?
+            .expect("provider should be ready");
+
+        assert_eq!(item.track_id(), &track_id);
+        assert_eq!(item.user().map(String::as_str), Some("user"));
+        assert_eq!(item.duration(), std::time::Duration::from_secs(120));
+
+        let missing = TrackId::Local(String::from("missing.mp3"));
+        assert!(providers.convert_item(None, &missing, None).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() {
+        let providers = providers();
+
+        assert!(matches!(
+            providers.parse("local:track:rock/one.mp3"),
+            Ok(TrackId::Local(path)) if path == "rock/one.mp3"
+        ));
+
+        // NB: the mock provider doesn't recognize any URLs.
+        assert!(matches!(
+            providers.parse("https://example.com/track"),
+            Err(FromStrError::BadHost(..))
+        ));
+    }
+}
@@ -1,7 +1,17 @@
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use common::models::track_id::FromStrError;
+use common::models::youtube::Kind;
+use common::models::{PlayerKind, Track, TrackId};
+use common::PtDuration;
+use parking_lot::Mutex;
+use url::Url;
+
+use crate::provider::TrackProvider;
 
 /// Setup a player.
 pub(super) async fn setup(
@@ -15,11 +25,16 @@ pub(super) async fn setup(
     let (mut volume_stream, volume) = settings.stream("volume").or_with(50).await?;
     let mut scaled_volume = (volume * volume_scale) / 100u32;
     let volume = settings::Var::new(volume);
+    let preload_window = settings
+        .var("preload-window", common::Duration::seconds(10))
+        .await?;
 
     let player = YouTubePlayer {
         bus,
         settings,
         volume: volume.clone(),
+        preload_window,
+        preloaded: Arc::new(Mutex::new(None)),
     };
 
     let returned_player = player.clone();
@@ -51,6 +66,11 @@ pub(super) struct YouTubePlayer {
     bus: bus::Bus<bus::YouTube>,
     settings: settings::Settings<::auth::Scope>,
     volume: settings::Var<u32>,
+    /// How long before the end of the current video to start buffering the
+    /// next one.
+    preload_window: settings::Var<common::Duration>,
+    /// The video which was most recently sent to be buffered.
+    preloaded: Arc<Mutex<Option<String>>>,
 }
 
 impl YouTubePlayer {
@@ -66,6 +86,8 @@ impl YouTubePlayer {
     }
 
     pub(super) async fn play(&self, elapsed: Duration, duration: Duration, video_id: String) {
+        *self.preloaded.lock() = None;
+
         let event = bus::YouTubeEvent::Play {
             video_id,
             elapsed: elapsed.as_secs(),
@@ -75,12 +97,40 @@ impl YouTubePlayer {
         self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
     }
 
+    /// Tell the overlay to buffer the given video if the current one is about
+    /// to end.
+    ///
+    /// Each video is only sent once, unless a different video was sent in
+    /// between.
+    pub(super) async fn preload(&self, remaining: Duration, video_id: String) {
+        let preload_window = self.preload_window.load().await.as_std();
+
+        if remaining > preload_window {
+            return;
+        }
+
+        {
+            let mut preloaded = self.preloaded.lock();
+
+            if preloaded.as_deref() == Some(video_id.as_str()) {
+                return;
+            }
+
+            *preloaded = Some(video_id.clone());
+        }
+
+        let event = bus::YouTubeEvent::Preload { video_id };
+        self.bus.send(bus::YouTube::YouTubeNext { event }).await;
+    }
+
     pub(super) async fn pause(&self) {
         let event = bus::YouTubeEvent::Pause;
         self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
     }
 
     pub(super) async fn stop(&self) {
+        *self.preloaded.lock() = None;
+
         let event = bus::YouTubeEvent::Stop;
         self.bus.send(bus::YouTube::YouTubeCurrent { event }).await;
     }
@@ -106,3 +156,228 @@ impl YouTubePlayer {
         self.bus.send(bus::YouTube::YouTubeVolume { volume }).await;
     }
 }
+
+/// Provides videos from YouTube, played through the YouTube overlay.
+pub(super) struct YouTubeProvider {
+    pub(super) youtube: Arc<api::YouTube>,
+    pub(super) player: YouTubePlayer,
+}
+
+#[async_trait]
+impl TrackProvider for YouTubeProvider {
+    fn kind(&self) -> PlayerKind {
+        PlayerKind::YouTube
+    }
+
+    fn search_prefix(&self) -> &'static str {
+        "youtube"
+    }
+
+    fn handles(&self, track_id: &TrackId) -> bool {
+        matches!(track_id, TrackId::YouTube(..))
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<Result<TrackId, FromStrError>> {
+        let parts = url.path().split('/').collect::<Vec<_>>();
+
+        match url.host_str()? {
+            "youtube.com" | "www.youtube.com" => {
+                if parts.as_slice() != ["", "watch"] {
+                    return Some(Err(FromStrError::BadUrl(url.to_string())));
+                }
+
+                let video_id = url
+                    .query_pairs()
+                    .filter(|(n, _)| n == "v")
+                    .map(|(_, value)| value.to_string())
+                    .last();
+
+                Some(match video_id {
+                    Some(video_id) => Ok(TrackId::YouTube(video_id)),
+                    None => Err(FromStrError::BadUrl(url.to_string())),
+                })
+            }
+            "youtu.be" => Some(match parts.as_slice() {
+                ["", video_id] => Ok(TrackId::YouTube(video_id.to_string())),
+                _ => Err(FromStrError::BadUrl(url.to_string())),
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.youtube.token().is_ready()
+    }
+
+    async fn lookup(&self, track_id: &TrackId) -> Result<(Track, Duration)> {
+        let TrackId::YouTube(id) = track_id else {
+            bail!("not a youtube video: {}", track_id);
+        };
+
+        let video = match self
+            .youtube
+            .videos_by_id(id, "contentDetails,snippet")
+            .await?
+        {
+            Some(video) => video,
+            None => bail!("no video found for id `{}`", id),
+        };
+
+        let content_details = video
+            .content_details
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("video does not have content details"))?;
+
+        let duration = str::parse::<PtDuration>(&content_details.duration)?;
+
+        Ok((
+            Track::YouTube {
+                video: Box::new(video),
+            },
+            duration.into_std(),
+        ))
+    }
+
+    async fn search(&self, q: &str) -> Result<Option<TrackId>> {
+        let results = self.youtube.search(q).await?;
+
+        let mut result = results
+            .items
+            .into_iter()
+            .filter(|r| matches!(r.id.kind, Kind::Video))
+            .flat_map(|r| r.id.video_id);
+
+        Ok(result.next().map(TrackId::YouTube))
+    }
+
+    async fn play(&self, track_id: &TrackId, elapsed: Duration, duration: Duration) {
+        if let TrackId::YouTube(id) = track_id {
+            self.player.play(elapsed, duration, id.clone()).await;
+        }
+    }
+
+    async fn tick(&self, track_id: &TrackId, elapsed: Duration, duration: Duration) {
+        if let TrackId::YouTube(id) = track_id {
+            self.player.tick(elapsed, duration, id.clone()).await;
+        }
+    }
+
+    async fn preload(&self, track_id: &TrackId, remaining: Duration) {
+        if let TrackId::YouTube(id) = track_id {
+            self.player.preload(remaining, id.clone()).await;
+        }
+    }
+
+    async fn pause(&self) {
+        self.player.pause().await;
+    }
+
+    async fn stop(&self) {
+        self.player.stop().await;
+    }
+
+    async fn volume(&self, modify: crate::ModifyVolume) -> u32 {
+        self.player.volume(modify).await
+    }
+
+    async fn current_volume(&self) -> u32 {
+        self.player.current_volume().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use parking_lot::Mutex;
+
+    use super::YouTubePlayer;
+
+    fn player() -> (YouTubePlayer, bus::Reader<bus::YouTube>) {
+        let db = db::Database::open(Path::new(":memory:")).unwrap();
+        let schema = settings::Schema::load_bytes(b"types: {}").unwrap();
+        let bus = bus::Bus::new();
+        let reader = bus.subscribe();
+
+        let player = YouTubePlayer {
+            bus,
+            settings: settings::Settings::new(db, schema),
+            volume: settings::Var::new(50),
+            preload_window: settings::Var::new(common::Duration::seconds(10)),
+            preloaded: Arc::new(Mutex::new(None)),
+        };
+
+        (player, reader)
+    }
+
+    /// Collect the videos which have been sent to be buffered.
+    fn preloaded(reader: &mut bus::Reader<bus::YouTube>) -> Vec<String> {
+        let mut out = Vec::new();
+
+        while let Ok(m) = reader.try_recv() {
+            if let bus::YouTube::YouTubeNext {
+                event: bus::YouTubeEvent::Preload { video_id },
+            } = m
+            {
+                out.push(video_id);
+            }
+        }
+
+        out
+    }
+
+    #[tokio::test]
+    async fn test_preload_window() {
+        let (player, mut reader) = player();
+
+        player
+            .preload(Duration::from_secs(11), String::from("a"))
+            .await;
+        assert!(preloaded(&mut reader).is_empty());
+
+        player
+            .preload(Duration::from_secs(10), String::from("a"))
+            .await;
+        assert_eq!(preloaded(&mut reader), ["a"]);
+
+        // changes to the window are picked up.
+        *player.preload_window.write().await = common::Duration::seconds(30);
+        player
+            .preload(Duration::from_secs(20), String::from("b"))
+            .await;
+        assert_eq!(preloaded(&mut reader), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn test_preload_dedupe() {
+        let (player, mut reader) = player();
+
+        player
+            .preload(Duration::from_secs(5), String::from("a"))
+            .await;
+        player
+            .preload(Duration::from_secs(4), String::from("a"))
+            .await;
+        assert_eq!(preloaded(&mut reader), ["a"]);
+
+        // the next video changed, for example because the queue was edited.
+        player
+            .preload(Duration::from_secs(3), String::from("b"))
+            .await;
+        player
+            .preload(Duration::from_secs(2), String::from("a"))
+            .await;
+        assert_eq!(preloaded(&mut reader), ["b", "a"]);
+
+        // starting a new video resets what has been buffered.
+        player
+            .play(Duration::ZERO, Duration::from_secs(60), String::from("a"))
+            .await;
+        player
+            .preload(Duration::from_secs(5), String::from("a"))
+            .await;
+        assert_eq!(preloaded(&mut reader), ["a"]);
+    }
+}
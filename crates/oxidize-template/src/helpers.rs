@@ -0,0 +1,510 @@
+//! Helpers available to all templates.
+//!
+//! Helpers which need data that has to be fetched asynchronously, like the
+//! current song or the balance of a user, read it from [HelperData] which is
+//! resolved by the caller before rendering and passed in under the `helpers`
+//! key.
+
+use std::collections::HashMap;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Utc};
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, JsonRender, Output, RenderContext, RenderError,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// The key under which [HelperData] is expected in the rendered data.
+pub const HELPERS_KEY: &str = "helpers";
+
+/// The format used by the `date` helper if none is specified.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Data used by helpers which has been resolved ahead of rendering.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HelperData {
+    /// Arguments passed to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The current time in the streamer's time zone.
+    #[serde(default)]
+    pub now: Option<DateTime<FixedOffset>>,
+    /// Information about the stream.
+    #[serde(default)]
+    pub stream: StreamData,
+    /// The song currently playing.
+    #[serde(default)]
+    pub song: Option<String>,
+    /// The currency balance of the user.
+    #[serde(default)]
+    pub balance: Option<i64>,
+    /// The watch time of the user in seconds.
+    #[serde(default)]
+    pub watch_time: Option<u64>,
+    /// The values of counters by name.
+    #[serde(default)]
+    pub counters: HashMap<String, i64>,
+}
+
+/// Information about the stream used by helpers.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StreamData {
+    /// The title of the stream.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The game being played.
+    #[serde(default)]
+    pub game: Option<String>,
+    /// When the stream started, if it's live.
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// Register all helpers.
+pub(crate) fn register(reg: &mut Handlebars<'_>) {
+    reg.register_helper("choose", Box::new(choose));
+    reg.register_helper("random", Box::new(random));
+    reg.register_helper("pluralize", Box::new(pluralize));
+    reg.register_helper("uptime", Box::new(uptime));
+    reg.register_helper("game", Box::new(game));
+    reg.register_helper("title", Box::new(title));
+    reg.register_helper("song", Box::new(song));
+    reg.register_helper("balance", Box::new(balance));
+    reg.register_helper("watch_time", Box::new(watch_time));
+    reg.register_helper("arg", Box::new(arg));
+    reg.register_helper("date", Box::new(date));
+    reg.register_helper("counter", Box::new(counter));
+}
+
+/// Access helper data in the given context.
+fn data(ctx: &Context) -> HelperData {
+    ctx.data()
+        .get(HELPERS_KEY)
+        .and_then(|data| HelperData::deserialize(data).ok())
+        .unwrap_or_default()
+}
+
+/// Get an integer parameter.
+fn int_param(h: &Helper<'_, '_>, index: usize) -> Result<i64, RenderError> {
+    h.param(index)
+        .and_then(|p| p.value().as_i64())
+        .ok_or_else(|| {
+            RenderError::new(format!(
+                "{}: expected integer argument #{}",
+                h.name(),
+                index + 1
+            ))
+        })
+}
+
+/// Pick one of the arguments at random, like `{{choose "heads" "tails"}}`.
+fn choose(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(param) = h.params().choose(&mut rand::thread_rng()) {
+        out.write(&param.value().render())?;
+    }
+
+    Ok(())
+}
+
+/// Pick a random number in an inclusive range, like `{{random 1 6}}`.
+fn random(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let low = int_param(h, 0)?;
+    let high = int_param(h, 1)?;
+
+    if low > high {
+        return Err(RenderError::new("random: empty range"));
+    }
+
+    let n = rand::thread_rng().gen_range(low..=high);
+    out.write(&n.to_string())?;
+    Ok(())
+}
+
+/// Pick the singular or plural form of a word depending on a count, like
+/// `{{pluralize count "death"}}` or `{{pluralize count "mouse" "mice"}}`.
+fn pluralize(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    _: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let count = int_param(h, 0)?;
+
+    let singular = h
+        .param(1)
+        .map(|p| p.value().render())
+        .ok_or_else(|| RenderError::new("pluralize: expected a word"))?;
+
+    if count.abs() == 1 {
+        out.write(&singular)?;
+        return Ok(());
+    }
+
+    match h.param(2) {
+        Some(plural) => out.write(&plural.value().render())?,
+        None => out.write(&format!("{}s", singular))?,
+    }
+
+    Ok(())
+}
+
+/// How long the stream has been live.
+fn uptime(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let data = data(ctx);
+
+    if let (Some(now), Some(started_at)) = (data.now, data.stream.started_at) {
+        let uptime = (now.with_timezone(&Utc) - started_at)
+            .to_std()
+            .unwrap_or_default();
+
+        out.write(&common::display::compact_duration(uptime))?;
+    }
+
+    Ok(())
+}
+
+/// The game being played.
+fn game(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(game) = data(ctx).stream.game {
+        out.write(&game)?;
+    }
+
+    Ok(())
+}
+
+/// The title of the stream.
+fn title(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(title) = data(ctx).stream.title {
+        out.write(&title)?;
+    }
+
+    Ok(())
+}
+
+/// The song currently playing.
+fn song(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(song) = data(ctx).song {
+        out.write(&song)?;
+    }
+
+    Ok(())
+}
+
+/// The currency balance of the user.
+fn balance(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(balance) = data(ctx).balance {
+        out.write(&balance.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// The watch time of the user.
+fn watch_time(
+    _: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if let Some(watch_time) = data(ctx).watch_time {
+        let watch_time = std::time::Duration::from_secs(watch_time);
+        out.write(&common::display::compact_duration(watch_time))?;
+    }
+
+    Ok(())
+}
+
+/// Access an argument to the command by position, like `{{arg 1}}`, with an
+/// optional default like `{{arg 1 "nobody"}}`.
+fn arg(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let n = int_param(h, 0)?;
+
+    let arg = usize::try_from(n)
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|n| data(ctx).args.get(n).cloned());
+
+    match arg {
+        Some(arg) => out.write(&arg)?,
+        None => {
+            if let Some(default) = h.param(1) {
+                out.write(&default.value().render())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Format the current time in the streamer's time zone, like
+/// `{{date "%H:%M"}}`.
+fn date(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let format = match h.param(0) {
+        Some(format) => format.value().render(),
+        None => DEFAULT_DATE_FORMAT.to_string(),
+    };
+
+    // NB: formatting with a bad format string panics.
+    let items = StrftimeItems::new(&format).collect::<Vec<_>>();
+
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(RenderError::new(format!("date: bad format `{}`", format)));
+    }
+
+    if let Some(now) = data(ctx).now {
+        out.write(&now.format_with_items(items.into_iter()).to_string())?;
+    }
+
+    Ok(())
+}
+
+/// The value of a counter, like `{{counter "deaths"}}`.
+///
+/// Counters which don't exist are zero.
+fn counter(
+    h: &Helper<'_, '_>,
+    _: &Handlebars<'_>,
+    ctx: &Context,
+    _: &mut RenderContext<'_, '_>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let name = h
+        .param(0)
+        .map(|p| p.value().render())
+        .ok_or_else(|| RenderError::new("counter: expected a name"))?;
+
+    let count = data(ctx)
+        .counters
+        .get(&name.to_lowercase())
+        .copied()
+        .unwrap_or_default();
+
+    out.write(&count.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::Serialize;
+
+    use super::{HelperData, StreamData};
+    use crate::Template;
+
+    #[derive(Serialize)]
+    struct Vars {
+        count: i64,
+        helpers: HelperData,
+    }
+
+    fn render(template: &str, helpers: HelperData) -> Result<String> {
+        Template::compile(template)?.render_to_string(Vars { count: 2, helpers })
+    }
+
+    fn now() -> Result<DateTime<chrono::FixedOffset>> {
+        Ok(DateTime::parse_from_rfc3339("2026-10-16T14:30:00+02:00")?)
+    }
+
+    #[test]
+    fn test_choose() -> Result<()> {
+        for _ in 0..10 {
+            let out = render(r#"{{choose "a" "b" "c"}}"#, HelperData::default())?;
+            assert!(["a", "b", "c"].contains(&out.as_str()));
+        }
+
+        assert_eq!(render("{{choose}}", HelperData::default())?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_random() -> Result<()> {
+        for _ in 0..10 {
+            let out = render("{{random 1 6}}", HelperData::default())?;
+            assert!((1..=6).contains(&out.parse::<i64>()?));
+        }
+
+        assert_eq!(render("{{random 3 3}}", HelperData::default())?, "3");
+        assert!(render("{{random 6 1}}", HelperData::default()).is_err());
+        assert!(render(r#"{{random "a" 1}}"#, HelperData::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pluralize() -> Result<()> {
+        assert_eq!(
+            render(r#"{{pluralize 1 "death"}}"#, HelperData::default())?,
+            "death"
+        );
+        assert_eq!(
+            render(
+                r#"{{count}} {{pluralize count "death"}}"#,
+                HelperData::default()
+            )?,
+            "2 deaths"
+        );
+        assert_eq!(
+            render(r#"{{pluralize 0 "mouse" "mice"}}"#, HelperData::default())?,
+            "mice"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream() -> Result<()> {
+        let helpers = HelperData {
+            now: Some(now()?),
+            stream: StreamData {
+                title: Some(String::from("Speedruns")),
+                game: Some(String::from("Celeste")),
+                started_at: Some(Utc.with_ymd_and_hms(2026, 10, 16, 10, 45, 30).unwrap()),
+            },
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render("{{title}} | {{game}} | {{uptime}}", helpers)?,
+            "Speedruns | Celeste | 1h 44m 30s"
+        );
+
+        assert_eq!(
+            render("{{title}}{{game}}{{uptime}}", HelperData::default())?,
+            ""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_song() -> Result<()> {
+        let helpers = HelperData {
+            song: Some(String::from("\"Never Gonna Give You Up\" by Rick Astley")),
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render("Now playing: {{song}}", helpers)?,
+            "Now playing: \"Never Gonna Give You Up\" by Rick Astley"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance() -> Result<()> {
+        let helpers = HelperData {
+            balance: Some(420),
+            watch_time: Some(3 * 3600 + 60),
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render("{{balance}} coins, watched {{watch_time}}", helpers)?,
+            "420 coins, watched 3h 1m"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_arg() -> Result<()> {
+        let helpers = HelperData {
+            args: vec![String::from("alice"), String::from("bob")],
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render(
+                r#"{{arg 2}} {{arg 1}} {{arg 3 "nobody"}} {{arg 0}}"#,
+                helpers
+            )?,
+            "bob alice nobody "
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_counter() -> Result<()> {
+        let helpers = HelperData {
+            counters: [(String::from("deaths"), 12)].into_iter().collect(),
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render(r#"{{counter "Deaths"}} / {{counter "wins"}}"#, helpers)?,
+            "12 / 0"
+        );
+        assert!(render("{{counter}}", HelperData::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_date() -> Result<()> {
+        let helpers = HelperData {
+            now: Some(now()?),
+            ..HelperData::default()
+        };
+
+        assert_eq!(
+            render(r#"{{date}} / {{date "%H:%M %z"}}"#, helpers.clone())?,
+            "2026-10-16 14:30:00 / 14:30 +0200"
+        );
+        assert!(render(r#"{{date "%Q"}}"#, helpers).is_err());
+        Ok(())
+    }
+}
@@ -6,10 +6,14 @@ use std::string;
 
 use anyhow::Result;
 
+mod helpers;
+pub use self::helpers::{HelperData, StreamData, HELPERS_KEY};
+
 lazy_static::lazy_static! {
     static ref REGISTRY: handlebars::Handlebars<'static> = {
         let mut reg = handlebars::Handlebars::new();
         reg.register_escape_fn(|s| s.to_string());
+        self::helpers::register(&mut reg);
         reg
     };
 }
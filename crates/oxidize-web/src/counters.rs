@@ -0,0 +1,225 @@
+use anyhow::{anyhow, bail, Result};
+use common::Channel;
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::Fragment;
+
+#[derive(Deserialize)]
+struct SetCounter {
+    count: i64,
+}
+
+#[derive(Deserialize)]
+struct AddCounter {
+    amount: i64,
+}
+
+#[derive(Deserialize)]
+struct PutScope {
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Counter endpoints.
+#[derive(Clone)]
+pub(crate) struct Counters {
+    counters: async_injector::Ref<db::Counters>,
+    global_bus: bus::Bus<bus::Global>,
+}
+
+impl Counters {
+    pub(crate) fn route(
+        counters: async_injector::Ref<db::Counters>,
+        global_bus: bus::Bus<bus::Global>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Counters {
+            counters,
+            global_bus,
+        };
+
+        let list = warp::get()
+            .and(path!("counters" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let get = warp::get()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.get(channel.as_channel(), name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let set = warp::put()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: SetCounter| {
+                    let api = api.clone();
+                    async move {
+                        api.modify(
+                            channel.as_channel(),
+                            name.as_str(),
+                            db::CounterOp::Set(body.count),
+                        )
+                        .await
+                        .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let add = warp::post()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: AddCounter| {
+                    let api = api.clone();
+                    async move {
+                        api.modify(
+                            channel.as_channel(),
+                            name.as_str(),
+                            db::CounterOp::Add(body.amount),
+                        )
+                        .await
+                        .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let scope = warp::put()
+            .and(path!("counters" / Fragment / Fragment / "scope").and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: PutScope| {
+                    let api = api.clone();
+                    async move {
+                        api.edit_scope(channel.as_channel(), name.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment, name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_channel(), name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(get).or(set).or(add).or(scope).or(delete).boxed()
+    }
+
+    /// Access underlying counters abstraction.
+    async fn counters(&self) -> Result<RwLockReadGuard<'_, db::Counters>> {
+        match self.counters.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("counters not configured")),
+        }
+    }
+
+    /// List all counters in the given channel.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let counters = self.counters().await?.list(channel).await?;
+        Ok(warp::reply::json(&counters))
+    }
+
+    /// Get the counter with the given name.
+    async fn get(&self, channel: &Channel, name: &str) -> Result<impl warp::Reply> {
+        let Some(counter) = self.counters().await?.get(channel, name).await? else {
+            bail!("no counter named {}", name);
+        };
+
+        Ok(warp::reply::json(&counter))
+    }
+
+    /// Modify the counter with the given name, creating it if it doesn't
+    /// exist.
+    async fn modify(
+        &self,
+        channel: &Channel,
+        name: &str,
+        op: db::CounterOp,
+    ) -> Result<impl warp::Reply> {
+        let counter = self.counters().await?.modify(channel, name, op).await?;
+
+        self.global_bus
+            .send(bus::Global::Counter {
+                name: counter.name.clone(),
+                count: counter.count,
+            })
+            .await;
+
+        Ok(warp::reply::json(&counter))
+    }
+
+    /// Set the scope or role which also allows users to modify the counter with
+    /// the given name, on top of those with `counter/edit`.
+    async fn edit_scope(
+        &self,
+        channel: &Channel,
+        name: &str,
+        body: PutScope,
+    ) -> Result<impl warp::Reply> {
+        if let Some(scope) = body.scope.as_deref() {
+            let known = if scope.starts_with('@') {
+                !matches!(str::parse::<auth::Role>(scope)?, auth::Role::Unknown)
+            } else {
+                !matches!(str::parse::<auth::Scope>(scope)?, auth::Scope::Unknown)
+            };
+
+            if !known {
+                bail!("no such scope or role: {}", scope);
+            }
+        }
+
+        if !self
+            .counters()
+            .await?
+            .edit_scope(channel, name, body.scope)
+            .await?
+        {
+            bail!("no counter named {}", name);
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+
+    /// Delete the counter with the given name.
+    async fn delete(&self, channel: &Channel, name: &str) -> Result<impl warp::Reply> {
+        if !self.counters().await?.delete(channel, name).await? {
+            bail!("no counter named {}", name);
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+}
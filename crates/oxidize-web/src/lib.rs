@@ -2,7 +2,14 @@
 
 mod cache;
 mod chat;
+mod counters;
+mod local_audio;
+mod polls;
+mod queue_snapshots;
 mod settings;
+mod song_bans;
+mod songs;
+mod strikes;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -28,7 +35,14 @@ use warp::{body, filters, path, Filter};
 use self::assets::Asset;
 use self::cache::Cache;
 use self::chat::Chat;
+use self::counters::Counters;
+use self::local_audio::LocalAudio;
+use self::polls::Polls;
+use self::queue_snapshots::QueueSnapshots;
 use self::settings::Settings;
+use self::song_bans::SongBans;
+use self::songs::Songs;
+use self::strikes::Strikes;
 
 /// URL of public web interface.
 pub const URL: &str = "http://localhost:12345";
@@ -268,6 +282,22 @@ impl Commands {
                 }
             });
 
+        let edit_restrictions = warp::put()
+            .and(path!("commands" / Fragment / Fragment / "restrictions").and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: db::commands::Restrictions| {
+                    let api = api.clone();
+
+                    async move {
+                        api.edit_restrictions(channel.as_channel(), name.as_str(), body)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
         let edit = warp::put()
             .and(path!("commands" / Fragment / Fragment).and(path::end()))
             .and(body::json())
@@ -282,7 +312,12 @@ impl Commands {
                 }
             });
 
-        return list.or(delete).or(edit).or(edit_disabled).boxed();
+        return list
+            .or(delete)
+            .or(edit)
+            .or(edit_disabled)
+            .or(edit_restrictions)
+            .boxed();
 
         #[derive(Deserialize)]
         pub(crate) struct PutCommand {
@@ -333,6 +368,42 @@ impl Commands {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Set the given command's restrictions.
+    async fn edit_restrictions(
+        &self,
+        channel: &Channel,
+        name: &str,
+        restrictions: db::commands::Restrictions,
+    ) -> Result<impl warp::Reply> {
+        if let Some(scope) = restrictions.scope.as_deref() {
+            let known = if scope.starts_with('@') {
+                !matches!(str::parse::<auth::Role>(scope)?, auth::Role::Unknown)
+            } else {
+                !matches!(str::parse::<auth::Scope>(scope)?, auth::Scope::Unknown)
+            };
+
+            if !known {
+                bail!("no such scope or role: {}", scope);
+            }
+        }
+
+        if restrictions.cost.is_some_and(|cost| cost < 0) {
+            bail!("cost must not be negative");
+        }
+
+        let edited = self
+            .commands()
+            .await?
+            .edit_restrictions(channel, name, |r| *r = restrictions)
+            .await?;
+
+        if !edited {
+            bail!("no such command: {}", name);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Delete the given command by key.
     async fn delete(&self, channel: &Channel, name: &str) -> Result<impl warp::Reply> {
         self.commands().await?.delete(channel, name).await?;
@@ -596,6 +667,152 @@ impl Themes {
     }
 }
 
+/// The largest list of bad words which can be imported at once.
+const IMPORT_LIMIT: u64 = 1024 * 1024;
+
+/// Bad words endpoint.
+#[derive(Clone)]
+struct BadWords(async_injector::Ref<db::Words>);
+
+impl BadWords {
+    fn route(words: async_injector::Ref<db::Words>) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = BadWords(words);
+
+        let list = warp::get()
+            .and(path!("bad-words").and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(custom_reject) }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("bad-words" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |word: Fragment| {
+                    let api = api.clone();
+                    async move { api.delete(word.as_str()).await.map_err(custom_reject) }
+                }
+            });
+
+        let edit = warp::put()
+            .and(path!("bad-words" / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |word: Fragment, body: PutBadWord| {
+                    let api = api.clone();
+
+                    async move {
+                        api.edit(word.as_str(), body.why.as_deref(), body.kind)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
+        let export = warp::get()
+            .and(path!("bad-words" / "export" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |format: Fragment| {
+                    let api = api.clone();
+                    async move { api.export(format.as_str()).await.map_err(custom_reject) }
+                }
+            });
+
+        let import = warp::put()
+            .and(path!("bad-words" / "import" / Fragment).and(path::end()))
+            .and(body::content_length_limit(IMPORT_LIMIT))
+            .and(body::bytes())
+            .and_then({
+                move |format: Fragment, body: warp::hyper::body::Bytes| {
+                    let api = api.clone();
+                    async move {
+                        api.import(format.as_str(), &body)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
+        return list.or(export).or(import).or(delete).or(edit).boxed();
+
+        #[derive(Deserialize)]
+        pub(crate) struct PutBadWord {
+            #[serde(default)]
+            why: Option<String>,
+            #[serde(default)]
+            kind: db::WordKind,
+        }
+    }
+
+    /// Access underlying bad words abstraction.
+    async fn words(&self) -> Result<RwLockReadGuard<'_, db::Words>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => bail!("bad words not configured"),
+        }
+    }
+
+    /// Get the list of all bad words.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let words = self.words().await?.list().await?;
+        Ok(warp::reply::json(&words))
+    }
+
+    /// Edit the given bad word.
+    async fn edit(
+        &self,
+        word: &str,
+        why: Option<&str>,
+        kind: db::WordKind,
+    ) -> Result<impl warp::Reply> {
+        self.words().await?.edit(word, why, kind).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Import bad words in the given format.
+    async fn import(&self, format: &str, body: &[u8]) -> Result<impl warp::Reply> {
+        let format = str::parse::<db::WordsFormat>(format)?;
+        let imported = self.words().await?.import(format, body).await?;
+        return Ok(warp::reply::json(&Imported { imported }));
+
+        #[derive(Serialize)]
+        struct Imported {
+            imported: usize,
+        }
+    }
+
+    /// Export all bad words in the given format.
+    async fn export(&self, format: &str) -> Result<impl warp::Reply> {
+        let format = str::parse::<db::WordsFormat>(format)?;
+        let body = self.words().await?.export(format).await?;
+
+        let (content_type, file_name) = match format {
+            db::WordsFormat::Csv => ("text/csv", "bad-words.csv"),
+            db::WordsFormat::Yaml => ("application/yaml", "bad-words.yaml"),
+        };
+
+        let reply = warp::reply::with_header(body, "content-type", content_type);
+
+        Ok(warp::reply::with_header(
+            reply,
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        ))
+    }
+
+    /// Delete the given bad word.
+    async fn delete(&self, word: &str) -> Result<impl warp::Reply> {
+        self.words().await?.delete(word).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
+
 /// Auth API endpoints.
 #[derive(Clone)]
 struct Auth {
@@ -610,6 +827,12 @@ pub(crate) struct AuthKeyQuery {
     key: Option<Fragment>,
 }
 
+#[derive(Deserialize)]
+pub(crate) struct PutRole {
+    #[serde(default)]
+    doc: Option<String>,
+}
+
 impl Auth {
     fn route(
         auth: auth::Auth,
@@ -640,7 +863,7 @@ impl Auth {
                     let api = api.clone();
                     move || {
                         let api = api.clone();
-                        async move { api.roles().map_err(custom_reject) }
+                        async move { api.roles().await.map_err(custom_reject) }
                     }
                 }))
             .boxed();
@@ -702,6 +925,95 @@ impl Auth {
                 }))
             .boxed();
 
+        let route = route
+            .or(warp::get()
+                .and(warp::path!("temporary").and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move || {
+                        let api = api.clone();
+                        async move { api.temporary().await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::delete()
+                .and(warp::path!("temporary" / Fragment / Fragment).and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move |scope: Fragment, principal: Fragment| {
+                        let api = api.clone();
+                        async move {
+                            api.delete_temporary(scope.as_str(), principal.as_str())
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::put()
+                .and(warp::path!("roles" / Fragment).and(path::end()))
+                .and(body::json())
+                .and_then({
+                    let api = api.clone();
+                    move |role: Fragment, body: PutRole| {
+                        let api = api.clone();
+                        async move {
+                            api.insert_role(role.as_str(), body)
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::delete()
+                .and(warp::path!("roles" / Fragment).and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move |role: Fragment| {
+                        let api = api.clone();
+                        async move { api.delete_role(role.as_str()).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::put()
+                .and(warp::path!("roles" / Fragment / "members" / Fragment).and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move |role: Fragment, user: Fragment| {
+                        let api = api.clone();
+                        async move {
+                            api.insert_member(role.as_str(), user.as_str())
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::delete()
+                .and(warp::path!("roles" / Fragment / "members" / Fragment).and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move |role: Fragment, user: Fragment| {
+                        let api = api.clone();
+                        async move {
+                            api.delete_member(role.as_str(), user.as_str())
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
         let route = route
             .or(warp::get()
                 .and(
@@ -746,8 +1058,8 @@ impl Auth {
     }
 
     /// Get the list of all roles.
-    fn roles(&self) -> Result<impl warp::Reply> {
-        let roles = self.auth.roles();
+    async fn roles(&self) -> Result<impl warp::Reply> {
+        let roles = self.auth.roles().await;
         Ok(warp::reply::json(&roles))
     }
 
@@ -757,6 +1069,24 @@ impl Auth {
         Ok(warp::reply::json(&auth))
     }
 
+    /// Get the list of all active temporary grants and their remaining time.
+    async fn temporary(&self) -> Result<impl warp::Reply> {
+        let temporary = self.auth.temporary().await;
+        Ok(warp::reply::json(&temporary))
+    }
+
+    /// Revoke a temporary grant.
+    async fn delete_temporary(&self, scope: &str, principal: &str) -> Result<impl warp::Reply> {
+        let scope = str::parse(scope)?;
+        let principal = str::parse(principal)?;
+
+        if !self.auth.delete_temporary(scope, &principal).await? {
+            bail!("no temporary grant for {} on {}", principal, scope);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Delete a single scope assignment.
     async fn delete_grant(&self, scope: &str, role: &str) -> Result<impl warp::Reply> {
         let scope = str::parse(scope)?;
@@ -771,6 +1101,46 @@ impl Auth {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Create a custom role.
+    async fn insert_role(&self, role: &str, body: PutRole) -> Result<impl warp::Reply> {
+        let role = custom_role(role)?;
+
+        if !self.auth.insert_role(role.clone(), body.doc).await? {
+            bail!("role {} already exists", role);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete a custom role.
+    async fn delete_role(&self, role: &str) -> Result<impl warp::Reply> {
+        let role = custom_role(role)?;
+
+        if !self.auth.delete_role(&role).await? {
+            bail!("no such role: {}", role);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Add a member to a custom role.
+    async fn insert_member(&self, role: &str, user: &str) -> Result<impl warp::Reply> {
+        let role = custom_role(role)?;
+        self.auth.insert_member(&role, user).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove a member from a custom role.
+    async fn delete_member(&self, role: &str, user: &str) -> Result<impl warp::Reply> {
+        let role = custom_role(role)?;
+
+        if !self.auth.delete_member(&role, user).await? {
+            bail!("{} is not a member of {}", user, role);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     async fn set_key(&self, key: AuthKeyQuery) -> Result<impl warp::Reply> {
         match self.settings.read().await {
             Some(settings) => {
@@ -795,6 +1165,20 @@ impl Auth {
     }
 }
 
+/// Parse a custom role from a path, with or without the leading `@`.
+fn custom_role(role: &str) -> Result<auth::Role> {
+    let role = match role.strip_prefix('@') {
+        Some(..) => str::parse::<auth::Role>(role)?,
+        None => str::parse::<auth::Role>(&format!("@{}", role))?,
+    };
+
+    if !matches!(role, auth::Role::Custom(..)) {
+        bail!("not a custom role: {}", role);
+    }
+
+    Ok(role)
+}
+
 /// API to manage device.
 #[derive(Clone)]
 struct Api {
@@ -802,9 +1186,23 @@ struct Api {
     player: async_injector::Ref<player::Player>,
     after_streams: async_injector::Ref<db::AfterStreams>,
     currency: async_injector::Ref<currency::Currency>,
+    channel: async_injector::Ref<String>,
     latest: ::settings::Var<Option<api::github::Release>>,
 }
 
+/// The maximum number of balances returned per page.
+const TOP_BALANCES_LIMIT: usize = 100;
+
+#[derive(Deserialize)]
+pub(crate) struct TopBalancesQuery {
+    #[serde(default)]
+    order: currency::BalanceOrder,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Balance {
     name: String,
@@ -933,6 +1331,54 @@ impl Api {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Get a page of the highest ranked balances in the current channel.
+    async fn top_balances(self, query: TopBalancesQuery) -> Result<impl warp::Reply, WebError> {
+        let currency = self.currency.load().await.ok_or(WebError::NotFound)?;
+        let channel = self.channel.load().await.ok_or(WebError::NotFound)?;
+
+        let limit = query
+            .limit
+            .unwrap_or(TOP_BALANCES_LIMIT)
+            .min(TOP_BALANCES_LIMIT);
+
+        let balances = currency
+            .balances_top(Channel::new(&channel), query.order, query.offset, limit)
+            .await?;
+
+        let balances = balances
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| RankedBalance {
+                rank: query.offset + i + 1,
+                balance: Balance {
+                    name: b.user,
+                    balance: b.amount,
+                    watch_time: b.watch_time,
+                },
+            })
+            .collect();
+
+        return Ok(warp::reply::json(&TopBalances {
+            offset: query.offset,
+            limit,
+            balances,
+        }));
+
+        #[derive(Serialize)]
+        struct TopBalances {
+            offset: usize,
+            limit: usize,
+            balances: Vec<RankedBalance>,
+        }
+
+        #[derive(Serialize)]
+        struct RankedBalance {
+            rank: usize,
+            #[serde(flatten)]
+            balance: Balance,
+        }
+    }
+
     /// Export balances.
     async fn export_balances(self) -> Result<impl warp::Reply, WebError> {
         let balances = self
@@ -1002,6 +1448,7 @@ pub async fn run(
     message_bus: bus::Bus<messagelog::Event>,
     global_bus: bus::Bus<bus::Global>,
     youtube_bus: bus::Bus<bus::YouTube>,
+    local_bus: bus::Bus<bus::LocalAudio>,
     command_bus: bus::Bus<bus::Command>,
     auth: auth::Auth,
     latest: ::settings::Var<Option<api::github::Release>>,
@@ -1020,6 +1467,7 @@ pub async fn run(
         player: player.clone(),
         after_streams: injector.var().await,
         currency: injector.var().await,
+        channel: channel.clone(),
         latest,
     };
 
@@ -1094,6 +1542,19 @@ pub async fn run(
                 }))
             .boxed();
 
+        let route = route
+            .or(warp::get()
+                .and(path!("balances" / "top").and(path::end()))
+                .and(warp::query::<TopBalancesQuery>())
+                .and_then({
+                    let api = api.clone();
+                    move |query: TopBalancesQuery| {
+                        let api = api.clone();
+                        async move { api.top_balances(query).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
         let route = route
             .or(warp::get().and(warp::path("balances")).and_then({
                 move || {
@@ -1115,8 +1576,19 @@ pub async fn run(
         let route = route.or(Commands::route(injector.var().await));
         let route = route.or(Promotions::route(injector.var().await));
         let route = route.or(Themes::route(injector.var().await));
+        let route = route.or(BadWords::route(injector.var().await));
         let route = route.or(Settings::route(injector.var().await));
         let route = route.or(Cache::route(injector.var().await));
+        let route = route.or(Strikes::route(injector.var().await));
+        let route = route.or(Counters::route(injector.var().await, global_bus.clone()));
+        let route = route.or(Polls::route(injector.var().await));
+        let route = route.or(LocalAudio::route(injector.var().await));
+        let route = route.or(SongBans::route(injector.var().await, injector.var().await));
+        let route = route.or(QueueSnapshots::route(
+            injector.var().await,
+            injector.var().await,
+        ));
+        let route = route.or(Songs::route(injector.var().await, injector.var().await));
         let route = route.or(Chat::route(command_bus, message_log));
 
         // TODO: move endpoint into abstraction thingie.
@@ -1150,10 +1622,15 @@ pub async fn run(
         .and(warp::path!("ws" / "youtube"))
         .and(send_bus(youtube_bus).recover(recover));
 
+    let ws_local = warp::get()
+        .and(warp::path!("ws" / "local"))
+        .and(send_bus(local_bus).recover(recover));
+
     let routes = api.recover(recover);
     let routes = routes.or(ws_messages.recover(recover));
     let routes = routes.or(ws_overlay.recover(recover));
     let routes = routes.or(ws_youtube.recover(recover));
+    let routes = routes.or(ws_local.recover(recover));
 
     let fallback = Asset::get("index.html");
     let fallback = fallback.map(|f| f.data);
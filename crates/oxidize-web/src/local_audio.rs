@@ -0,0 +1,113 @@
+use std::io::SeekFrom;
+
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use warp::filters;
+use warp::http::{Response, StatusCode};
+use warp::hyper::Body;
+use warp::path;
+use warp::Filter;
+
+use crate::Fragment;
+
+/// Endpoints for the local music library.
+#[derive(Clone)]
+pub(crate) struct LocalAudio(async_injector::Ref<player::LocalLibrary>);
+
+impl LocalAudio {
+    pub(crate) fn route(
+        library: async_injector::Ref<player::LocalLibrary>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = LocalAudio(library);
+
+        warp::get()
+            .and(path!("local" / "tracks" / Fragment).and(path::end()))
+            .and(warp::header::optional::<String>("range"))
+            .and_then({
+                move |track: Fragment, range: Option<String>| {
+                    let api = api.clone();
+                    async move {
+                        api.track(track.as_str(), range.as_deref())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed()
+    }
+
+    /// Stream the audio file of an indexed track, or the part of it asked for
+    /// by a `Range` header so that the overlay can seek in it.
+    async fn track(&self, track: &str, range: Option<&str>) -> Result<Response<Body>> {
+        let library = self
+            .0
+            .load()
+            .await
+            .ok_or_else(|| anyhow!("local music library not configured"))?;
+
+        let path = library
+            .resolve(track)
+            .ok_or_else(|| anyhow!("no such track: {}", track))?;
+
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        let mut file = tokio::fs::File::open(&path).await?;
+        let size = file.metadata().await?.len();
+
+        let response = Response::builder()
+            .header("content-type", mime.to_string())
+            .header("accept-ranges", "bytes");
+
+        let Some(range) = range else {
+            return Ok(response
+                .header("content-length", size)
+                .body(Body::wrap_stream(ReaderStream::new(file)))?);
+        };
+
+        let Some((start, end)) = parse_range(range, size) else {
+            return Ok(response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header("content-range", format!("bytes */{}", size))
+                .body(Body::empty())?);
+        };
+
+        file.seek(SeekFrom::Start(start)).await?;
+        let len = end - start + 1;
+
+        Ok(response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("content-range", format!("bytes {}-{}/{}", start, end, size))
+            .header("content-length", len)
+            .body(Body::wrap_stream(ReaderStream::new(file.take(len))))?)
+    }
+}
+
+/// Parse a `Range` header into the inclusive byte range it asks for in a file
+/// of the given size.
+///
+/// Only a single range is supported, which is all that audio elements ask for.
+/// Returns `None` if the range can't be satisfied.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let last = size.checked_sub(1)?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return None,
+        // A suffix range, asking for the last `n` bytes.
+        ("", n) => match str::parse::<u64>(n).ok()? {
+            0 => return None,
+            n => (size.saturating_sub(n), last),
+        },
+        (start, "") => (str::parse(start).ok()?, last),
+        (start, end) => (
+            str::parse(start).ok()?,
+            str::parse::<u64>(end).ok()?.min(last),
+        ),
+    };
+
+    if start > end {
+        return None;
+    }
+
+    Some((start, end))
+}
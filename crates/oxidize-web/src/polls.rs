@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use common::Channel;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::Fragment;
+
+/// The number of polls listed through the API.
+const LIST_LIMIT: i64 = 50;
+
+/// Poll history endpoints.
+#[derive(Clone)]
+pub(crate) struct Polls(async_injector::Ref<db::Polls>);
+
+impl Polls {
+    pub(crate) fn route(
+        polls: async_injector::Ref<db::Polls>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Polls(polls);
+
+        warp::get()
+            .and(path!("polls" / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed()
+    }
+
+    /// Access underlying polls abstraction.
+    async fn polls(&self) -> Result<RwLockReadGuard<'_, db::Polls>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("polls not configured")),
+        }
+    }
+
+    /// List the most recent polls in the given channel, with their results.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let polls = self.polls().await?.list_recent(channel, LIST_LIMIT).await?;
+        Ok(warp::reply::json(&polls))
+    }
+}
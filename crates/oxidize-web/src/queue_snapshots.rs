@@ -0,0 +1,183 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::Fragment;
+
+#[derive(Deserialize)]
+struct LoadSnapshot {
+    /// The user to request songs as if the snapshot doesn't know who
+    /// originally requested them.
+    user: String,
+}
+
+#[derive(Serialize)]
+struct Saved {
+    songs: usize,
+}
+
+#[derive(Serialize)]
+struct Loaded {
+    added: usize,
+    skipped: usize,
+}
+
+#[derive(Serialize)]
+struct Restored {
+    restored: usize,
+}
+
+/// Queue snapshot endpoints.
+#[derive(Clone)]
+pub(crate) struct QueueSnapshots {
+    queue_snapshots: async_injector::Ref<db::QueueSnapshots>,
+    player: async_injector::Ref<player::Player>,
+}
+
+impl QueueSnapshots {
+    pub(crate) fn route(
+        queue_snapshots: async_injector::Ref<db::QueueSnapshots>,
+        player: async_injector::Ref<player::Player>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = QueueSnapshots {
+            queue_snapshots,
+            player,
+        };
+
+        let list = warp::get()
+            .and(path!("queue-snapshots").and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            });
+
+        let get = warp::get()
+            .and(path!("queue-snapshots" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment| {
+                    let api = api.clone();
+                    async move { api.get(name.as_str()).await.map_err(super::custom_reject) }
+                }
+            });
+
+        let save = warp::put()
+            .and(path!("queue-snapshots" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment| {
+                    let api = api.clone();
+                    async move { api.save(name.as_str()).await.map_err(super::custom_reject) }
+                }
+            });
+
+        let load = warp::post()
+            .and(path!("queue-snapshots" / Fragment / "load").and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment, body: LoadSnapshot| {
+                    let api = api.clone();
+                    async move {
+                        api.load(name.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("queue-snapshots" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let undo = warp::post()
+            .and(path!("queue" / "undo").and(path::end()))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.undo().await.map_err(super::custom_reject) }
+                }
+            });
+
+        list.or(get).or(save).or(load).or(delete).or(undo).boxed()
+    }
+
+    /// Access underlying queue snapshots abstraction.
+    async fn queue_snapshots(&self) -> Result<RwLockReadGuard<'_, db::QueueSnapshots>> {
+        match self.queue_snapshots.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("queue snapshots not configured")),
+        }
+    }
+
+    /// Access underlying player abstraction.
+    async fn player(&self) -> Result<RwLockReadGuard<'_, player::Player>> {
+        match self.player.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("player not configured")),
+        }
+    }
+
+    /// List all snapshots.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let snapshots = self.queue_snapshots().await?.list().await?;
+        Ok(warp::reply::json(&snapshots))
+    }
+
+    /// Get the songs in the snapshot with the given name.
+    async fn get(&self, name: &str) -> Result<impl warp::Reply> {
+        let Some(songs) = self.queue_snapshots().await?.get(name).await? else {
+            bail!("no snapshot named {}", name);
+        };
+
+        Ok(warp::reply::json(&songs))
+    }
+
+    /// Save the current queue as a snapshot with the given name.
+    async fn save(&self, name: &str) -> Result<impl warp::Reply> {
+        let songs = self.player().await?.save_snapshot(name).await?;
+        Ok(warp::reply::json(&Saved { songs }))
+    }
+
+    /// Add the songs in the snapshot with the given name to the queue.
+    async fn load(&self, name: &str, body: LoadSnapshot) -> Result<impl warp::Reply> {
+        let Some((added, skipped)) = self.player().await?.load_snapshot(name, &body.user).await?
+        else {
+            bail!("no snapshot named {}", name);
+        };
+
+        Ok(warp::reply::json(&Loaded { added, skipped }))
+    }
+
+    /// Delete the snapshot with the given name.
+    async fn delete(&self, name: &str) -> Result<impl warp::Reply> {
+        if !self.queue_snapshots().await?.delete(name).await? {
+            bail!("no snapshot named {}", name);
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+
+    /// Undo the last destructive queue operation.
+    async fn undo(&self) -> Result<impl warp::Reply> {
+        let restored = self.player().await?.undo().await?.len();
+        Ok(warp::reply::json(&Restored { restored }))
+    }
+}
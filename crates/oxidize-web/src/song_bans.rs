@@ -0,0 +1,110 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+#[derive(Deserialize)]
+struct PutSongBan {
+    kind: db::SongBanKind,
+    pattern: String,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Song ban endpoints.
+#[derive(Clone)]
+pub(crate) struct SongBans {
+    song_bans: async_injector::Ref<db::SongBans>,
+    player: async_injector::Ref<player::Player>,
+}
+
+impl SongBans {
+    pub(crate) fn route(
+        song_bans: async_injector::Ref<db::SongBans>,
+        player: async_injector::Ref<player::Player>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = SongBans { song_bans, player };
+
+        let list = warp::get()
+            .and(path!("song-bans").and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            });
+
+        let insert = warp::put()
+            .and(path!("song-bans").and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |body: PutSongBan| {
+                    let api = api.clone();
+                    async move { api.insert(body).await.map_err(super::custom_reject) }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("song-bans" / i32).and(path::end()))
+            .and_then({
+                move |id: i32| {
+                    let api = api.clone();
+                    async move { api.delete(id).await.map_err(super::custom_reject) }
+                }
+            });
+
+        list.or(insert).or(delete).boxed()
+    }
+
+    /// Access underlying song bans abstraction.
+    async fn song_bans(&self) -> Result<RwLockReadGuard<'_, db::SongBans>> {
+        match self.song_bans.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("song bans not configured")),
+        }
+    }
+
+    /// List all song bans.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let bans = self.song_bans().await?.list().await?;
+        Ok(warp::reply::json(&bans))
+    }
+
+    /// Add a song ban.
+    ///
+    /// Tracks can be given as URLs if the player is available, otherwise
+    /// they have to be URIs.
+    async fn insert(&self, body: PutSongBan) -> Result<impl warp::Reply> {
+        let pattern = match (body.kind, self.player.load().await) {
+            (db::SongBanKind::Track, Some(player)) => {
+                player.parse_track(&body.pattern)?.to_string()
+            }
+            _ => body.pattern,
+        };
+
+        if !self
+            .song_bans()
+            .await?
+            .insert(body.kind, &pattern, body.reason.as_deref(), None)
+            .await?
+        {
+            bail!("{} {} is already banned", body.kind, pattern);
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+
+    /// Remove the song ban with the given id.
+    async fn delete(&self, id: i32) -> Result<impl warp::Reply> {
+        if !self.song_bans().await?.delete_by_id(id).await? {
+            bail!("no song ban with id {}", id);
+        }
+
+        Ok(warp::reply::json(&super::EMPTY))
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use anyhow::{anyhow, Result};
+use common::models::TrackId;
+use common::stream::StreamExt;
+use common::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+/// The maximum number of entries returned by the history and top endpoints.
+const LIST_LIMIT: i64 = 100;
+
+/// The window used for top lists unless one is specified.
+const DEFAULT_TOP_WINDOW: Duration = Duration::seconds(7 * 24 * 60 * 60);
+
+/// The largest gap between two played songs which are considered part of the
+/// same stream.
+const STREAM_GAP: Duration = Duration::seconds(6 * 60 * 60);
+
+/// The maximum number of songs included in the export of the last stream.
+const EXPORT_LIMIT: i64 = 500;
+
+/// The maximum number of distinct tracks looked up for the export of the last
+/// stream. Remaining tracks are exported with their URL as the title.
+const LOOKUP_LIMIT: usize = 100;
+
+/// The number of tracks looked up concurrently for the export.
+const LOOKUP_CONCURRENCY: usize = 8;
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TopQuery {
+    #[serde(default)]
+    window: Option<Duration>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ExportFormat {
+    #[default]
+    Json,
+    Html,
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// A single song request.
+#[derive(Serialize)]
+struct Song {
+    track_id: TrackId,
+    url: String,
+    user: Option<String>,
+    played: bool,
+    added_at: String,
+}
+
+/// A song played during a stream, with its title resolved.
+#[derive(Serialize)]
+struct PlayedSong {
+    title: String,
+    url: String,
+    user: Option<String>,
+    added_at: String,
+}
+
+#[derive(Serialize)]
+struct Top {
+    window: Duration,
+    tracks: Vec<db::models::TrackCount>,
+    requesters: Vec<db::models::RequesterCount>,
+}
+
+/// Song request history endpoints.
+#[derive(Clone)]
+pub(crate) struct Songs {
+    db: async_injector::Ref<db::Database>,
+    player: async_injector::Ref<player::Player>,
+}
+
+impl Songs {
+    pub(crate) fn route(
+        db: async_injector::Ref<db::Database>,
+        player: async_injector::Ref<player::Player>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Songs { db, player };
+
+        let route = warp::get()
+            .and(path!("songs" / "history").and(path::end()))
+            .and(warp::query::<HistoryQuery>())
+            .and_then({
+                let api = api.clone();
+                move |query: HistoryQuery| {
+                    let api = api.clone();
+                    async move { api.history(query).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let route = route
+            .or(warp::get()
+                .and(path!("songs" / "top").and(path::end()))
+                .and(warp::query::<TopQuery>())
+                .and_then({
+                    let api = api.clone();
+                    move |query: TopQuery| {
+                        let api = api.clone();
+                        async move { api.top(query).await.map_err(super::custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        route
+            .or(warp::get()
+                .and(path!("songs" / "last-stream").and(path::end()))
+                .and(warp::query::<ExportQuery>())
+                .and_then({
+                    move |query: ExportQuery| {
+                        let api = api.clone();
+                        async move { api.last_stream(query).await.map_err(super::custom_reject) }
+                    }
+                }))
+            .boxed()
+    }
+
+    /// Access underlying database.
+    async fn db(&self) -> Result<RwLockReadGuard<'_, db::Database>> {
+        match self.db.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("database not configured")),
+        }
+    }
+
+    /// List the most recent song requests, optionally by the given user.
+    async fn history(&self, query: HistoryQuery) -> Result<impl warp::Reply> {
+        let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(0, LIST_LIMIT);
+
+        let songs = self
+            .db()
+            .await?
+            .player_history(query.user.as_deref(), limit)
+            .await?;
+
+        let songs = songs
+            .into_iter()
+            .map(|song| Song {
+                url: song.track_id.url(),
+                track_id: song.track_id,
+                user: song.user,
+                played: song.played,
+                added_at: song.added_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(warp::reply::json(&songs))
+    }
+
+    /// List the most requested tracks and most active requesters.
+    async fn top(&self, query: TopQuery) -> Result<impl warp::Reply> {
+        let window = query.window.unwrap_or(DEFAULT_TOP_WINDOW);
+        let limit = query.limit.unwrap_or(LIST_LIMIT).clamp(0, LIST_LIMIT);
+
+        let db = self.db().await?;
+        let tracks = db.player_top_tracks(Some(&window), limit).await?;
+        let requesters = db.player_top_requesters(Some(&window), limit).await?;
+
+        Ok(warp::reply::json(&Top {
+            window,
+            tracks,
+            requesters,
+        }))
+    }
+
+    /// Export the songs played during the last stream, suitable for VOD
+    /// descriptions.
+    async fn last_stream(&self, query: ExportQuery) -> Result<warp::reply::Response> {
+        use warp::Reply as _;
+
+        let songs = self
+            .db()
+            .await?
+            .player_last_stream(&STREAM_GAP, EXPORT_LIMIT)
+            .await?;
+
+        let titles = match self.player.load().await {
+            Some(player) => lookup_titles(&player, &songs).await,
+            None => HashMap::new(),
+        };
+
+        let mut played = Vec::with_capacity(songs.len());
+
+        for song in songs {
+            let title = match titles.get(&song.track_id) {
+                Some(title) => title.clone(),
+                None => song.track_id.url(),
+            };
+
+            played.push(PlayedSong {
+                title,
+                url: song.track_id.url(),
+                user: song.user,
+                added_at: song.added_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            });
+        }
+
+        Ok(match query.format {
+            ExportFormat::Json => warp::reply::json(&played).into_response(),
+            ExportFormat::Html => warp::reply::html(render_html(&played)).into_response(),
+        })
+    }
+}
+
+/// Look up the titles of the distinct tracks among the given songs.
+///
+/// At most [LOOKUP_LIMIT] tracks are looked up, [LOOKUP_CONCURRENCY] at a
+/// time. Tracks which aren't looked up are left out.
+async fn lookup_titles(
+    player: &player::Player,
+    songs: &[db::models::Song],
+) -> HashMap<TrackId, String> {
+    let mut seen = HashSet::new();
+
+    let track_ids = songs
+        .iter()
+        .map(|song| &song.track_id)
+        .filter(|track_id| seen.insert(*track_id))
+        .take(LOOKUP_LIMIT)
+        .collect::<Vec<_>>();
+
+    let mut titles = HashMap::with_capacity(track_ids.len());
+
+    for chunk in track_ids.chunks(LOOKUP_CONCURRENCY) {
+        let mut lookups = common::Futures::default();
+
+        for &track_id in chunk {
+            lookups.push(Box::pin(async move {
+                (track_id, player.lookup_track(track_id).await)
+            }));
+        }
+
+        while let Some((track_id, result)) = lookups.next().await {
+            match result {
+                Ok(Some(item)) => {
+                    titles.insert(track_id.clone(), item.what());
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    common::log_warn!(e, "Failed to look up track: {}", track_id);
+                }
+            }
+        }
+    }
+
+    titles
+}
+
+/// Render the given songs as an HTML list.
+fn render_html(songs: &[PlayedSong]) -> String {
+    let mut out = String::from("<ol>\n");
+
+    for song in songs {
+        let _ = write!(
+            out,
+            "<li><a href=\"{}\">{}</a>",
+            escape(&song.url),
+            escape(&song.title)
+        );
+
+        if let Some(user) = &song.user {
+            let _ = write!(out, " (requested by {})", escape(user));
+        }
+
+        out.push_str("</li>\n");
+    }
+
+    out.push_str("</ol>\n");
+    out
+}
+
+/// Escape text for inclusion in HTML.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use common::Channel;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::Fragment;
+
+/// The number of strikes listed through the API.
+const LIST_LIMIT: i64 = 100;
+
+/// Strikes endpoints.
+#[derive(Clone)]
+pub(crate) struct Strikes(async_injector::Ref<db::Strikes>);
+
+impl Strikes {
+    pub(crate) fn route(
+        strikes: async_injector::Ref<db::Strikes>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Strikes(strikes);
+
+        warp::get()
+            .and(path!("strikes" / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed()
+    }
+
+    /// Access underlying strikes abstraction.
+    async fn strikes(&self) -> Result<RwLockReadGuard<'_, db::Strikes>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("strikes not configured")),
+        }
+    }
+
+    /// List the most recent strikes in the given channel.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let strikes = self
+            .strikes()
+            .await?
+            .list_recent(channel, LIST_LIMIT)
+            .await?;
+        Ok(warp::reply::json(&strikes))
+    }
+}